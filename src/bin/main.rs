@@ -1,7 +1,7 @@
 use std::io;
 use std::io::{BufRead, Write};
 use pcap::{Device, Error};
-use packet_snooper::{PacketSnooper, State};
+use packet_snooper::{PacketSnooper, PSError, State};
 
 fn main() {
     let mut packet_snooper = PacketSnooper::new();
@@ -82,6 +82,31 @@ fn main() {
                         match cmd.to_lowercase().as_str() {
                             "start" => { packet_snooper.start().unwrap(); },
                             "abort" => { packet_snooper.abort().unwrap(); },
+                            "checksum-offload on" => { packet_snooper.set_checksum_offload(true).unwrap(); },
+                            "checksum-offload off" => { packet_snooper.set_checksum_offload(false).unwrap(); },
+                            "flows unidirectional" => { packet_snooper.set_flow_aggregation("unidirectional").unwrap(); },
+                            "flows bidirectional" => { packet_snooper.set_flow_aggregation("bidirectional").unwrap(); },
+                            cmd if cmd.starts_with("flow-timeouts ") => {
+                                let timeouts: Vec<u64> = cmd.split_whitespace().skip(1).filter_map(|timeout| timeout.parse().ok()).collect();
+                                let res = match timeouts.as_slice() {
+                                    [idle_timeout, active_timeout] => packet_snooper.set_flow_timeouts(*idle_timeout, *active_timeout),
+                                    _ => Err(PSError::new("Invalid flow timeouts given as a parameter.")),
+                                };
+                                if let Err(e) = res { println!("{}. Retry. Press any key to continue.", e); wait_for_key_press(); }
+                            },
+                            command if command.starts_with("export ") => {
+                                let res = match cmd.split_whitespace().skip(1).collect::<Vec<&str>>().as_slice() {
+                                    [collector, version] => packet_snooper.set_flow_export(collector, version),
+                                    _ => Err(PSError::new("Invalid flow export given as a parameter.")),
+                                };
+                                if let Err(e) = res { println!("{}. Retry. Press any key to continue.", e); wait_for_key_press(); }
+                            },
+                            command if command.starts_with("collect ") => {
+                                if let Err(e) = packet_snooper.set_flow_collector(cmd["collect ".len()..].trim()) { println!("{}. Retry. Press any key to continue.", e); wait_for_key_press(); }
+                            },
+                            command if command.starts_with("services ") => {
+                                if let Err(e) = packet_snooper.set_services_file(cmd["services ".len()..].trim()) { println!("{}. Retry. Press any key to continue.", e); wait_for_key_press(); }
+                            },
                             "exit" => { return; }
                             _ => { println!("Invalid command. Retry. Press any key to continue"); wait_for_key_press(); }
                         };
@@ -99,6 +124,20 @@ fn main() {
                             "abort" => { packet_snooper.abort().unwrap(); },
                             "end" => { packet_snooper.end().unwrap(); },
                             "stop" => { packet_snooper.stop().unwrap(); },
+                            command if command.starts_with("filter ") => {
+                                if let Err(e) = packet_snooper.swap_packet_filter(cmd["filter ".len()..].trim()) { println!("{}. Retry. Press any key to continue.", e); wait_for_key_press(); }
+                            },
+                            "filter" => { packet_snooper.swap_packet_filter("").unwrap(); },
+                            command if command.starts_with("format ") => {
+                                if let Err(e) = packet_snooper.swap_report_format(cmd["format ".len()..].trim()) { println!("{}. Retry. Press any key to continue.", e); wait_for_key_press(); }
+                            },
+                            command if command.starts_with("interval ") => {
+                                let res = match cmd["interval ".len()..].trim().parse::<u64>() {
+                                    Ok(time_interval) => packet_snooper.swap_time_interval(time_interval),
+                                    Err(_) => Err(PSError::new("Invalid time interval given as a parameter.")),
+                                };
+                                if let Err(e) = res { println!("{}. Retry. Press any key to continue.", e); wait_for_key_press(); }
+                            },
                             "exit" => { return; }
                             _ => { println!("Invalid command. Retry. Press any key to continue"); wait_for_key_press(); }
                         };
@@ -116,6 +155,20 @@ fn main() {
                             "abort" => { packet_snooper.abort().unwrap(); },
                             "end" => { packet_snooper.end().unwrap(); },
                             "resume" => { packet_snooper.resume().unwrap(); },
+                            command if command.starts_with("filter ") => {
+                                if let Err(e) = packet_snooper.swap_packet_filter(cmd["filter ".len()..].trim()) { println!("{}. Retry. Press any key to continue.", e); wait_for_key_press(); }
+                            },
+                            "filter" => { packet_snooper.swap_packet_filter("").unwrap(); },
+                            command if command.starts_with("format ") => {
+                                if let Err(e) = packet_snooper.swap_report_format(cmd["format ".len()..].trim()) { println!("{}. Retry. Press any key to continue.", e); wait_for_key_press(); }
+                            },
+                            command if command.starts_with("interval ") => {
+                                let res = match cmd["interval ".len()..].trim().parse::<u64>() {
+                                    Ok(time_interval) => packet_snooper.swap_time_interval(time_interval),
+                                    Err(_) => Err(PSError::new("Invalid time interval given as a parameter.")),
+                                };
+                                if let Err(e) = res { println!("{}. Retry. Press any key to continue.", e); wait_for_key_press(); }
+                            },
                             "exit" => { return; }
                             _ => { println!("Invalid command. Retry. Press any key to continue"); wait_for_key_press(); }
                         };
@@ -190,7 +243,7 @@ fn print_report_format_menu() {
     print_main_menu();
     println!("Report format selection");
     println!("------------------------");
-    println!("Choose the format of the report (raw/verbose/report) :");
+    println!("Choose the format of the report (raw/verbose/report/csv/json/ndjson/html) :");
     print!(">>> ");
     io::stdout().flush().unwrap();
 }
@@ -200,8 +253,10 @@ fn print_packet_filter_menu() {
     println!("Packet filters selection");
     println!("------------------------");
     println!("Insert the filters that packets must satisfy :");
-    println!("(Filters accepted: IP address / port address / layer3 protocol / layer4 protocols / upper layer service) ");
-    println!("(separate each filter keyword with a space or press Enter to skip) : ");
+    println!("(Keywords accepted: IP address or CIDR block / MAC address / port / layer4 protocol / upper layer service / TCP flags (SYN, ACK, FIN, RST, ...))");
+    println!("(Fields accepted: ip.src, ip.dst, ip.addr, port, srcport, dstport, tcp.port, udp.port, proto, service, tcp.flags, len)");
+    println!("(Payload: payload contains \"text\" or hex bytes like 47:45:54, payload matches \"regex\")");
+    println!("(combine them with and/or/not and parentheses, e.g. service == https and not ip.dst in 203.0.113.0/24, or press Enter to skip) : ");
     print!(">>> ");
     io::stdout().flush().unwrap();
 }
@@ -211,6 +266,12 @@ fn print_ready_menu() {
     println!("Packet Snooper is ready");
     println!("- start");
     println!("- abort");
+    println!("- checksum-offload on/off (don't verify checksums of packets sent from this interface)");
+    println!("- flows unidirectional/bidirectional (one report row for each direction or for each conversation)");
+    println!("- flow-timeouts <idle> <active> (seconds after which a flow is ended, default 15 1800)");
+    println!("- services <file> (name the upper layer services also with a file in the /etc/services format)");
+    println!("- collect <address> (merge the flows of NetFlow/IPFIX/sFlow datagrams received on a local address, e.g. 0.0.0.0:6343)");
+    println!("- export <collector> <v5/v9/ipfix> (send the flows of each report to a NetFlow or IPFIX collector, e.g. 127.0.0.1:2055)");
     println!("- exit");
     println!("------------------------");
     println!("Type command :");
@@ -224,6 +285,9 @@ fn print_working_menu() {
     println!("- abort (back to configuration)");
     println!("- end (back to ready state)");
     println!("- stop");
+    println!("- filter <expression> (select the packets with a new filter, or every packet without one)");
+    println!("- format raw/verbose/report/csv/json/ndjson/html (report format of the packets from now on)");
+    println!("- interval <seconds> (time interval between two reports)");
     println!("- exit");
     println!("------------------------");
     println!("Type command :");
@@ -237,6 +301,9 @@ fn print_stopped_menu() {
     println!("- abort (back to configuration)");
     println!("- end (back to ready state)");
     println!("- resume");
+    println!("- filter <expression> (select the packets with a new filter, or every packet without one)");
+    println!("- format raw/verbose/report/csv/json/ndjson/html (report format of the packets from now on)");
+    println!("- interval <seconds> (time interval between two reports)");
     println!("- exit");
     println!("------------------------");
     println!("Type command :");
@@ -88,6 +88,7 @@
 //!                "abort" => { packet_snooper.abort().unwrap(); },
 //!                "end" => { packet_snooper.end().unwrap(); },
 //!                "stop" => { packet_snooper.stop().unwrap(); },
+//!                "format verbose" => { packet_snooper.swap_report_format("verbose").unwrap(); },  // also swap_packet_filter, swap_time_interval
 //!                "exit" => { return; }
 //!                _ => { println ! ("Invalid command"); },
 //!            }
@@ -127,13 +128,17 @@ use pcap::{Capture, Device, Packet};
 use std::{thread};
 use std::error::Error;
 use std::fs::OpenOptions;
-use std::path::{PathBuf};
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs, UdpSocket};
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Condvar, Mutex};
 use std::sync::mpsc::{channel, Receiver, Sender};
 use std::thread::{JoinHandle};
 use std::time::Duration;
+use crate::network_components::services_upper_layers::service_registry::load_services_file;
 use crate::network_components::layer_2::ethernet_packet::EthernetPacket;
-use crate::report_generator::{ReportFormat, ReportGenerator};
+use crate::report_generator::{FlowAggregation, ReportFormat, ReportGenerator};
+use crate::report_generator::packet_filter::PacketFilter;
+use crate::report_generator::flow_export::{FlowExport, FlowExportVersion};
 
 const CAPTURE_BUFFER_TIMEOUT_MS: i32 = 25;
 
@@ -187,6 +192,11 @@ pub enum State {
     Stopped,
 }
 
+/// Default flow idle timeout (in seconds)
+const DEFAULT_FLOW_IDLE_TIMEOUT: u64 = 15;
+/// Default flow active timeout (in seconds)
+const DEFAULT_FLOW_ACTIVE_TIMEOUT: u64 = 1800;
+
 #[derive(Debug, Clone)]
 /// Configuration Options for packet snooper framework.
 pub struct ConfigOptions {
@@ -200,6 +210,18 @@ pub struct ConfigOptions {
     pub report_format: ReportFormat,
     /// Packet filter
     pub packet_filter: String,
+    /// Checksum offload: checksums of packets sent from the capture interface are not verified
+    pub checksum_offload: bool,
+    /// Flow aggregation of the packets in the "report" format
+    pub flow_aggregation: FlowAggregation,
+    /// Flow idle timeout: a flow without packets for this time is ended
+    pub flow_idle_timeout: Duration,
+    /// Flow active timeout: a flow lasting more than this time is ended, and the following packets start a new flow
+    pub flow_active_timeout: Duration,
+    /// Flow export: collector receiving the flows of the aggregated formats as NetFlow or IPFIX records
+    pub flow_export: Option<FlowExport>,
+    /// Flow collector: local address receiving NetFlow/IPFIX/sFlow datagrams, whose flows are merged into the report
+    pub flow_collector: Option<SocketAddr>,
 }
 
 impl ConfigOptions {
@@ -211,6 +233,12 @@ impl ConfigOptions {
             file_path: PathBuf::from(file_path),
             report_format,
             packet_filter: packet_filter.to_string(),
+            checksum_offload: false,
+            flow_aggregation: FlowAggregation::Unidirectional,
+            flow_idle_timeout: Duration::from_secs(DEFAULT_FLOW_IDLE_TIMEOUT),
+            flow_active_timeout: Duration::from_secs(DEFAULT_FLOW_ACTIVE_TIMEOUT),
+            flow_export: None,
+            flow_collector: None,
         }
     }
 }
@@ -267,6 +295,8 @@ pub struct PacketSnooper {
     end_thread: Arc<Mutex<bool>>,
     network_capture_thread: Option<JoinHandle<()>>,
     consumer_thread: Option<JoinHandle<()>>,
+    /// Report generator of the running analysis, shared with the consumer thread
+    report_generator: Option<Arc<Mutex<ReportGenerator>>>,
 }
 
 impl PacketSnooper {
@@ -284,12 +314,19 @@ impl PacketSnooper {
                 file_path: PathBuf::from("output.txt"),
                 report_format: ReportFormat::Report,
                 packet_filter: String::new(),
+                checksum_offload: false,
+                flow_aggregation: FlowAggregation::Unidirectional,
+                flow_idle_timeout: Duration::from_secs(DEFAULT_FLOW_IDLE_TIMEOUT),
+                flow_active_timeout: Duration::from_secs(DEFAULT_FLOW_ACTIVE_TIMEOUT),
+                flow_export: None,
+                flow_collector: None,
             },
             stop_thread: Arc::new(Mutex::new(false)),
             stop_thread_cv: Arc::new(Condvar::new()),
             end_thread: Arc::new(Mutex::new(false)),
             network_capture_thread: None,
             consumer_thread: None,
+            report_generator: None,
         }
     }
 
@@ -437,6 +474,10 @@ impl PacketSnooper {
     /// Set *`report_format`* (as format of the packets in the report) inside PacketSnooper struct.
     /// It's part of the configuration phase.
     ///
+    /// The formats are `raw` and `verbose` (a dump of each packet), `report` (a table of the flows), `csv` (the flows as
+    /// comma-separated values), `json` (the flows as a JSON document), `ndjson` (a JSON line for each packet, with the
+    /// layers decoded) and `html` (a page with the flows, totals, breakdowns and a bandwidth chart, in a single file).
+    ///
     /// Transitions from ConfigFormat state to PacketFilter state.
     ///
     /// # Examples
@@ -466,14 +507,7 @@ impl PacketSnooper {
     /// ```
     pub fn set_report_format(&mut self, report_format: &str) -> Result<()>{
         if self.state == State::ReportFormat {
-            match report_format {
-                "raw" => { self.config_options.report_format = ReportFormat::Raw },
-                "verbose" => { self.config_options.report_format = ReportFormat::Verbose },
-                "report" => { self.config_options.report_format = ReportFormat::Report },
-                _ => {
-                    return Err(PSError::new("Invalid format name given as a parameter"))
-                }
-            }
+            self.config_options.report_format = PacketSnooper::parse_report_format(report_format)?;
             self.state = State::PacketFilter;
             Ok(())
         } else {
@@ -484,6 +518,14 @@ impl PacketSnooper {
     /// Set *`packet_filter`* (as selection of the packets in the report) inside PacketSnooper struct.
     /// It's part of the configuration phase.
     ///
+    /// The filter is a boolean expression of keywords (protocol, service and TCP flag names, ports, IP addresses and CIDR
+    /// blocks) and field comparisons (`ip.src == 10.0.0.1`, `tcp.port in 80..90`), combined with `and`, `or`, `not` and
+    /// parentheses. The TCP/UDP payload is searched with `payload contains "text"` or hex bytes, and `payload matches "regex"`:
+    /// see [`report_generator::packet_filter`] for the whole syntax. An empty filter selects every packet.
+    ///
+    /// The summaries of the report (DNS, DHCP, HTTP, TLS and QUIC) and the TCP connection states come from the selected
+    /// packets only, while the flow records of the NetFlow/IPFIX/sFlow datagrams captured are selected one by one.
+    ///
     /// Transitions from PacketFilter state to Ready state.
     /// PacketSnooper is now configured and ready to analyze network traffic
     ///
@@ -496,11 +538,14 @@ impl PacketSnooper {
     /// ```
     /// ```
     /// packet_snooper.set_packet_filter("TCP").unwrap();
+    /// packet_snooper.set_packet_filter("service == https and not ip.dst in 203.0.113.0/24").unwrap();
+    /// packet_snooper.set_packet_filter("tcp.port == 8080 and payload contains \"ORD-004711\"").unwrap();
     /// ```
     ///
     /// # Error
     ///
     /// - `Invalid format given as a parameter`
+    /// - `Invalid packet filter given as a parameter (...)`, with the first problem found in the filter expression
     /// - `Invalid call on set_packet_filter when in an illegal state`
     ///
     /// Handling error cases:
@@ -514,7 +559,7 @@ impl PacketSnooper {
     /// ```
     pub fn set_packet_filter(&mut self, packet_filter: &str) -> Result<()>{
         if self.state == State::PacketFilter {
-            if !packet_filter.is_ascii() { return Err(PSError::new("Invalid format given as a parameter.")); }
+            PacketSnooper::parse_packet_filter(packet_filter)?;
             self.config_options.packet_filter = packet_filter.to_string();
             self.state = State::Ready;
             Ok(())
@@ -523,6 +568,234 @@ impl PacketSnooper {
         }
     }
 
+    /// Set *`checksum_offload`* inside PacketSnooper struct.
+    /// It's an optional configuration option, available until network traffic analysis is started.
+    ///
+    /// When enabled, checksums of packets sent from the addresses of the capture interface are not verified:
+    /// with checksum offload they are computed by the network card after the packet is captured.
+    ///
+    /// # Examples
+    ///
+    /// Simplified call (without error handling)
+    /// ```
+    /// packet_snooper.set_checksum_offload(true).unwrap();
+    /// ```
+    ///
+    /// # Error
+    ///
+    /// - `Invalid call on set_checksum_offload when in an illegal state`
+    ///
+    /// Handling error cases:
+    /// ```
+    /// match packet_snooper.set_checksum_offload(true) {
+    ///     Ok(_) => (),
+    ///     Err(e) => { println!("{}", e); },
+    /// }
+    /// ```
+    pub fn set_checksum_offload(&mut self, checksum_offload: bool) -> Result<()> {
+        if self.state == State::Working || self.state == State::Stopped {
+            return Err(PSError::new("Invalid call on set_checksum_offload when in an illegal state."));
+        }
+        self.config_options.checksum_offload = checksum_offload;
+        Ok(())
+    }
+
+    /// Set *`flow_aggregation`* (as aggregation of the packets in the "report" format) inside PacketSnooper struct.
+    /// It's an optional configuration option, available until network traffic analysis is started.
+    ///
+    /// - `unidirectional` (default): one row for each direction of a conversation
+    /// - `bidirectional`: one row for each conversation, oriented from the client to the server, with per-direction bytes and packets
+    ///
+    /// # Examples
+    ///
+    /// Simplified call (without error handling)
+    /// ```
+    /// packet_snooper.set_flow_aggregation("bidirectional").unwrap();
+    /// ```
+    ///
+    /// # Error
+    ///
+    /// - `Invalid flow aggregation name given as a parameter`
+    /// - `Invalid call on set_flow_aggregation when in an illegal state`
+    ///
+    /// Handling error cases:
+    /// ```
+    /// match packet_snooper.set_flow_aggregation("bidirectional") {
+    ///     Ok(_) => (),
+    ///     Err(e) => { println!("{}", e); },
+    /// }
+    /// ```
+    pub fn set_flow_aggregation(&mut self, flow_aggregation: &str) -> Result<()> {
+        if self.state == State::Working || self.state == State::Stopped {
+            return Err(PSError::new("Invalid call on set_flow_aggregation when in an illegal state."));
+        }
+        match flow_aggregation {
+            "unidirectional" => { self.config_options.flow_aggregation = FlowAggregation::Unidirectional },
+            "bidirectional" => { self.config_options.flow_aggregation = FlowAggregation::Bidirectional },
+            _ => {
+                return Err(PSError::new("Invalid flow aggregation name given as a parameter."))
+            }
+        }
+        Ok(())
+    }
+
+    /// Set *`flow_idle_timeout`* and *`flow_active_timeout`* (in seconds) of the flows in the "report" format inside PacketSnooper struct.
+    /// It's an optional configuration option, available until network traffic analysis is started.
+    ///
+    /// A flow is ended when it has no packets for the idle timeout (default 15 seconds) or when it lasts more than the active
+    /// timeout (default 1800 seconds): in the latter case the following packets start a new flow.
+    ///
+    /// # Examples
+    ///
+    /// Simplified call (without error handling)
+    /// ```
+    /// packet_snooper.set_flow_timeouts(30, 600).unwrap();
+    /// ```
+    ///
+    /// # Error
+    ///
+    /// - `Invalid flow timeouts given as a parameter`
+    /// - `Invalid call on set_flow_timeouts when in an illegal state`
+    ///
+    /// Handling error cases:
+    /// ```
+    /// match packet_snooper.set_flow_timeouts(30, 600) {
+    ///     Ok(_) => (),
+    ///     Err(e) => { println!("{}", e); },
+    /// }
+    /// ```
+    pub fn set_flow_timeouts(&mut self, idle_timeout: u64, active_timeout: u64) -> Result<()> {
+        if self.state == State::Working || self.state == State::Stopped {
+            return Err(PSError::new("Invalid call on set_flow_timeouts when in an illegal state."));
+        }
+        if idle_timeout == 0 || active_timeout < idle_timeout {
+            return Err(PSError::new("Invalid flow timeouts given as a parameter."));
+        }
+        self.config_options.flow_idle_timeout = Duration::from_secs(idle_timeout);
+        self.config_options.flow_active_timeout = Duration::from_secs(active_timeout);
+        Ok(())
+    }
+
+    /// Load *`services_file`* (a file in the `/etc/services` format) into the service registry.
+    /// It's an optional configuration option, available until network traffic analysis is started.
+    ///
+    /// The services in the file extend the built-in IANA assignments used to name the upper layer services, overriding them
+    /// on the same port and transport (e.g. `postgres 5433/tcp`). Names are shown in upper case.
+    ///
+    /// The registry is shared by the whole process and can only be extended: the services stay loaded after the
+    /// PacketSnooper is dropped, and every PacketSnooper of the process names the services with them.
+    ///
+    /// # Examples
+    ///
+    /// Simplified call (without error handling)
+    /// ```
+    /// packet_snooper.set_services_file("/etc/services").unwrap();
+    /// ```
+    ///
+    /// # Error
+    ///
+    /// - `Invalid services file given as a parameter`
+    /// - `Invalid call on set_services_file when in an illegal state`
+    ///
+    /// Handling error cases:
+    /// ```
+    /// match packet_snooper.set_services_file("/etc/services") {
+    ///     Ok(_) => (),
+    ///     Err(e) => { println!("{}", e); },
+    /// }
+    /// ```
+    pub fn set_services_file(&mut self, services_file: &str) -> Result<()> {
+        if self.state == State::Working || self.state == State::Stopped {
+            return Err(PSError::new("Invalid call on set_services_file when in an illegal state."));
+        }
+        if let Err(e) = load_services_file(Path::new(services_file)) {
+            return Err(PSError::new(format!("Invalid services file given as a parameter ({}).", e).as_str()));
+        }
+        Ok(())
+    }
+
+    /// Set *`flow_export`* (collector address and NetFlow/IPFIX version) inside PacketSnooper struct.
+    /// It's an optional configuration option, available until network traffic analysis is started.
+    ///
+    /// At each report generation of an aggregated format ("report", "csv", "json" or "html"), the flows of the interval are
+    /// sent over UDP to the collector as NetFlow v5 (`v5`), NetFlow v9 (`v9`) or IPFIX (`ipfix`) records, with the
+    /// packets and bytes of the interval. Templates of NetFlow v9 and IPFIX are sent in every datagram.
+    ///
+    /// # Examples
+    ///
+    /// Simplified call (without error handling)
+    /// ```
+    /// packet_snooper.set_flow_export("127.0.0.1:2055", "ipfix").unwrap();
+    /// ```
+    ///
+    /// # Error
+    ///
+    /// - `Invalid flow export collector given as a parameter`
+    /// - `Invalid flow export version given as a parameter`
+    /// - `Invalid call on set_flow_export when in an illegal state`
+    ///
+    /// Handling error cases:
+    /// ```
+    /// match packet_snooper.set_flow_export("127.0.0.1:2055", "ipfix") {
+    ///     Ok(_) => (),
+    ///     Err(e) => { println!("{}", e); },
+    /// }
+    /// ```
+    pub fn set_flow_export(&mut self, collector: &str, version: &str) -> Result<()> {
+        if self.state == State::Working || self.state == State::Stopped {
+            return Err(PSError::new("Invalid call on set_flow_export when in an illegal state."));
+        }
+        let collector = match collector.to_socket_addrs().ok().and_then(|mut addresses| addresses.next()) {
+            Some(collector) => collector,
+            None => return Err(PSError::new("Invalid flow export collector given as a parameter.")),
+        };
+        let version = match FlowExportVersion::parse(version) {
+            Some(version) => version,
+            None => return Err(PSError::new("Invalid flow export version given as a parameter.")),
+        };
+        self.config_options.flow_export = Some(FlowExport { collector, version });
+        Ok(())
+    }
+
+    /// Set *`flow_collector`* (local address receiving NetFlow/IPFIX/sFlow datagrams) inside PacketSnooper struct.
+    /// It's an optional configuration option, available until network traffic analysis is started.
+    ///
+    /// The flow records of the NetFlow v5/v9, IPFIX and sFlow v5 datagrams received on the address are merged into the
+    /// aggregated formats ("report", "csv", "json" or "html") with the packets captured, selected by the same packet filter.
+    /// The datagrams captured on their ports (2055, 4739 and 6343) are decoded anyway, except the ones sent to this address.
+    ///
+    /// # Examples
+    ///
+    /// Simplified call (without error handling)
+    /// ```
+    /// packet_snooper.set_flow_collector("0.0.0.0:6343").unwrap();
+    /// ```
+    ///
+    /// # Error
+    ///
+    /// - `Invalid flow collector address given as a parameter`
+    /// - `Invalid call on set_flow_collector when in an illegal state`
+    ///
+    /// Handling error cases:
+    /// ```
+    /// match packet_snooper.set_flow_collector("0.0.0.0:6343") {
+    ///     Ok(_) => (),
+    ///     Err(e) => { println!("{}", e); },
+    /// }
+    /// ```
+    pub fn set_flow_collector(&mut self, address: &str) -> Result<()> {
+        if self.state == State::Working || self.state == State::Stopped {
+            return Err(PSError::new("Invalid call on set_flow_collector when in an illegal state."));
+        }
+        // the address is bound when the analysis is started, it must be available
+        let address = address.to_socket_addrs().ok().and_then(|mut addresses| addresses.next());
+        match address.filter(|address| UdpSocket::bind(address).is_ok()) {
+            Some(address) => self.config_options.flow_collector = Some(address),
+            None => return Err(PSError::new("Invalid flow collector address given as a parameter.")),
+        }
+        Ok(())
+    }
+
     /// *`start`* network traffic analysis inside PacketSnooper framework.
     ///
     /// Transitions from Ready state to Working state, spawning a worker thread able to capture network traffic and a consumer thread in
@@ -552,18 +825,22 @@ impl PacketSnooper {
         *self.stop_thread.lock().unwrap() = false;
         *self.end_thread.lock().unwrap() = false;
 
+        let report_generator = match ReportGenerator::new(self.config_options.clone(), self.stop_thread.clone(), self.stop_thread_cv.clone()) {
+            Ok(report_generator) => Arc::new(Mutex::new(report_generator)),
+            Err(e) => { return Err(PSError::new(e.message.as_str())); }
+        };
+        self.report_generator = Some(report_generator.clone());
         let ( tx, rx ) = channel();
 
         self.network_capture_thread = Option::from(thread::spawn(PacketSnooper::network_analysis(
             self.config_options.current_interface.clone(),
+            self.config_options.checksum_offload,
             self.stop_thread.clone(),
             self.stop_thread_cv.clone(),
             self.end_thread.clone(),
             tx)));
         self.consumer_thread = Option::from(thread::spawn(PacketSnooper::consume_packets(
-            self.config_options.clone(),
-            self.stop_thread.clone(),
-            self.stop_thread_cv.clone(),
+            report_generator,
             Box::new(rx))));
 
         self.state = State::Working;
@@ -664,6 +941,7 @@ impl PacketSnooper {
 
         self.network_capture_thread.take().map(JoinHandle::join);
         self.consumer_thread.take().map(JoinHandle::join);
+        self.report_generator = None;
 
         self.state = State::Ready;
         Ok(())
@@ -714,11 +992,95 @@ impl PacketSnooper {
             },
             None => (),
         };
+        self.report_generator = None;
 
         self.state = State::ConfigDevice;
         Ok(())
     }
 
+    /// Swap the *`packet_filter`* of the running network traffic analysis.
+    /// The packets captured from now on are selected by the new filter, the data accumulated so far is kept.
+    ///
+    /// Available in Working and Stopped states, without any state transition.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// packet_snooper.swap_packet_filter("tcp.port == 443 and not ip.dst in 203.0.113.0/24").unwrap();
+    /// ```
+    ///
+    /// # Error
+    ///
+    /// - `Invalid format given as a parameter`
+    /// - `Invalid packet filter given as a parameter (...)`, with the first problem found in the filter expression
+    /// - `Invalid call on swap_packet_filter when in an illegal state`
+    pub fn swap_packet_filter(&mut self, packet_filter: &str) -> Result<()> {
+        if self.state != State::Working && self.state != State::Stopped { return Err(PSError::new("Invalid call on swap_packet_filter when in an illegal state.")); }
+
+        let filter = PacketSnooper::parse_packet_filter(packet_filter)?;
+        if let Some(report_generator) = self.report_generator.as_ref() {
+            report_generator.lock().unwrap().set_packet_filter(filter);
+        }
+        self.config_options.packet_filter = packet_filter.to_string();
+        Ok(())
+    }
+
+    /// Swap the *`report_format`* of the running network traffic analysis.
+    /// When switching from or to a packet dump (raw/verbose/ndjson), the data accumulated in the current interval is written
+    /// right away as a report in the previous format.
+    ///
+    /// Available in Working and Stopped states, without any state transition.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// packet_snooper.swap_report_format("verbose").unwrap();
+    /// ```
+    ///
+    /// # Error
+    ///
+    /// - `Invalid format name given as a parameter`
+    /// - `Invalid call on swap_report_format when in an illegal state`
+    /// - `Unable to write the report in the previous format`
+    pub fn swap_report_format(&mut self, report_format: &str) -> Result<()> {
+        if self.state != State::Working && self.state != State::Stopped { return Err(PSError::new("Invalid call on swap_report_format when in an illegal state.")); }
+
+        let report_format = PacketSnooper::parse_report_format(report_format)?;
+        if let Some(report_generator) = self.report_generator.as_ref() {
+            report_generator.lock().unwrap().set_report_format(report_format.clone())
+                .map_err(|e| PSError::new(format!("Unable to write the report in the previous format ({}).", e.message).as_str()))?;
+        }
+        self.config_options.report_format = report_format;
+        Ok(())
+    }
+
+    /// Swap the *`time_interval`* (in seconds) of the report generation of the running network traffic analysis.
+    /// The next report is generated when the new interval has elapsed since the last one (right away if it already has).
+    ///
+    /// Available in Working and Stopped states, without any state transition.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// packet_snooper.swap_time_interval(30).unwrap();
+    /// ```
+    ///
+    /// # Error
+    ///
+    /// - `Invalid time interval given as a parameter`
+    /// - `Invalid call on swap_time_interval when in an illegal state`
+    pub fn swap_time_interval(&mut self, time_interval: u64) -> Result<()> {
+        if self.state != State::Working && self.state != State::Stopped { return Err(PSError::new("Invalid call on swap_time_interval when in an illegal state.")); }
+        if time_interval == 0 { return Err(PSError::new("Invalid time interval given as a parameter.")); }
+
+        let time_interval = Duration::from_secs(time_interval);
+        if let Some(report_generator) = self.report_generator.as_ref() {
+            report_generator.lock().unwrap().set_time_interval(time_interval);
+        }
+        self.config_options.time_interval = time_interval;
+        Ok(())
+    }
+
     /// Retrieves device from an interface name
     fn retrieve_device(interface_name: &str) -> Result<Device> {
         for device in Device::list().unwrap() {
@@ -740,13 +1102,22 @@ impl PacketSnooper {
     }
 
     /// Network Analysis Thread for collecting packets from an interface
-    fn network_analysis(interface_name: String, stop_thread: Arc<Mutex<bool>>, stop_thread_cv: Arc<Condvar>, end_thread: Arc<Mutex<bool>>, tx: Sender<String>) -> impl FnOnce() -> () {
+    fn network_analysis(interface_name: String, checksum_offload: bool, stop_thread: Arc<Mutex<bool>>, stop_thread_cv: Arc<Condvar>, end_thread: Arc<Mutex<bool>>, tx: Sender<String>) -> impl FnOnce() -> () {
         let mut cap = Capture::from_device(interface_name.as_str()).unwrap()
                 .promisc(true)
                 .timeout(CAPTURE_BUFFER_TIMEOUT_MS)
                 .open().unwrap()
                 .setnonblock().unwrap();
 
+        // with checksum offload, checksums of packets sent from the capture interface are not verified
+        let offload_addresses: Vec<IpAddr> = if checksum_offload {
+            PacketSnooper::retrieve_device(interface_name.as_str())
+                .map(|device| device.addresses.iter().map(|address| address.addr).collect())
+                .unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+
         move || {
             loop {
                 let mut stop_flag = *stop_thread.lock().unwrap();
@@ -764,28 +1135,53 @@ impl PacketSnooper {
                 }
                 if let Ok(packet) = cap.next() {
                     if *end_thread.lock().unwrap() == false && *stop_thread.lock().unwrap() == false {
-                            tx.send(PacketSnooper::decode_packet(packet).to_json()).unwrap();
+                            tx.send(PacketSnooper::decode_packet(packet, &offload_addresses).to_json()).unwrap();
                     }
                 }
             }
         }
     }
 
-    /// Consumer thread. Receives packets from the Analyzer thread and generates a report periodically.
-    fn consume_packets(config_options: ConfigOptions, stop_thread: Arc<Mutex<bool>>, stop_thread_cv: Arc<Condvar>, rx: Box<Receiver<String>>) -> impl FnOnce() -> () {
+    /// Consumer thread. Receives packets from the Analyzer thread and pushes them into the report generator, that generates a report periodically.
+    fn consume_packets(report_generator: Arc<Mutex<ReportGenerator>>, rx: Box<Receiver<String>>) -> impl FnOnce() -> () {
         move || {
-            let mut report_generator = ReportGenerator::new(config_options, stop_thread, stop_thread_cv).expect("Something went wrong");
-
             while let Ok(packet) = rx.recv() {
-                report_generator.push(&packet);
+                report_generator.lock().unwrap().push(&packet);
             }
         }
     }
 
+    /// Parses the name of a report format
+    fn parse_report_format(report_format: &str) -> Result<ReportFormat> {
+        match report_format {
+            "raw" => Ok(ReportFormat::Raw),
+            "verbose" => Ok(ReportFormat::Verbose),
+            "report" => Ok(ReportFormat::Report),
+            "csv" => Ok(ReportFormat::Csv),
+            "json" => Ok(ReportFormat::Json),
+            "ndjson" => Ok(ReportFormat::Ndjson),
+            "html" => Ok(ReportFormat::Html),
+            _ => Err(PSError::new("Invalid format name given as a parameter")),
+        }
+    }
+
+    /// Parses a packet filter expression, with the first problem found if it's invalid
+    fn parse_packet_filter(packet_filter: &str) -> Result<PacketFilter> {
+        if !packet_filter.is_ascii() { return Err(PSError::new("Invalid format given as a parameter.")); }
+        PacketFilter::parse(packet_filter)
+            .map_err(|e| PSError::new(format!("Invalid packet filter given as a parameter ({}).", e.message).as_str()))
+    }
+
     /// Decode using the TCP/IP stack standard from a packet (vector of bytes)
-    fn decode_packet(packet: Packet) -> EthernetPacket {
+    fn decode_packet(packet: Packet, offload_addresses: &[IpAddr]) -> EthernetPacket {
         let data = packet.data;
-        EthernetPacket::new(data)
+        let mut ethernet_packet = EthernetPacket::new(data);
+        if let Some(ip_addr_src) = ethernet_packet.ip_addr_src() {
+            if offload_addresses.contains(&ip_addr_src) {
+                ethernet_packet.set_checksum_offloaded();
+            }
+        }
+        ethernet_packet
     }
 }
 
@@ -0,0 +1,126 @@
+//! # Checksum
+//!
+//! Internet checksum (RFC 1071) computation and verification for IPv4 headers and for TCP/UDP/ICMP segments,
+//! including the IPv4/IPv6 pseudo-headers used by Layer 4 protocols.
+//!
+
+use std::fmt::{Display, Formatter};
+use std::net::IpAddr;
+use serde::{Serialize, Deserialize};
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ChecksumStatus {
+    /// Checksum verified and correct.
+    Valid,
+    /// Checksum verified and wrong.
+    Invalid,
+    /// Checksum not verified (truncated capture, fragment, checksum not used or offloaded to the NIC).
+    Unverified,
+}
+
+impl Display for ChecksumStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChecksumStatus::Valid => { write!(f, "correct") },
+            ChecksumStatus::Invalid => { write!(f, "BAD CHECKSUM") },
+            ChecksumStatus::Unverified => { write!(f, "unverified") },
+        }
+    }
+}
+
+impl ChecksumStatus {
+    /// Worst status between two layers (Invalid wins over Valid, Valid wins over Unverified).
+    pub fn combine(self, other: ChecksumStatus) -> ChecksumStatus {
+        match (self, other) {
+            (ChecksumStatus::Invalid, _) | (_, ChecksumStatus::Invalid) => ChecksumStatus::Invalid,
+            (ChecksumStatus::Valid, _) | (_, ChecksumStatus::Valid) => ChecksumStatus::Valid,
+            _ => ChecksumStatus::Unverified,
+        }
+    }
+}
+
+/// Suffix shown in Verbose output next to a checksum field (only bad checksums are marked).
+pub fn checksum_mark(status: ChecksumStatus) -> &'static str {
+    match status {
+        ChecksumStatus::Invalid => " [BAD CHECKSUM]",
+        _ => "",
+    }
+}
+
+fn ones_complement_sum(mut sum: u32, data_in_u8: &[u8]) -> u32 {
+    let mut chunks = data_in_u8.chunks_exact(2);
+    for chunk in chunks.by_ref() {
+        sum += u16::from_be_bytes([chunk[0], chunk[1]]) as u32;
+    }
+    if let [last] = chunks.remainder() {
+        sum += (*last as u32) << 8;
+    }
+    while sum > 0xFFFF {
+        sum = (sum & 0xFFFF) + (sum >> 16);
+    }
+    sum
+}
+
+fn pseudo_header_sum(ip_addr_src: IpAddr, ip_addr_dst: IpAddr, protocol: u8, length: usize) -> u32 {
+    let mut sum = 0;
+    match (ip_addr_src, ip_addr_dst) {
+        (IpAddr::V4(src), IpAddr::V4(dst)) => {
+            sum = ones_complement_sum(sum, &src.octets());
+            sum = ones_complement_sum(sum, &dst.octets());
+            sum = ones_complement_sum(sum, &[0, protocol]);
+            sum = ones_complement_sum(sum, &(length as u16).to_be_bytes());
+        },
+        (src, dst) => {
+            sum = ones_complement_sum(sum, &to_ipv6_octets(src));
+            sum = ones_complement_sum(sum, &to_ipv6_octets(dst));
+            sum = ones_complement_sum(sum, &(length as u32).to_be_bytes());
+            sum = ones_complement_sum(sum, &[0, 0, 0, protocol]);
+        }
+    }
+    sum
+}
+
+fn to_ipv6_octets(ip_addr: IpAddr) -> [u8; 16] {
+    match ip_addr {
+        IpAddr::V4(ip) => ip.to_ipv6_mapped().octets(),
+        IpAddr::V6(ip) => ip.octets(),
+    }
+}
+
+/// Internet checksum of a sequence of bytes (the value to be written in a checksum field set to zero).
+pub fn internet_checksum(data_in_u8: &[u8]) -> u16 {
+    !(ones_complement_sum(0, data_in_u8) as u16)
+}
+
+/// Internet checksum of a Layer 4 segment, including the IPv4/IPv6 pseudo-header.
+pub fn transport_checksum(ip_addr_src: IpAddr, ip_addr_dst: IpAddr, protocol: u8, segment_in_u8: &[u8]) -> u16 {
+    let sum = pseudo_header_sum(ip_addr_src, ip_addr_dst, protocol, segment_in_u8.len());
+    !(ones_complement_sum(sum, segment_in_u8) as u16)
+}
+
+/// Verifies an IPv4 header (checksum field included).
+pub fn verify_ipv4_header(header_in_u8: &[u8]) -> ChecksumStatus {
+    if header_in_u8.len() < 20 {
+        return ChecksumStatus::Unverified;
+    }
+    match internet_checksum(header_in_u8) {
+        0 => ChecksumStatus::Valid,
+        _ => ChecksumStatus::Invalid,
+    }
+}
+
+/// Verifies a Layer 4 segment (checksum field included) using the pseudo-header built from the IP addresses.
+pub fn verify_transport(ip_addr_src: IpAddr, ip_addr_dst: IpAddr, protocol: u8, segment_in_u8: &[u8]) -> ChecksumStatus {
+    match transport_checksum(ip_addr_src, ip_addr_dst, protocol, segment_in_u8) {
+        0 => ChecksumStatus::Valid,
+        _ => ChecksumStatus::Invalid,
+    }
+}
+
+/// Verifies a segment protected by a checksum without pseudo-header (ICMPv4).
+pub fn verify(segment_in_u8: &[u8]) -> ChecksumStatus {
+    match internet_checksum(segment_in_u8) {
+        0 => ChecksumStatus::Valid,
+        _ => ChecksumStatus::Invalid,
+    }
+}
@@ -1,12 +1,26 @@
+use std::net::IpAddr;
 use chrono::{DateTime, Utc};
+use crate::network_components::checksum::ChecksumStatus;
 use crate::network_components::layer_2::mac_address::MacAddress;
+use crate::network_components::layer_3::ip_protocol_name;
 use crate::network_components::layer_3::ipv4_packet::{IPv4Packet, Ipv4ProtocolType};
 use crate::network_components::layer_3::ipv6_packet::{Ipv6NextHeader, IPv6Packet};
+use crate::network_components::layer_4::tcp_packet::TcpPacket;
+use crate::network_components::layer_4::upd_packet::UdpPacket;
 use serde::{Serialize, Deserialize};
-use crate::network_components::services_upper_layers::upper_layer_services::{known_port, UpperLayerService};
+use crate::network_components::services_upper_layers::upper_layer_services::UpperLayerService;
 use crate::report_generator::{DisplayAs, ReportDataInfo};
+use crate::report_generator::json_format::JsonPacket;
 use crate::ReportFormat;
 
+/// IP protocol numbers of the segments keyed by ports in the report
+const IP_PROTOCOL_TCP: u8 = 6;
+const IP_PROTOCOL_UDP: u8 = 17;
+/// IPv6 Hop by Hop extension header
+const IPV6_HOP_BY_HOP: u8 = 0;
+/// Placeholder of the report columns without a value (service of non TCP/UDP packets, addresses of the `OTHER` bucket)
+pub(crate) const NO_SERVICE: &str = "-";
+
 #[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
 pub enum EtherType {
     Ethernet802_3,
@@ -24,6 +38,9 @@ pub struct EthernetPacket {
     pub size: usize,
     pub timestamp_recv: DateTime<Utc>,
     pub report_data: Option<ReportDataInfo>,
+    /// Packet sent from the capture host with checksum offload enabled (checksums not verified)
+    #[serde(default)]
+    pub checksum_offloaded: bool,
 }
 
 impl EthernetPacket {
@@ -36,8 +53,9 @@ impl EthernetPacket {
             size: ether_data_in_u8.len(),
             timestamp_recv: Utc::now(),
             report_data: None,
+            checksum_offloaded: false,
         };
-        ethernet_packet.report_data = ethernet_packet.report_data();
+        ethernet_packet.report_data = ethernet_packet.report_data(&ether_data_in_u8[12..14]);
         ethernet_packet
     }
 
@@ -49,122 +67,260 @@ impl EthernetPacket {
         serde_json::from_str(json)
     }
 
-    fn report_data(&self) -> Option<ReportDataInfo> {
-        #[allow(unused_assignments)]
-        let mut ip_src = String::new();
-        #[allow(unused_assignments)]
-        let mut ip_dst = String::new();
-        #[allow(unused_assignments)]
-        let mut port_src = 0;
-        #[allow(unused_assignments)]
-        let mut port_dst = 0;
-        let l4_protocol;
-        let upper_service;
+    /// Source IP address (IPv4/IPv6 packets only)
+    pub fn ip_addr_src(&self) -> Option<IpAddr> {
+        if !self.ip_header_complete() { return None; }
+        match self.ether_type {
+            Some(EtherType::IPV4) => { Some(IpAddr::V4(IPv4Packet::new(&self.payload).ip_addr_src)) },
+            Some(EtherType::IPV6) => { Some(IpAddr::V6(IPv6Packet::new(&self.payload).ip_addr_src)) },
+            _ => { None }
+        }
+    }
+
+    /// IP addresses and TCP segment (without Ethernet padding) of TCP packets. None for fragments, truncated or malformed segments.
+    pub fn tcp_packet(&self) -> Option<(IpAddr, IpAddr, TcpPacket)> {
+        let (ip_addr_src, ip_addr_dst, segment) = self.l4_segment(Ipv4ProtocolType::TCP, Ipv6NextHeader::TCP)?;
+        let data_offset = segment.get(12).map_or(0, |data_offset| (data_offset >> 4) as usize * 4);
+        match 20 <= data_offset && data_offset <= segment.len() {
+            true => Some((ip_addr_src, ip_addr_dst, TcpPacket::new(&segment))),
+            false => None,
+        }
+    }
+
+    /// IP addresses and UDP datagram (without Ethernet padding) of UDP packets. None for fragments, truncated or malformed datagrams.
+    pub fn udp_packet(&self) -> Option<(IpAddr, IpAddr, UdpPacket)> {
+        let (ip_addr_src, ip_addr_dst, segment) = self.l4_segment(Ipv4ProtocolType::UDP, Ipv6NextHeader::UDP)?;
+        match segment.len() >= 8 {
+            true => Some((ip_addr_src, ip_addr_dst, UdpPacket::new(&segment))),
+            false => None,
+        }
+    }
+
+    /// Payload of the TCP segment or UDP datagram carried by the packet, empty for the other packets.
+    pub fn l4_payload(&self) -> Vec<u8> {
+        if let Some((_, _, tcp_packet)) = self.tcp_packet() {
+            return tcp_packet.payload;
+        }
+        match self.udp_packet() {
+            Some((_, _, udp_packet)) => udp_packet.payload,
+            None => Vec::new(),
+        }
+    }
 
+    fn l4_segment(&self, ipv4_protocol: Ipv4ProtocolType, ipv6_next_header: Ipv6NextHeader) -> Option<(IpAddr, IpAddr, Vec<u8>)> {
+        if !self.ip_header_complete() { return None; }
         match self.ether_type {
-            Some(EtherType::Ethernet802_3) => { return None; },
             Some(EtherType::IPV4) => {
                 let ipv4_packet = IPv4Packet::new(&self.payload);
-                ip_src = ipv4_packet.ip_addr_src.to_string();
-                ip_dst = ipv4_packet.ip_addr_dst.to_string();
+                if ipv4_packet.protocol_type != Some(ipv4_protocol) { return None; }
+                Some((IpAddr::V4(ipv4_packet.ip_addr_src), IpAddr::V4(ipv4_packet.ip_addr_dst), Vec::from(ipv4_packet.l4_segment()?)))
+            },
+            Some(EtherType::IPV6) => {
+                let ipv6_packet = IPv6Packet::new(&self.payload);
+                let (next_header, segment) = ipv6_packet.l4_segment()?;
+                if next_header != Some(ipv6_next_header) { return None; }
+                Some((IpAddr::V6(ipv6_packet.ip_addr_src), IpAddr::V6(ipv6_packet.ip_addr_dst), Vec::from(segment)))
+            },
+            _ => { None }
+        }
+    }
 
-                (port_src, port_dst) = self.ports(&ipv4_packet.payload);
+    /// Marks the packet as sent with checksum offload: its checksums are computed by the NIC after the capture, so they're not verified.
+    pub fn set_checksum_offloaded(&mut self) {
+        self.checksum_offloaded = true;
+        if let Some(report_data) = self.report_data.as_mut() {
+            report_data.bad_checksum = false;
+        }
+    }
 
-                l4_protocol = self.l4_protocol(&self.payload);
-                if l4_protocol.is_none() { return None; }
+    /// Report data of the packet, decoded again when the packet doesn't carry it (e.g. sent without it as JSON): the report
+    /// data is what the packet filters are evaluated against, whatever the report format.
+    pub fn decoded_report_data(&self) -> ReportDataInfo {
+        if let Some(report_data) = &self.report_data {
+            return report_data.clone();
+        }
+        let ether_type_in_u8 = match self.ether_type {
+            Some(EtherType::IPV4) => [8, 0],
+            Some(EtherType::IPV6) => [134, 221],
+            Some(EtherType::ARP) => [8, 6],
+            Some(EtherType::Ethernet802_3) | None => [0, 0],
+        };
+        let report_data = self.report_data(&ether_type_in_u8).unwrap();
+        match self.ether_type {
+            // the value of an unknown EtherType isn't kept in the packet
+            None => EthernetPacket::other_bucket(report_data),
+            Some(_) => report_data,
+        }
+    }
 
-                upper_service = self.upper_layer_service(&ipv4_packet.payload);
-                if upper_service.is_none() { return None; }
+    /// Checksum status of the whole packet: invalid if the IPv4 header checksum or the TCP/UDP/ICMP checksum is wrong.
+    pub fn checksum_status(&self) -> ChecksumStatus {
+        if self.checksum_offloaded || !self.ip_header_complete() { return ChecksumStatus::Unverified; }
+        match self.ether_type {
+            Some(EtherType::IPV4) => {
+                let ipv4_packet = IPv4Packet::new(&self.payload);
+                ipv4_packet.checksum_status().combine(ipv4_packet.l4_checksum_status())
             },
-            Some(EtherType::IPV6) => {
-                let ipv6_packet = IPv6Packet::new(self.payload.as_slice());
-                ip_src = ipv6_packet.ip_addr_src.to_string();
-                ip_dst = ipv6_packet.ip_addr_src.to_string();
+            Some(EtherType::IPV6) => { IPv6Packet::new(&self.payload).l4_checksum_status() },
+            _ => { ChecksumStatus::Unverified }
+        }
+    }
 
-                (port_src, port_dst) = self.ports(&ipv6_packet.payload);
+    /// `Report Data` of the packet. TCP/UDP packets are keyed by IP addresses and ports, the other IP packets by IP addresses
+    /// and protocol, the frames without IP by MAC addresses and EtherType. The packets that can't be decoded share the
+    /// `OTHER` bucket, so that every packet captured is in the report.
+    fn report_data(&self, ether_type_in_u8: &[u8]) -> Option<ReportDataInfo> {
+        let mut report_data = ReportDataInfo {
+            ip_src: self.mac_addr_src.to_string(),
+            ip_dst: self.mac_addr_dst.to_string(),
+            port_src: 0,
+            port_dst: 0,
+            l4_protocol: String::new(),
+            upper_service: String::from(NO_SERVICE),
+            service_confidence: 0,
+            num_bytes: self.size,
+            timestamp_recv: self.timestamp_recv,
+            tcp_flags: Vec::new(),
+            bad_checksum: self.checksum_status() == ChecksumStatus::Invalid,
+        };
 
-                l4_protocol = self.l4_protocol(&self.payload);
-                if l4_protocol.is_none() { return None; }
+        match self.ether_type {
+            Some(EtherType::IPV4) | Some(EtherType::IPV6) => {
+                let (ip_addr_src, ip_addr_dst, protocol, segment) = match self.ip_protocol() {
+                    Some(ip_protocol) => ip_protocol,
+                    None => { return Some(EthernetPacket::other_bucket(report_data)); }
+                };
+                report_data.ip_src = ip_addr_src.to_string();
+                report_data.ip_dst = ip_addr_dst.to_string();
+                report_data.l4_protocol = ip_protocol_name(protocol);
 
-                upper_service = self.upper_layer_service(&ipv6_packet.payload);
-                if upper_service.is_none() { return None; }
+                let identification = match protocol {
+                    IP_PROTOCOL_TCP => {
+                        let data_offset = segment.get(12).map_or(0, |data_offset| (data_offset >> 4) as usize * 4);
+                        if data_offset < 20 || data_offset > segment.len() { return Some(EthernetPacket::other_bucket(report_data)); }
+                        let tcp_packet = TcpPacket::new(&segment);
+                        report_data.tcp_flags = tcp_packet.tcp_flags().names();
+                        Some((tcp_packet.src_port, tcp_packet.dst_port, tcp_packet.identification))
+                    },
+                    IP_PROTOCOL_UDP => {
+                        if segment.len() < 8 { return Some(EthernetPacket::other_bucket(report_data)); }
+                        let udp_packet = UdpPacket::new(&segment);
+                        Some((udp_packet.src_port, udp_packet.dst_port, udp_packet.identification))
+                    },
+                    _ => None,
+                };
+                if let Some((port_src, port_dst, identification)) = identification {
+                    report_data.port_src = port_src;
+                    report_data.port_dst = port_dst;
+                    report_data.upper_service = identification.name.unwrap_or(format!("{:?}", UpperLayerService::UNKNOWN));
+                    report_data.service_confidence = identification.confidence;
+                }
             },
-            Some(EtherType::ARP) => { return None; },
-            _ => { return None; }
+            Some(EtherType::Ethernet802_3) => {
+                report_data.l4_protocol = String::from("802.3");
+                report_data.upper_service = EthernetPacket::llc_name(&self.payload).to_string();
+            },
+            Some(EtherType::ARP) => { report_data.l4_protocol = String::from("ARP"); },
+            None => { report_data.l4_protocol = EthernetPacket::ether_type_name(u16::from_be_bytes([ether_type_in_u8[0], ether_type_in_u8[1]])); },
         };
-
-        Some(
-            ReportDataInfo {
-                ip_src, ip_dst,
-                port_src, port_dst,
-                l4_protocol: l4_protocol.unwrap(),
-                upper_service: upper_service.unwrap(),
-                num_bytes: self.size,
-                timestamp_recv: self.timestamp_recv,
-            } )
+        Some(report_data)
     }
 
-    fn to_ether_type(ether_type_in_u8: &[u8]) -> Option<EtherType> {
-        match ether_type_in_u8 {
-            [x, _] if *x <= 5 => { Some(EtherType::Ethernet802_3) },
-            [8, 0] => return Some(EtherType::IPV4),
-            [8, 6] => return Some(EtherType::ARP),
-            [134, 221] => return Some(EtherType::IPV6),
-            x => {
-                return {
-                    println!("no info on this protocol: {:?}", x);
-                    None
-                }
-            }
+    /// `OTHER` bucket of the report: packets that can't be decoded (truncated or malformed headers)
+    fn other_bucket(report_data: ReportDataInfo) -> ReportDataInfo {
+        ReportDataInfo {
+            ip_src: String::from(NO_SERVICE),
+            ip_dst: String::from(NO_SERVICE),
+            port_src: 0,
+            port_dst: 0,
+            l4_protocol: String::from("OTHER"),
+            upper_service: String::from(NO_SERVICE),
+            service_confidence: 0,
+            tcp_flags: Vec::new(),
+            ..report_data
         }
     }
 
-    fn ports(&self, payload_in_u8: &[u8] ) -> (u16, u16) {
-        let src_port = u16::from_be_bytes((&payload_in_u8[0..2]).try_into().unwrap());
-        let dst_port = u16::from_be_bytes((&payload_in_u8[2..4]).try_into().unwrap());
-        (src_port,dst_port)
+    /// Tells if the IPv4/IPv6 header is complete in the capture
+    fn ip_header_complete(&self) -> bool {
+        match self.ether_type {
+            Some(EtherType::IPV4) => {
+                let header_length = self.payload.first().map_or(0, |first| (first & 0x0F) as usize * 4);
+                header_length >= 20 && header_length <= self.payload.len()
+            },
+            Some(EtherType::IPV6) => { self.payload.len() >= 40 },
+            _ => { false }
+        }
     }
 
-    fn l4_protocol(&self, payload_in_u8: &[u8] ) -> Option<String> {
-        match self.ether_type.unwrap() {
-            EtherType::IPV4 => {
-                let ipv4_packet = IPv4Packet::new(&payload_in_u8);
-                match ipv4_packet.protocol_type {
-                    Some(Ipv4ProtocolType::TCP) => { Some("TCP".to_string()) },
-                    Some(Ipv4ProtocolType::UDP) => { Some("UDP".to_string()) }
-                    _ => { None }
-                }
+    /// IP addresses, protocol number (after the IPv6 Hop by Hop extension header) and payload of IPv4/IPv6 packets.
+    /// None if the headers are truncated.
+    pub fn ip_protocol(&self) -> Option<(IpAddr, IpAddr, u8, Vec<u8>)> {
+        if !self.ip_header_complete() { return None; }
+        match self.ether_type {
+            Some(EtherType::IPV4) => {
+                let ipv4_packet = IPv4Packet::new(&self.payload);
+                Some((IpAddr::V4(ipv4_packet.ip_addr_src), IpAddr::V4(ipv4_packet.ip_addr_dst), self.payload[9], ipv4_packet.payload))
             },
-            EtherType::IPV6 => {
-                let ipv6_packet = IPv6Packet::new(&payload_in_u8);
-                match ipv6_packet.next_header {
-                    Some(Ipv6NextHeader::TCP) => { Some("TCP".to_string()) },
-                    Some(Ipv6NextHeader::UDP) => { Some("UDP".to_string()) }
-                    Some(Ipv6NextHeader::IPv6HopByHopOption) => {
-                        match ipv6_packet.payload.get(0) {
-                            Some(x) => {
-                                let next_header = IPv6Packet::to_protocol_type(*x);
-                                match next_header {
-                                    Some(Ipv6NextHeader::UDP) => { Some("UDP".to_string()) },
-                                    Some(Ipv6NextHeader::TCP) => { Some("TCP".to_string()) },
-                                    _ => { None }
-                                }
-                            }, None => { None }
-                        }
-                    }
-                    _ => { None }
+            Some(EtherType::IPV6) => {
+                let ipv6_packet = IPv6Packet::new(&self.payload);
+                let (ip_addr_src, ip_addr_dst) = (IpAddr::V6(ipv6_packet.ip_addr_src), IpAddr::V6(ipv6_packet.ip_addr_dst));
+                match self.payload[6] {
+                    IPV6_HOP_BY_HOP => {
+                        let extension_length = (*ipv6_packet.payload.get(1)? as usize + 1) * 8;
+                        Some((ip_addr_src, ip_addr_dst, ipv6_packet.payload[0], Vec::from(ipv6_packet.payload.get(extension_length..)?)))
+                    },
+                    next_header => Some((ip_addr_src, ip_addr_dst, next_header, ipv6_packet.payload)),
                 }
             },
             _ => { None }
         }
     }
 
-    fn upper_layer_service(&self, payload_in_u8: &[u8] ) -> Option<String> {
-        let (port_src, port_dst) = self.ports(payload_in_u8);
-        match UpperLayerService::from(known_port(port_src, port_dst)) {
-            UpperLayerService::UNKNOWN => { None },
-            service => {
-                Some(format!("{:?}", service).to_string())
+    /// Protocol of an IEEE 802.3 frame from the LLC destination service access point
+    fn llc_name(payload_in_u8: &[u8]) -> &'static str {
+        match payload_in_u8.first() {
+            Some(0x42) => "STP",
+            Some(0xAA) => "SNAP",
+            Some(0xE0) => "IPX",
+            Some(0xF0) => "NETBIOS",
+            Some(0xFE) => "OSI",
+            _ => "LLC",
+        }
+    }
+
+    /// Name of the EtherTypes without a decoder (IEEE Registration Authority), `ETHERTYPE 0x....` if unknown
+    fn ether_type_name(ether_type: u16) -> String {
+        match ether_type {
+            0x0842 => "WAKE-ON-LAN",
+            0x8035 => "RARP",
+            0x8100 => "802.1Q",
+            0x8809 => "SLOW-PROTOCOLS",
+            0x8847 => "MPLS",
+            0x8848 => "MPLS-MULTICAST",
+            0x8863 => "PPPOE-DISCOVERY",
+            0x8864 => "PPPOE-SESSION",
+            0x888E => "EAPOL",
+            0x88A8 => "802.1AD",
+            0x88CC => "LLDP",
+            0x88E5 => "MACSEC",
+            0x88F7 => "PTP",
+            0x893A => "IEEE1905",
+            _ => { return format!("ETHERTYPE {:#06x}", ether_type); }
+        }.to_string()
+    }
+
+    fn to_ether_type(ether_type_in_u8: &[u8]) -> Option<EtherType> {
+        match ether_type_in_u8 {
+            [x, _] if *x <= 5 => { Some(EtherType::Ethernet802_3) },
+            [8, 0] => return Some(EtherType::IPV4),
+            [8, 6] => return Some(EtherType::ARP),
+            [134, 221] => return Some(EtherType::IPV6),
+            x => {
+                return {
+                    println!("no info on this protocol: {:?}", x);
+                    None
+                }
             }
         }
     }
@@ -179,15 +335,32 @@ impl DisplayAs for EthernetPacket {
         match report_format {
             ReportFormat::Raw => {
                 res.push_str("Ethernet ");
-                res.push_str( format!("{:?} ", self.ether_type.unwrap()).as_str());
+                match self.ether_type {
+                    Some(ether_type) => { res.push_str(format!("{:?} ", ether_type).as_str()) },
+                    // the value of an unknown EtherType is kept in the report data only, named or as a number
+                    None => { res.push_str(format!("{} ", self.report_data.as_ref().map_or("OTHER", |report_data| report_data.l4_protocol.as_str())).as_str()) },
+                };
 
                 match self.ether_type {
                     Some(EtherType::Ethernet802_3) => { res.push_str("Ethernet 802.3 : Unknown Details") },
-                    Some(EtherType::IPV4) => { res.push_str(format!("{:?}", IPv4Packet::new(self.payload.as_slice()).protocol_type.unwrap()).as_str()) },
-                    Some(EtherType::IPV6) => { res.push_str(format!("{:?}", IPv6Packet::new(self.payload.as_slice()).next_header.unwrap()).as_str()) },
+                    Some(EtherType::IPV4) => {
+                        match IPv4Packet::new(self.payload.as_slice()).protocol_type {
+                            Some(protocol_type) => { res.push_str(format!("{:?}", protocol_type).as_str()) },
+                            None => { res.push_str(format!("IP-PROTO {}", self.payload[9]).as_str()) },
+                        }
+                    },
+                    Some(EtherType::IPV6) => {
+                        match IPv6Packet::new(self.payload.as_slice()).next_header {
+                            Some(next_header) => { res.push_str(format!("{:?}", next_header).as_str()) },
+                            None => { res.push_str(format!("IP-PROTO {}", self.payload[6]).as_str()) },
+                        }
+                    },
                     Some(EtherType::ARP) => { res.push_str( "ARP      : Unknown Details") },
                     _ => { res.push_str("Other Protocol incapsulated in Ethernet frame (Unknown Protocol)") }
                 };
+                if self.checksum_status() == ChecksumStatus::Invalid {
+                    res.push_str(" [BAD CHECKSUM]");
+                }
                 res.push('\n');
                 res
             },
@@ -197,15 +370,28 @@ impl DisplayAs for EthernetPacket {
 
                 match self.ether_type {
                     Some(EtherType::Ethernet802_3) => { res.push_str("Ethernet 802.3 : Unknown Details") },
-                    Some(EtherType::IPV4) => { res.push_str(format!("{}", IPv4Packet::new(self.payload.as_slice())).as_str()) },
-                    Some(EtherType::IPV6) => { res.push_str(format!("{}", IPv6Packet::new(self.payload.as_slice())).as_str()) },
+                    Some(EtherType::IPV4) => {
+                        let mut ipv4_packet = IPv4Packet::new(self.payload.as_slice());
+                        ipv4_packet.checksum_offloaded = self.checksum_offloaded;
+                        res.push_str(format!("{}", ipv4_packet).as_str())
+                    },
+                    Some(EtherType::IPV6) => {
+                        let mut ipv6_packet = IPv6Packet::new(self.payload.as_slice());
+                        ipv6_packet.checksum_offloaded = self.checksum_offloaded;
+                        res.push_str(format!("{}", ipv6_packet).as_str())
+                    },
                     Some(EtherType::ARP) => { res.push_str( "ARP      : Unknown Details") },
                     _ => { res.push_str("Other Protocol incapsulated in Ethernet frame (Unknown Protocol)") }
                 };
                 res.push('\n');
                 res
             },
-            ReportFormat::Report => {
+            ReportFormat::Ndjson => {
+                res.push_str(serde_json::to_string(&JsonPacket::new(self)).unwrap().as_str());
+                res.push('\n');
+                res
+            },
+            ReportFormat::Report | ReportFormat::Csv | ReportFormat::Json | ReportFormat::Html => {
                 res.push_str("Printing format for report");
                 res
             }
@@ -1,5 +1,8 @@
 use std::fmt::{Display, Formatter};
-use std::net::{Ipv4Addr};
+use std::net::{IpAddr, Ipv4Addr};
+use crate::network_components::checksum;
+use crate::network_components::checksum::{checksum_mark, ChecksumStatus};
+use crate::network_components::layer_4::icmp_packet::{IcmpPacket, IcmpVersion};
 use crate::network_components::layer_4::tcp_packet::TcpPacket;
 use crate::network_components::layer_4::upd_packet::UdpPacket;
 use crate::utility;
@@ -23,6 +26,9 @@ pub struct IPv4Packet {
     pub ttl: u8,
     pub protocol_type: Option<Ipv4ProtocolType>,
     pub header_checksum: u16,
+    pub header_checksum_status: ChecksumStatus,
+    /// Checksums computed by the NIC (outgoing packets with checksum offload): not verified
+    pub checksum_offloaded: bool,
     pub ip_addr_src: Ipv4Addr,
     pub ip_addr_dst: Ipv4Addr,
     pub options: Vec<u8>,
@@ -32,6 +38,7 @@ pub struct IPv4Packet {
 impl IPv4Packet {
     pub fn new(ipv4_data_in_u8: &[u8]) -> IPv4Packet {
         let header_nibble = ipv4_data_in_u8[0] & 0x0F;
+        let header_length = IPv4Packet::calc_header_length(header_nibble) as usize;
         IPv4Packet {
             version: ipv4_data_in_u8[0] >> 4,
             header_length: header_nibble,
@@ -43,6 +50,8 @@ impl IPv4Packet {
             ttl: ipv4_data_in_u8[8],
             protocol_type: IPv4Packet::to_protocol_type(ipv4_data_in_u8[9]),
             header_checksum: u16::from_be_bytes((&ipv4_data_in_u8[10..12]).try_into().unwrap()),
+            header_checksum_status: checksum::verify_ipv4_header(&ipv4_data_in_u8[..header_length.min(ipv4_data_in_u8.len())]),
+            checksum_offloaded: false,
             ip_addr_src: Ipv4Addr::new(ipv4_data_in_u8[12], ipv4_data_in_u8[13], ipv4_data_in_u8[14], ipv4_data_in_u8[15]),
             ip_addr_dst: Ipv4Addr::new(ipv4_data_in_u8[16], ipv4_data_in_u8[17], ipv4_data_in_u8[18], ipv4_data_in_u8[19]),
             options: IPv4Packet::options(IPv4Packet::calc_header_length(header_nibble), &ipv4_data_in_u8[..]),
@@ -66,6 +75,42 @@ impl IPv4Packet {
         Vec::from(&ipv4_data_in_u8[20..header_length as usize])
     }
 
+    /// Layer 4 segment without Ethernet padding. None if the packet is a fragment or if the capture is truncated.
+    pub fn l4_segment(&self) -> Option<&[u8]> {
+        let more_fragments = self.flags & 0x20 != 0;
+        let fragment_offset = (((self.flags & 0x1F) as u16) << 8) | self.fragmentation_offset as u16;
+        if more_fragments || fragment_offset != 0 {
+            return None;
+        }
+        let segment_length = (self.total_length as usize).checked_sub(self.header_length() as usize)?;
+        self.payload.get(..segment_length)
+    }
+
+    /// Verifies the checksum of the encapsulated TCP/UDP/ICMP segment.
+    pub fn l4_checksum_status(&self) -> ChecksumStatus {
+        let segment = match self.l4_segment() {
+            Some(segment) if !self.checksum_offloaded => segment,
+            _ => { return ChecksumStatus::Unverified; }
+        };
+        let (ip_addr_src, ip_addr_dst) = (IpAddr::V4(self.ip_addr_src), IpAddr::V4(self.ip_addr_dst));
+        match self.protocol_type {
+            Some(Ipv4ProtocolType::TCP) if segment.len() >= 20 => checksum::verify_transport(ip_addr_src, ip_addr_dst, 6, segment),
+            Some(Ipv4ProtocolType::UDP) if segment.len() >= 8 => {
+                match segment[6..8] {
+                    [0, 0] => ChecksumStatus::Unverified, // checksum not used
+                    _ => checksum::verify_transport(ip_addr_src, ip_addr_dst, 17, segment),
+                }
+            },
+            Some(Ipv4ProtocolType::ICMPv4) if segment.len() >= 8 => checksum::verify(segment),
+            _ => ChecksumStatus::Unverified,
+        }
+    }
+
+    /// Status of the IPv4 header checksum (unverified when offloaded).
+    pub fn checksum_status(&self) -> ChecksumStatus {
+        if self.checksum_offloaded { ChecksumStatus::Unverified } else { self.header_checksum_status }
+    }
+
     pub fn to_protocol_type(protocol_type_in_u8: u8) -> Option<Ipv4ProtocolType> {
         match protocol_type_in_u8 {
             1 => return Some(Ipv4ProtocolType::ICMPv4),
@@ -82,7 +127,7 @@ impl IPv4Packet {
 impl Display for IPv4Packet {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "IPv4     ").unwrap();
-        write!(f, ": {} -> {}\n > [version: {}, header-length: {}B, diff-serv: {:#04x}, tot-length: {}B, identification: {:#04x}, flags: {:#04x}, frag-offset: {}, ttl: {}, header-checksum: {:#04x} ]\n",
+        write!(f, ": {} -> {}\n > [version: {}, header-length: {}B, diff-serv: {:#04x}, tot-length: {}B, identification: {:#04x}, flags: {:#04x}, frag-offset: {}, ttl: {}, header-checksum: {:#04x}{} ]\n",
             self.ip_addr_src,
             self.ip_addr_dst,
             self.version,
@@ -94,22 +139,33 @@ impl Display for IPv4Packet {
             self.fragmentation_offset,
             self.ttl,
             self.header_checksum,
+            checksum_mark(self.checksum_status()),
         ).unwrap();
 
         write!(f, " > [{}]\n", utility::to_compact_hex(&self.options)).unwrap();
 
         match self.protocol_type {
             Some(Ipv4ProtocolType::ICMPv4) => {
-                write!(f, "ICMP     : Unknown Details")
+                match IcmpPacket::new(self.payload.as_slice(), IcmpVersion::ICMPv4) {
+                    Some(mut icmp_packet) => {
+                        icmp_packet.checksum_status = self.l4_checksum_status();
+                        write!(f, "{}", icmp_packet)
+                    },
+                    None => { write!(f, "ICMP     : Unknown Details") },
+                }
             },
             Some(Ipv4ProtocolType::IGMP) => {
                 write!(f, "IGMP     : Unknown Details")
             },
             Some(Ipv4ProtocolType::UDP) => {
-                write!(f, "{}", UdpPacket::new(self.payload.as_slice()))
+                let mut udp_packet = UdpPacket::new(self.payload.as_slice());
+                udp_packet.checksum_status = self.l4_checksum_status();
+                write!(f, "{}", udp_packet)
             },
             Some(Ipv4ProtocolType::TCP) => {
-                write!(f, "{}", TcpPacket::new(self.payload.as_slice()))
+                let mut tcp_packet = TcpPacket::new(self.payload.as_slice());
+                tcp_packet.checksum_status = self.l4_checksum_status();
+                write!(f, "{}", tcp_packet)
             },
             _ => {
                 write!(f, "Other Protocol incapsulated in IPv4 (Unknown Protocol)")
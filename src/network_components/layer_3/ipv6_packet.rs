@@ -1,5 +1,8 @@
 use std::fmt::{Display, Formatter};
-use std::net::{Ipv6Addr};
+use std::net::{IpAddr, Ipv6Addr};
+use crate::network_components::checksum;
+use crate::network_components::checksum::ChecksumStatus;
+use crate::network_components::layer_4::icmp_packet::{IcmpPacket, IcmpVersion};
 use crate::network_components::layer_4::tcp_packet::TcpPacket;
 use crate::network_components::layer_4::upd_packet::UdpPacket;
 use crate::utility;
@@ -15,19 +18,52 @@ pub enum Ipv6NextHeader {
 }
 
 pub struct IPv6Packet {
+    pub payload_length: u16,
     pub next_header: Option<Ipv6NextHeader>,
     pub ip_addr_src: Ipv6Addr,
     pub ip_addr_dst: Ipv6Addr,
     pub payload: Vec<u8>,
+    /// Checksums computed by the NIC (outgoing packets with checksum offload): not verified
+    pub checksum_offloaded: bool,
 }
 
 impl IPv6Packet {
     pub fn new(ipv6_data_in_u8: &[u8]) -> Self {
         IPv6Packet {
+            payload_length: u16::from_be_bytes((&ipv6_data_in_u8[4..6]).try_into().unwrap()),
             next_header: IPv6Packet::to_protocol_type(ipv6_data_in_u8[6]),
             ip_addr_src: Ipv6Addr::from(u128::from_be_bytes((&ipv6_data_in_u8[8..24]).try_into().unwrap())),
             ip_addr_dst: Ipv6Addr::from(u128::from_be_bytes((&ipv6_data_in_u8[24..40]).try_into().unwrap())),
             payload: Vec::from(&ipv6_data_in_u8[40..]),
+            checksum_offloaded: false,
+        }
+    }
+
+    /// Layer 4 protocol and segment (without Ethernet padding), skipping the Hop by Hop extension header.
+    /// None if the capture is truncated.
+    pub fn l4_segment(&self) -> Option<(Option<Ipv6NextHeader>, &[u8])> {
+        let payload = self.payload.get(..self.payload_length as usize)?;
+        match self.next_header {
+            Some(Ipv6NextHeader::IPv6HopByHopOption) => {
+                let extension_length = (*payload.get(1)? as usize + 1) * 8;
+                Some((IPv6Packet::to_protocol_type(payload[0]), payload.get(extension_length..)?))
+            },
+            next_header => Some((next_header, payload)),
+        }
+    }
+
+    /// Verifies the checksum of the encapsulated TCP/UDP/ICMPv6 segment.
+    pub fn l4_checksum_status(&self) -> ChecksumStatus {
+        let (next_header, segment) = match self.l4_segment() {
+            Some(l4_segment) if !self.checksum_offloaded => l4_segment,
+            _ => { return ChecksumStatus::Unverified; }
+        };
+        let (ip_addr_src, ip_addr_dst) = (IpAddr::V6(self.ip_addr_src), IpAddr::V6(self.ip_addr_dst));
+        match next_header {
+            Some(Ipv6NextHeader::TCP) if segment.len() >= 20 => checksum::verify_transport(ip_addr_src, ip_addr_dst, 6, segment),
+            Some(Ipv6NextHeader::UDP) if segment.len() >= 8 => checksum::verify_transport(ip_addr_src, ip_addr_dst, 17, segment),
+            Some(Ipv6NextHeader::ICMPv6) if segment.len() >= 8 => checksum::verify_transport(ip_addr_src, ip_addr_dst, 58, segment),
+            _ => ChecksumStatus::Unverified,
         }
     }
 
@@ -77,7 +113,13 @@ impl Display for IPv6Packet {
                                 write!(f, "{}", TcpPacket::new(self.payload.as_slice()))
                             },
                             Some(Ipv6NextHeader::ICMPv6) => {
-                                write!(f, "ICMPv6   : Unknown Details")
+                                match self.l4_segment().and_then(|(_, segment)| IcmpPacket::new(segment, IcmpVersion::ICMPv6)) {
+                                    Some(mut icmp_packet) => {
+                                        icmp_packet.checksum_status = self.l4_checksum_status();
+                                        write!(f, "{}", icmp_packet)
+                                    },
+                                    None => { write!(f, "ICMPv6   : Unknown Details") },
+                                }
                             },
                             _ => {
                                 write!(f, "Other Protocol encapsulated in IPv6 Hop by Hop Option (Unknown Protocol)")
@@ -94,13 +136,23 @@ impl Display for IPv6Packet {
                 write!(f, "IGMP     : Unknown Details")
             },
             Some(Ipv6NextHeader::UDP) => {
-                write!(f, "{}", UdpPacket::new(self.payload.as_slice()))
+                let mut udp_packet = UdpPacket::new(self.payload.as_slice());
+                udp_packet.checksum_status = self.l4_checksum_status();
+                write!(f, "{}", udp_packet)
             },
             Some(Ipv6NextHeader::TCP) => {
-                write!(f, "{}", TcpPacket::new(self.payload.as_slice()))
+                let mut tcp_packet = TcpPacket::new(self.payload.as_slice());
+                tcp_packet.checksum_status = self.l4_checksum_status();
+                write!(f, "{}", tcp_packet)
             },
             Some(Ipv6NextHeader::ICMPv6) => {
-                write!(f, "ICMPv6   : Unknown Details")
+                match IcmpPacket::new(self.payload.as_slice(), IcmpVersion::ICMPv6) {
+                    Some(mut icmp_packet) => {
+                        icmp_packet.checksum_status = self.l4_checksum_status();
+                        write!(f, "{}", icmp_packet)
+                    },
+                    None => { write!(f, "ICMPv6   : Unknown Details") },
+                }
             },
             _ => {
                 write!(f, "Other Protocol encapsulated in IPv6 (Unknown Protocol)")
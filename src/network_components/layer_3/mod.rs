@@ -12,4 +12,52 @@
 
 pub mod ipv4_packet;
 
-pub mod ipv6_packet;
\ No newline at end of file
+pub mod ipv6_packet;
+/// Name of the protocol carried by an IPv4/IPv6 packet (IANA Assigned Internet Protocol Numbers), `IP-PROTO n` if unknown
+pub fn ip_protocol_name(protocol: u8) -> String {
+    match protocol {
+        1 => "ICMP",
+        2 => "IGMP",
+        4 => "IPIP",
+        6 => "TCP",
+        17 => "UDP",
+        41 => "IPv6",
+        47 => "GRE",
+        50 => "ESP",
+        51 => "AH",
+        58 => "ICMPv6",
+        59 => "IPv6-NoNxt",
+        88 => "EIGRP",
+        89 => "OSPF",
+        103 => "PIM",
+        112 => "VRRP",
+        115 => "L2TP",
+        132 => "SCTP",
+        _ => { return format!("IP-PROTO {}", protocol); }
+    }.to_string()
+}
+
+/// Protocol number of a name given by `ip_protocol_name`
+pub fn ip_protocol_number(name: &str) -> Option<u8> {
+    let protocol = match name {
+        "ICMP" => 1,
+        "IGMP" => 2,
+        "IPIP" => 4,
+        "TCP" => 6,
+        "UDP" => 17,
+        "IPv6" => 41,
+        "GRE" => 47,
+        "ESP" => 50,
+        "AH" => 51,
+        "ICMPv6" => 58,
+        "IPv6-NoNxt" => 59,
+        "EIGRP" => 88,
+        "OSPF" => 89,
+        "PIM" => 103,
+        "VRRP" => 112,
+        "L2TP" => 115,
+        "SCTP" => 132,
+        _ => name.strip_prefix("IP-PROTO ")?.parse().ok()?,
+    };
+    Some(protocol)
+}
@@ -0,0 +1,87 @@
+use std::fmt::{Display, Formatter};
+use crate::network_components::checksum::{checksum_mark, ChecksumStatus};
+use crate::utility;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum IcmpVersion {
+    ICMPv4,
+    ICMPv6,
+}
+
+pub struct IcmpPacket {
+    pub icmp_version: IcmpVersion,
+    pub icmp_type: u8,
+    pub code: u8,
+    pub checksum: u16,
+    pub checksum_status: ChecksumStatus,
+    pub rest_of_header: u32,
+    pub payload: Vec<u8>,
+}
+
+/// Size of the ICMP header: type, code, checksum and rest of the header
+pub const ICMP_HEADER_SIZE: usize = 8;
+
+impl IcmpPacket {
+    /// `new` ICMP message (None when shorter than the header, e.g. truncated)
+    pub fn new(icmp_data_in_u8: &[u8], icmp_version: IcmpVersion) -> Option<IcmpPacket> {
+        if icmp_data_in_u8.len() < ICMP_HEADER_SIZE {
+            return None;
+        }
+        Some(IcmpPacket {
+            icmp_version,
+            icmp_type: icmp_data_in_u8[0],
+            code: icmp_data_in_u8[1],
+            checksum: u16::from_be_bytes((&icmp_data_in_u8[2..4]).try_into().unwrap()),
+            checksum_status: ChecksumStatus::Unverified,
+            rest_of_header: u32::from_be_bytes((&icmp_data_in_u8[4..8]).try_into().unwrap()),
+            payload: Vec::from(&icmp_data_in_u8[ICMP_HEADER_SIZE..]),
+        })
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match (self.icmp_version, self.icmp_type) {
+            (IcmpVersion::ICMPv4, 0) => "Echo Reply",
+            (IcmpVersion::ICMPv4, 3) => "Destination Unreachable",
+            (IcmpVersion::ICMPv4, 5) => "Redirect",
+            (IcmpVersion::ICMPv4, 8) => "Echo Request",
+            (IcmpVersion::ICMPv4, 9) => "Router Advertisement",
+            (IcmpVersion::ICMPv4, 10) => "Router Solicitation",
+            (IcmpVersion::ICMPv4, 11) => "Time Exceeded",
+            (IcmpVersion::ICMPv4, 12) => "Parameter Problem",
+            (IcmpVersion::ICMPv4, 13) => "Timestamp",
+            (IcmpVersion::ICMPv4, 14) => "Timestamp Reply",
+            (IcmpVersion::ICMPv6, 1) => "Destination Unreachable",
+            (IcmpVersion::ICMPv6, 2) => "Packet Too Big",
+            (IcmpVersion::ICMPv6, 3) => "Time Exceeded",
+            (IcmpVersion::ICMPv6, 4) => "Parameter Problem",
+            (IcmpVersion::ICMPv6, 128) => "Echo Request",
+            (IcmpVersion::ICMPv6, 129) => "Echo Reply",
+            (IcmpVersion::ICMPv6, 133) => "Router Solicitation",
+            (IcmpVersion::ICMPv6, 134) => "Router Advertisement",
+            (IcmpVersion::ICMPv6, 135) => "Neighbor Solicitation",
+            (IcmpVersion::ICMPv6, 136) => "Neighbor Advertisement",
+            (IcmpVersion::ICMPv6, 137) => "Redirect",
+            (IcmpVersion::ICMPv6, 143) => "Multicast Listener Report v2",
+            _ => "Unknown Type",
+        }
+    }
+}
+
+impl Display for IcmpPacket {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.icmp_version {
+            IcmpVersion::ICMPv4 => { write!(f, "ICMP     ") },
+            IcmpVersion::ICMPv6 => { write!(f, "ICMPv6   ") },
+        }.unwrap();
+        write!(f, ": {} - [type: {}, code: {}, checksum: {:#04x}{}, rest-of-header: {:#010x}]",
+            self.type_name(),
+            self.icmp_type,
+            self.code,
+            self.checksum,
+            checksum_mark(self.checksum_status),
+            self.rest_of_header,
+        ).unwrap();
+
+        write!(f, "\n > [{}]", utility::to_compact_hex(&self.payload))
+    }
+}
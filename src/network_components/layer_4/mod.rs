@@ -3,11 +3,18 @@
 //! Module to handle `TCP/IP Layer 4`.
 //!
 //! ### Layer 4 ("Protocol Type" field of L3 protocols)
-//!     full-support: UDP, (TCP)
-//!     identification: (). Others: IGMP, ICMPv4, ICMPv6
+//!     full-support: UDP, (TCP), ICMPv4, ICMPv6
+//!     identification: (). Others: IGMP
 //!     future support: ()
 //!
 
 pub mod upd_packet;
 
-pub mod tcp_packet;
\ No newline at end of file
+pub mod tcp_packet;
+
+pub mod tcp_options;
+
+pub mod icmp_packet;
+
+pub mod tcp_reassembly;
+pub mod tcp_connection;
@@ -0,0 +1,180 @@
+//! # TCP Connection
+//!
+//! Per-connection TCP state tracking through handshake (SYN, SYN/ACK, ACK), data transfer and teardown (FIN/RST),
+//! telling completed sessions apart from half-open attempts.
+//!
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::net::IpAddr;
+use chrono::{DateTime, Duration, Utc};
+use crate::network_components::layer_4::tcp_options::TcpFlag;
+use crate::network_components::layer_4::tcp_packet::TcpPacket;
+use crate::network_components::layer_4::tcp_reassembly::{ConnectionKey, Endpoint};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum TcpState {
+    /// SYN sent by the initiator, not answered yet.
+    SynSent,
+    /// SYN/ACK sent by the responder, not acknowledged yet.
+    SynReceived,
+    /// Handshake completed, or connection already open when the capture started.
+    Established,
+    /// FIN sent by one of the endpoints.
+    Closing,
+    /// FIN sent by both endpoints.
+    Closed,
+    /// Connection aborted by a RST.
+    Reset,
+}
+
+impl Display for TcpState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TcpState::SynSent => write!(f, "SYN_SENT"),
+            TcpState::SynReceived => write!(f, "SYN_RECEIVED"),
+            TcpState::Established => write!(f, "ESTABLISHED"),
+            TcpState::Closing => write!(f, "CLOSING"),
+            TcpState::Closed => write!(f, "CLOSED"),
+            TcpState::Reset => write!(f, "RESET"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// `TCP Connection` state and connection-level details.
+pub struct TcpConnection {
+    /// Endpoint that sent the SYN (None when the connection was picked up mid-stream)
+    pub initiator: Option<Endpoint>,
+    pub state: TcpState,
+    /// SYN, SYN/ACK and ACK all captured
+    pub handshake_completed: bool,
+    /// Endpoint that sent the RST
+    pub reset_by: Option<Endpoint>,
+    /// Endpoints that sent a FIN
+    pub fin_by: Vec<Endpoint>,
+    syn_seen: bool,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+}
+
+impl TcpConnection {
+    fn new(initiator: Option<Endpoint>, state: TcpState, timestamp: DateTime<Utc>) -> TcpConnection {
+        TcpConnection {
+            initiator,
+            state,
+            handshake_completed: false,
+            reset_by: None,
+            fin_by: Vec::new(),
+            syn_seen: state == TcpState::SynSent,
+            first_seen: timestamp,
+            last_seen: timestamp,
+        }
+    }
+
+    /// Connection terminated (both FINs or RST): no more state changes expected.
+    pub fn is_terminated(&self) -> bool {
+        matches!(self.state, TcpState::Closed | TcpState::Reset)
+    }
+
+    fn update(&mut self, src: Endpoint, syn: bool, ack: bool, fin: bool, rst: bool, timestamp: DateTime<Utc>) {
+        self.last_seen = timestamp;
+        if rst {
+            self.state = TcpState::Reset;
+            self.reset_by = Some(src);
+            return;
+        }
+        if self.is_terminated() {
+            return;
+        }
+
+        match (self.state, syn, ack) {
+            (TcpState::SynSent, true, true) if self.initiator != Some(src) => {
+                self.state = TcpState::SynReceived;
+            },
+            (TcpState::SynReceived, false, true) if self.initiator == Some(src) => {
+                self.state = TcpState::Established;
+                self.handshake_completed = self.syn_seen;
+            },
+            _ => {}
+        }
+
+        if fin && !self.fin_by.contains(&src) {
+            self.fin_by.push(src);
+            self.state = if self.fin_by.len() == 2 { TcpState::Closed } else { TcpState::Closing };
+        }
+    }
+}
+
+/// `TCP Connection Tracker` following the state of every TCP connection seen in the capture.
+///
+/// # Example
+/// ```
+/// let mut tcp_connections = TcpConnectionTracker::new();
+/// tcp_connections.process(ip_addr_src, ip_addr_dst, &tcp_packet, timestamp);
+/// let connection = tcp_connections.get(endpoint_1, endpoint_2);
+/// ```
+#[derive(Default)]
+pub struct TcpConnectionTracker {
+    connections: HashMap<(Endpoint, Endpoint), TcpConnection>,
+}
+
+impl TcpConnectionTracker {
+    /// `new`
+    pub fn new() -> Self {
+        Self { connections: HashMap::new() }
+    }
+
+    /// Number of connections currently tracked
+    pub fn len(&self) -> usize {
+        self.connections.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.connections.is_empty()
+    }
+
+    /// Updates the state of the connection the segment belongs to.
+    pub fn process(&mut self, ip_addr_src: IpAddr, ip_addr_dst: IpAddr, tcp_packet: &TcpPacket, timestamp: DateTime<Utc>) {
+        let src = Endpoint { ip_addr: ip_addr_src, port: tcp_packet.src_port };
+        let dst = Endpoint { ip_addr: ip_addr_dst, port: tcp_packet.dst_port };
+        let flags = tcp_packet.tcp_flags();
+        let syn = flags.contains(TcpFlag::SYN);
+        let ack = flags.contains(TcpFlag::ACK);
+        let fin = flags.contains(TcpFlag::FIN);
+        let rst = flags.contains(TcpFlag::RST);
+        let canonical = ConnectionKey::canonical(src, dst);
+
+        // a new SYN on a terminated connection starts a new connection on the same 4-tuple
+        if syn && !ack && self.connections.get(&canonical).is_some_and(|connection| connection.is_terminated()) {
+            self.connections.remove(&canonical);
+        }
+
+        match self.connections.get_mut(&canonical) {
+            Some(connection) => connection.update(src, syn, ack, fin, rst, timestamp),
+            None => {
+                let connection = match (syn, ack) {
+                    (true, false) => TcpConnection::new(Some(src), TcpState::SynSent, timestamp),
+                    (true, true) => TcpConnection::new(Some(dst), TcpState::SynReceived, timestamp),
+                    // segments without data of unknown connections (e.g. late ACKs) don't open a connection
+                    _ if !fin && !rst && tcp_packet.payload.is_empty() => return,
+                    _ => TcpConnection::new(None, TcpState::Established, timestamp),
+                };
+                let connection = self.connections.entry(canonical).or_insert(connection);
+                if fin || rst {
+                    connection.update(src, syn, ack, fin, rst, timestamp);
+                }
+            }
+        }
+    }
+
+    /// Connection between two endpoints, in any direction.
+    pub fn get(&self, endpoint_1: Endpoint, endpoint_2: Endpoint) -> Option<&TcpConnection> {
+        self.connections.get(&ConnectionKey::canonical(endpoint_1, endpoint_2))
+    }
+
+    /// Forgets the terminated connections and the ones idle for more than `idle_timeout`.
+    pub fn purge(&mut self, now: DateTime<Utc>, idle_timeout: Duration) {
+        self.connections.retain(|_, connection| !connection.is_terminated() && now - connection.last_seen <= idle_timeout);
+    }
+}
@@ -0,0 +1,197 @@
+use std::fmt::{Display, Formatter};
+use crate::utility;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum TcpFlag {
+    NS,
+    CWR,
+    ECE,
+    URG,
+    ACK,
+    PSH,
+    RST,
+    SYN,
+    FIN,
+}
+
+impl TcpFlag {
+    /// All the flags, in the order they appear inside the TCP header.
+    pub const ALL: [TcpFlag; 9] = [TcpFlag::NS, TcpFlag::CWR, TcpFlag::ECE, TcpFlag::URG, TcpFlag::ACK, TcpFlag::PSH, TcpFlag::RST, TcpFlag::SYN, TcpFlag::FIN];
+
+    /// Bit of the flag inside the 9 bits following the data offset nibble.
+    pub fn mask(&self) -> u16 {
+        match self {
+            TcpFlag::NS  => 0x100,
+            TcpFlag::CWR => 0x080,
+            TcpFlag::ECE => 0x040,
+            TcpFlag::URG => 0x020,
+            TcpFlag::ACK => 0x010,
+            TcpFlag::PSH => 0x008,
+            TcpFlag::RST => 0x004,
+            TcpFlag::SYN => 0x002,
+            TcpFlag::FIN => 0x001,
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<TcpFlag> {
+        TcpFlag::ALL.iter().find(|flag| format!("{:?}", flag) == name).copied()
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TcpFlags {
+    pub raw: u16,
+}
+
+impl TcpFlags {
+    /// Builds the flags from the data offset byte (NS is its lowest bit) and the flags byte of the TCP header.
+    pub fn new(data_offset: u8, flags: u8) -> TcpFlags {
+        TcpFlags {
+            raw: ((data_offset as u16 & 0x01) << 8) | flags as u16,
+        }
+    }
+
+    pub fn contains(&self, flag: TcpFlag) -> bool {
+        self.raw & flag.mask() != 0
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        TcpFlag::ALL.iter()
+            .filter(|flag| self.contains(**flag))
+            .map(|flag| format!("{:?}", flag))
+            .collect()
+    }
+}
+
+impl Display for TcpFlags {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.names().join(" "))
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum MptcpSubtype {
+    MpCapable,
+    MpJoin,
+    Dss,
+    AddAddr,
+    RemoveAddr,
+    MpPrio,
+    MpFail,
+    MpFastclose,
+    MpTcprst,
+    Unknown(u8),
+}
+
+impl From<u8> for MptcpSubtype {
+    fn from(v: u8) -> MptcpSubtype {
+        match v {
+            0 => MptcpSubtype::MpCapable,
+            1 => MptcpSubtype::MpJoin,
+            2 => MptcpSubtype::Dss,
+            3 => MptcpSubtype::AddAddr,
+            4 => MptcpSubtype::RemoveAddr,
+            5 => MptcpSubtype::MpPrio,
+            6 => MptcpSubtype::MpFail,
+            7 => MptcpSubtype::MpFastclose,
+            8 => MptcpSubtype::MpTcprst,
+            x => MptcpSubtype::Unknown(x),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TcpOption {
+    EndOfOptionList,
+    NoOperation,
+    MaximumSegmentSize(u16),
+    WindowScale(u8),
+    SackPermitted,
+    /// Left and right edges of each SACK block.
+    Sack(Vec<(u32, u32)>),
+    Timestamps { value: u32, echo_reply: u32 },
+    /// TCP Fast Open cookie (empty when the option is a cookie request).
+    FastOpen(Vec<u8>),
+    Mptcp { subtype: MptcpSubtype, data: Vec<u8> },
+    /// Unsupported option, or malformed option with the remaining bytes of the options field.
+    Unknown { kind: u8, data: Vec<u8> },
+}
+
+impl TcpOption {
+    const FAST_OPEN_EXPERIMENTAL_MAGIC: [u8; 2] = [0xF9, 0x89];
+
+    /// Parses the options field of a TCP header. Parsing stops at the End of Option List or at the first malformed option.
+    pub fn parse(options_in_u8: &[u8]) -> Vec<TcpOption> {
+        let mut options = Vec::new();
+        let mut i = 0;
+
+        while i < options_in_u8.len() {
+            let kind = options_in_u8[i];
+            match kind {
+                0 => {
+                    options.push(TcpOption::EndOfOptionList);
+                    break;
+                },
+                1 => {
+                    options.push(TcpOption::NoOperation);
+                    i += 1;
+                    continue;
+                },
+                _ => {}
+            }
+
+            let length = match options_in_u8.get(i + 1) {
+                Some(&length) if length >= 2 && i + length as usize <= options_in_u8.len() => length as usize,
+                _ => {
+                    options.push(TcpOption::Unknown { kind, data: Vec::from(&options_in_u8[i + 1..]) });
+                    break;
+                }
+            };
+            let data = &options_in_u8[i + 2..i + length];
+
+            options.push(TcpOption::from_kind(kind, data));
+            i += length;
+        }
+        options
+    }
+
+    fn from_kind(kind: u8, data: &[u8]) -> TcpOption {
+        match (kind, data.len()) {
+            (2, 2) => TcpOption::MaximumSegmentSize(u16::from_be_bytes([data[0], data[1]])),
+            (3, 1) => TcpOption::WindowScale(data[0]),
+            (4, 0) => TcpOption::SackPermitted,
+            (5, len) if len % 8 == 0 => TcpOption::Sack(data.chunks(8)
+                .map(|block| (u32::from_be_bytes(block[0..4].try_into().unwrap()), u32::from_be_bytes(block[4..8].try_into().unwrap())))
+                .collect()),
+            (8, 8) => TcpOption::Timestamps {
+                value: u32::from_be_bytes(data[0..4].try_into().unwrap()),
+                echo_reply: u32::from_be_bytes(data[4..8].try_into().unwrap()),
+            },
+            (30, len) if len >= 1 => TcpOption::Mptcp { subtype: MptcpSubtype::from(data[0] >> 4), data: Vec::from(data) },
+            (34, _) => TcpOption::FastOpen(Vec::from(data)),
+            (254, len) if len >= 2 && data[0..2] == TcpOption::FAST_OPEN_EXPERIMENTAL_MAGIC => TcpOption::FastOpen(Vec::from(&data[2..])),
+            _ => TcpOption::Unknown { kind, data: Vec::from(data) },
+        }
+    }
+}
+
+impl Display for TcpOption {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TcpOption::EndOfOptionList => write!(f, "EOL"),
+            TcpOption::NoOperation => write!(f, "NOP"),
+            TcpOption::MaximumSegmentSize(mss) => write!(f, "MSS {}", mss),
+            TcpOption::WindowScale(shift) => write!(f, "WS {}", shift),
+            TcpOption::SackPermitted => write!(f, "SACK-permitted"),
+            TcpOption::Sack(blocks) => {
+                let blocks: Vec<String> = blocks.iter().map(|(left, right)| format!("{}-{}", left, right)).collect();
+                write!(f, "SACK {}", blocks.join(" "))
+            },
+            TcpOption::Timestamps { value, echo_reply } => write!(f, "TS val {} ecr {}", value, echo_reply),
+            TcpOption::FastOpen(cookie) if cookie.is_empty() => write!(f, "TFO cookie-request"),
+            TcpOption::FastOpen(cookie) => write!(f, "TFO cookie {}", utility::to_compact_hex(cookie)),
+            TcpOption::Mptcp { subtype, .. } => write!(f, "MPTCP {:?}", subtype),
+            TcpOption::Unknown { kind, data } => write!(f, "Option {} [{}]", kind, utility::to_compact_hex(data)),
+        }
+    }
+}
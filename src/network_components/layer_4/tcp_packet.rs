@@ -1,17 +1,20 @@
 use crate::utility;
 use std::fmt::{Display, Formatter};
-use crate::network_components::services_upper_layers::upper_layer_services::{known_port, print_upper_layer, UpperLayerService};
+use crate::network_components::checksum::{checksum_mark, ChecksumStatus};
+use crate::network_components::layer_4::tcp_options::{TcpFlags, TcpOption};
+use crate::network_components::services_upper_layers::upper_layer_services::{identify_service, print_upper_layer, ServiceIdentification, Transport};
 
 pub struct TcpPacket {
     pub src_port: u16,
     pub dst_port: u16,
-    pub upper_layer_service: UpperLayerService,
+    pub identification: ServiceIdentification,
     pub sequence_number: u32,
     pub ack_number: u32,
     pub data_offset: u8,
     pub flags: u8,
     pub window_size: u16,
     pub checksum: u16,
+    pub checksum_status: ChecksumStatus,
     pub urgent_pointer: u16,
     pub options: Vec<u8>,
     pub payload: Vec<u8>,
@@ -32,8 +35,9 @@ impl TcpPacket {
             flags: tcp_data_in_u8[13],
             window_size: u16::from_be_bytes((&tcp_data_in_u8[14..16]).try_into().unwrap()),
             checksum: u16::from_be_bytes((&tcp_data_in_u8[16..18]).try_into().unwrap()),
+            checksum_status: ChecksumStatus::Unverified,
             urgent_pointer: u16::from_be_bytes((&tcp_data_in_u8[18..20]).try_into().unwrap()),
-            upper_layer_service: UpperLayerService::from(known_port(src_port, dst_port)),
+            identification: identify_service(Transport::TCP, src_port, dst_port, &tcp_data_in_u8[data_offset_nibble..]),
             options: Vec::from(&tcp_data_in_u8[20..data_offset_nibble]),
             payload: Vec::from(&tcp_data_in_u8[data_offset_nibble..]),
         }
@@ -42,26 +46,37 @@ impl TcpPacket {
     fn data_offset(&self) -> u8 {
         ((self.data_offset & 0xF0) >> 4) * 4
     }
+
+    pub fn tcp_flags(&self) -> TcpFlags {
+        TcpFlags::new(self.data_offset, self.flags)
+    }
+
+    pub fn tcp_options(&self) -> Vec<TcpOption> {
+        TcpOption::parse(&self.options)
+    }
 }
 
 impl Display for TcpPacket {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "TCP      ").unwrap();
-        write!(f, ": {} -> {}\n > [seq#: {}, ACK#: {}, data-offset: {}B, flags: {:#02x}, windows-size: {}, checksum: {:#04x}, urgent-pointer: {:#04x} ]\n",
+        write!(f, ": {} -> {}\n > [seq#: {}, ACK#: {}, data-offset: {}B, flags: {:#02x} ({}), windows-size: {}, checksum: {:#04x}{}, urgent-pointer: {:#04x} ]\n",
                self.src_port,
                self.dst_port,
                self.sequence_number,
                self.ack_number,
                self.data_offset(),
                self.flags,
+               self.tcp_flags(),
                self.window_size,
                self.checksum,
+               checksum_mark(self.checksum_status),
                self.urgent_pointer,
         ).unwrap();
 
-        print_upper_layer(f, self.upper_layer_service).unwrap();
+        print_upper_layer(f, &self.identification, &self.payload, Transport::TCP).unwrap();
 
-        write!(f, "\n Options > [{}]", utility::to_compact_hex(&self.options)).unwrap();
+        let options: Vec<String> = self.tcp_options().iter().map(|option| option.to_string()).collect();
+        write!(f, "\n Options > [{}]", options.join(", ")).unwrap();
         write!(f, "\n Payload > [{}]", utility::to_compact_hex(&self.payload))
     }
 }
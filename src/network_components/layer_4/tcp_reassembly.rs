@@ -0,0 +1,350 @@
+//! # TCP Reassembly
+//!
+//! Per-connection TCP stream reassembly: segments are ordered by sequence number, retransmissions and overlaps are
+//! trimmed and each direction of the connection is delivered as an in-order byte stream to application-layer dissectors.
+//!
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Display, Formatter};
+use std::net::IpAddr;
+use chrono::{DateTime, Duration, Utc};
+use crate::network_components::layer_4::tcp_options::TcpFlag;
+use crate::network_components::layer_4::tcp_packet::TcpPacket;
+
+/// Maximum number of out-of-order bytes buffered for each direction, before skipping the missing bytes.
+const MAX_BUFFERED_BYTES: usize = 4 * 1024 * 1024;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Endpoint {
+    pub ip_addr: IpAddr,
+    pub port: u16,
+}
+
+impl Display for Endpoint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.ip_addr {
+            IpAddr::V4(ip_addr) => write!(f, "{}:{}", ip_addr, self.port),
+            IpAddr::V6(ip_addr) => write!(f, "[{}]:{}", ip_addr, self.port),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+/// TCP connection identifier. The client is the endpoint that sent the SYN (or the first packet seen, when the handshake wasn't captured).
+pub struct ConnectionKey {
+    pub client: Endpoint,
+    pub server: Endpoint,
+}
+
+impl ConnectionKey {
+    /// Orientation-independent identifier of a connection.
+    pub fn canonical(endpoint_1: Endpoint, endpoint_2: Endpoint) -> (Endpoint, Endpoint) {
+        if endpoint_1 <= endpoint_2 { (endpoint_1, endpoint_2) } else { (endpoint_2, endpoint_1) }
+    }
+
+    pub fn direction(&self, src: &Endpoint) -> Direction {
+        if *src == self.client { Direction::ClientToServer } else { Direction::ServerToClient }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Direction {
+    ClientToServer,
+    ServerToClient,
+}
+
+impl Direction {
+    fn index(&self) -> usize {
+        match self {
+            Direction::ClientToServer => 0,
+            Direction::ServerToClient => 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamEventKind {
+    /// New in-order bytes.
+    Data(Vec<u8>),
+    /// Bytes never captured: the stream continues after the gap.
+    Gap(u64),
+    /// Connection closed (FIN in both directions, RST or idle connection).
+    Close,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamEvent {
+    pub connection: ConnectionKey,
+    pub direction: Direction,
+    pub timestamp: DateTime<Utc>,
+    pub kind: StreamEventKind,
+}
+
+impl StreamEvent {
+    /// Delivers the event to an application-layer dissector.
+    pub fn dispatch(&self, dissector: &mut dyn StreamDissector) {
+        match &self.kind {
+            StreamEventKind::Data(data) => dissector.on_data(&self.connection, self.direction, data, self.timestamp),
+            StreamEventKind::Gap(length) => dissector.on_gap(&self.connection, self.direction, *length),
+            StreamEventKind::Close => dissector.on_close(&self.connection),
+        }
+    }
+}
+
+/// `StreamDissector` trait
+/// Implemented by application-layer dissectors consuming the reassembled TCP byte streams.
+pub trait StreamDissector: Send {
+    /// New in-order bytes of a direction of the connection.
+    fn on_data(&mut self, connection: &ConnectionKey, direction: Direction, data: &[u8], timestamp: DateTime<Utc>);
+    /// Bytes missing from the capture: the next data doesn't follow the previous one.
+    fn on_gap(&mut self, _connection: &ConnectionKey, _direction: Direction, _length: u64) {}
+    /// Connection closed: no more data will be delivered.
+    fn on_close(&mut self, _connection: &ConnectionKey) {}
+    /// Section appended to the report at the end of each report interval (None if the dissector has nothing to report).
+    fn report_section(&mut self) -> Option<String> { None }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StreamStatistics {
+    /// Bytes delivered in order
+    pub delivered_bytes: u64,
+    /// Bytes received more than once (retransmissions and overlapping segments)
+    pub retransmitted_bytes: u64,
+    /// Segments received before the previous data
+    pub out_of_order_segments: u64,
+    /// Bytes never captured
+    pub missing_bytes: u64,
+}
+
+/// One direction of a TCP connection.
+#[derive(Default)]
+struct HalfStream {
+    /// Sequence number of the first byte of the stream
+    base_seq: Option<u32>,
+    /// Stream offset of the next expected byte
+    delivered: u64,
+    /// Out-of-order segments, by stream offset
+    pending: BTreeMap<u64, Vec<u8>>,
+    pending_bytes: usize,
+    fin_offset: Option<u64>,
+    statistics: StreamStatistics,
+}
+
+impl HalfStream {
+    /// Stream offset of a sequence number (negative for data preceding the start of the stream).
+    fn offset(&self, seq: u32) -> i64 {
+        let next_seq = self.base_seq.unwrap().wrapping_add(self.delivered as u32);
+        self.delivered as i64 + seq.wrapping_sub(next_seq) as i32 as i64
+    }
+
+    fn is_closed(&self) -> bool {
+        self.fin_offset.is_some_and(|fin_offset| self.delivered >= fin_offset)
+    }
+
+    fn segment(&mut self, seq: u32, syn: bool, fin: bool, payload: &[u8], chunks: &mut Vec<StreamEventKind>) {
+        let seq = if syn { seq.wrapping_add(1) } else { seq };
+        if self.base_seq.is_none() {
+            self.base_seq = Some(seq);
+        }
+
+        let offset = self.offset(seq);
+        let end = offset + payload.len() as i64;
+        if fin {
+            self.fin_offset = Some(end.max(0) as u64);
+        }
+
+        if !payload.is_empty() {
+            if end <= self.delivered as i64 {
+                self.statistics.retransmitted_bytes += payload.len() as u64;
+            } else if offset <= self.delivered as i64 {
+                let overlap = (self.delivered as i64 - offset) as usize;
+                self.statistics.retransmitted_bytes += overlap as u64;
+                self.deliver(&payload[overlap..], chunks);
+                self.drain(chunks);
+            } else {
+                self.statistics.out_of_order_segments += 1;
+                let buffered = self.pending.entry(offset as u64).or_default();
+                if buffered.len() < payload.len() {
+                    self.pending_bytes += payload.len() - buffered.len();
+                    *buffered = Vec::from(payload);
+                } else {
+                    self.statistics.retransmitted_bytes += payload.len() as u64;
+                }
+                if self.pending_bytes > MAX_BUFFERED_BYTES {
+                    self.skip_gap(chunks);
+                }
+            }
+        }
+    }
+
+    fn deliver(&mut self, data: &[u8], chunks: &mut Vec<StreamEventKind>) {
+        self.delivered += data.len() as u64;
+        self.statistics.delivered_bytes += data.len() as u64;
+        chunks.push(StreamEventKind::Data(Vec::from(data)));
+    }
+
+    /// Delivers the buffered segments following the data already delivered.
+    fn drain(&mut self, chunks: &mut Vec<StreamEventKind>) {
+        while let Some((&offset, _)) = self.pending.iter().next() {
+            if offset > self.delivered {
+                break;
+            }
+            let data = self.pending.remove(&offset).unwrap();
+            self.pending_bytes -= data.len();
+            let overlap = (self.delivered - offset) as usize;
+            if overlap < data.len() {
+                self.statistics.retransmitted_bytes += overlap as u64;
+                self.deliver(&data[overlap..], chunks);
+            } else {
+                self.statistics.retransmitted_bytes += data.len() as u64;
+            }
+        }
+    }
+
+    /// Gives up waiting for the missing bytes before the first buffered segment.
+    fn skip_gap(&mut self, chunks: &mut Vec<StreamEventKind>) {
+        if let Some((&offset, _)) = self.pending.iter().next() {
+            let length = offset - self.delivered;
+            self.statistics.missing_bytes += length;
+            self.delivered = offset;
+            chunks.push(StreamEventKind::Gap(length));
+            self.drain(chunks);
+        }
+    }
+
+    fn flush(&mut self, chunks: &mut Vec<StreamEventKind>) {
+        while !self.pending.is_empty() {
+            self.skip_gap(chunks);
+        }
+    }
+}
+
+struct TcpConnection {
+    key: ConnectionKey,
+    streams: [HalfStream; 2],
+    reset: bool,
+    last_seen: DateTime<Utc>,
+}
+
+impl TcpConnection {
+    fn is_closed(&self) -> bool {
+        self.reset || self.streams.iter().all(|stream| stream.is_closed())
+    }
+}
+
+/// `TCP Reassembler` tracking every TCP connection seen in the capture.
+///
+/// # Example
+/// ```
+/// let mut tcp_reassembler = TcpReassembler::new();
+/// for event in tcp_reassembler.process(ip_addr_src, ip_addr_dst, &tcp_packet, timestamp) {
+///     event.dispatch(&mut http_dissector);
+/// }
+/// ```
+#[derive(Default)]
+pub struct TcpReassembler {
+    connections: HashMap<(Endpoint, Endpoint), TcpConnection>,
+}
+
+impl TcpReassembler {
+    /// `new`
+    pub fn new() -> Self {
+        Self { connections: HashMap::new() }
+    }
+
+    /// Number of connections currently tracked
+    pub fn len(&self) -> usize {
+        self.connections.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.connections.is_empty()
+    }
+
+    /// Adds a segment to its connection, returning the data now available in order (and the connection closure).
+    pub fn process(&mut self, ip_addr_src: IpAddr, ip_addr_dst: IpAddr, tcp_packet: &TcpPacket, timestamp: DateTime<Utc>) -> Vec<StreamEvent> {
+        let src = Endpoint { ip_addr: ip_addr_src, port: tcp_packet.src_port };
+        let dst = Endpoint { ip_addr: ip_addr_dst, port: tcp_packet.dst_port };
+        let flags = tcp_packet.tcp_flags();
+        let syn = flags.contains(TcpFlag::SYN);
+        let fin = flags.contains(TcpFlag::FIN);
+        let rst = flags.contains(TcpFlag::RST);
+        let canonical = ConnectionKey::canonical(src, dst);
+        let mut events = Vec::new();
+
+        // new connection on a 4-tuple already used by a closed (or reused) connection
+        let reused = match self.connections.get(&canonical) {
+            Some(connection) if syn && !flags.contains(TcpFlag::ACK) => {
+                let stream = &connection.streams[connection.key.direction(&src).index()];
+                connection.is_closed() || stream.base_seq != Some(tcp_packet.sequence_number.wrapping_add(1))
+            },
+            Some(connection) => connection.is_closed(),
+            None => false,
+        };
+        if reused {
+            let connection = self.connections.remove(&canonical).unwrap();
+            events.extend(TcpReassembler::close(connection, timestamp));
+        }
+        // segments without data of untracked connections (e.g. last ACK after the FINs) don't open a connection
+        if !syn && tcp_packet.payload.is_empty() && !self.connections.contains_key(&canonical) {
+            return events;
+        }
+
+        let connection = self.connections.entry(canonical).or_insert_with(|| {
+            let key = match (syn, flags.contains(TcpFlag::ACK)) {
+                (true, true) => ConnectionKey { client: dst, server: src },
+                _ => ConnectionKey { client: src, server: dst },
+            };
+            TcpConnection { key, streams: [HalfStream::default(), HalfStream::default()], reset: false, last_seen: timestamp }
+        });
+        connection.last_seen = timestamp;
+
+        let key = connection.key;
+        let direction = key.direction(&src);
+        let mut chunks = Vec::new();
+        connection.streams[direction.index()].segment(tcp_packet.sequence_number, syn, fin, &tcp_packet.payload, &mut chunks);
+        events.extend(chunks.into_iter().map(|kind| StreamEvent { connection: key, direction, timestamp, kind }));
+
+        if rst {
+            connection.reset = true;
+        }
+        if connection.is_closed() {
+            let connection = self.connections.remove(&canonical).unwrap();
+            events.extend(TcpReassembler::close(connection, timestamp));
+        }
+        events
+    }
+
+    /// Closes the connections idle for more than `idle_timeout`, delivering the data still buffered.
+    pub fn expire(&mut self, now: DateTime<Utc>, idle_timeout: Duration) -> Vec<StreamEvent> {
+        let expired: Vec<(Endpoint, Endpoint)> = self.connections.iter()
+            .filter(|(_, connection)| now - connection.last_seen > idle_timeout)
+            .map(|(canonical, _)| *canonical)
+            .collect();
+
+        let mut events = Vec::new();
+        for canonical in expired {
+            let connection = self.connections.remove(&canonical).unwrap();
+            events.extend(TcpReassembler::close(connection, now));
+        }
+        events
+    }
+
+    /// Statistics of both directions (client to server, server to client) of a tracked connection.
+    pub fn statistics(&self, endpoint_1: Endpoint, endpoint_2: Endpoint) -> Option<(StreamStatistics, StreamStatistics)> {
+        self.connections.get(&ConnectionKey::canonical(endpoint_1, endpoint_2))
+            .map(|connection| (connection.streams[0].statistics.clone(), connection.streams[1].statistics.clone()))
+    }
+
+    fn close(mut connection: TcpConnection, timestamp: DateTime<Utc>) -> Vec<StreamEvent> {
+        let key = connection.key;
+        let mut events = Vec::new();
+        for direction in [Direction::ClientToServer, Direction::ServerToClient] {
+            let mut chunks = Vec::new();
+            connection.streams[direction.index()].flush(&mut chunks);
+            events.extend(chunks.into_iter().map(|kind| StreamEvent { connection: key, direction, timestamp, kind }));
+        }
+        events.push(StreamEvent { connection: key, direction: Direction::ClientToServer, timestamp, kind: StreamEventKind::Close });
+        events
+    }
+}
@@ -1,5 +1,6 @@
 use std::fmt::{Display, Formatter};
-use crate::network_components::services_upper_layers::upper_layer_services::{known_port, print_upper_layer, UpperLayerService};
+use crate::network_components::checksum::{checksum_mark, ChecksumStatus};
+use crate::network_components::services_upper_layers::upper_layer_services::{identify_service, print_upper_layer, ServiceIdentification, Transport};
 use crate::utility;
 
 pub struct UdpPacket {
@@ -7,7 +8,8 @@ pub struct UdpPacket {
     pub dst_port: u16,
     pub length: u16,
     pub checksum: u16,
-    pub upper_layer_service: UpperLayerService,
+    pub checksum_status: ChecksumStatus,
+    pub identification: ServiceIdentification,
     pub payload: Vec<u8>,
 }
 
@@ -21,7 +23,8 @@ impl UdpPacket {
             dst_port,
             length: u16::from_be_bytes((&udp_data_in_u8[4..6]).try_into().unwrap()),
             checksum: u16::from_be_bytes((&udp_data_in_u8[6..8]).try_into().unwrap()),
-            upper_layer_service: UpperLayerService::from(known_port(src_port, dst_port)),
+            checksum_status: ChecksumStatus::Unverified,
+            identification: identify_service(Transport::UDP, src_port, dst_port, &udp_data_in_u8[8..]),
             payload: Vec::from(&udp_data_in_u8[8..]),
         }
     }
@@ -32,14 +35,15 @@ impl Display for UdpPacket {
         write!(f, "UDP      ").unwrap();
         write!(
             f,
-            ": {} -> {}  - [length: {}, checksum: {:#04x}]\n",
+            ": {} -> {}  - [length: {}, checksum: {:#04x}{}]\n",
             self.src_port,
             self.dst_port,
             self.length,
             self.checksum,
+            checksum_mark(self.checksum_status),
         ).unwrap();
 
-        print_upper_layer(f, self.upper_layer_service).unwrap();
+        print_upper_layer(f, &self.identification, &self.payload, Transport::UDP).unwrap();
 
         write!(f, "\n > [{}]", utility::to_compact_hex(&self.payload))
     }
@@ -12,13 +12,15 @@
 //!     identification: ARP
 //!     future support: ()
 //! ### Layer 4 ("Protocol Type" field of L3 protocols)
-//!     full-support: UDP, (TCP)
-//!     identification: (). Others: IGMP, ICMPv4, ICMPv6
+//!     full-support: UDP, (TCP), ICMPv4, ICMPv6
+//!     identification: (). Others: IGMP
 //!     future support: ()
 //! ### Upper Layers
-//!     full-support: ()
-//!     identification: FTP=20, SSH=22, SMPT=23, DNS=53, HTTP=80, POP3=110
-//!                     SFTP=115, SNMP=161, BGP=179, HTTPS=443
+//!     full-support: DNS (UDP and TCP), HTTP/1.x (any port), TLS handshake (any port), QUIC (Initial decryption), DHCP, DHCPv6
+//!     identification: IANA well-known and registered ports, by transport (e.g. FTP=21, SSH=22, TELNET=23, SMTP=25,
+//!                     POP3=110, SNMP=161/udp, BGP=179, MYSQL=3306, MDNS=5353/udp), extensible with an /etc/services file.
+//!                     Payload inspection on any port, with a confidence: TLS, HTTP, SSH, DNS, QUIC, DHCP, SMTP, FTP,
+//!                     POP3, IMAP, SIP, RTP/RTCP, BitTorrent. Traffic identified by neither is reported as UNKNOWN
 //!     future support: ()
 //!
 
@@ -30,4 +32,6 @@ pub mod layer_4;
 
 pub mod services_upper_layers;
 
+pub mod checksum;
+
 mod tests;
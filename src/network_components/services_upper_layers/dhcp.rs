@@ -0,0 +1,546 @@
+//! # DHCP
+//!
+//! Parsing of DHCPv4 messages (RFC 2131, options of RFC 2132) and DHCPv6 messages (RFC 8415),
+//! and summary of the lease activity (clients, hostnames, addresses offered, leased, declined and released).
+//!
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::net::{Ipv4Addr, Ipv6Addr};
+use chrono::{DateTime, Utc};
+use crate::network_components::layer_2::mac_address::MacAddress;
+
+const MAGIC_COOKIE: [u8; 4] = [99, 130, 83, 99];
+/// Length of the fixed part of a DHCPv4 message (up to the magic cookie)
+const DHCPV4_HEADER_LENGTH: usize = 236;
+
+const OPTION_PAD: u8 = 0;
+const OPTION_SUBNET_MASK: u8 = 1;
+const OPTION_ROUTER: u8 = 3;
+const OPTION_DNS_SERVERS: u8 = 6;
+const OPTION_HOSTNAME: u8 = 12;
+const OPTION_REQUESTED_IP: u8 = 50;
+const OPTION_LEASE_TIME: u8 = 51;
+const OPTION_MESSAGE_TYPE: u8 = 53;
+const OPTION_SERVER_ID: u8 = 54;
+const OPTION_VENDOR_CLASS: u8 = 60;
+const OPTION_END: u8 = 255;
+
+const OPTION_V6_CLIENT_ID: u16 = 1;
+const OPTION_V6_SERVER_ID: u16 = 2;
+const OPTION_V6_IA_NA: u16 = 3;
+const OPTION_V6_IA_TA: u16 = 4;
+const OPTION_V6_IA_ADDRESS: u16 = 5;
+const OPTION_V6_RELAY_MESSAGE: u16 = 9;
+const OPTION_V6_STATUS_CODE: u16 = 13;
+const OPTION_V6_VENDOR_CLASS: u16 = 16;
+const OPTION_V6_DNS_SERVERS: u16 = 23;
+const OPTION_V6_IA_PD: u16 = 25;
+const OPTION_V6_IA_PREFIX: u16 = 26;
+const OPTION_V6_CLIENT_FQDN: u16 = 39;
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn ipv4_addresses(data: &[u8]) -> Vec<Ipv4Addr> {
+    data.chunks_exact(4).map(|address| Ipv4Addr::new(address[0], address[1], address[2], address[3])).collect()
+}
+
+fn ipv6_address(data: &[u8]) -> Ipv6Addr {
+    Ipv6Addr::from(<[u8; 16]>::try_from(data).unwrap())
+}
+
+fn join<T: ToString>(values: &[T]) -> String {
+    values.iter().map(|value| value.to_string()).collect::<Vec<String>>().join(", ")
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum DhcpMessageType {
+    Discover,
+    Offer,
+    Request,
+    Decline,
+    Ack,
+    Nak,
+    Release,
+    Inform,
+    Other(u8),
+}
+
+impl From<u8> for DhcpMessageType {
+    fn from(v: u8) -> DhcpMessageType {
+        match v {
+            1 => DhcpMessageType::Discover,
+            2 => DhcpMessageType::Offer,
+            3 => DhcpMessageType::Request,
+            4 => DhcpMessageType::Decline,
+            5 => DhcpMessageType::Ack,
+            6 => DhcpMessageType::Nak,
+            7 => DhcpMessageType::Release,
+            8 => DhcpMessageType::Inform,
+            x => DhcpMessageType::Other(x),
+        }
+    }
+}
+
+impl Display for DhcpMessageType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DhcpMessageType::Discover => write!(f, "DISCOVER"),
+            DhcpMessageType::Offer => write!(f, "OFFER"),
+            DhcpMessageType::Request => write!(f, "REQUEST"),
+            DhcpMessageType::Decline => write!(f, "DECLINE"),
+            DhcpMessageType::Ack => write!(f, "ACK"),
+            DhcpMessageType::Nak => write!(f, "NAK"),
+            DhcpMessageType::Release => write!(f, "RELEASE"),
+            DhcpMessageType::Inform => write!(f, "INFORM"),
+            DhcpMessageType::Other(x) => write!(f, "TYPE{}", x),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// `DHCPv4 Message`
+pub struct DhcpMessage {
+    /// 1 for the messages sent by the clients, 2 for the ones sent by the servers
+    pub op: u8,
+    pub transaction_id: u32,
+    /// Client IP address (ciaddr)
+    pub client_ip: Ipv4Addr,
+    /// Address assigned to the client (yiaddr)
+    pub your_ip: Ipv4Addr,
+    /// Next server IP address (siaddr)
+    pub server_ip: Ipv4Addr,
+    /// Relay agent IP address (giaddr)
+    pub relay_ip: Ipv4Addr,
+    /// Client hardware address (chaddr)
+    pub client_hardware_address: String,
+    /// Message type option (None for BOOTP messages)
+    pub message_type: Option<DhcpMessageType>,
+    pub requested_ip: Option<Ipv4Addr>,
+    pub hostname: Option<String>,
+    pub vendor_class: Option<String>,
+    /// Lease time in seconds
+    pub lease_time: Option<u32>,
+    pub subnet_mask: Option<Ipv4Addr>,
+    pub routers: Vec<Ipv4Addr>,
+    pub dns_servers: Vec<Ipv4Addr>,
+    pub server_id: Option<Ipv4Addr>,
+    /// All the options (code, data) in order
+    pub options: Vec<(u8, Vec<u8>)>,
+}
+
+impl DhcpMessage {
+    /// Parses a DHCPv4 message from the payload of a UDP datagram.
+    pub fn parse(payload_in_u8: &[u8]) -> Option<DhcpMessage> {
+        if payload_in_u8.len() < DHCPV4_HEADER_LENGTH + MAGIC_COOKIE.len() || payload_in_u8[DHCPV4_HEADER_LENGTH..DHCPV4_HEADER_LENGTH + 4] != MAGIC_COOKIE {
+            return None;
+        }
+        let op = payload_in_u8[0];
+        if op != 1 && op != 2 {
+            return None;
+        }
+        let hardware_type = payload_in_u8[1];
+        let hardware_length = (payload_in_u8[2] as usize).min(16);
+        let chaddr = &payload_in_u8[28..28 + hardware_length];
+        let address = |offset: usize| Ipv4Addr::new(payload_in_u8[offset], payload_in_u8[offset + 1], payload_in_u8[offset + 2], payload_in_u8[offset + 3]);
+
+        let mut message = DhcpMessage {
+            op,
+            transaction_id: u32::from_be_bytes(payload_in_u8[4..8].try_into().unwrap()),
+            client_ip: address(12),
+            your_ip: address(16),
+            server_ip: address(20),
+            relay_ip: address(24),
+            client_hardware_address: if hardware_type == 1 && hardware_length == 6 { MacAddress::new(chaddr).mac() } else { hex(chaddr) },
+            message_type: None,
+            requested_ip: None,
+            hostname: None,
+            vendor_class: None,
+            lease_time: None,
+            subnet_mask: None,
+            routers: Vec::new(),
+            dns_servers: Vec::new(),
+            server_id: None,
+            options: Vec::new(),
+        };
+
+        let mut offset = DHCPV4_HEADER_LENGTH + MAGIC_COOKIE.len();
+        while let Some(&code) = payload_in_u8.get(offset) {
+            match code {
+                OPTION_PAD => { offset += 1; continue; },
+                OPTION_END => break,
+                _ => {}
+            }
+            let length = *payload_in_u8.get(offset + 1)? as usize;
+            let data = payload_in_u8.get(offset + 2..offset + 2 + length)?;
+            offset += 2 + length;
+
+            match code {
+                OPTION_MESSAGE_TYPE => message.message_type = data.first().map(|message_type| DhcpMessageType::from(*message_type)),
+                OPTION_REQUESTED_IP => message.requested_ip = ipv4_addresses(data).first().cloned(),
+                OPTION_HOSTNAME => message.hostname = Some(String::from_utf8_lossy(data).to_string()),
+                OPTION_VENDOR_CLASS => message.vendor_class = Some(String::from_utf8_lossy(data).to_string()),
+                OPTION_LEASE_TIME if length == 4 => message.lease_time = Some(u32::from_be_bytes(data.try_into().unwrap())),
+                OPTION_SUBNET_MASK => message.subnet_mask = ipv4_addresses(data).first().cloned(),
+                OPTION_ROUTER => message.routers = ipv4_addresses(data),
+                OPTION_DNS_SERVERS => message.dns_servers = ipv4_addresses(data),
+                OPTION_SERVER_ID => message.server_id = ipv4_addresses(data).first().cloned(),
+                _ => {}
+            }
+            message.options.push((code, Vec::from(data)));
+        }
+        Some(message)
+    }
+
+    /// Name of the message: DHCP message type, or BOOTP operation
+    pub fn name(&self) -> String {
+        match self.message_type {
+            Some(message_type) => message_type.to_string(),
+            None if self.op == 1 => "BOOTREQUEST".to_string(),
+            None => "BOOTREPLY".to_string(),
+        }
+    }
+
+    /// Address the message is about: assigned by the server, requested or in use by the client
+    pub fn address(&self) -> Option<Ipv4Addr> {
+        [Some(self.your_ip), self.requested_ip, Some(self.client_ip)].into_iter().flatten().find(|address| !address.is_unspecified())
+    }
+}
+
+impl Display for DhcpMessage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "DHCP    : {} 0x{:08x} - [client: {}, client-ip: {}, your-ip: {}, server-ip: {}, relay-ip: {}]",
+            self.name(),
+            self.transaction_id,
+            self.client_hardware_address,
+            self.client_ip,
+            self.your_ip,
+            self.server_ip,
+            self.relay_ip).unwrap();
+        if let Some(requested_ip) = self.requested_ip { write!(f, "\n > requested  : {}", requested_ip).unwrap(); }
+        if let Some(hostname) = &self.hostname { write!(f, "\n > hostname   : {}", hostname).unwrap(); }
+        if let Some(vendor_class) = &self.vendor_class { write!(f, "\n > vendor     : {}", vendor_class).unwrap(); }
+        if let Some(lease_time) = self.lease_time { write!(f, "\n > lease time : {}s", lease_time).unwrap(); }
+        if let Some(subnet_mask) = self.subnet_mask { write!(f, "\n > mask       : {}", subnet_mask).unwrap(); }
+        if !self.routers.is_empty() { write!(f, "\n > router     : {}", join(&self.routers)).unwrap(); }
+        if !self.dns_servers.is_empty() { write!(f, "\n > dns        : {}", join(&self.dns_servers)).unwrap(); }
+        if let Some(server_id) = self.server_id { write!(f, "\n > server id  : {}", server_id).unwrap(); }
+        Ok(())
+    }
+}
+
+/// Name of a DHCPv6 message type
+pub fn dhcpv6_message_type_name(message_type: u8) -> String {
+    match message_type {
+        1 => "SOLICIT",
+        2 => "ADVERTISE",
+        3 => "REQUEST",
+        4 => "CONFIRM",
+        5 => "RENEW",
+        6 => "REBIND",
+        7 => "REPLY",
+        8 => "RELEASE",
+        9 => "DECLINE",
+        10 => "RECONFIGURE",
+        11 => "INFORMATION-REQUEST",
+        12 => "RELAY-FORW",
+        13 => "RELAY-REPL",
+        x => return format!("TYPE{}", x),
+    }.to_string()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Address (or delegated prefix) of a DHCPv6 identity association
+pub struct Dhcpv6Lease {
+    pub address: Ipv6Addr,
+    /// Prefix length of a delegated prefix (None for addresses)
+    pub prefix_length: Option<u8>,
+    /// Preferred lifetime in seconds
+    pub preferred_lifetime: u32,
+    /// Valid lifetime in seconds
+    pub valid_lifetime: u32,
+}
+
+impl Display for Dhcpv6Lease {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.prefix_length {
+            Some(prefix_length) => write!(f, "{}/{}", self.address, prefix_length),
+            None => write!(f, "{}", self.address),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// `DHCPv6 Message` (the message relayed is decoded in place of relay messages)
+pub struct Dhcpv6Message {
+    pub message_type: u8,
+    pub transaction_id: u32,
+    /// Relay agents the message went through (link addresses)
+    pub relays: Vec<Ipv6Addr>,
+    /// Client DUID
+    pub client_id: Option<String>,
+    /// Server DUID
+    pub server_id: Option<String>,
+    /// Addresses and prefixes of the identity associations
+    pub leases: Vec<Dhcpv6Lease>,
+    pub dns_servers: Vec<Ipv6Addr>,
+    /// Domain name of the Client FQDN option
+    pub hostname: Option<String>,
+    pub vendor_class: Option<String>,
+    /// Status code and message
+    pub status: Option<(u16, String)>,
+}
+
+impl Dhcpv6Message {
+    /// Parses a DHCPv6 message from the payload of a UDP datagram.
+    pub fn parse(payload_in_u8: &[u8]) -> Option<Dhcpv6Message> {
+        let mut relays = Vec::new();
+        let mut payload_in_u8 = payload_in_u8;
+        // relay messages carry the client message in the Relay Message option
+        while matches!(payload_in_u8.first(), Some(12) | Some(13)) {
+            relays.push(ipv6_address(payload_in_u8.get(2..18)?));
+            payload_in_u8 = Dhcpv6Message::options(payload_in_u8.get(34..)?)?
+                .into_iter()
+                .find(|(code, _)| *code == OPTION_V6_RELAY_MESSAGE)
+                .map(|(_, data)| data)?;
+        }
+
+        let header = payload_in_u8.get(..4)?;
+        let mut message = Dhcpv6Message {
+            message_type: header[0],
+            transaction_id: u32::from_be_bytes([0, header[1], header[2], header[3]]),
+            relays,
+            client_id: None,
+            server_id: None,
+            leases: Vec::new(),
+            dns_servers: Vec::new(),
+            hostname: None,
+            vendor_class: None,
+            status: None,
+        };
+        if message.message_type == 0 || message.message_type > 13 {
+            return None;
+        }
+        message.read_options(&payload_in_u8[4..])?;
+        Some(message)
+    }
+
+    /// Options (code, data) of an option list
+    fn options(data: &[u8]) -> Option<Vec<(u16, &[u8])>> {
+        let mut options = Vec::new();
+        let mut offset = 0;
+        while offset < data.len() {
+            let code = u16::from_be_bytes(data.get(offset..offset + 2)?.try_into().unwrap());
+            let length = u16::from_be_bytes(data.get(offset + 2..offset + 4)?.try_into().unwrap()) as usize;
+            options.push((code, data.get(offset + 4..offset + 4 + length)?));
+            offset += 4 + length;
+        }
+        Some(options)
+    }
+
+    fn read_options(&mut self, data: &[u8]) -> Option<()> {
+        for (code, data) in Dhcpv6Message::options(data)? {
+            match code {
+                OPTION_V6_CLIENT_ID => self.client_id = Some(hex(data)),
+                OPTION_V6_SERVER_ID => self.server_id = Some(hex(data)),
+                // identity associations: IAID, T1 and T2 (not for temporary addresses), then the IA options
+                OPTION_V6_IA_NA | OPTION_V6_IA_PD => self.read_options(data.get(12..)?)?,
+                OPTION_V6_IA_TA => self.read_options(data.get(4..)?)?,
+                OPTION_V6_IA_ADDRESS if data.len() >= 24 => {
+                    self.leases.push(Dhcpv6Lease {
+                        address: ipv6_address(&data[..16]),
+                        prefix_length: None,
+                        preferred_lifetime: u32::from_be_bytes(data[16..20].try_into().unwrap()),
+                        valid_lifetime: u32::from_be_bytes(data[20..24].try_into().unwrap()),
+                    });
+                },
+                OPTION_V6_IA_PREFIX if data.len() >= 25 => {
+                    self.leases.push(Dhcpv6Lease {
+                        address: ipv6_address(&data[9..25]),
+                        prefix_length: Some(data[8]),
+                        preferred_lifetime: u32::from_be_bytes(data[0..4].try_into().unwrap()),
+                        valid_lifetime: u32::from_be_bytes(data[4..8].try_into().unwrap()),
+                    });
+                },
+                OPTION_V6_STATUS_CODE if data.len() >= 2 => {
+                    self.status = Some((u16::from_be_bytes([data[0], data[1]]), String::from_utf8_lossy(&data[2..]).to_string()));
+                },
+                OPTION_V6_DNS_SERVERS => self.dns_servers = data.chunks_exact(16).map(ipv6_address).collect(),
+                OPTION_V6_CLIENT_FQDN if !data.is_empty() => {
+                    // flags, then the domain name in DNS format
+                    let mut labels = Vec::new();
+                    let mut offset = 1;
+                    while let Some(&length) = data.get(offset) {
+                        if length == 0 { break; }
+                        labels.push(String::from_utf8_lossy(data.get(offset + 1..offset + 1 + length as usize)?).to_string());
+                        offset += 1 + length as usize;
+                    }
+                    self.hostname = Some(labels.join("."));
+                },
+                OPTION_V6_VENDOR_CLASS if data.len() > 6 => {
+                    // enterprise number, then vendor class data items with a 2 bytes length
+                    let mut items = Vec::new();
+                    let mut offset = 4;
+                    while offset + 2 <= data.len() {
+                        let length = u16::from_be_bytes([data[offset], data[offset + 1]]) as usize;
+                        items.push(String::from_utf8_lossy(data.get(offset + 2..offset + 2 + length)?).to_string());
+                        offset += 2 + length;
+                    }
+                    self.vendor_class = Some(items.join(" "));
+                },
+                _ => {}
+            }
+        }
+        Some(())
+    }
+
+    /// Name of the message type
+    pub fn name(&self) -> String {
+        dhcpv6_message_type_name(self.message_type)
+    }
+}
+
+impl Display for Dhcpv6Message {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "DHCPv6  : {} 0x{:06x} - [client: {}, server: {}]",
+            self.name(),
+            self.transaction_id,
+            self.client_id.as_deref().unwrap_or("-"),
+            self.server_id.as_deref().unwrap_or("-")).unwrap();
+        if !self.relays.is_empty() { write!(f, "\n > relayed by: {}", join(&self.relays)).unwrap(); }
+        for lease in self.leases.iter() {
+            write!(f, "\n > lease      : {} (preferred {}s, valid {}s)", lease, lease.preferred_lifetime, lease.valid_lifetime).unwrap();
+        }
+        if let Some(hostname) = &self.hostname { write!(f, "\n > hostname   : {}", hostname).unwrap(); }
+        if let Some(vendor_class) = &self.vendor_class { write!(f, "\n > vendor     : {}", vendor_class).unwrap(); }
+        if !self.dns_servers.is_empty() { write!(f, "\n > dns        : {}", join(&self.dns_servers)).unwrap(); }
+        if let Some((code, message)) = &self.status { write!(f, "\n > status     : {} {}", code, message).unwrap(); }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// DHCP message of the lease activity
+pub struct DhcpActivity {
+    pub timestamp: DateTime<Utc>,
+    /// "DHCPv4"/"DHCPv6"
+    pub version: &'static str,
+    pub message: String,
+    pub transaction_id: u32,
+    /// Client hardware address (DHCPv4) or DUID (DHCPv6)
+    pub client: String,
+    /// Hostname of the client, also when sent in a previous message
+    pub hostname: Option<String>,
+    pub addresses: Vec<String>,
+    /// Lease time in seconds
+    pub lease_time: Option<u32>,
+    /// Server identifier
+    pub server: Option<String>,
+}
+
+impl Display for DhcpActivity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let optional = |value: Option<String>| value.unwrap_or_else(|| "-".to_string());
+        write!(f, "{0: <35} | {1: <7} | {2: <19} | {3: <11} | {4: <40} | {5: <25} | {6: <39} | {7: <10} | {8: <39}",
+            self.timestamp.to_string(),
+            self.version,
+            self.message,
+            format!("0x{:x}", self.transaction_id),
+            self.client,
+            optional(self.hostname.clone()),
+            if self.addresses.is_empty() { "-".to_string() } else { self.addresses.join(", ") },
+            optional(self.lease_time.map(|lease_time| lease_time.to_string())),
+            optional(self.server.clone()))
+    }
+}
+
+/// `DHCP Leases`: lease activity of the "report" format, one row for each DHCP message,
+/// with the hostnames sent by the clients remembered across messages.
+///
+/// # Example
+/// ```
+/// let mut dhcp_leases = DhcpLeases::new();
+/// dhcp_leases.push(&dhcp_message, timestamp);
+/// let section = dhcp_leases.report_section();
+/// ```
+#[derive(Default)]
+pub struct DhcpLeases {
+    /// Messages of the current report interval
+    activity: Vec<DhcpActivity>,
+    /// Hostnames sent by the clients
+    hostnames: HashMap<String, String>,
+}
+
+impl DhcpLeases {
+    /// `new`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Messages of the current report interval
+    pub fn activity(&self) -> &[DhcpActivity] {
+        &self.activity
+    }
+
+    /// Hostname sent by a client (hardware address or DUID)
+    pub fn hostname(&self, client: &str) -> Option<&str> {
+        self.hostnames.get(client).map(String::as_str)
+    }
+
+    fn remember_hostname(&mut self, client: &str, hostname: &Option<String>) -> Option<String> {
+        if let Some(hostname) = hostname {
+            self.hostnames.insert(client.to_string(), hostname.clone());
+        }
+        self.hostnames.get(client).cloned()
+    }
+
+    /// Adds a DHCPv4 message to the lease activity.
+    pub fn push(&mut self, message: &DhcpMessage, timestamp: DateTime<Utc>) {
+        let hostname = self.remember_hostname(&message.client_hardware_address, &message.hostname);
+        self.activity.push(DhcpActivity {
+            timestamp,
+            version: "DHCPv4",
+            message: message.name(),
+            transaction_id: message.transaction_id,
+            client: message.client_hardware_address.clone(),
+            hostname,
+            addresses: message.address().into_iter().map(|address| address.to_string()).collect(),
+            lease_time: message.lease_time,
+            server: message.server_id.map(|server_id| server_id.to_string()),
+        });
+    }
+
+    /// Adds a DHCPv6 message to the lease activity.
+    pub fn push_v6(&mut self, message: &Dhcpv6Message, timestamp: DateTime<Utc>) {
+        let client = message.client_id.clone().unwrap_or_else(|| "-".to_string());
+        let hostname = self.remember_hostname(&client, &message.hostname);
+        self.activity.push(DhcpActivity {
+            timestamp,
+            version: "DHCPv6",
+            message: message.name(),
+            transaction_id: message.transaction_id,
+            client,
+            hostname,
+            addresses: message.leases.iter().map(|lease| lease.to_string()).collect(),
+            lease_time: message.leases.iter().map(|lease| lease.valid_lifetime).max(),
+            server: message.server_id.clone(),
+        });
+    }
+
+    /// Section of the "report" format with the lease activity of the interval (None without DHCP traffic).
+    /// The activity is reset every report interval.
+    pub fn report_section(&mut self) -> Option<String> {
+        if self.activity.is_empty() {
+            return None;
+        }
+        let mut section = format!("DHCP lease activity: {} messages\n", self.activity.len());
+        section.push_str(format!("{0: <35} | {1: <7} | {2: <19} | {3: <11} | {4: <40} | {5: <25} | {6: <39} | {7: <10} | {8: <39}\n",
+            "Timestamp", "Version", "Message", "Transaction", "Client", "Hostname", "Addresses", "Lease s", "Server").as_str());
+        for activity in self.activity.drain(..) {
+            section.push_str(format!("{}\n", activity).as_str());
+        }
+        Some(section)
+    }
+}
@@ -0,0 +1,567 @@
+//! # DNS
+//!
+//! Decoding of DNS messages (RFC 1035) carried over UDP and TCP: header, questions and resource records
+//! (A, AAAA, CNAME, NS, PTR, MX, TXT, SRV, SOA, SVCB/HTTPS, OPT/EDNS) with name compression.
+//!
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::net::{Ipv4Addr, Ipv6Addr};
+use chrono::{DateTime, Utc};
+use crate::network_components::layer_4::tcp_reassembly::{ConnectionKey, Direction, StreamDissector};
+use crate::utility;
+
+/// Maximum number of compression pointers followed while reading a name (protection against loops).
+const MAX_COMPRESSION_POINTERS: usize = 64;
+/// Maximum length of a name, as in RFC 1035.
+const MAX_NAME_LENGTH: usize = 255;
+/// Number of names listed in the "Top queried names" section of the DNS summary.
+const TOP_QUERIED_NAMES: usize = 10;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum DnsRecordType {
+    A,
+    NS,
+    CNAME,
+    SOA,
+    PTR,
+    MX,
+    TXT,
+    AAAA,
+    SRV,
+    OPT,
+    SVCB,
+    HTTPS,
+    Other(u16),
+}
+
+impl From<u16> for DnsRecordType {
+    fn from(v: u16) -> DnsRecordType {
+        match v {
+            1 => DnsRecordType::A,
+            2 => DnsRecordType::NS,
+            5 => DnsRecordType::CNAME,
+            6 => DnsRecordType::SOA,
+            12 => DnsRecordType::PTR,
+            15 => DnsRecordType::MX,
+            16 => DnsRecordType::TXT,
+            28 => DnsRecordType::AAAA,
+            33 => DnsRecordType::SRV,
+            41 => DnsRecordType::OPT,
+            64 => DnsRecordType::SVCB,
+            65 => DnsRecordType::HTTPS,
+            x => DnsRecordType::Other(x),
+        }
+    }
+}
+
+impl Display for DnsRecordType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DnsRecordType::Other(x) => write!(f, "TYPE{}", x),
+            record_type => write!(f, "{:?}", record_type),
+        }
+    }
+}
+
+/// Name of a DNS class (the top bit, used by mDNS as unicast-response/cache-flush flag, is ignored).
+pub fn class_name(class: u16) -> String {
+    match class & 0x7FFF {
+        1 => "IN".to_string(),
+        3 => "CH".to_string(),
+        4 => "HS".to_string(),
+        255 => "ANY".to_string(),
+        x => format!("CLASS{}", x),
+    }
+}
+
+/// Name of a (possibly extended) response code.
+pub fn rcode_name(rcode: u16) -> String {
+    match rcode {
+        0 => "NOERROR".to_string(),
+        1 => "FORMERR".to_string(),
+        2 => "SERVFAIL".to_string(),
+        3 => "NXDOMAIN".to_string(),
+        4 => "NOTIMP".to_string(),
+        5 => "REFUSED".to_string(),
+        6 => "YXDOMAIN".to_string(),
+        7 => "YXRRSET".to_string(),
+        8 => "NXRRSET".to_string(),
+        9 => "NOTAUTH".to_string(),
+        10 => "NOTZONE".to_string(),
+        16 => "BADVERS".to_string(),
+        x => format!("RCODE{}", x),
+    }
+}
+
+fn opcode_name(opcode: u8) -> String {
+    match opcode {
+        0 => "QUERY".to_string(),
+        1 => "IQUERY".to_string(),
+        2 => "STATUS".to_string(),
+        4 => "NOTIFY".to_string(),
+        5 => "UPDATE".to_string(),
+        x => format!("OPCODE{}", x),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// SVCB/HTTPS service parameter (RFC 9460).
+pub struct SvcParam {
+    pub key: u16,
+    pub value: Vec<u8>,
+}
+
+impl Display for SvcParam {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.key {
+            0 => {
+                let keys: Vec<String> = self.value.chunks_exact(2).map(|key| u16::from_be_bytes([key[0], key[1]]).to_string()).collect();
+                write!(f, "mandatory={}", keys.join(","))
+            },
+            1 => {
+                let mut protocols = Vec::new();
+                let mut i = 0;
+                while let Some(&length) = self.value.get(i) {
+                    match self.value.get(i + 1..i + 1 + length as usize) {
+                        Some(protocol) => protocols.push(String::from_utf8_lossy(protocol).to_string()),
+                        None => break,
+                    }
+                    i += 1 + length as usize;
+                }
+                write!(f, "alpn={}", protocols.join(","))
+            },
+            2 => write!(f, "no-default-alpn"),
+            3 if self.value.len() == 2 => write!(f, "port={}", u16::from_be_bytes([self.value[0], self.value[1]])),
+            4 => {
+                let addresses: Vec<String> = self.value.chunks_exact(4).map(|ip| Ipv4Addr::new(ip[0], ip[1], ip[2], ip[3]).to_string()).collect();
+                write!(f, "ipv4hint={}", addresses.join(","))
+            },
+            5 => write!(f, "ech={}", utility::to_compact_hex(&self.value)),
+            6 => {
+                let addresses: Vec<String> = self.value.chunks_exact(16)
+                    .map(|ip| Ipv6Addr::from(<[u8; 16]>::try_from(ip).unwrap()).to_string())
+                    .collect();
+                write!(f, "ipv6hint={}", addresses.join(","))
+            },
+            key => write!(f, "key{}={}", key, utility::to_compact_hex(&self.value)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DnsRecordData {
+    A(Ipv4Addr),
+    AAAA(Ipv6Addr),
+    /// CNAME, NS and PTR records.
+    Name(String),
+    MX { preference: u16, exchange: String },
+    TXT(Vec<String>),
+    SRV { priority: u16, weight: u16, port: u16, target: String },
+    SOA { mname: String, rname: String, serial: u32, refresh: u32, retry: u32, expire: u32, minimum: u32 },
+    /// SVCB and HTTPS records.
+    SVCB { priority: u16, target: String, params: Vec<SvcParam> },
+    /// EDNS pseudo-record: the class holds the UDP payload size and the TTL the extended rcode, the version and the flags.
+    OPT { udp_payload_size: u16, extended_rcode: u8, version: u8, dnssec_ok: bool, options: Vec<(u16, Vec<u8>)> },
+    /// Unsupported or malformed record data.
+    Unknown(Vec<u8>),
+}
+
+impl Display for DnsRecordData {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DnsRecordData::A(ip_addr) => write!(f, "{}", ip_addr),
+            DnsRecordData::AAAA(ip_addr) => write!(f, "{}", ip_addr),
+            DnsRecordData::Name(name) => write!(f, "{}", name),
+            DnsRecordData::MX { preference, exchange } => write!(f, "{} {}", preference, exchange),
+            DnsRecordData::TXT(strings) => {
+                let strings: Vec<String> = strings.iter().map(|string| format!("{:?}", string)).collect();
+                write!(f, "{}", strings.join(" "))
+            },
+            DnsRecordData::SRV { priority, weight, port, target } => write!(f, "{} {} {} {}", priority, weight, port, target),
+            DnsRecordData::SOA { mname, rname, serial, refresh, retry, expire, minimum } => {
+                write!(f, "{} {} {} {} {} {} {}", mname, rname, serial, refresh, retry, expire, minimum)
+            },
+            DnsRecordData::SVCB { priority, target, params } => {
+                write!(f, "{} {}", priority, target).unwrap();
+                params.iter().try_for_each(|param| write!(f, " {}", param))
+            },
+            DnsRecordData::OPT { udp_payload_size, extended_rcode, version, dnssec_ok, options } => {
+                write!(f, "udp-payload-size: {}, extended-rcode: {}, version: {}, flags: {}", udp_payload_size, extended_rcode, version, if *dnssec_ok { "DO" } else { "" }).unwrap();
+                options.iter().try_for_each(|(code, data)| write!(f, ", option {} [{}]", code, utility::to_compact_hex(data)))
+            },
+            DnsRecordData::Unknown(data) => write!(f, "[{}]", utility::to_compact_hex(data)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DnsQuestion {
+    pub name: String,
+    pub record_type: DnsRecordType,
+    pub class: u16,
+}
+
+impl Display for DnsQuestion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} {}", self.name, class_name(self.class), self.record_type)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DnsResourceRecord {
+    pub name: String,
+    pub record_type: DnsRecordType,
+    pub class: u16,
+    pub ttl: u32,
+    pub data: DnsRecordData,
+}
+
+impl Display for DnsResourceRecord {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.data {
+            DnsRecordData::OPT { .. } => write!(f, "{} {} {}", self.name, self.record_type, self.data),
+            _ => write!(f, "{} {} {} {} {}", self.name, class_name(self.class), self.record_type, self.ttl, self.data),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DnsMessage {
+    pub id: u16,
+    /// Header flags: QR, opcode, AA, TC, RD, RA, Z, AD, CD and rcode
+    pub flags: u16,
+    pub questions: Vec<DnsQuestion>,
+    pub answers: Vec<DnsResourceRecord>,
+    pub authorities: Vec<DnsResourceRecord>,
+    pub additionals: Vec<DnsResourceRecord>,
+}
+
+impl DnsMessage {
+    /// Decodes a DNS message (as carried by UDP). None if the message is truncated or malformed.
+    pub fn parse(message_in_u8: &[u8]) -> Option<DnsMessage> {
+        if message_in_u8.len() < 12 {
+            return None;
+        }
+        let count = |i: usize| u16::from_be_bytes([message_in_u8[i], message_in_u8[i + 1]]) as usize;
+        let (question_count, answer_count, authority_count, additional_count) = (count(4), count(6), count(8), count(10));
+
+        let mut offset = 12;
+        let mut questions = Vec::new();
+        for _ in 0..question_count {
+            let (name, next) = read_name(message_in_u8, offset)?;
+            let fixed = message_in_u8.get(next..next + 4)?;
+            questions.push(DnsQuestion {
+                name,
+                record_type: DnsRecordType::from(u16::from_be_bytes([fixed[0], fixed[1]])),
+                class: u16::from_be_bytes([fixed[2], fixed[3]]),
+            });
+            offset = next + 4;
+        }
+
+        let mut sections = [Vec::new(), Vec::new(), Vec::new()];
+        for (section, record_count) in sections.iter_mut().zip([answer_count, authority_count, additional_count]) {
+            for _ in 0..record_count {
+                let (record, next) = read_record(message_in_u8, offset)?;
+                section.push(record);
+                offset = next;
+            }
+        }
+        let [answers, authorities, additionals] = sections;
+
+        Some(DnsMessage {
+            id: u16::from_be_bytes([message_in_u8[0], message_in_u8[1]]),
+            flags: u16::from_be_bytes([message_in_u8[2], message_in_u8[3]]),
+            questions,
+            answers,
+            authorities,
+            additionals,
+        })
+    }
+
+    /// Decodes the DNS messages of a TCP segment, each one preceded by its 2 bytes length.
+    /// Stops at the first incomplete or malformed message.
+    pub fn parse_tcp(payload_in_u8: &[u8]) -> Vec<DnsMessage> {
+        let mut messages = Vec::new();
+        let mut offset = 0;
+        while let Some(length) = payload_in_u8.get(offset..offset + 2) {
+            let length = u16::from_be_bytes([length[0], length[1]]) as usize;
+            match payload_in_u8.get(offset + 2..offset + 2 + length).and_then(DnsMessage::parse) {
+                Some(message) => messages.push(message),
+                None => break,
+            }
+            offset += 2 + length;
+        }
+        messages
+    }
+
+    pub fn is_response(&self) -> bool {
+        self.flags & 0x8000 != 0
+    }
+
+    pub fn opcode(&self) -> u8 {
+        ((self.flags >> 11) & 0x0F) as u8
+    }
+
+    /// Response code, extended with the upper bits carried by the OPT record (EDNS).
+    pub fn rcode(&self) -> u16 {
+        let extended_rcode = self.additionals.iter().find_map(|record| match record.data {
+            DnsRecordData::OPT { extended_rcode, .. } => Some(extended_rcode as u16),
+            _ => None,
+        }).unwrap_or(0);
+        (extended_rcode << 4) | (self.flags & 0x000F)
+    }
+
+    /// Names of the header flags set (AA, TC, RD, RA, AD, CD)
+    pub fn flag_names(&self) -> Vec<&'static str> {
+        [(0x0400, "AA"), (0x0200, "TC"), (0x0100, "RD"), (0x0080, "RA"), (0x0020, "AD"), (0x0010, "CD")].iter()
+            .filter(|(mask, _)| self.flags & mask != 0)
+            .map(|(_, name)| *name)
+            .collect()
+    }
+}
+
+impl Display for DnsMessage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "DNS     : {} {:#06x}{} - [opcode: {}, flags: {}, questions: {}, answers: {}, authorities: {}, additionals: {}]",
+            if self.is_response() { "response" } else { "query" },
+            self.id,
+            if self.is_response() { format!(" {}", rcode_name(self.rcode())) } else { String::new() },
+            opcode_name(self.opcode()),
+            self.flag_names().join(" "),
+            self.questions.len(),
+            self.answers.len(),
+            self.authorities.len(),
+            self.additionals.len(),
+        ).unwrap();
+
+        self.questions.iter().try_for_each(|question| write!(f, "\n > question   : {}", question)).unwrap();
+        self.answers.iter().try_for_each(|record| write!(f, "\n > answer     : {}", record)).unwrap();
+        self.authorities.iter().try_for_each(|record| write!(f, "\n > authority  : {}", record)).unwrap();
+        self.additionals.iter().try_for_each(|record| write!(f, "\n > additional : {}", record))
+    }
+}
+
+/// Reads a (possibly compressed) name starting at `offset`. Returns the name and the offset following it.
+fn read_name(message_in_u8: &[u8], offset: usize) -> Option<(String, usize)> {
+    let mut labels: Vec<String> = Vec::new();
+    let mut name_length = 0;
+    let mut position = offset;
+    let mut next = None;
+    let mut pointers = 0;
+
+    loop {
+        let length = *message_in_u8.get(position)? as usize;
+        match length & 0xC0 {
+            0x00 if length == 0 => {
+                break;
+            },
+            0x00 => {
+                let label = message_in_u8.get(position + 1..position + 1 + length)?;
+                name_length += length + 1;
+                if name_length > MAX_NAME_LENGTH { return None; }
+                labels.push(String::from_utf8_lossy(label).to_string());
+                position += 1 + length;
+            },
+            0xC0 => {
+                let pointer = ((length & 0x3F) << 8) | *message_in_u8.get(position + 1)? as usize;
+                pointers += 1;
+                if pointers > MAX_COMPRESSION_POINTERS { return None; }
+                next.get_or_insert(position + 2);
+                position = pointer;
+            },
+            _ => { return None; }
+        }
+    }
+
+    let name = if labels.is_empty() { ".".to_string() } else { labels.join(".") };
+    Some((name, next.unwrap_or(position + 1)))
+}
+
+/// Reads a resource record starting at `offset`. Returns the record and the offset following it.
+fn read_record(message_in_u8: &[u8], offset: usize) -> Option<(DnsResourceRecord, usize)> {
+    let (name, next) = read_name(message_in_u8, offset)?;
+    let fixed = message_in_u8.get(next..next + 10)?;
+    let record_type = DnsRecordType::from(u16::from_be_bytes([fixed[0], fixed[1]]));
+    let class = u16::from_be_bytes([fixed[2], fixed[3]]);
+    let ttl = u32::from_be_bytes([fixed[4], fixed[5], fixed[6], fixed[7]]);
+    let data_length = u16::from_be_bytes([fixed[8], fixed[9]]) as usize;
+    let data_offset = next + 10;
+    let data_in_u8 = message_in_u8.get(data_offset..data_offset + data_length)?;
+
+    let data = read_record_data(message_in_u8, data_offset, data_in_u8, record_type, class, ttl)
+        .unwrap_or_else(|| DnsRecordData::Unknown(Vec::from(data_in_u8)));
+
+    Some((DnsResourceRecord { name, record_type, class, ttl, data }, data_offset + data_length))
+}
+
+fn read_record_data(message_in_u8: &[u8], data_offset: usize, data_in_u8: &[u8], record_type: DnsRecordType, class: u16, ttl: u32) -> Option<DnsRecordData> {
+    let u16_at = |i: usize| -> Option<u16> { Some(u16::from_be_bytes(data_in_u8.get(i..i + 2)?.try_into().ok()?)) };
+    let u32_at = |i: usize| -> Option<u32> { Some(u32::from_be_bytes(data_in_u8.get(i..i + 4)?.try_into().ok()?)) };
+
+    match record_type {
+        DnsRecordType::A if data_in_u8.len() == 4 => Some(DnsRecordData::A(Ipv4Addr::new(data_in_u8[0], data_in_u8[1], data_in_u8[2], data_in_u8[3]))),
+        DnsRecordType::AAAA if data_in_u8.len() == 16 => Some(DnsRecordData::AAAA(Ipv6Addr::from(<[u8; 16]>::try_from(data_in_u8).ok()?))),
+        DnsRecordType::CNAME | DnsRecordType::NS | DnsRecordType::PTR => Some(DnsRecordData::Name(read_name(message_in_u8, data_offset)?.0)),
+        DnsRecordType::MX => Some(DnsRecordData::MX {
+            preference: u16_at(0)?,
+            exchange: read_name(message_in_u8, data_offset + 2)?.0,
+        }),
+        DnsRecordType::TXT => {
+            let mut strings = Vec::new();
+            let mut i = 0;
+            while let Some(&length) = data_in_u8.get(i) {
+                strings.push(String::from_utf8_lossy(data_in_u8.get(i + 1..i + 1 + length as usize)?).to_string());
+                i += 1 + length as usize;
+            }
+            Some(DnsRecordData::TXT(strings))
+        },
+        DnsRecordType::SRV => Some(DnsRecordData::SRV {
+            priority: u16_at(0)?,
+            weight: u16_at(2)?,
+            port: u16_at(4)?,
+            target: read_name(message_in_u8, data_offset + 6)?.0,
+        }),
+        DnsRecordType::SOA => {
+            let (mname, next) = read_name(message_in_u8, data_offset)?;
+            let (rname, next) = read_name(message_in_u8, next)?;
+            let i = next - data_offset;
+            Some(DnsRecordData::SOA { mname, rname, serial: u32_at(i)?, refresh: u32_at(i + 4)?, retry: u32_at(i + 8)?, expire: u32_at(i + 12)?, minimum: u32_at(i + 16)? })
+        },
+        DnsRecordType::SVCB | DnsRecordType::HTTPS => {
+            let (target, next) = read_name(message_in_u8, data_offset + 2)?;
+            let mut params = Vec::new();
+            let mut i = next - data_offset;
+            while i < data_in_u8.len() {
+                let length = u16_at(i + 2)? as usize;
+                params.push(SvcParam { key: u16_at(i)?, value: Vec::from(data_in_u8.get(i + 4..i + 4 + length)?) });
+                i += 4 + length;
+            }
+            Some(DnsRecordData::SVCB { priority: u16_at(0)?, target, params })
+        },
+        DnsRecordType::OPT => {
+            let mut options = Vec::new();
+            let mut i = 0;
+            while i < data_in_u8.len() {
+                let length = u16_at(i + 2)? as usize;
+                options.push((u16_at(i)?, Vec::from(data_in_u8.get(i + 4..i + 4 + length)?)));
+                i += 4 + length;
+            }
+            Some(DnsRecordData::OPT {
+                udp_payload_size: class,
+                extended_rcode: (ttl >> 24) as u8,
+                version: (ttl >> 16) as u8,
+                dnssec_ok: ttl & 0x8000 != 0,
+                options,
+            })
+        },
+        _ => None,
+    }
+}
+
+/// `DNS Summary` of the DNS traffic of a report interval: top queried names and response codes.
+/// DNS over UDP is pushed message by message, DNS over TCP is received as reassembled stream.
+#[derive(Default)]
+pub struct DnsSummary {
+    queries: HashMap<String, usize>,
+    rcodes: HashMap<String, usize>,
+    messages: usize,
+    /// Partial DNS over TCP messages, for each direction of each connection (None after a gap: the framing is lost)
+    tcp_buffers: HashMap<(ConnectionKey, Direction), Option<Vec<u8>>>,
+}
+
+impl DnsSummary {
+    /// `new`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of DNS messages of the current report interval
+    pub fn len(&self) -> usize {
+        self.messages
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages == 0
+    }
+
+    /// `Push` a DNS message into the summary.
+    pub fn push(&mut self, message: &DnsMessage) {
+        self.messages += 1;
+        if message.is_response() {
+            *self.rcodes.entry(rcode_name(message.rcode())).or_insert(0) += 1;
+        } else {
+            for question in message.questions.iter() {
+                *self.queries.entry(question.name.to_lowercase()).or_insert(0) += 1;
+            }
+        }
+    }
+
+    /// Queried names sorted by number of queries (most queried first).
+    pub fn top_queried_names(&self, n: usize) -> Vec<(String, usize)> {
+        let mut queries: Vec<(String, usize)> = self.queries.iter().map(|(name, count)| (name.clone(), *count)).collect();
+        queries.sort_by(|(name_1, count_1), (name_2, count_2)| count_2.cmp(count_1).then(name_1.cmp(name_2)));
+        queries.truncate(n);
+        queries
+    }
+
+    /// Number of responses for each response code.
+    pub fn rcode_counts(&self) -> Vec<(String, usize)> {
+        let mut rcodes: Vec<(String, usize)> = self.rcodes.iter().map(|(rcode, count)| (rcode.clone(), *count)).collect();
+        rcodes.sort_by(|(rcode_1, count_1), (rcode_2, count_2)| count_2.cmp(count_1).then(rcode_1.cmp(rcode_2)));
+        rcodes
+    }
+}
+
+impl StreamDissector for DnsSummary {
+    fn on_data(&mut self, connection: &ConnectionKey, direction: Direction, data: &[u8], _timestamp: DateTime<Utc>) {
+        if connection.server.port != 53 && connection.client.port != 53 {
+            return;
+        }
+        let buffer = match self.tcp_buffers.entry((*connection, direction)).or_insert_with(|| Some(Vec::new())) {
+            Some(buffer) => buffer,
+            None => { return; }
+        };
+        buffer.extend_from_slice(data);
+
+        let mut messages = Vec::new();
+        while buffer.len() >= 2 {
+            let length = u16::from_be_bytes([buffer[0], buffer[1]]) as usize;
+            if buffer.len() < 2 + length {
+                break;
+            }
+            messages.extend(DnsMessage::parse(&buffer[2..2 + length]));
+            buffer.drain(..2 + length);
+        }
+        messages.iter().for_each(|message| self.push(message));
+    }
+
+    fn on_gap(&mut self, connection: &ConnectionKey, direction: Direction, _length: u64) {
+        // the framing is lost: the following bytes can't be decoded
+        self.tcp_buffers.insert((*connection, direction), None);
+    }
+
+    fn on_close(&mut self, connection: &ConnectionKey) {
+        self.tcp_buffers.remove(&(*connection, Direction::ClientToServer));
+        self.tcp_buffers.remove(&(*connection, Direction::ServerToClient));
+    }
+
+    fn report_section(&mut self) -> Option<String> {
+        if self.is_empty() {
+            return None;
+        }
+        let mut section = format!("DNS summary: {} messages\nTop queried names:\n", self.messages);
+        for (name, count) in self.top_queried_names(TOP_QUERIED_NAMES) {
+            section.push_str(format!("  {0: <9} {1}\n", count, name).as_str());
+        }
+        section.push_str("Response codes:\n");
+        for (rcode, count) in self.rcode_counts() {
+            section.push_str(format!("  {0: <9} {1}\n", count, rcode).as_str());
+        }
+
+        self.queries.clear();
+        self.rcodes.clear();
+        self.messages = 0;
+        Some(section)
+    }
+}
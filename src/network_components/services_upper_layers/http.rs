@@ -0,0 +1,447 @@
+//! # HTTP
+//!
+//! Parsing of HTTP/1.0 and HTTP/1.1 messages (RFC 9112): request lines, status lines and headers.
+//! Reassembled TCP streams are followed through message bodies (Content-Length, chunked encoding, until close)
+//! so that requests and responses can be paired into transactions.
+//!
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt::{Display, Formatter};
+use chrono::{DateTime, Utc};
+use crate::network_components::layer_4::tcp_reassembly::{ConnectionKey, Direction, Endpoint, StreamDissector};
+
+/// Methods recognized at the beginning of a request line.
+const METHODS: [&str; 9] = ["GET", "HEAD", "POST", "PUT", "DELETE", "CONNECT", "OPTIONS", "TRACE", "PATCH"];
+/// Maximum size of a message head (start line and headers) buffered while waiting for its end.
+const MAX_HEAD_LENGTH: usize = 64 * 1024;
+/// Default port of HTTP servers: other connections are dissected only if they start with a request line.
+const HTTP_PORT: u16 = 80;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HttpStartLine {
+    Request { method: String, target: String, version: String },
+    Response { version: String, status_code: u16, reason: String },
+}
+
+impl HttpStartLine {
+    fn parse(line: &str) -> Option<HttpStartLine> {
+        if line.starts_with("HTTP/1.") {
+            let mut parts = line.splitn(3, ' ');
+            let version = parts.next()?.to_string();
+            let status_code = parts.next()?;
+            if status_code.len() != 3 { return None; }
+            let status_code = status_code.parse().ok()?;
+            let reason = parts.next().unwrap_or("").to_string();
+            Some(HttpStartLine::Response { version, status_code, reason })
+        } else {
+            let mut parts = line.split(' ');
+            let method = parts.next()?;
+            let target = parts.next()?;
+            let version = parts.next()?;
+            if !METHODS.contains(&method) || target.is_empty() || !version.starts_with("HTTP/1.") || parts.next().is_some() {
+                return None;
+            }
+            Some(HttpStartLine::Request { method: method.to_string(), target: target.to_string(), version: version.to_string() })
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// `HTTP Message` head: start line and headers.
+pub struct HttpMessage {
+    pub start_line: HttpStartLine,
+    pub headers: Vec<(String, String)>,
+}
+
+impl HttpMessage {
+    /// Tells if a TCP payload starts with an HTTP/1.x request line or status line.
+    pub fn looks_like_http(payload_in_u8: &[u8]) -> bool {
+        match find(payload_in_u8, b"\r\n") {
+            Some(end) => std::str::from_utf8(&payload_in_u8[..end]).ok().and_then(HttpStartLine::parse).is_some(),
+            None => false,
+        }
+    }
+
+    /// Parses the head of a message at the beginning of a TCP payload. Headers not complete in the payload are ignored.
+    pub fn parse(payload_in_u8: &[u8]) -> Option<HttpMessage> {
+        let end = find(payload_in_u8, b"\r\n\r\n").map_or(payload_in_u8.len(), |end| end + 2);
+        HttpMessage::parse_lines(&payload_in_u8[..end])
+    }
+
+    /// Parses a complete message head (terminated by an empty line). Returns the message and the length of the head.
+    pub fn parse_head(buffer_in_u8: &[u8]) -> Option<(HttpMessage, usize)> {
+        let end = find(buffer_in_u8, b"\r\n\r\n")?;
+        Some((HttpMessage::parse_lines(&buffer_in_u8[..end + 2])?, end + 4))
+    }
+
+    /// Parses the CRLF terminated lines of a head (an unterminated last line is ignored).
+    fn parse_lines(head_in_u8: &[u8]) -> Option<HttpMessage> {
+        let head = String::from_utf8_lossy(head_in_u8);
+        let mut lines: Vec<&str> = head.split("\r\n").collect();
+        lines.pop(); // unterminated line (empty if the head ends with CRLF)
+
+        let mut lines = lines.into_iter();
+        let start_line = HttpStartLine::parse(lines.next()?)?;
+        let headers = lines
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+            .collect();
+        Some(HttpMessage { start_line, headers })
+    }
+
+    /// Value of the first header with the given name (case insensitive).
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find(|(header, _)| header.eq_ignore_ascii_case(name)).map(|(_, value)| value.as_str())
+    }
+
+    pub fn method(&self) -> Option<&str> {
+        match &self.start_line {
+            HttpStartLine::Request { method, .. } => Some(method),
+            _ => None,
+        }
+    }
+
+    pub fn path(&self) -> Option<&str> {
+        match &self.start_line {
+            HttpStartLine::Request { target, .. } => Some(target),
+            _ => None,
+        }
+    }
+
+    pub fn status_code(&self) -> Option<u16> {
+        match &self.start_line {
+            HttpStartLine::Response { status_code, .. } => Some(*status_code),
+            _ => None,
+        }
+    }
+
+    pub fn host(&self) -> Option<&str> {
+        self.header("Host")
+    }
+
+    pub fn content_type(&self) -> Option<&str> {
+        self.header("Content-Type")
+    }
+
+    pub fn content_length(&self) -> Option<usize> {
+        self.header("Content-Length").and_then(|length| length.parse().ok())
+    }
+
+    pub fn is_chunked(&self) -> bool {
+        self.header("Transfer-Encoding").is_some_and(|encoding| encoding.to_ascii_lowercase().contains("chunked"))
+    }
+}
+
+impl Display for HttpMessage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let optional = |value: Option<String>| value.unwrap_or_else(|| "-".to_string());
+        match &self.start_line {
+            HttpStartLine::Request { method, target, version } => {
+                write!(f, "HTTP    : request - [method: {}, host: {}, path: {}, version: {}, content-type: {}, content-length: {}]",
+                    method,
+                    optional(self.host().map(String::from)),
+                    target,
+                    version,
+                    optional(self.content_type().map(String::from)),
+                    optional(self.content_length().map(|length| length.to_string())))
+            },
+            HttpStartLine::Response { version, status_code, reason } => {
+                write!(f, "HTTP    : response - [status: {} {}, version: {}, content-type: {}, content-length: {}]",
+                    status_code,
+                    reason,
+                    version,
+                    optional(self.content_type().map(String::from)),
+                    optional(self.content_length().map(|length| length.to_string())))
+            },
+        }.unwrap();
+        self.headers.iter().try_for_each(|(name, value)| write!(f, "\n > {}: {}", name, value))
+    }
+}
+
+fn find(data: &[u8], pattern: &[u8]) -> Option<usize> {
+    data.windows(pattern.len()).position(|window| window == pattern)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// `HTTP Transaction`: a request and its response.
+pub struct HttpTransaction {
+    pub client: Endpoint,
+    pub server: Endpoint,
+    pub method: String,
+    pub host: Option<String>,
+    pub path: String,
+    /// Status code of the response (None if the connection closed without a response)
+    pub status_code: Option<u16>,
+    pub content_type: Option<String>,
+    pub content_length: Option<usize>,
+    pub request_time: DateTime<Utc>,
+    pub response_time: Option<DateTime<Utc>>,
+}
+
+impl Display for HttpTransaction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let optional = |value: Option<String>| value.unwrap_or_else(|| "-".to_string());
+        write!(f, "{0: <47} | {1: <47} | {2: <7} | {3: <30} | {4: <40} | {5: <6} | {6: <30} | {7: <14} | {8: <12}",
+            self.client.to_string(),
+            self.server.to_string(),
+            self.method,
+            optional(self.host.clone()),
+            self.path,
+            optional(self.status_code.map(|status_code| status_code.to_string())),
+            optional(self.content_type.clone()),
+            optional(self.content_length.map(|length| length.to_string())),
+            optional(self.response_time.map(|response_time| format!("{:.3}", (response_time - self.request_time).num_microseconds().unwrap_or(0) as f64 / 1000.0))))
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum ChunkState {
+    Size,
+    Data(usize),
+    DataEnd,
+    Trailer,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum BodyState {
+    /// Waiting for the head of the next message
+    Head,
+    /// Body with a Content-Length: bytes left
+    Length(usize),
+    Chunked(ChunkState),
+    /// Response body delimited by the connection closure
+    UntilClose,
+    /// Bytes missing from the capture: waiting for the start of a new message
+    Lost,
+}
+
+/// One direction of an HTTP connection.
+struct HttpStream {
+    buffer: Vec<u8>,
+    state: BodyState,
+}
+
+impl HttpStream {
+    fn new() -> HttpStream {
+        HttpStream { buffer: Vec::new(), state: BodyState::Head }
+    }
+
+    /// Consumes the buffered bytes, returning the heads of the messages found.
+    /// `body_state` tells how the body following each head is delimited.
+    fn messages(&mut self, mut body_state: impl FnMut(&HttpMessage) -> BodyState) -> Vec<HttpMessage> {
+        let mut messages = Vec::new();
+        loop {
+            match self.state {
+                BodyState::Lost => {
+                    if !HttpMessage::looks_like_http(&self.buffer) {
+                        self.buffer.clear();
+                        break;
+                    }
+                    self.state = BodyState::Head;
+                },
+                BodyState::Head => {
+                    match HttpMessage::parse_head(&self.buffer) {
+                        Some((message, length)) => {
+                            self.buffer.drain(..length);
+                            self.state = body_state(&message);
+                            messages.push(message);
+                        },
+                        None => {
+                            if self.buffer.len() > MAX_HEAD_LENGTH {
+                                self.state = BodyState::Lost;
+                                self.buffer.clear();
+                            }
+                            break;
+                        }
+                    }
+                },
+                BodyState::Length(remaining) => {
+                    let length = remaining.min(self.buffer.len());
+                    self.buffer.drain(..length);
+                    self.state = if remaining == length { BodyState::Head } else { BodyState::Length(remaining - length) };
+                    if self.buffer.is_empty() { break; }
+                },
+                BodyState::Chunked(ChunkState::Data(remaining)) => {
+                    let length = remaining.min(self.buffer.len());
+                    self.buffer.drain(..length);
+                    self.state = BodyState::Chunked(if remaining == length { ChunkState::DataEnd } else { ChunkState::Data(remaining - length) });
+                    if self.buffer.is_empty() { break; }
+                },
+                BodyState::Chunked(ChunkState::DataEnd) => {
+                    if self.buffer.len() < 2 { break; }
+                    self.buffer.drain(..2);
+                    self.state = BodyState::Chunked(ChunkState::Size);
+                },
+                BodyState::Chunked(chunk_state) => {
+                    let end = match find(&self.buffer, b"\r\n") {
+                        Some(end) => end,
+                        None => {
+                            if self.buffer.len() > MAX_HEAD_LENGTH {
+                                self.state = BodyState::Lost;
+                                self.buffer.clear();
+                            }
+                            break;
+                        }
+                    };
+                    let line = String::from_utf8_lossy(&self.buffer[..end]).to_string();
+                    self.buffer.drain(..end + 2);
+                    self.state = match chunk_state {
+                        ChunkState::Size => {
+                            let size = line.split(';').next().unwrap_or("").trim();
+                            match usize::from_str_radix(size, 16) {
+                                Ok(0) => BodyState::Chunked(ChunkState::Trailer),
+                                Ok(size) => BodyState::Chunked(ChunkState::Data(size)),
+                                Err(_) => BodyState::Lost,
+                            }
+                        },
+                        _ if line.is_empty() => BodyState::Head,
+                        _ => BodyState::Chunked(ChunkState::Trailer),
+                    };
+                },
+                BodyState::UntilClose => {
+                    self.buffer.clear();
+                    break;
+                },
+            }
+        }
+        messages
+    }
+}
+
+/// Requests and responses of an HTTP connection.
+struct HttpConnection {
+    streams: [HttpStream; 2],
+    /// Requests waiting for a response, in order (HTTP/1.1 pipelining)
+    pending: VecDeque<HttpTransaction>,
+}
+
+impl HttpConnection {
+    fn on_data(&mut self, connection: &ConnectionKey, direction: Direction, data: &[u8], timestamp: DateTime<Utc>, completed: &mut Vec<HttpTransaction>) {
+        match direction {
+            Direction::ClientToServer => {
+                let stream = &mut self.streams[0];
+                stream.buffer.extend_from_slice(data);
+                let requests = stream.messages(|request| {
+                    if request.is_chunked() {
+                        BodyState::Chunked(ChunkState::Size)
+                    } else {
+                        match request.content_length() {
+                            Some(length) if length > 0 => BodyState::Length(length),
+                            _ => BodyState::Head,
+                        }
+                    }
+                });
+                for request in requests {
+                    self.pending.push_back(HttpTransaction {
+                        client: connection.client,
+                        server: connection.server,
+                        method: request.method().unwrap_or("-").to_string(),
+                        host: request.host().map(String::from),
+                        path: request.path().unwrap_or("-").to_string(),
+                        status_code: None,
+                        content_type: None,
+                        content_length: None,
+                        request_time: timestamp,
+                        response_time: None,
+                    });
+                }
+            },
+            Direction::ServerToClient => {
+                let stream = &mut self.streams[1];
+                stream.buffer.extend_from_slice(data);
+                // responses are paired with the requests in order: the method of the request decides if the response has a body
+                let mut methods = self.pending.iter().map(|transaction| transaction.method.clone()).collect::<VecDeque<String>>();
+                let responses = stream.messages(|response| {
+                    let status_code = response.status_code().unwrap_or(0);
+                    let method = if (100..200).contains(&status_code) { methods.front().cloned() } else { methods.pop_front() };
+                    if method.as_deref() == Some("HEAD") || (100..200).contains(&status_code) || status_code == 204 || status_code == 304 {
+                        BodyState::Head
+                    } else if response.is_chunked() {
+                        BodyState::Chunked(ChunkState::Size)
+                    } else {
+                        match response.content_length() {
+                            Some(0) => BodyState::Head,
+                            Some(length) => BodyState::Length(length),
+                            None => BodyState::UntilClose,
+                        }
+                    }
+                });
+                for response in responses {
+                    // interim responses (100 Continue) don't complete the transaction
+                    if response.status_code().is_some_and(|status_code| (100..200).contains(&status_code)) {
+                        continue;
+                    }
+                    if let Some(mut transaction) = self.pending.pop_front() {
+                        transaction.status_code = response.status_code();
+                        transaction.content_type = response.content_type().map(String::from);
+                        transaction.content_length = response.content_length();
+                        transaction.response_time = Some(timestamp);
+                        completed.push(transaction);
+                    }
+                }
+            },
+        }
+    }
+}
+
+/// `HTTP Transactions` of the reassembled TCP streams, paired request by request.
+/// Connections on port 80 are always dissected, other connections only if they start with an HTTP request.
+#[derive(Default)]
+pub struct HttpTransactions {
+    /// HTTP connections (None for connections not carrying HTTP)
+    connections: HashMap<ConnectionKey, Option<HttpConnection>>,
+    /// Transactions completed in the current report interval
+    completed: Vec<HttpTransaction>,
+}
+
+impl HttpTransactions {
+    /// `new`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Transactions completed in the current report interval
+    pub fn completed(&self) -> &[HttpTransaction] {
+        &self.completed
+    }
+}
+
+impl StreamDissector for HttpTransactions {
+    fn on_data(&mut self, connection: &ConnectionKey, direction: Direction, data: &[u8], timestamp: DateTime<Utc>) {
+        let http_connection = self.connections.entry(*connection).or_insert_with(|| {
+            let is_http = connection.server.port == HTTP_PORT || HttpMessage::looks_like_http(data);
+            is_http.then(|| HttpConnection { streams: [HttpStream::new(), HttpStream::new()], pending: VecDeque::new() })
+        });
+        if let Some(http_connection) = http_connection {
+            http_connection.on_data(connection, direction, data, timestamp, &mut self.completed);
+        }
+    }
+
+    fn on_gap(&mut self, connection: &ConnectionKey, direction: Direction, _length: u64) {
+        if let Some(Some(http_connection)) = self.connections.get_mut(connection) {
+            let stream = &mut http_connection.streams[match direction { Direction::ClientToServer => 0, Direction::ServerToClient => 1 }];
+            stream.buffer.clear();
+            stream.state = BodyState::Lost;
+        }
+    }
+
+    fn on_close(&mut self, connection: &ConnectionKey) {
+        // requests never answered
+        if let Some(Some(http_connection)) = self.connections.remove(connection) {
+            self.completed.extend(http_connection.pending);
+        }
+    }
+
+    fn report_section(&mut self) -> Option<String> {
+        if self.completed.is_empty() {
+            return None;
+        }
+        let mut section = format!("HTTP transactions: {}\n", self.completed.len());
+        section.push_str(format!("{0: <47} | {1: <47} | {2: <7} | {3: <30} | {4: <40} | {5: <6} | {6: <30} | {7: <14} | {8: <12}\n",
+            "Client", "Server", "Method", "Host", "Path", "Status", "Content-Type", "Content-Length", "Response ms").as_str());
+        for transaction in self.completed.drain(..) {
+            section.push_str(format!("{}\n", transaction).as_str());
+        }
+        Some(section)
+    }
+}
@@ -3,10 +3,27 @@
 //! Module to handle `TCP/IP Upper Layers`.
 //!
 //! ### Upper Layers
-//!     full-support: ()
-//!     identification: FTP=20, SSH=22, SMPT=23, DNS=53, HTTP=80, POP3=110
-//!                     SFTP=115, SNMP=161, BGP=179, HTTPS=443
+//!     full-support: DNS (UDP and TCP), HTTP/1.x (any port), TLS handshake (any port), QUIC (Initial decryption), DHCP, DHCPv6,
+//!                   NetFlow v5/v9, IPFIX and sFlow v5 (flow records merged into the report)
+//!     identification: IANA well-known and registered ports, by transport (e.g. FTP=21, SSH=22, TELNET=23, SMTP=25,
+//!                     POP3=110, SNMP=161/udp, BGP=179, MYSQL=3306, MDNS=5353/udp), extensible with an /etc/services file.
+//!                     Payload inspection on any port, with a confidence: TLS, HTTP, SSH, DNS, QUIC, DHCP, SMTP, FTP,
+//!                     POP3, IMAP, SIP, RTP/RTCP, BitTorrent. Traffic identified by neither is reported as UNKNOWN
 //!     future support: ()
 //!
 
-pub mod upper_layer_services;
\ No newline at end of file
+pub mod upper_layer_services;
+
+pub mod dhcp;
+
+pub mod dns;
+
+pub mod http;
+
+pub mod payload_inspection;
+
+pub mod quic;
+
+pub mod service_registry;
+
+pub mod tls;
@@ -0,0 +1,180 @@
+use crate::network_components::services_upper_layers::dhcp::DhcpMessage;
+use crate::network_components::services_upper_layers::dns::DnsMessage;
+use crate::network_components::services_upper_layers::http::HttpMessage;
+use crate::network_components::services_upper_layers::quic::QuicPacket;
+use crate::network_components::services_upper_layers::tls::{TlsHandshake, TlsRecord};
+use crate::network_components::services_upper_layers::upper_layer_services::{Transport, UpperLayerService};
+
+/// TLS record content type of the handshake messages
+const TLS_HANDSHAKE: u8 = 22;
+/// Static (0-34) and dynamic (96-127) RTP payload types
+const RTP_PAYLOAD_TYPES: [std::ops::RangeInclusive<u8>; 2] = [0..=34, 96..=127];
+/// RTCP packet types: SR, RR, SDES, BYE, APP
+const RTCP_PACKET_TYPES: std::ops::RangeInclusive<u8> = 200..=204;
+/// SIP methods starting a request line
+const SIP_METHODS: [&str; 14] = ["INVITE", "ACK", "BYE", "CANCEL", "OPTIONS", "REGISTER", "PRACK", "SUBSCRIBE", "NOTIFY", "PUBLISH", "INFO", "REFER", "MESSAGE", "UPDATE"];
+
+#[derive(Debug, Clone, PartialEq)]
+/// `Payload Match`: protocol identified from the content of a segment, whatever the ports.
+pub struct PayloadMatch {
+    /// Name of the protocol, as shown in the reports
+    pub name: &'static str,
+    /// Service used to decode the payload (`UNKNOWN` when there is no decoder)
+    pub upper_layer_service: UpperLayerService,
+    /// Confidence of the identification, from 1 (guess) to 100 (certain)
+    pub confidence: u8,
+    /// The protocol secures another one (TLS, QUIC): an assigned port tells better what is carried
+    pub encapsulating: bool,
+}
+
+impl PayloadMatch {
+    fn new(name: &'static str, upper_layer_service: UpperLayerService, confidence: u8) -> Self {
+        PayloadMatch { name, upper_layer_service, confidence, encapsulating: false }
+    }
+
+    fn encapsulating(name: &'static str, upper_layer_service: UpperLayerService, confidence: u8) -> Self {
+        PayloadMatch { name, upper_layer_service, confidence, encapsulating: true }
+    }
+}
+
+/// Identifies the protocol of a TCP or UDP payload from its content. When several protocols match, the most confident wins.
+pub fn inspect_payload(transport: Transport, payload: &[u8]) -> Option<PayloadMatch> {
+    if payload.is_empty() {
+        return None;
+    }
+    let matches = match transport {
+        Transport::TCP => [ssh(payload), http(payload), tls(payload), mail_or_ftp(payload), sip(payload), bittorrent(payload), dns_tcp(payload)],
+        Transport::UDP => [quic(payload), dhcp(payload), sip(payload), dns(payload), rtcp(payload), rtp(payload), None],
+    };
+    matches.into_iter().flatten().max_by_key(|payload_match| payload_match.confidence)
+}
+
+/// SSH identification string (RFC 4253, section 4.2)
+fn ssh(payload: &[u8]) -> Option<PayloadMatch> {
+    if payload.starts_with(b"SSH-2.0-") || payload.starts_with(b"SSH-1.99-") || payload.starts_with(b"SSH-1.5-") {
+        return Some(PayloadMatch::new("SSH", UpperLayerService::SSH, 95));
+    }
+    None
+}
+
+/// HTTP/1.x request line or status line
+fn http(payload: &[u8]) -> Option<PayloadMatch> {
+    if HttpMessage::looks_like_http(payload) {
+        return Some(PayloadMatch::new("HTTP", UpperLayerService::HTTP, 90));
+    }
+    None
+}
+
+/// TLS record header, almost certain when it carries a ClientHello or ServerHello
+fn tls(payload: &[u8]) -> Option<PayloadMatch> {
+    let records = TlsRecord::parse_records(payload);
+    let first = records.first()?;
+    let hello = first.content_type == TLS_HANDSHAKE && TlsHandshake::parse(&first.fragment).is_some();
+    Some(PayloadMatch::encapsulating("TLS", UpperLayerService::HTTPS, if hello { 95 } else { 60 }))
+}
+
+/// SMTP, FTP, POP3 and IMAP greetings of the server, SMTP commands of the client
+fn mail_or_ftp(payload: &[u8]) -> Option<PayloadMatch> {
+    let line = first_line(payload)?;
+    let upper_line = line.to_ascii_uppercase();
+    if upper_line.starts_with("220") && upper_line.contains("SMTP") {
+        return Some(PayloadMatch::new("SMTP", UpperLayerService::SMTP, 85));
+    }
+    if upper_line.starts_with("EHLO ") || upper_line.starts_with("HELO ") {
+        return Some(PayloadMatch::new("SMTP", UpperLayerService::SMTP, 80));
+    }
+    if upper_line.starts_with("220") && upper_line.contains("FTP") {
+        return Some(PayloadMatch::new("FTP", UpperLayerService::FTP, 80));
+    }
+    if upper_line.starts_with("* OK") {
+        return Some(PayloadMatch::new("IMAP", UpperLayerService::UNKNOWN, 70));
+    }
+    if upper_line.starts_with("+OK") {
+        return Some(PayloadMatch::new("POP3", UpperLayerService::POP3, 60));
+    }
+    None
+}
+
+/// SIP request line (`INVITE sip:...`) or status line (`SIP/2.0 200 OK`)
+fn sip(payload: &[u8]) -> Option<PayloadMatch> {
+    let line = first_line(payload)?;
+    let request = line.split_once(' ').is_some_and(|(method, uri)| SIP_METHODS.contains(&method) && (uri.starts_with("sip:") || uri.starts_with("sips:")));
+    if request || line.starts_with("SIP/2.0 ") {
+        return Some(PayloadMatch::new("SIP", UpperLayerService::UNKNOWN, 90));
+    }
+    None
+}
+
+/// BitTorrent peer wire handshake
+fn bittorrent(payload: &[u8]) -> Option<PayloadMatch> {
+    if payload.starts_with(b"\x13BitTorrent protocol") {
+        return Some(PayloadMatch::new("BITTORRENT", UpperLayerService::UNKNOWN, 95));
+    }
+    None
+}
+
+/// QUIC long header of a known version, almost certain when the client Initial packet is decrypted
+fn quic(payload: &[u8]) -> Option<PayloadMatch> {
+    if !QuicPacket::looks_like_quic(payload) {
+        return None;
+    }
+    let packets = QuicPacket::parse_datagram(payload);
+    if packets.is_empty() {
+        return None;
+    }
+    let decrypted = packets.iter().any(|packet| packet.client_hello().is_some());
+    Some(PayloadMatch::encapsulating("QUIC", UpperLayerService::HTTPS, if decrypted { 95 } else { 70 }))
+}
+
+/// BOOTP message with the DHCP magic cookie
+fn dhcp(payload: &[u8]) -> Option<PayloadMatch> {
+    DhcpMessage::parse(payload).map(|_| PayloadMatch::new("DHCP", UpperLayerService::DHCP, 90))
+}
+
+/// DNS message structure: a standard header with at least a well formed question
+fn dns(payload: &[u8]) -> Option<PayloadMatch> {
+    let message = DnsMessage::parse(payload)?;
+    if message.questions.is_empty() || message.opcode() > 6 {
+        return None;
+    }
+    Some(PayloadMatch::new("DNS", UpperLayerService::DNS, 50))
+}
+
+fn dns_tcp(payload: &[u8]) -> Option<PayloadMatch> {
+    let length = u16::from_be_bytes([*payload.first()?, *payload.get(1)?]) as usize;
+    if length + 2 != payload.len() {
+        return None;
+    }
+    dns(&payload[2..])
+}
+
+/// RTP fixed header (RFC 3550): version 2 and a static or dynamic payload type. A weak guess, used only without assigned ports.
+fn rtp(payload: &[u8]) -> Option<PayloadMatch> {
+    if payload.len() < 12 || payload[0] >> 6 != 2 {
+        return None;
+    }
+    let csrc_count = (payload[0] & 0x0F) as usize;
+    let payload_type = payload[1] & 0x7F;
+    if 12 + 4 * csrc_count > payload.len() || !RTP_PAYLOAD_TYPES.iter().any(|payload_types| payload_types.contains(&payload_type)) {
+        return None;
+    }
+    Some(PayloadMatch::new("RTP", UpperLayerService::UNKNOWN, 40))
+}
+
+/// RTCP packet (RFC 3550): version 2, a known packet type and a length within the datagram
+fn rtcp(payload: &[u8]) -> Option<PayloadMatch> {
+    if payload.len() < 8 || payload[0] >> 6 != 2 || !RTCP_PACKET_TYPES.contains(&payload[1]) {
+        return None;
+    }
+    let length = (u16::from_be_bytes([payload[2], payload[3]]) as usize + 1) * 4;
+    if length > payload.len() {
+        return None;
+    }
+    Some(PayloadMatch::new("RTCP", UpperLayerService::UNKNOWN, 50))
+}
+
+/// First line of a text payload
+fn first_line(payload: &[u8]) -> Option<&str> {
+    let end = payload.windows(2).position(|window| window == b"\r\n")?;
+    std::str::from_utf8(&payload[..end]).ok()
+}
@@ -0,0 +1,465 @@
+//! # QUIC
+//!
+//! Parsing of QUIC packet headers (RFC 9000, RFC 8999): long headers (Initial, 0-RTT, Handshake, Retry),
+//! version negotiation and short headers, with versions and connection IDs.
+//! Client Initial packets are decrypted with the keys derived from the version-specific initial salt
+//! and the Destination Connection ID (RFC 9001, RFC 9369), to recover the TLS ClientHello carried by the CRYPTO frames.
+//!
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Display, Formatter};
+use std::net::IpAddr;
+use aes_gcm::{Aes128Gcm, KeyInit, Nonce};
+use aes_gcm::aead::{Aead, Payload};
+use aes_gcm::aes::Aes128;
+use aes_gcm::aes::cipher::{BlockEncrypt, generic_array::GenericArray};
+use chrono::{DateTime, Duration, Utc};
+use hkdf::Hkdf;
+use sha2::Sha256;
+use crate::network_components::layer_4::tcp_reassembly::{ConnectionKey, Endpoint};
+use crate::network_components::layer_4::upd_packet::UdpPacket;
+use crate::network_components::services_upper_layers::tls::{ClientHello, TlsHandshake};
+use crate::network_components::services_upper_layers::upper_layer_services::UpperLayerService;
+
+pub const QUIC_VERSION_1: u32 = 0x0000_0001;
+pub const QUIC_VERSION_2: u32 = 0x6B33_43CF;
+pub const QUIC_DRAFT_29: u32 = 0xFF00_001D;
+
+const INITIAL_SALT_V1: [u8; 20] = [0x38, 0x76, 0x2C, 0xF7, 0xF5, 0x59, 0x34, 0xB3, 0x4D, 0x17, 0x9A, 0xE6, 0xA4, 0xC8, 0x0C, 0xAD, 0xCC, 0xBB, 0x7F, 0x0A];
+const INITIAL_SALT_V2: [u8; 20] = [0x0D, 0xED, 0xE3, 0xDE, 0xF7, 0x00, 0xA6, 0xDB, 0x81, 0x93, 0x81, 0xBE, 0x6E, 0x26, 0x9D, 0xCB, 0xF9, 0xBD, 0x2E, 0xD9];
+const INITIAL_SALT_DRAFT_29: [u8; 20] = [0xAF, 0xBF, 0xEC, 0x28, 0x99, 0x93, 0xD2, 0x4C, 0x9E, 0x97, 0x86, 0xF1, 0x9C, 0x61, 0x11, 0xE0, 0x43, 0x90, 0xA8, 0x99];
+
+/// Maximum length of a connection ID
+const MAX_CID_LENGTH: usize = 20;
+/// Length of the header protection sample
+const SAMPLE_LENGTH: usize = 16;
+/// Maximum length of the CRYPTO data buffered while waiting for a complete ClientHello
+const MAX_CRYPTO_LENGTH: usize = 64 * 1024;
+
+/// Name of a QUIC version
+pub fn version_name(version: u32) -> String {
+    match version {
+        QUIC_VERSION_1 => "1".to_string(),
+        QUIC_VERSION_2 => "2".to_string(),
+        QUIC_DRAFT_29 => "draft-29".to_string(),
+        _ => format!("0x{:08x}", version),
+    }
+}
+
+/// Variable-length integer (RFC 9000, 16): value and length in bytes
+fn varint(data: &[u8]) -> Option<(u64, usize)> {
+    let length = 1 << (data.first()? >> 6);
+    let bytes = data.get(..length)?;
+    let value = bytes[1..].iter().fold((bytes[0] & 0x3F) as u64, |value, byte| value << 8 | *byte as u64);
+    Some((value, length))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    if bytes.is_empty() { "-".to_string() } else { bytes.iter().map(|byte| format!("{:02x}", byte)).collect() }
+}
+
+/// HKDF-Expand-Label of TLS 1.3 (RFC 8446, 7.1) with an empty context
+fn hkdf_expand_label(secret: &[u8], label: &str, length: usize) -> Vec<u8> {
+    let label = format!("tls13 {}", label);
+    let mut info = (length as u16).to_be_bytes().to_vec();
+    info.push(label.len() as u8);
+    info.extend_from_slice(label.as_bytes());
+    info.push(0);
+    let mut output = vec![0; length];
+    Hkdf::<Sha256>::from_prk(secret).unwrap().expand(&info, &mut output).unwrap();
+    output
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// `Initial Keys` protecting the Initial packets sent by the client
+pub struct InitialKeys {
+    pub key: [u8; 16],
+    pub iv: [u8; 12],
+    pub hp: [u8; 16],
+}
+
+impl InitialKeys {
+    /// Keys of the client Initial packets for a version and the Destination Connection ID chosen by the client (None for unknown versions).
+    pub fn client(version: u32, dcid: &[u8]) -> Option<InitialKeys> {
+        let (salt, prefix) = match version {
+            QUIC_VERSION_1 => (&INITIAL_SALT_V1, "quic"),
+            QUIC_VERSION_2 => (&INITIAL_SALT_V2, "quicv2"),
+            QUIC_DRAFT_29 => (&INITIAL_SALT_DRAFT_29, "quic"),
+            _ => return None,
+        };
+        let (initial_secret, _) = Hkdf::<Sha256>::extract(Some(salt), dcid);
+        let client_secret = hkdf_expand_label(&initial_secret, "client in", 32);
+
+        let mut keys = InitialKeys { key: [0; 16], iv: [0; 12], hp: [0; 16] };
+        keys.key.copy_from_slice(&hkdf_expand_label(&client_secret, format!("{} key", prefix).as_str(), 16));
+        keys.iv.copy_from_slice(&hkdf_expand_label(&client_secret, format!("{} iv", prefix).as_str(), 12));
+        keys.hp.copy_from_slice(&hkdf_expand_label(&client_secret, format!("{} hp", prefix).as_str(), 16));
+        Some(keys)
+    }
+
+    /// Header protection mask computed from a sample of the protected payload (RFC 9001, 5.4.3)
+    pub fn header_protection_mask(&self, sample: &[u8]) -> [u8; 5] {
+        let mut block = GenericArray::clone_from_slice(&sample[..SAMPLE_LENGTH]);
+        Aes128::new(GenericArray::from_slice(&self.hp)).encrypt_block(&mut block);
+        let mut mask = [0; 5];
+        mask.copy_from_slice(&block[..5]);
+        mask
+    }
+
+    /// Nonce of a packet: the IV combined with the packet number (RFC 9001, 5.3)
+    pub fn nonce(&self, packet_number: u64) -> [u8; 12] {
+        let mut nonce = self.iv;
+        for (index, byte) in packet_number.to_be_bytes().iter().enumerate() {
+            nonce[4 + index] ^= byte;
+        }
+        nonce
+    }
+
+    /// Removes the header protection and decrypts the payload of a packet.
+    /// Returns the packet number and the plaintext, or None if the packet isn't protected with these keys.
+    fn decrypt(&self, packet: &[u8], packet_number_offset: usize) -> Option<(u64, Vec<u8>)> {
+        let sample = packet.get(packet_number_offset + 4..packet_number_offset + 4 + SAMPLE_LENGTH)?;
+        let mask = self.header_protection_mask(sample);
+        let mut header = Vec::from(packet.get(..packet_number_offset + 4)?);
+        header[0] ^= mask[0] & 0x0F;
+        let packet_number_length = (header[0] & 0x03) as usize + 1;
+        header.truncate(packet_number_offset + packet_number_length);
+        let mut packet_number = 0;
+        for index in 0..packet_number_length {
+            header[packet_number_offset + index] ^= mask[1 + index];
+            packet_number = packet_number << 8 | header[packet_number_offset + index] as u64;
+        }
+
+        let cipher = Aes128Gcm::new(GenericArray::from_slice(&self.key));
+        let nonce = self.nonce(packet_number);
+        let plaintext = cipher.decrypt(Nonce::from_slice(&nonce), Payload { msg: &packet[header.len()..], aad: &header }).ok()?;
+        Some((packet_number, plaintext))
+    }
+}
+
+/// Data of the CRYPTO frames of a decrypted payload (offset, data). None if a frame can't be parsed.
+fn crypto_frames(plaintext: &[u8]) -> Option<Vec<(u64, Vec<u8>)>> {
+    let mut frames = Vec::new();
+    let mut offset = 0;
+    let next = |offset: &mut usize| -> Option<u64> {
+        let (value, length) = varint(plaintext.get(*offset..)?)?;
+        *offset += length;
+        Some(value)
+    };
+    while offset < plaintext.len() {
+        let frame_type = next(&mut offset)?;
+        match frame_type {
+            0x00 | 0x01 => {}, // PADDING, PING
+            0x02 | 0x03 => {  // ACK
+                next(&mut offset)?;
+                next(&mut offset)?;
+                let ranges = next(&mut offset)?;
+                next(&mut offset)?;
+                for _ in 0..ranges * 2 {
+                    next(&mut offset)?;
+                }
+                if frame_type == 0x03 {
+                    for _ in 0..3 {
+                        next(&mut offset)?;
+                    }
+                }
+            },
+            0x06 => {  // CRYPTO
+                let crypto_offset = next(&mut offset)?;
+                let length = next(&mut offset)? as usize;
+                let data = plaintext.get(offset..offset.checked_add(length)?)?;
+                frames.push((crypto_offset, Vec::from(data)));
+                offset += length;
+            },
+            0x1C | 0x1D => {  // CONNECTION_CLOSE
+                next(&mut offset)?;
+                if frame_type == 0x1C {
+                    next(&mut offset)?;
+                }
+                let length = next(&mut offset)? as usize;
+                offset = offset.checked_add(length)?;
+            },
+            _ => return None,
+        }
+    }
+    Some(frames)
+}
+
+/// CRYPTO data contiguous from offset 0
+fn contiguous(fragments: &BTreeMap<u64, Vec<u8>>) -> Vec<u8> {
+    let mut data = Vec::new();
+    for (offset, fragment) in fragments.iter() {
+        let offset = *offset as usize;
+        if offset > data.len() {
+            break;
+        }
+        if offset + fragment.len() > data.len() {
+            data.extend_from_slice(&fragment[data.len() - offset..]);
+        }
+    }
+    data
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum QuicPacketType {
+    Initial,
+    ZeroRtt,
+    Handshake,
+    Retry,
+    VersionNegotiation,
+    /// Short header
+    OneRtt,
+}
+
+impl Display for QuicPacketType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QuicPacketType::Initial => write!(f, "Initial"),
+            QuicPacketType::ZeroRtt => write!(f, "0-RTT"),
+            QuicPacketType::Handshake => write!(f, "Handshake"),
+            QuicPacketType::Retry => write!(f, "Retry"),
+            QuicPacketType::VersionNegotiation => write!(f, "Version Negotiation"),
+            QuicPacketType::OneRtt => write!(f, "1-RTT (short header)"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// `QUIC Packet` header, with the CRYPTO frames of the client Initial packets
+pub struct QuicPacket {
+    pub packet_type: QuicPacketType,
+    /// Version (None for short headers)
+    pub version: Option<u32>,
+    /// Destination Connection ID (empty for short headers: its length is known only to the endpoints)
+    pub dcid: Vec<u8>,
+    /// Source Connection ID
+    pub scid: Vec<u8>,
+    /// Versions offered in a Version Negotiation packet
+    pub supported_versions: Vec<u32>,
+    /// Length in bytes of the packet
+    pub length: usize,
+    /// Packet number of a decrypted client Initial packet
+    pub packet_number: Option<u64>,
+    /// CRYPTO frames (offset, data) of a decrypted client Initial packet
+    pub crypto: Vec<(u64, Vec<u8>)>,
+}
+
+impl QuicPacket {
+    /// Tells if a UDP payload starts with a QUIC long header of a known version.
+    pub fn looks_like_quic(payload_in_u8: &[u8]) -> bool {
+        payload_in_u8.len() >= 7 && payload_in_u8[0] & 0x80 != 0 &&
+            matches!(u32::from_be_bytes([payload_in_u8[1], payload_in_u8[2], payload_in_u8[3], payload_in_u8[4]]), 0 | QUIC_VERSION_1 | QUIC_VERSION_2 | QUIC_DRAFT_29)
+    }
+
+    /// Parses the packets coalesced in a UDP datagram, decrypting the client Initial packets.
+    pub fn parse_datagram(payload_in_u8: &[u8]) -> Vec<QuicPacket> {
+        let mut packets = Vec::new();
+        let mut offset = 0;
+        while offset < payload_in_u8.len() {
+            match QuicPacket::parse(&payload_in_u8[offset..]) {
+                Some(packet) => {
+                    offset += packet.length;
+                    packets.push(packet);
+                },
+                None => break,
+            }
+        }
+        packets
+    }
+
+    fn parse(packet_in_u8: &[u8]) -> Option<QuicPacket> {
+        let first = *packet_in_u8.first()?;
+        if first & 0x80 == 0 {
+            // short header: fixed bit set
+            if first & 0x40 == 0 { return None; }
+            return Some(QuicPacket { packet_type: QuicPacketType::OneRtt, version: None, dcid: Vec::new(), scid: Vec::new(), supported_versions: Vec::new(), length: packet_in_u8.len(), packet_number: None, crypto: Vec::new() });
+        }
+
+        let version = u32::from_be_bytes(packet_in_u8.get(1..5)?.try_into().unwrap());
+        let mut offset = 5;
+        let connection_id = |offset: &mut usize| -> Option<Vec<u8>> {
+            let length = *packet_in_u8.get(*offset)? as usize;
+            let id = packet_in_u8.get(*offset + 1..*offset + 1 + length)?;
+            *offset += 1 + length;
+            Some(Vec::from(id))
+        };
+        let dcid = connection_id(&mut offset)?;
+        let scid = connection_id(&mut offset)?;
+        let mut packet = QuicPacket { packet_type: QuicPacketType::VersionNegotiation, version: Some(version), dcid, scid, supported_versions: Vec::new(), length: packet_in_u8.len(), packet_number: None, crypto: Vec::new() };
+
+        if version == 0 {
+            packet.supported_versions = packet_in_u8[offset..].chunks_exact(4).map(|version| u32::from_be_bytes(version.try_into().unwrap())).collect();
+            return Some(packet);
+        }
+        if packet.dcid.len() > MAX_CID_LENGTH || packet.scid.len() > MAX_CID_LENGTH || first & 0x40 == 0 {
+            return None;
+        }
+        let long_packet_type = (first >> 4) & 0x03;
+        packet.packet_type = match (version, long_packet_type) {
+            (QUIC_VERSION_2, 0) => QuicPacketType::Retry,
+            (QUIC_VERSION_2, 1) => QuicPacketType::Initial,
+            (QUIC_VERSION_2, 2) => QuicPacketType::ZeroRtt,
+            (QUIC_VERSION_2, _) => QuicPacketType::Handshake,
+            (_, 0) => QuicPacketType::Initial,
+            (_, 1) => QuicPacketType::ZeroRtt,
+            (_, 2) => QuicPacketType::Handshake,
+            _ => QuicPacketType::Retry,
+        };
+        if packet.packet_type == QuicPacketType::Retry {
+            return Some(packet);
+        }
+
+        if packet.packet_type == QuicPacketType::Initial {
+            let (token_length, length) = varint(packet_in_u8.get(offset..)?)?;
+            offset = offset.checked_add(length + token_length as usize)?;
+        }
+        let (length, varint_length) = varint(packet_in_u8.get(offset..)?)?;
+        let packet_number_offset = offset + varint_length;
+        packet.length = packet_number_offset.checked_add(length as usize).filter(|end| *end <= packet_in_u8.len())?;
+
+        if packet.packet_type == QuicPacketType::Initial {
+            // only the Initial packets of the client can be decrypted: the server ones use keys derived from the client DCID
+            let decrypted = InitialKeys::client(version, &packet.dcid)
+                .and_then(|keys| keys.decrypt(&packet_in_u8[..packet.length], packet_number_offset));
+            if let Some((packet_number, plaintext)) = decrypted {
+                packet.packet_number = Some(packet_number);
+                packet.crypto = crypto_frames(&plaintext).unwrap_or_default();
+            }
+        }
+        Some(packet)
+    }
+
+    /// ClientHello carried by the CRYPTO frames of this packet alone (None if it spans several packets)
+    pub fn client_hello(&self) -> Option<ClientHello> {
+        let fragments: BTreeMap<u64, Vec<u8>> = self.crypto.iter().cloned().collect();
+        match TlsHandshake::parse(&contiguous(&fragments)) {
+            Some((TlsHandshake::ClientHello(client_hello), _)) => Some(client_hello),
+            _ => None,
+        }
+    }
+}
+
+impl Display for QuicPacket {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.packet_type {
+            QuicPacketType::OneRtt => return write!(f, "QUIC    : {} - [length: {}B]", self.packet_type, self.length),
+            QuicPacketType::VersionNegotiation => {
+                return write!(f, "QUIC    : {} - [dcid: {}, scid: {}, versions: {}]",
+                    self.packet_type,
+                    hex(&self.dcid),
+                    hex(&self.scid),
+                    self.supported_versions.iter().map(|version| version_name(*version)).collect::<Vec<String>>().join(", "))
+            },
+            _ => {},
+        }
+        write!(f, "QUIC    : {} - [version: {}, dcid: {}, scid: {}, length: {}B]",
+            self.packet_type,
+            version_name(self.version.unwrap_or(0)),
+            hex(&self.dcid),
+            hex(&self.scid),
+            self.length).unwrap();
+        if self.packet_type != QuicPacketType::Initial {
+            return Ok(());
+        }
+        match (self.packet_number, self.client_hello()) {
+            (Some(packet_number), Some(client_hello)) => write!(f, "\n > packet number: {}\n{}", packet_number, client_hello),
+            (Some(packet_number), None) => {
+                write!(f, "\n > packet number: {}, crypto: {}B (ClientHello spanning several packets)",
+                    packet_number,
+                    self.crypto.iter().map(|(_, data)| data.len()).sum::<usize>())
+            },
+            (None, _) => write!(f, "\n > payload not decrypted (server Initial)"),
+        }
+    }
+}
+
+/// ClientHello reassembly of a QUIC connection
+struct QuicSession {
+    crypto: BTreeMap<u64, Vec<u8>>,
+    client_hello: Option<ClientHello>,
+    last_seen: DateTime<Utc>,
+}
+
+/// `QUIC Sessions`: ClientHello of the QUIC connections, rebuilt from the CRYPTO frames of the client Initial packets.
+///
+/// # Example
+/// ```
+/// let mut quic_sessions = QuicSessions::new();
+/// quic_sessions.push(ip_addr_src, ip_addr_dst, &udp_packet, timestamp);
+/// let server_name = quic_sessions.server_name(endpoint_1, endpoint_2);
+/// let alpn = quic_sessions.alpn(endpoint_1, endpoint_2);
+/// ```
+#[derive(Default)]
+pub struct QuicSessions {
+    sessions: HashMap<(Endpoint, Endpoint), QuicSession>,
+}
+
+impl QuicSessions {
+    /// `new`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of connections currently tracked
+    pub fn len(&self) -> usize {
+        self.sessions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sessions.is_empty()
+    }
+
+    /// Collects the CRYPTO frames of the client Initial packets of a datagram.
+    pub fn push(&mut self, ip_addr_src: IpAddr, ip_addr_dst: IpAddr, udp_packet: &UdpPacket, timestamp: DateTime<Utc>) {
+        if udp_packet.identification.upper_layer_service != UpperLayerService::HTTPS && !QuicPacket::looks_like_quic(&udp_packet.payload) {
+            return;
+        }
+        let src = Endpoint { ip_addr: ip_addr_src, port: udp_packet.src_port };
+        let dst = Endpoint { ip_addr: ip_addr_dst, port: udp_packet.dst_port };
+        let key = ConnectionKey::canonical(src, dst);
+        if let Some(session) = self.sessions.get_mut(&key) {
+            session.last_seen = timestamp;
+            if session.client_hello.is_some() {
+                return;
+            }
+        }
+
+        for packet in QuicPacket::parse_datagram(&udp_packet.payload) {
+            if packet.crypto.is_empty() {
+                continue;
+            }
+            let session = self.sessions.entry(key).or_insert_with(|| QuicSession { crypto: BTreeMap::new(), client_hello: None, last_seen: timestamp });
+            for (offset, data) in packet.crypto {
+                session.crypto.insert(offset, data);
+            }
+            let crypto = contiguous(&session.crypto);
+            if let Some((TlsHandshake::ClientHello(client_hello), _)) = TlsHandshake::parse(&crypto) {
+                session.client_hello = Some(client_hello);
+                session.crypto.clear();
+            } else if session.crypto.values().map(|data| data.len()).sum::<usize>() > MAX_CRYPTO_LENGTH {
+                session.crypto.clear();
+            }
+        }
+    }
+
+    /// ClientHello of the connection between two endpoints, in any direction.
+    pub fn client_hello(&self, endpoint_1: Endpoint, endpoint_2: Endpoint) -> Option<&ClientHello> {
+        self.sessions.get(&ConnectionKey::canonical(endpoint_1, endpoint_2)).and_then(|session| session.client_hello.as_ref())
+    }
+
+    /// Server name requested by the client (SNI) on the connection between two endpoints.
+    pub fn server_name(&self, endpoint_1: Endpoint, endpoint_2: Endpoint) -> Option<&str> {
+        self.client_hello(endpoint_1, endpoint_2).and_then(|client_hello| client_hello.server_name.as_deref())
+    }
+
+    /// Application protocols offered by the client (ALPN) on the connection between two endpoints: the choice of the server
+    /// is in its Handshake packets, which can't be decrypted.
+    pub fn alpn(&self, endpoint_1: Endpoint, endpoint_2: Endpoint) -> Option<&[String]> {
+        self.client_hello(endpoint_1, endpoint_2).map(|client_hello| client_hello.alpn.as_slice()).filter(|alpn| !alpn.is_empty())
+    }
+
+    /// Forgets the connections idle for more than `idle_timeout`.
+    pub fn purge(&mut self, now: DateTime<Utc>, idle_timeout: Duration) {
+        self.sessions.retain(|_, session| now - session.last_seen <= idle_timeout);
+    }
+}
@@ -0,0 +1,244 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::{OnceLock, RwLock, RwLockReadGuard};
+use crate::network_components::services_upper_layers::upper_layer_services::Transport;
+
+const TCP: &[Transport] = &[Transport::TCP];
+const UDP: &[Transport] = &[Transport::UDP];
+const TCP_UDP: &[Transport] = &[Transport::TCP, Transport::UDP];
+
+/// IANA Service Name and Transport Protocol Port Number Registry: well-known (0-1023) and registered (1024-49151) ports
+/// of the most common services. Names are the IANA ones in upper case, except for the services the reports always named
+/// after the protocol (DNS, DHCP, DHCPv6). NetFlow has no assigned port: 2055 is the customary one.
+const IANA_SERVICES: &[(&str, u16, &[Transport])] = &[
+    ("ECHO", 7, TCP_UDP),
+    ("DISCARD", 9, TCP_UDP),
+    ("DAYTIME", 13, TCP_UDP),
+    ("QOTD", 17, TCP_UDP),
+    ("CHARGEN", 19, TCP_UDP),
+    ("FTP-DATA", 20, TCP),
+    ("FTP", 21, TCP),
+    ("SSH", 22, TCP),
+    ("TELNET", 23, TCP),
+    ("SMTP", 25, TCP),
+    ("TIME", 37, TCP_UDP),
+    ("WHOIS", 43, TCP),
+    ("TACACS", 49, TCP_UDP),
+    ("DNS", 53, TCP_UDP),
+    ("DHCP", 67, UDP),
+    ("DHCP", 68, UDP),
+    ("TFTP", 69, UDP),
+    ("GOPHER", 70, TCP),
+    ("FINGER", 79, TCP),
+    ("HTTP", 80, TCP_UDP),
+    ("KERBEROS", 88, TCP_UDP),
+    ("ISO-TSAP", 102, TCP),
+    ("POP3", 110, TCP),
+    ("SUNRPC", 111, TCP_UDP),
+    ("IDENT", 113, TCP),
+    ("SFTP", 115, TCP),
+    ("NNTP", 119, TCP),
+    ("NTP", 123, UDP),
+    ("EPMAP", 135, TCP_UDP),
+    ("NETBIOS-NS", 137, UDP),
+    ("NETBIOS-DGM", 138, UDP),
+    ("NETBIOS-SSN", 139, TCP),
+    ("IMAP", 143, TCP),
+    ("SNMP", 161, UDP),
+    ("SNMPTRAP", 162, UDP),
+    ("BGP", 179, TCP),
+    ("IRC", 194, TCP),
+    ("LDAP", 389, TCP_UDP),
+    ("SVRLOC", 427, TCP_UDP),
+    ("HTTPS", 443, TCP_UDP),
+    ("MICROSOFT-DS", 445, TCP),
+    ("KPASSWD", 464, TCP_UDP),
+    ("SUBMISSIONS", 465, TCP),
+    ("ISAKMP", 500, UDP),
+    ("MBAP", 502, TCP),
+    ("SHELL", 514, TCP),
+    ("SYSLOG", 514, UDP),
+    ("PRINTER", 515, TCP),
+    ("RIP", 520, UDP),
+    ("RIPNG", 521, UDP),
+    ("DHCPv6", 546, UDP),
+    ("DHCPv6", 547, UDP),
+    ("RTSP", 554, TCP_UDP),
+    ("SUBMISSION", 587, TCP),
+    ("IPP", 631, TCP),
+    ("LDAPS", 636, TCP),
+    ("LDP", 646, TCP_UDP),
+    ("DOMAIN-S", 853, TCP_UDP),
+    ("ISCSI", 860, TCP),
+    ("RSYNC", 873, TCP),
+    ("FTPS-DATA", 989, TCP),
+    ("FTPS", 990, TCP),
+    ("IMAPS", 993, TCP),
+    ("POP3S", 995, TCP),
+    ("SOCKS", 1080, TCP),
+    ("OPENVPN", 1194, TCP_UDP),
+    ("MS-SQL-S", 1433, TCP),
+    ("MS-SQL-M", 1434, UDP),
+    ("L2TP", 1701, UDP),
+    ("PPTP", 1723, TCP),
+    ("RADIUS", 1812, UDP),
+    ("RADIUS-ACCT", 1813, UDP),
+    ("MQTT", 1883, TCP),
+    ("SSDP", 1900, UDP),
+    ("NFS", 2049, TCP_UDP),
+    ("NETFLOW", 2055, UDP),
+    ("DOCKER", 2375, TCP),
+    ("DOCKER-S", 2376, TCP),
+    ("IEC-104", 2404, TCP),
+    ("MYSQL", 3306, TCP),
+    ("MS-WBT-SERVER", 3389, TCP_UDP),
+    ("STUN", 3478, TCP_UDP),
+    ("IPSEC-NAT-T", 4500, UDP),
+    ("IPFIX", 4739, TCP_UDP),
+    ("VXLAN", 4789, UDP),
+    ("SIP", 5060, TCP_UDP),
+    ("SIPS", 5061, TCP),
+    ("XMPP-CLIENT", 5222, TCP),
+    ("XMPP-SERVER", 5269, TCP),
+    ("STUNS", 5349, TCP_UDP),
+    ("MDNS", 5353, UDP),
+    ("LLMNR", 5355, TCP_UDP),
+    ("POSTGRESQL", 5432, TCP),
+    ("AMQPS", 5671, TCP),
+    ("AMQP", 5672, TCP),
+    ("COAP", 5683, UDP),
+    ("COAPS", 5684, UDP),
+    ("RFB", 5900, TCP),
+    ("WSMAN", 5985, TCP),
+    ("WSMANS", 5986, TCP),
+    ("GENEVE", 6081, UDP),
+    ("SFLOW", 6343, UDP),
+    ("REDIS", 6379, TCP),
+    ("SYSLOG-TLS", 6514, TCP),
+    ("OPENFLOW", 6653, TCP),
+    ("HTTP-ALT", 8080, TCP),
+    ("SECURE-MQTT", 8883, TCP),
+    ("GIT", 9418, TCP),
+    ("MEMCACHE", 11211, TCP_UDP),
+    ("DNP", 20000, TCP_UDP),
+    ("MONGODB", 27017, TCP),
+    ("BACNET", 47808, UDP),
+];
+
+/// `Service Registry` naming the services from their (port, transport) pair.
+///
+/// It ships the IANA assignments of the most common services and can be extended with a file in the `/etc/services` format,
+/// whose entries override the built-in ones.
+#[derive(Debug, Clone)]
+pub struct ServiceRegistry {
+    tcp: HashMap<u16, String>,
+    udp: HashMap<u16, String>,
+}
+
+impl ServiceRegistry {
+    /// Registry with the built-in IANA assignments
+    pub fn new() -> Self {
+        let mut registry = ServiceRegistry { tcp: HashMap::new(), udp: HashMap::new() };
+        for (name, port, transports) in IANA_SERVICES {
+            for transport in transports.iter() {
+                registry.insert(*transport, *port, name);
+            }
+        }
+        registry
+    }
+
+    pub fn insert(&mut self, transport: Transport, port: u16, name: &str) {
+        let services = match transport {
+            Transport::TCP => &mut self.tcp,
+            Transport::UDP => &mut self.udp,
+        };
+        services.insert(port, name.to_string());
+    }
+
+    /// Name of the service assigned to `port` over `transport`
+    pub fn name(&self, transport: Transport, port: u16) -> Option<&str> {
+        let services = match transport {
+            Transport::TCP => &self.tcp,
+            Transport::UDP => &self.udp,
+        };
+        services.get(&port).map(|name| name.as_str())
+    }
+
+    /// Port of the service between the two endpoints: the assigned one, or the lowest when both are assigned
+    pub fn service_port(&self, transport: Transport, src_port: u16, dst_port: u16) -> Option<u16> {
+        match (self.name(transport, src_port), self.name(transport, dst_port)) {
+            (Some(_), Some(_)) => Some(src_port.min(dst_port)),
+            (Some(_), None) => Some(src_port),
+            (None, Some(_)) => Some(dst_port),
+            (None, None) => None,
+        }
+    }
+
+    /// Name of the service between the two endpoints
+    pub fn service_name(&self, transport: Transport, src_port: u16, dst_port: u16) -> Option<&str> {
+        self.service_port(transport, src_port, dst_port).and_then(|port| self.name(transport, port))
+    }
+
+    /// Extends the registry with the entries in the `/etc/services` format, returning how many were added.
+    ///
+    /// Each line is `name port/protocol [aliases...] [# comment]`: names are upper cased, and protocols other than tcp and
+    /// udp (e.g. sctp, ddp) are skipped.
+    pub fn extend_from_services(&mut self, services: &str) -> Result<usize, String> {
+        let mut entries = Vec::new();
+        for (index, line) in services.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default();
+            let mut fields = line.split_whitespace();
+            let name = match fields.next() {
+                Some(name) => name,
+                None => continue,
+            };
+            let (port, protocol) = match fields.next().and_then(|field| field.split_once('/')) {
+                Some((port, protocol)) => (port, protocol),
+                None => return Err(format!("line {}: expected port/protocol after the service name", index + 1)),
+            };
+            let port = match port.parse::<u16>() {
+                Ok(port) => port,
+                Err(_) => return Err(format!("line {}: invalid port number {}", index + 1, port)),
+            };
+            let transport = match protocol.to_ascii_lowercase().as_str() {
+                "tcp" => Transport::TCP,
+                "udp" => Transport::UDP,
+                _ => continue,
+            };
+            entries.push((transport, port, name.to_ascii_uppercase()));
+        }
+
+        for (transport, port, name) in entries.iter() {
+            self.insert(*transport, *port, name);
+        }
+        Ok(entries.len())
+    }
+}
+
+impl Default for ServiceRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Registry shared by the whole process: packets are decoded in several threads, far from the configuration.
+/// It is never reset, so the services files loaded by a PacketSnooper are seen by all the others.
+static SERVICE_REGISTRY: OnceLock<RwLock<ServiceRegistry>> = OnceLock::new();
+
+fn global_registry() -> &'static RwLock<ServiceRegistry> {
+    SERVICE_REGISTRY.get_or_init(|| RwLock::new(ServiceRegistry::new()))
+}
+
+/// Read access to the registry used to identify the upper layer services
+pub fn service_registry() -> RwLockReadGuard<'static, ServiceRegistry> {
+    global_registry().read().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Extends the registry used to identify the upper layer services with the file at `path` (`/etc/services` format).
+/// Nothing is added if the file can't be read or has a malformed line; the added entries last for the whole process.
+pub fn load_services_file(path: &Path) -> Result<usize, String> {
+    let services = fs::read_to_string(path).map_err(|error| error.to_string())?;
+    let mut registry = global_registry().write().unwrap_or_else(|poisoned| poisoned.into_inner());
+    registry.extend_from_services(&services)
+}
@@ -0,0 +1,476 @@
+//! # TLS
+//!
+//! Parsing of TLS records and handshake messages (RFC 8446, RFC 5246): ClientHello server name (SNI), ALPN,
+//! supported versions and cipher suites, ServerHello selected version and cipher suite.
+//! Handshake messages spanning several records or TCP segments are rebuilt from the reassembled TCP streams.
+//!
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use chrono::{DateTime, Utc};
+use crate::network_components::layer_4::tcp_reassembly::{ConnectionKey, Direction, Endpoint, StreamDissector};
+
+const RECORD_HEADER_LENGTH: usize = 5;
+/// Maximum length of a record fragment (2^14 plus the expansion allowed for protected records)
+const MAX_RECORD_LENGTH: usize = (1 << 14) + 2048;
+/// Maximum length of the handshake bytes buffered while waiting for a hello message
+const MAX_HANDSHAKE_LENGTH: usize = 64 * 1024;
+
+const CONTENT_TYPE_HANDSHAKE: u8 = 22;
+const HANDSHAKE_CLIENT_HELLO: u8 = 1;
+const HANDSHAKE_SERVER_HELLO: u8 = 2;
+
+const EXTENSION_SERVER_NAME: u16 = 0;
+const EXTENSION_ALPN: u16 = 16;
+const EXTENSION_SUPPORTED_VERSIONS: u16 = 43;
+
+/// Name of a TLS protocol version
+pub fn version_name(version: u16) -> String {
+    match version {
+        0x0300 => "SSL 3.0".to_string(),
+        0x0301 => "TLS 1.0".to_string(),
+        0x0302 => "TLS 1.1".to_string(),
+        0x0303 => "TLS 1.2".to_string(),
+        0x0304 => "TLS 1.3".to_string(),
+        0x7F00..=0x7FFF => format!("TLS 1.3 draft {}", version & 0xFF),
+        _ => format!("0x{:04x}", version),
+    }
+}
+
+/// Name of a cipher suite (the most common ones, the code point otherwise)
+pub fn cipher_suite_name(cipher_suite: u16) -> String {
+    match cipher_suite {
+        0x000A => "TLS_RSA_WITH_3DES_EDE_CBC_SHA",
+        0x002F => "TLS_RSA_WITH_AES_128_CBC_SHA",
+        0x0035 => "TLS_RSA_WITH_AES_256_CBC_SHA",
+        0x009C => "TLS_RSA_WITH_AES_128_GCM_SHA256",
+        0x009D => "TLS_RSA_WITH_AES_256_GCM_SHA384",
+        0x00FF => "TLS_EMPTY_RENEGOTIATION_INFO_SCSV",
+        0x1301 => "TLS_AES_128_GCM_SHA256",
+        0x1302 => "TLS_AES_256_GCM_SHA384",
+        0x1303 => "TLS_CHACHA20_POLY1305_SHA256",
+        0x1304 => "TLS_AES_128_CCM_SHA256",
+        0x1305 => "TLS_AES_128_CCM_8_SHA256",
+        0x5600 => "TLS_FALLBACK_SCSV",
+        0xC009 => "TLS_ECDHE_ECDSA_WITH_AES_128_CBC_SHA",
+        0xC00A => "TLS_ECDHE_ECDSA_WITH_AES_256_CBC_SHA",
+        0xC013 => "TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA",
+        0xC014 => "TLS_ECDHE_RSA_WITH_AES_256_CBC_SHA",
+        0xC02B => "TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256",
+        0xC02C => "TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384",
+        0xC02F => "TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256",
+        0xC030 => "TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384",
+        0xCCA8 => "TLS_ECDHE_RSA_WITH_CHACHA20_POLY1305_SHA256",
+        0xCCA9 => "TLS_ECDHE_ECDSA_WITH_CHACHA20_POLY1305_SHA256",
+        _ => return format!("0x{:04x}", cipher_suite),
+    }.to_string()
+}
+
+/// Name of a record content type
+fn content_type_name(content_type: u8) -> &'static str {
+    match content_type {
+        20 => "change_cipher_spec",
+        21 => "alert",
+        22 => "handshake",
+        23 => "application_data",
+        24 => "heartbeat",
+        _ => "unknown",
+    }
+}
+
+/// GREASE values (RFC 8701) advertised by clients to keep the ecosystem extensible: meaningless, not shown
+fn is_grease(value: u16) -> bool {
+    value & 0x0F0F == 0x0A0A && value >> 8 == value & 0xFF
+}
+
+/// Bounds-checked reader of big-endian fields
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Reader<'a> {
+        Reader { data, offset: 0 }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.offset >= self.data.len()
+    }
+
+    fn bytes(&mut self, length: usize) -> Option<&'a [u8]> {
+        let bytes = self.data.get(self.offset..self.offset.checked_add(length)?)?;
+        self.offset += length;
+        Some(bytes)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.bytes(1).map(|bytes| bytes[0])
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        self.bytes(2).map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u24(&mut self) -> Option<usize> {
+        self.bytes(3).map(|bytes| (bytes[0] as usize) << 16 | (bytes[1] as usize) << 8 | bytes[2] as usize)
+    }
+
+    /// Vector with a 1 byte length
+    fn vector_u8(&mut self) -> Option<Reader<'a>> {
+        let length = self.u8()? as usize;
+        self.bytes(length).map(Reader::new)
+    }
+
+    /// Vector with a 2 bytes length
+    fn vector_u16(&mut self) -> Option<Reader<'a>> {
+        let length = self.u16()? as usize;
+        self.bytes(length).map(Reader::new)
+    }
+}
+
+/// Extensions (type, data) of a hello message
+fn extensions(reader: &mut Reader) -> Option<Vec<(u16, Vec<u8>)>> {
+    let mut extensions = Vec::new();
+    if reader.is_empty() {
+        return Some(extensions);
+    }
+    let mut list = reader.vector_u16()?;
+    while !list.is_empty() {
+        let extension_type = list.u16()?;
+        let data = list.vector_u16()?;
+        extensions.push((extension_type, Vec::from(data.data)));
+    }
+    Some(extensions)
+}
+
+/// Protocol names of an ALPN extension
+fn alpn_protocols(data: &[u8]) -> Option<Vec<String>> {
+    let mut list = Reader::new(data).vector_u16()?;
+    let mut protocols = Vec::new();
+    while !list.is_empty() {
+        protocols.push(String::from_utf8_lossy(list.vector_u8()?.data).to_string());
+    }
+    Some(protocols)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// `ClientHello` handshake message
+pub struct ClientHello {
+    pub legacy_version: u16,
+    /// Host name of the server_name extension (SNI)
+    pub server_name: Option<String>,
+    /// Application protocols offered (ALPN)
+    pub alpn: Vec<String>,
+    /// Versions of the supported_versions extension (GREASE values excluded)
+    pub supported_versions: Vec<u16>,
+    /// Cipher suites offered (GREASE values excluded)
+    pub cipher_suites: Vec<u16>,
+}
+
+impl ClientHello {
+    /// Parses the body of a ClientHello handshake message.
+    pub fn parse(body: &[u8]) -> Option<ClientHello> {
+        let mut reader = Reader::new(body);
+        let legacy_version = reader.u16()?;
+        reader.bytes(32)?; // random
+        reader.vector_u8()?; // legacy_session_id
+        let mut cipher_suites_list = reader.vector_u16()?;
+        let mut cipher_suites = Vec::new();
+        while let Some(cipher_suite) = cipher_suites_list.u16() {
+            if !is_grease(cipher_suite) {
+                cipher_suites.push(cipher_suite);
+            }
+        }
+        reader.vector_u8()?; // legacy_compression_methods
+
+        let mut client_hello = ClientHello { legacy_version, server_name: None, alpn: Vec::new(), supported_versions: Vec::new(), cipher_suites };
+        for (extension_type, data) in extensions(&mut reader)? {
+            match extension_type {
+                EXTENSION_SERVER_NAME => {
+                    let mut list = Reader::new(&data).vector_u16()?;
+                    while !list.is_empty() {
+                        let name_type = list.u8()?;
+                        let name = list.vector_u16()?;
+                        if name_type == 0 {
+                            client_hello.server_name = Some(String::from_utf8_lossy(name.data).to_string());
+                        }
+                    }
+                },
+                EXTENSION_ALPN => client_hello.alpn = alpn_protocols(&data)?,
+                EXTENSION_SUPPORTED_VERSIONS => {
+                    let mut versions = Reader::new(&data).vector_u8()?;
+                    while let Some(version) = versions.u16() {
+                        if !is_grease(version) {
+                            client_hello.supported_versions.push(version);
+                        }
+                    }
+                },
+                _ => {}
+            }
+        }
+        Some(client_hello)
+    }
+}
+
+impl Display for ClientHello {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let versions = if self.supported_versions.is_empty() { vec![self.legacy_version] } else { self.supported_versions.clone() };
+        write!(f, "TLS     : ClientHello - [server name: {}, alpn: {}, versions: {}]\n > cipher suites: {}",
+            self.server_name.as_deref().unwrap_or("-"),
+            if self.alpn.is_empty() { "-".to_string() } else { self.alpn.join(", ") },
+            versions.iter().map(|version| version_name(*version)).collect::<Vec<String>>().join(", "),
+            self.cipher_suites.iter().map(|cipher_suite| cipher_suite_name(*cipher_suite)).collect::<Vec<String>>().join(", "))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// `ServerHello` handshake message
+pub struct ServerHello {
+    pub legacy_version: u16,
+    /// Version selected by the server (supported_versions extension in TLS 1.3, legacy_version before)
+    pub version: u16,
+    /// Cipher suite selected by the server
+    pub cipher_suite: u16,
+    /// Application protocol selected (ALPN)
+    pub alpn: Option<String>,
+}
+
+impl ServerHello {
+    /// Parses the body of a ServerHello handshake message.
+    pub fn parse(body: &[u8]) -> Option<ServerHello> {
+        let mut reader = Reader::new(body);
+        let legacy_version = reader.u16()?;
+        reader.bytes(32)?; // random
+        reader.vector_u8()?; // legacy_session_id_echo
+        let cipher_suite = reader.u16()?;
+        reader.u8()?; // legacy_compression_method
+
+        let mut server_hello = ServerHello { legacy_version, version: legacy_version, cipher_suite, alpn: None };
+        for (extension_type, data) in extensions(&mut reader)? {
+            match extension_type {
+                EXTENSION_ALPN => server_hello.alpn = alpn_protocols(&data)?.into_iter().next(),
+                EXTENSION_SUPPORTED_VERSIONS => server_hello.version = Reader::new(&data).u16()?,
+                _ => {}
+            }
+        }
+        Some(server_hello)
+    }
+}
+
+impl Display for ServerHello {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "TLS     : ServerHello - [version: {}, cipher suite: {}, alpn: {}]",
+            version_name(self.version),
+            cipher_suite_name(self.cipher_suite),
+            self.alpn.as_deref().unwrap_or("-"))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// `TLS Record` header, with the fragment (possibly truncated at the end of a TCP segment)
+pub struct TlsRecord {
+    pub content_type: u8,
+    pub version: u16,
+    pub length: usize,
+    pub fragment: Vec<u8>,
+}
+
+impl TlsRecord {
+    /// Tells if a TCP payload starts with a TLS record header.
+    pub fn looks_like_tls(payload_in_u8: &[u8]) -> bool {
+        payload_in_u8.len() >= RECORD_HEADER_LENGTH
+            && (20..=24).contains(&payload_in_u8[0])
+            && payload_in_u8[1] == 3 && payload_in_u8[2] <= 4
+            && (u16::from_be_bytes([payload_in_u8[3], payload_in_u8[4]]) as usize) <= MAX_RECORD_LENGTH
+    }
+
+    /// Parses the records starting in a TCP payload. The last one may be truncated.
+    pub fn parse_records(payload_in_u8: &[u8]) -> Vec<TlsRecord> {
+        let mut records = Vec::new();
+        let mut offset = 0;
+        while TlsRecord::looks_like_tls(&payload_in_u8[offset..]) {
+            let length = u16::from_be_bytes([payload_in_u8[offset + 3], payload_in_u8[offset + 4]]) as usize;
+            let start = offset + RECORD_HEADER_LENGTH;
+            let end = (start + length).min(payload_in_u8.len());
+            records.push(TlsRecord {
+                content_type: payload_in_u8[offset],
+                version: u16::from_be_bytes([payload_in_u8[offset + 1], payload_in_u8[offset + 2]]),
+                length,
+                fragment: Vec::from(&payload_in_u8[start..end]),
+            });
+            offset = end;
+        }
+        records
+    }
+
+    /// Hello message carried by a complete handshake record
+    fn hello(&self) -> Option<TlsHandshake> {
+        if self.content_type != CONTENT_TYPE_HANDSHAKE || self.fragment.len() < self.length {
+            return None;
+        }
+        TlsHandshake::parse(&self.fragment).map(|(handshake, _)| handshake)
+    }
+}
+
+impl Display for TlsRecord {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.hello() {
+            Some(hello) => write!(f, "{}", hello),
+            None => write!(f, "TLS     : {} record - [version: {}, length: {}]", content_type_name(self.content_type), version_name(self.version), self.length),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Hello handshake messages
+pub enum TlsHandshake {
+    ClientHello(ClientHello),
+    ServerHello(ServerHello),
+}
+
+impl TlsHandshake {
+    /// Parses the handshake message at the beginning of the handshake bytes.
+    /// Returns the message (None for other handshake types) and its length, or None if the message is incomplete or malformed.
+    pub fn parse(handshake_in_u8: &[u8]) -> Option<(TlsHandshake, usize)> {
+        let mut reader = Reader::new(handshake_in_u8);
+        let handshake_type = reader.u8()?;
+        let length = reader.u24()?;
+        let body = reader.bytes(length)?;
+        let handshake = match handshake_type {
+            HANDSHAKE_CLIENT_HELLO => TlsHandshake::ClientHello(ClientHello::parse(body)?),
+            HANDSHAKE_SERVER_HELLO => TlsHandshake::ServerHello(ServerHello::parse(body)?),
+            _ => return None,
+        };
+        Some((handshake, length + 4))
+    }
+}
+
+impl Display for TlsHandshake {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TlsHandshake::ClientHello(client_hello) => write!(f, "{}", client_hello),
+            TlsHandshake::ServerHello(server_hello) => write!(f, "{}", server_hello),
+        }
+    }
+}
+
+/// Handshake bytes of one direction of a connection, collected until the hello message is complete.
+#[derive(Default)]
+struct HandshakeStream {
+    /// Record bytes not yet complete
+    records: Vec<u8>,
+    /// Handshake bytes of the complete records
+    handshake: Vec<u8>,
+    done: bool,
+}
+
+impl HandshakeStream {
+    /// Collects the stream bytes, returning the hello message when complete.
+    fn push(&mut self, data: &[u8]) -> Option<TlsHandshake> {
+        if self.done {
+            return None;
+        }
+        self.records.extend_from_slice(data);
+        while self.records.len() >= RECORD_HEADER_LENGTH {
+            if !TlsRecord::looks_like_tls(&self.records) || self.records[0] != CONTENT_TYPE_HANDSHAKE {
+                self.stop();
+                return None;
+            }
+            let length = u16::from_be_bytes([self.records[3], self.records[4]]) as usize;
+            if self.records.len() < RECORD_HEADER_LENGTH + length {
+                break;
+            }
+            self.handshake.extend_from_slice(&self.records[RECORD_HEADER_LENGTH..RECORD_HEADER_LENGTH + length]);
+            self.records.drain(..RECORD_HEADER_LENGTH + length);
+        }
+
+        // the hello is the first handshake message of each direction
+        if self.handshake.len() >= 4 {
+            let length = (self.handshake[1] as usize) << 16 | (self.handshake[2] as usize) << 8 | self.handshake[3] as usize;
+            if self.handshake.len() >= length + 4 {
+                let hello = TlsHandshake::parse(&self.handshake).map(|(hello, _)| hello);
+                self.stop();
+                return hello;
+            }
+        }
+        if self.records.len() + self.handshake.len() > MAX_HANDSHAKE_LENGTH {
+            self.stop();
+        }
+        None
+    }
+
+    fn stop(&mut self) {
+        self.done = true;
+        self.records = Vec::new();
+        self.handshake = Vec::new();
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+/// `TLS Session` handshake metadata
+pub struct TlsSession {
+    pub client_hello: Option<ClientHello>,
+    pub server_hello: Option<ServerHello>,
+}
+
+impl TlsSession {
+    /// Server name requested by the client (SNI)
+    pub fn server_name(&self) -> Option<&str> {
+        self.client_hello.as_ref().and_then(|client_hello| client_hello.server_name.as_deref())
+    }
+
+    /// Application protocol selected by the server (ALPN)
+    pub fn alpn(&self) -> Option<&str> {
+        self.server_hello.as_ref().and_then(|server_hello| server_hello.alpn.as_deref())
+    }
+}
+
+/// `TLS Sessions` of the reassembled TCP streams: hello messages of the connections still open.
+#[derive(Default)]
+pub struct TlsSessions {
+    streams: HashMap<ConnectionKey, [HandshakeStream; 2]>,
+    sessions: HashMap<(Endpoint, Endpoint), TlsSession>,
+}
+
+impl TlsSessions {
+    /// `new`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Session between two endpoints, in any direction.
+    pub fn get(&self, endpoint_1: Endpoint, endpoint_2: Endpoint) -> Option<&TlsSession> {
+        self.sessions.get(&ConnectionKey::canonical(endpoint_1, endpoint_2))
+    }
+}
+
+impl StreamDissector for TlsSessions {
+    fn on_data(&mut self, connection: &ConnectionKey, direction: Direction, data: &[u8], _timestamp: DateTime<Utc>) {
+        let streams = self.streams.entry(*connection).or_default();
+        let stream = &mut streams[match direction { Direction::ClientToServer => 0, Direction::ServerToClient => 1 }];
+        let hello = stream.push(data);
+
+        let session = ConnectionKey::canonical(connection.client, connection.server);
+        match hello {
+            Some(TlsHandshake::ClientHello(client_hello)) if direction == Direction::ClientToServer => {
+                self.sessions.entry(session).or_default().client_hello = Some(client_hello);
+            },
+            Some(TlsHandshake::ServerHello(server_hello)) if direction == Direction::ServerToClient => {
+                self.sessions.entry(session).or_default().server_hello = Some(server_hello);
+            },
+            _ => {}
+        }
+    }
+
+    fn on_gap(&mut self, connection: &ConnectionKey, direction: Direction, _length: u64) {
+        if let Some(streams) = self.streams.get_mut(connection) {
+            streams[match direction { Direction::ClientToServer => 0, Direction::ServerToClient => 1 }].stop();
+        }
+    }
+
+    fn on_close(&mut self, connection: &ConnectionKey) {
+        self.streams.remove(connection);
+        self.sessions.remove(&ConnectionKey::canonical(connection.client, connection.server));
+    }
+}
@@ -1,62 +1,181 @@
 use std::fmt::Formatter;
+use crate::network_components::services_upper_layers::dhcp::{DhcpMessage, Dhcpv6Message};
+use crate::network_components::services_upper_layers::dns::DnsMessage;
+use crate::network_components::services_upper_layers::http::HttpMessage;
+use crate::network_components::services_upper_layers::payload_inspection::inspect_payload;
+use crate::network_components::services_upper_layers::quic::QuicPacket;
+use crate::network_components::services_upper_layers::service_registry::service_registry;
+use crate::network_components::services_upper_layers::tls::TlsRecord;
 
 #[derive(Debug, Copy, Clone, PartialEq)]
+/// Layer 4 protocol carrying the upper layer service.
+pub enum Transport {
+    TCP,
+    UDP,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+/// Upper layer service identified from the port of the service, used to choose how to decode the payload.
+/// The name of any service in the registry is given by `service_registry`.
 pub enum UpperLayerService {
-    FTP = 20,
+    FTPDATA = 20,
+    FTP = 21,
     SSH = 22,
-    SMTP = 23,
+    TELNET = 23,
+    SMTP = 25,
     DNS = 53,
+    DHCP = 67,
     HTTP = 80,
     POP3 = 110,
     SFTP = 115,
     SNMP = 161,
     BGP = 179,
     HTTPS = 443,
+    DHCPv6 = 547,
+    NETFLOW = 2055,
+    IPFIX = 4739,
+    SFLOW = 6343,
     UNKNOWN = 65354,
 }
 
+/// Port of the DHCP clients, the servers listening on `UpperLayerService::DHCP`
+pub const DHCP_CLIENT_PORT: u16 = 68;
+/// Port of the DHCPv6 clients, the servers and relay agents listening on `UpperLayerService::DHCPv6`
+pub const DHCPV6_CLIENT_PORT: u16 = 546;
+
 impl From<u16> for UpperLayerService {
     fn from(v: u16) -> UpperLayerService {
         match v {
+            x if x == UpperLayerService::FTPDATA as u16 => UpperLayerService::FTPDATA,
             x if x == UpperLayerService::FTP   as u16 => UpperLayerService::FTP,
             x if x == UpperLayerService::SSH   as u16 => UpperLayerService::SSH,
+            x if x == UpperLayerService::TELNET as u16 => UpperLayerService::TELNET,
             x if x == UpperLayerService::SMTP  as u16 => UpperLayerService::SMTP,
             x if x == UpperLayerService::DNS   as u16 => UpperLayerService::DNS,
+            x if x == UpperLayerService::DHCP  as u16 => UpperLayerService::DHCP,
+            x if x == DHCP_CLIENT_PORT => UpperLayerService::DHCP,
             x if x == UpperLayerService::HTTP  as u16 => UpperLayerService::HTTP,
             x if x == UpperLayerService::POP3  as u16 => UpperLayerService::POP3,
             x if x == UpperLayerService::SFTP  as u16 => UpperLayerService::SFTP,
             x if x == UpperLayerService::SNMP  as u16 => UpperLayerService::SNMP,
             x if x == UpperLayerService::BGP   as u16 => UpperLayerService::BGP,
             x if x == UpperLayerService::HTTPS as u16 => UpperLayerService::HTTPS,
+            x if x == UpperLayerService::DHCPv6 as u16 => UpperLayerService::DHCPv6,
+            x if x == DHCPV6_CLIENT_PORT => UpperLayerService::DHCPv6,
+            x if x == UpperLayerService::NETFLOW as u16 => UpperLayerService::NETFLOW,
+            x if x == UpperLayerService::IPFIX as u16 => UpperLayerService::IPFIX,
+            x if x == UpperLayerService::SFLOW as u16 => UpperLayerService::SFLOW,
             65354 => UpperLayerService::UNKNOWN,
             _ => UpperLayerService::UNKNOWN
         }
     }
 }
 
-pub fn known_port(src_port: u16, dst_port: u16) -> u16 {
-    if src_port < 1024 {
-        src_port
-    } else if dst_port < 1024 {
-        dst_port
-    } else {
-        65354
+/// Port of the service between the two endpoints according to the service registry (`UpperLayerService::UNKNOWN` if none is assigned)
+pub fn known_port(transport: Transport, src_port: u16, dst_port: u16) -> u16 {
+    service_registry().service_port(transport, src_port, dst_port).unwrap_or(UpperLayerService::UNKNOWN as u16)
+}
+
+/// Confidence of an identification from the assigned port only
+pub const PORT_CONFIDENCE: u8 = 50;
+
+#[derive(Debug, Clone, PartialEq)]
+/// `Service Identification` of a TCP/UDP segment, from the assigned ports and the content of the payload.
+pub struct ServiceIdentification {
+    /// Service used to decode the payload
+    pub upper_layer_service: UpperLayerService,
+    /// Name of the service (None when neither the ports nor the payload tell it)
+    pub name: Option<String>,
+    /// Confidence of the identification, from 0 (unknown) to 100 (certain)
+    pub confidence: u8,
+    /// Identified from the payload rather than from the ports
+    pub from_payload: bool,
+}
+
+/// Identifies the service of a segment. The port assigned in the service registry is overridden by a more confident
+/// identification of the payload (e.g. HTTP on 8080, SSH on 2222), except for TLS and QUIC, which tell only how another
+/// service is secured.
+pub fn identify_service(transport: Transport, src_port: u16, dst_port: u16, payload: &[u8]) -> ServiceIdentification {
+    let port = known_port(transport, src_port, dst_port);
+    let name = service_registry().name(transport, port).map(String::from);
+    let by_port = ServiceIdentification {
+        upper_layer_service: UpperLayerService::from(port),
+        confidence: if name.is_some() { PORT_CONFIDENCE } else { 0 },
+        name,
+        from_payload: false,
+    };
+
+    match inspect_payload(transport, payload) {
+        Some(payload_match) if (payload_match.upper_layer_service != UpperLayerService::UNKNOWN && payload_match.upper_layer_service == by_port.upper_layer_service)
+            || by_port.name.as_deref() == Some(payload_match.name) => {
+            ServiceIdentification { confidence: by_port.confidence.max(payload_match.confidence), ..by_port }
+        },
+        Some(payload_match) if payload_match.encapsulating && by_port.name.is_some() => by_port,
+        Some(payload_match) if payload_match.confidence > by_port.confidence => {
+            ServiceIdentification {
+                upper_layer_service: payload_match.upper_layer_service,
+                name: Some(payload_match.name.to_string()),
+                confidence: payload_match.confidence,
+                from_payload: true,
+            }
+        },
+        _ => by_port,
     }
 }
 
-pub fn print_upper_layer(f: &mut Formatter<'_>, upper_layer_service: UpperLayerService) -> std::fmt::Result {
-    match upper_layer_service {
-        UpperLayerService::FTP   => { write!(f, "FTP     ") },
-        UpperLayerService::SSH   => { write!(f, "SSH     ") },
-        UpperLayerService::SMTP  => { write!(f, "SMTP    ") },
-        UpperLayerService::DNS   => { write!(f, "DNS     ") },
-        UpperLayerService::HTTP  => { write!(f, "HTTP    ") },
-        UpperLayerService::POP3  => { write!(f, "POP3    ") },
-        UpperLayerService::SFTP  => { write!(f, "SFTP    ") },
-        UpperLayerService::SNMP  => { write!(f, "SNMP    ") },
-        UpperLayerService::BGP   => { write!(f, "BGP     ") },
-        UpperLayerService::HTTPS => { write!(f, "HTTPS   ") },
-        _ => { write!(f, "Other Protocol incapsulated in TCP/UDP segment (Unknown Protocol)") }
+pub fn print_upper_layer(f: &mut Formatter<'_>, identification: &ServiceIdentification, payload: &[u8], transport: Transport) -> std::fmt::Result {
+    let upper_layer_service = identification.upper_layer_service;
+    if upper_layer_service == UpperLayerService::DNS {
+        let messages = match transport {
+            Transport::UDP => DnsMessage::parse(payload).into_iter().collect(),
+            Transport::TCP => DnsMessage::parse_tcp(payload),
+        };
+        if !messages.is_empty() {
+            let messages: Vec<String> = messages.iter().map(|message| message.to_string()).collect();
+            return write!(f, "{}", messages.join("\n"));
+        }
+    }
+
+    if transport == Transport::UDP {
+        match upper_layer_service {
+            UpperLayerService::DHCP => if let Some(message) = DhcpMessage::parse(payload) { return write!(f, "{}", message); },
+            UpperLayerService::DHCPv6 => if let Some(message) = Dhcpv6Message::parse(payload) { return write!(f, "{}", message); },
+            _ => {}
+        }
+    }
+
+    // HTTP/1.x is recognized on any port from its request or status line
+    if transport == Transport::TCP && (upper_layer_service == UpperLayerService::HTTP || HttpMessage::looks_like_http(payload)) {
+        if let Some(message) = HttpMessage::parse(payload) {
+            return write!(f, "{}", message);
+        }
+    }
+
+    // QUIC is recognized on any port from its long header, short headers only on the HTTPS port
+    if transport == Transport::UDP && (upper_layer_service == UpperLayerService::HTTPS || QuicPacket::looks_like_quic(payload)) {
+        let packets = QuicPacket::parse_datagram(payload);
+        if !packets.is_empty() {
+            let packets: Vec<String> = packets.iter().map(|packet| packet.to_string()).collect();
+            return write!(f, "{}", packets.join("\n"));
+        }
+    }
+
+    // TLS is recognized on any port from its record header
+    if transport == Transport::TCP && (upper_layer_service == UpperLayerService::HTTPS || TlsRecord::looks_like_tls(payload)) {
+        let records = TlsRecord::parse_records(payload);
+        if !records.is_empty() {
+            let records: Vec<String> = records.iter().map(|record| record.to_string()).collect();
+            return write!(f, "{}", records.join("\n"));
+        }
+    }
+
+    match identification.name.as_deref() {
+        Some(name) => { write!(f, "{: <8}", name) },
+        None => { write!(f, "Other Protocol incapsulated in TCP/UDP segment (Unknown Protocol)") }
     }.unwrap();
-    write!(f, ": Protocol details unknown")
-}
\ No newline at end of file
+    write!(f, ": Protocol details unknown").unwrap();
+    if identification.from_payload {
+        write!(f, " - [identified from the payload, confidence: {}%]", identification.confidence).unwrap();
+    }
+    Ok(())
+}
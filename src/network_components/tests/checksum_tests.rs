@@ -0,0 +1,83 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use crate::EthernetPacket;
+use crate::network_components::checksum::{ChecksumStatus, internet_checksum, transport_checksum, verify_ipv4_header, verify_transport};
+use crate::network_components::layer_3::ipv4_packet::IPv4Packet;
+use crate::network_components::tests::layer_2::PACKET;
+
+const IPV4_HEADER_IN_U8: [u8; 20] = [0x45, 0x00, 0x00, 0x73, 0x00, 0x00, 0x40, 0x00, 0x40, 0x11, 0xb8, 0x61, 0xc0, 0xa8, 0x00, 0x01, 0xc0, 0xa8, 0x00, 0xc7];
+
+#[test]
+fn ipv4_header_checksum() {
+    let mut header = IPV4_HEADER_IN_U8;
+    assert_eq!(verify_ipv4_header(&header), ChecksumStatus::Valid);
+
+    header[10] = 0;
+    header[11] = 0;
+    assert_eq!(internet_checksum(&header), 0xb861);
+
+    header[8] = 0x3f;
+    assert_eq!(verify_ipv4_header(&header), ChecksumStatus::Invalid);
+    assert_eq!(verify_ipv4_header(&header[..10]), ChecksumStatus::Unverified);
+}
+
+#[test]
+fn transport_checksum_ipv4_and_ipv6() {
+    let mut udp_segment: Vec<u8> = vec![0x30, 0x39, 0x00, 0x35, 0x00, 0x0d, 0x00, 0x00, b'h', b'e', b'l', b'l', b'o'];
+    for (ip_addr_src, ip_addr_dst) in [
+        (IpAddr::V4(Ipv4Addr::new(192, 168, 1, 90)), IpAddr::V4(Ipv4Addr::new(8, 8, 8, 8))),
+        (IpAddr::V6(Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1)), IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 2))),
+    ] {
+        udp_segment[6] = 0;
+        udp_segment[7] = 0;
+        let checksum = transport_checksum(ip_addr_src, ip_addr_dst, 17, &udp_segment);
+        udp_segment[6..8].copy_from_slice(&checksum.to_be_bytes());
+        assert_eq!(verify_transport(ip_addr_src, ip_addr_dst, 17, &udp_segment), ChecksumStatus::Valid);
+        assert_eq!(verify_transport(ip_addr_dst, ip_addr_dst, 17, &udp_segment), ChecksumStatus::Invalid);
+    }
+}
+
+#[test]
+fn packet_checksum_status() {
+    let mut ethernet_packet = EthernetPacket::from_json(PACKET).unwrap();
+    assert_eq!(ethernet_packet.checksum_status(), ChecksumStatus::Valid);
+
+    let ipv4_packet = IPv4Packet::new(&ethernet_packet.payload);
+    assert_eq!(ipv4_packet.header_checksum_status, ChecksumStatus::Valid);
+    assert_eq!(ipv4_packet.l4_checksum_status(), ChecksumStatus::Valid);
+
+    // corrupted TCP payload
+    ethernet_packet.payload[50] ^= 0xFF;
+    let ipv4_packet = IPv4Packet::new(&ethernet_packet.payload);
+    assert_eq!(ipv4_packet.header_checksum_status, ChecksumStatus::Valid);
+    assert_eq!(ipv4_packet.l4_checksum_status(), ChecksumStatus::Invalid);
+    assert_eq!(ethernet_packet.checksum_status(), ChecksumStatus::Invalid);
+
+    ethernet_packet.set_checksum_offloaded();
+    assert_eq!(ethernet_packet.checksum_status(), ChecksumStatus::Unverified);
+    assert!(!ethernet_packet.report_data.unwrap().bad_checksum);
+}
+
+#[test]
+fn ethernet_padding_ignored() {
+    let mut ethernet_packet = EthernetPacket::from_json(PACKET).unwrap();
+    ethernet_packet.payload.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+    assert_eq!(ethernet_packet.checksum_status(), ChecksumStatus::Valid);
+}
+
+#[test]
+fn fragments_and_truncated_packets_unverified() {
+    let mut ipv4_data_in_u8 = EthernetPacket::from_json(PACKET).unwrap().payload;
+    ipv4_data_in_u8.truncate(40);
+    assert_eq!(IPv4Packet::new(&ipv4_data_in_u8).l4_checksum_status(), ChecksumStatus::Unverified);
+
+    let mut ipv4_data_in_u8 = EthernetPacket::from_json(PACKET).unwrap().payload;
+    ipv4_data_in_u8[6] = 0x20; // more fragments
+    assert_eq!(IPv4Packet::new(&ipv4_data_in_u8).l4_checksum_status(), ChecksumStatus::Unverified);
+}
+
+#[test]
+fn combine_status() {
+    assert_eq!(ChecksumStatus::Valid.combine(ChecksumStatus::Invalid), ChecksumStatus::Invalid);
+    assert_eq!(ChecksumStatus::Unverified.combine(ChecksumStatus::Valid), ChecksumStatus::Valid);
+    assert_eq!(ChecksumStatus::Unverified.combine(ChecksumStatus::Unverified), ChecksumStatus::Unverified);
+}
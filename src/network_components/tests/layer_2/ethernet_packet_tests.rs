@@ -1,7 +1,8 @@
-use crate::EthernetPacket;
+use crate::{EthernetPacket, ReportFormat};
 use crate::network_components::layer_2::ethernet_packet::EtherType;
 use crate::network_components::layer_2::mac_address::MacAddress;
 use crate::network_components::tests::layer_2::PACKET;
+use crate::report_generator::DisplayAs;
 
 #[test]
 fn new_ether_packet() {
@@ -54,3 +55,76 @@ fn ethernet802_3_identified() {
 }
 
 
+/// Frame from e0:b9:e5:30:ef:98 to 74:e5:f9:16:ee:9b with the EtherType and payload given
+fn frame(ether_type: [u8; 2], payload: &[u8]) -> Vec<u8> {
+    let mut ether_data_in_u8 = vec![116, 229, 249, 22, 238, 155, 224, 185, 229, 48, 239, 152];
+    ether_data_in_u8.extend_from_slice(&ether_type);
+    ether_data_in_u8.extend_from_slice(payload);
+    ether_data_in_u8
+}
+
+/// IPv4 header from 192.168.1.90 to 192.168.1.1 with the protocol given (checksum not computed)
+fn ipv4(protocol: u8, payload: &[u8]) -> Vec<u8> {
+    let mut ipv4_data_in_u8 = vec![69, 0];
+    ipv4_data_in_u8.extend_from_slice(&(20 + payload.len() as u16).to_be_bytes());
+    ipv4_data_in_u8.extend_from_slice(&[0, 0, 0, 0, 64, protocol, 0, 0, 192, 168, 1, 90, 192, 168, 1, 1]);
+    ipv4_data_in_u8.extend_from_slice(payload);
+    ipv4_data_in_u8
+}
+
+#[test]
+fn report_data_non_tcp_udp_test() {
+    let report_data = |ether_data_in_u8: Vec<u8>| {
+        let report_data = EthernetPacket::new(&ether_data_in_u8).report_data.unwrap();
+        (report_data.ip_src, report_data.ip_dst, report_data.port_src, report_data.port_dst, report_data.l4_protocol, report_data.upper_service)
+    };
+    let (mac_src, mac_dst) = (String::from("e0:b9:e5:30:ef:98"), String::from("74:e5:f9:16:ee:9b"));
+
+    // IP packets keyed by protocol
+    let (ip_src, ip_dst) = (String::from("192.168.1.90"), String::from("192.168.1.1"));
+    assert_eq!(report_data(frame([8, 0], &ipv4(1, &[8, 0, 0xF7, 0xFF, 0, 0, 0, 0]))), (ip_src.clone(), ip_dst.clone(), 0, 0, "ICMP".to_string(), "-".to_string()));
+    assert_eq!(report_data(frame([8, 0], &ipv4(47, &[0, 0, 8, 0]))), (ip_src.clone(), ip_dst.clone(), 0, 0, "GRE".to_string(), "-".to_string()));
+    assert_eq!(report_data(frame([8, 0], &ipv4(253, &[0; 4]))), (ip_src, ip_dst, 0, 0, "IP-PROTO 253".to_string(), "-".to_string()));
+
+    // frames without IP keyed by MAC addresses and EtherType
+    assert_eq!(report_data(frame([8, 6], &[0, 1, 8, 0, 6, 4, 0, 1])), (mac_src.clone(), mac_dst.clone(), 0, 0, "ARP".to_string(), "-".to_string()));
+    assert_eq!(report_data(frame([0x88, 0xCC], &[2, 7, 4])), (mac_src.clone(), mac_dst.clone(), 0, 0, "LLDP".to_string(), "-".to_string()));
+    assert_eq!(report_data(frame([0x12, 0x34], &[0; 10])).4, "ETHERTYPE 0x1234");
+    assert_eq!(report_data(frame([0, 38], &[0x42, 0x42, 3, 0, 0])), (mac_src, mac_dst, 0, 0, "802.3".to_string(), "STP".to_string()));
+
+    // truncated headers in the OTHER bucket
+    let other = ("-".to_string(), "-".to_string(), 0, 0, "OTHER".to_string(), "-".to_string());
+    assert_eq!(report_data(frame([8, 0], &[69, 0, 0, 20, 0, 0])), other);
+    assert_eq!(report_data(frame([8, 0], &ipv4(6, &[0x01, 0xBB, 0xC7, 0x38, 0, 0, 0, 1]))), other);
+    assert_eq!(report_data(frame([0x86, 0xDD], &[0x60, 0, 0, 0])), other);
+}
+
+#[test]
+fn decoded_report_data_test() {
+    let mut ethernet_packet = EthernetPacket::new(&frame([8, 0], &ipv4(1, &[8, 0, 0xF7, 0xFF, 0, 0, 0, 0])));
+    let report_data = ethernet_packet.report_data.take().unwrap();
+    let decoded = ethernet_packet.decoded_report_data();
+    assert_eq!((decoded.ip_src, decoded.ip_dst, decoded.l4_protocol), (report_data.ip_src, report_data.ip_dst, report_data.l4_protocol));
+
+    let mut arp = EthernetPacket::new(&frame([8, 6], &[0, 1, 8, 0, 6, 4, 0, 1]));
+    arp.report_data = None;
+    assert_eq!(arp.decoded_report_data().l4_protocol, "ARP");
+
+    let mut lldp = EthernetPacket::new(&frame([0x88, 0xCC], &[2, 7, 4]));
+    lldp.report_data = None;
+    assert_eq!(lldp.decoded_report_data().l4_protocol, "OTHER");
+}
+
+#[test]
+fn raw_display_unknown_protocols_test() {
+    let raw = |ether_data_in_u8: Vec<u8>| EthernetPacket::new(&ether_data_in_u8).display_as(ReportFormat::Raw);
+
+    // unknown EtherTypes and IP protocols by name or number
+    assert_eq!(raw(frame([0x88, 0xCC], &[2, 7, 4])), "Ethernet LLDP Other Protocol incapsulated in Ethernet frame (Unknown Protocol)\n");
+    assert_eq!(raw(frame([0x12, 0x34], &[0; 10])), "Ethernet ETHERTYPE 0x1234 Other Protocol incapsulated in Ethernet frame (Unknown Protocol)\n");
+    assert!(raw(frame([8, 0], &ipv4(47, &[0, 0, 8, 0]))).starts_with("Ethernet IPV4 IP-PROTO 47"));
+    let mut ipv6_data_in_u8 = vec![0x60, 0, 0, 0, 0, 4, 47, 64];
+    ipv6_data_in_u8.extend_from_slice(&[0; 32]);
+    ipv6_data_in_u8.extend_from_slice(&[0, 0, 0x86, 0xDD]);
+    assert!(raw(frame([0x86, 0xDD], &ipv6_data_in_u8)).starts_with("Ethernet IPV6 IP-PROTO 47"));
+}
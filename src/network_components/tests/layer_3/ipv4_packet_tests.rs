@@ -1,4 +1,5 @@
 use std::net::Ipv4Addr;
+use crate::network_components::layer_3::{ip_protocol_name, ip_protocol_number};
 use crate::network_components::layer_3::ipv4_packet::{IPv4Packet, Ipv4ProtocolType};
 use crate::network_components::tests::{IPV4_DATA_IN_U8_UDP, IPV4_DATA_IN_U8_WITH_OPTIONS_UDP};
 
@@ -62,3 +63,12 @@ fn ipv4_protocol_types() {
     let ipv4_packet = IPv4Packet::new(&ipv4_data_in_u8_4[..]);
     assert_eq!(ipv4_packet.protocol_type.unwrap(), Ipv4ProtocolType::UDP);
 }
+
+#[test]
+fn ip_protocol_numbers() {
+    for protocol in 0..=u8::MAX {
+        assert_eq!(ip_protocol_number(&ip_protocol_name(protocol)), Some(protocol));
+    }
+    assert_eq!(ip_protocol_number("ARP"), None);
+    assert_eq!(ip_protocol_number("IP-PROTO 256"), None);
+}
@@ -0,0 +1,51 @@
+use crate::network_components::checksum::{ChecksumStatus, internet_checksum};
+use crate::network_components::layer_3::ipv4_packet::IPv4Packet;
+use crate::network_components::layer_4::icmp_packet::{IcmpPacket, IcmpVersion};
+
+const ICMP_ECHO_REQUEST_IN_U8: [u8; 12] = [8, 0, 0, 0, 0x12, 0x34, 0x00, 0x01, b'p', b'i', b'n', b'g'];
+
+#[test]
+fn new_icmp_packet() {
+    let icmp_packet = IcmpPacket::new(&ICMP_ECHO_REQUEST_IN_U8, IcmpVersion::ICMPv4).unwrap();
+
+    assert_eq!(icmp_packet.icmp_type, 8);
+    assert_eq!(icmp_packet.code, 0);
+    assert_eq!(icmp_packet.rest_of_header, 0x12340001);
+    assert_eq!(icmp_packet.payload, Vec::from("ping".as_bytes()));
+    assert_eq!(icmp_packet.type_name(), "Echo Request");
+    assert_eq!(IcmpPacket::new(&[128, 0, 0, 0, 0, 0, 0, 0], IcmpVersion::ICMPv6).unwrap().type_name(), "Echo Request");
+}
+
+#[test]
+fn icmp_checksum_inside_ipv4() {
+    let mut icmp_data_in_u8 = ICMP_ECHO_REQUEST_IN_U8;
+    let checksum = internet_checksum(&icmp_data_in_u8);
+    icmp_data_in_u8[2..4].copy_from_slice(&checksum.to_be_bytes());
+
+    let mut ipv4_data_in_u8 = vec![0x45, 0, 0, 32, 0, 0, 0, 0, 64, 1, 0, 0, 192, 168, 1, 90, 192, 168, 1, 1];
+    let header_checksum = internet_checksum(&ipv4_data_in_u8);
+    ipv4_data_in_u8[10..12].copy_from_slice(&header_checksum.to_be_bytes());
+    ipv4_data_in_u8.extend_from_slice(&icmp_data_in_u8);
+
+    let ipv4_packet = IPv4Packet::new(&ipv4_data_in_u8);
+    assert_eq!(ipv4_packet.header_checksum_status, ChecksumStatus::Valid);
+    assert_eq!(ipv4_packet.l4_checksum_status(), ChecksumStatus::Valid);
+    assert!(!ipv4_packet.to_string().contains("BAD CHECKSUM"));
+
+    ipv4_data_in_u8[30] = b'x';
+    let ipv4_packet = IPv4Packet::new(&ipv4_data_in_u8);
+    assert_eq!(ipv4_packet.l4_checksum_status(), ChecksumStatus::Invalid);
+    assert!(ipv4_packet.to_string().contains("BAD CHECKSUM"));
+}
+
+#[test]
+fn truncated_icmp_packet() {
+    assert!(IcmpPacket::new(&ICMP_ECHO_REQUEST_IN_U8[..7], IcmpVersion::ICMPv4).is_none());
+    assert!(IcmpPacket::new(&[], IcmpVersion::ICMPv6).is_none());
+
+    // a truncated message inside an IPv4 packet is displayed without its details
+    let mut ipv4_data_in_u8 = vec![0x45, 0, 0, 24, 0, 0, 0, 0, 64, 1, 0, 0, 192, 168, 1, 90, 192, 168, 1, 1];
+    ipv4_data_in_u8.extend_from_slice(&ICMP_ECHO_REQUEST_IN_U8[..4]);
+    let ipv4_packet = IPv4Packet::new(&ipv4_data_in_u8);
+    assert!(ipv4_packet.to_string().contains("ICMP     : Unknown Details"));
+}
@@ -2,3 +2,11 @@
 pub mod tcp_packet_tests;
 #[cfg(test)]
 pub mod udp_packet_tests;
+#[cfg(test)]
+pub mod tcp_options_tests;
+#[cfg(test)]
+pub mod icmp_packet_tests;
+#[cfg(test)]
+pub mod tcp_reassembly_tests;
+#[cfg(test)]
+pub mod tcp_connection_tests;
@@ -0,0 +1,108 @@
+use std::net::{IpAddr, Ipv4Addr};
+use chrono::{Duration, Utc};
+use crate::network_components::layer_4::tcp_connection::{TcpConnection, TcpConnectionTracker, TcpState};
+use crate::network_components::layer_4::tcp_packet::TcpPacket;
+use crate::network_components::layer_4::tcp_reassembly::Endpoint;
+
+const SYN: u8 = 0x02;
+const SYN_ACK: u8 = 0x12;
+const ACK: u8 = 0x10;
+const PSH_ACK: u8 = 0x18;
+const FIN_ACK: u8 = 0x11;
+const RST: u8 = 0x04;
+
+fn client() -> Endpoint { Endpoint { ip_addr: IpAddr::V4(Ipv4Addr::new(192, 168, 1, 90)), port: 33685 } }
+fn server() -> Endpoint { Endpoint { ip_addr: IpAddr::V4(Ipv4Addr::new(142, 250, 184, 42)), port: 443 } }
+
+fn send(tracker: &mut TcpConnectionTracker, src: Endpoint, dst: Endpoint, flags: u8, payload: &[u8]) {
+    let mut tcp_data_in_u8 = Vec::new();
+    tcp_data_in_u8.extend_from_slice(&src.port.to_be_bytes());
+    tcp_data_in_u8.extend_from_slice(&dst.port.to_be_bytes());
+    tcp_data_in_u8.extend_from_slice(&[0, 0, 0, 1, 0, 0, 0, 0, 0x50, flags, 0xFF, 0xFF, 0, 0, 0, 0]);
+    tcp_data_in_u8.extend_from_slice(payload);
+    tracker.process(src.ip_addr, dst.ip_addr, &TcpPacket::new(&tcp_data_in_u8), Utc::now());
+}
+
+fn connection(tracker: &TcpConnectionTracker) -> TcpConnection {
+    tracker.get(server(), client()).unwrap().clone()
+}
+
+#[test]
+fn tcp_connection_handshake_test() {
+    let mut tracker = TcpConnectionTracker::new();
+    send(&mut tracker, client(), server(), SYN, &[]);
+    assert_eq!(connection(&tracker).state, TcpState::SynSent);
+    assert_eq!(connection(&tracker).initiator, Some(client()));
+
+    send(&mut tracker, server(), client(), SYN_ACK, &[]);
+    assert_eq!(connection(&tracker).state, TcpState::SynReceived);
+    assert!(!connection(&tracker).handshake_completed);
+
+    send(&mut tracker, client(), server(), ACK, &[]);
+    assert_eq!(connection(&tracker).state, TcpState::Established);
+    assert!(connection(&tracker).handshake_completed);
+    assert_eq!(tracker.len(), 1);
+}
+
+#[test]
+fn tcp_connection_teardown_test() {
+    let mut tracker = TcpConnectionTracker::new();
+    send(&mut tracker, client(), server(), SYN, &[]);
+    send(&mut tracker, server(), client(), SYN_ACK, &[]);
+    send(&mut tracker, client(), server(), ACK, &[]);
+
+    send(&mut tracker, server(), client(), FIN_ACK, &[]);
+    assert_eq!(connection(&tracker).state, TcpState::Closing);
+    send(&mut tracker, client(), server(), FIN_ACK, &[]);
+    assert_eq!(connection(&tracker).state, TcpState::Closed);
+    assert_eq!(connection(&tracker).fin_by, vec![server(), client()]);
+    assert_eq!(connection(&tracker).reset_by, None);
+
+    // late ACK doesn't change the state
+    send(&mut tracker, server(), client(), ACK, &[]);
+    assert_eq!(connection(&tracker).state, TcpState::Closed);
+
+    tracker.purge(Utc::now(), Duration::seconds(60));
+    assert!(tracker.is_empty());
+}
+
+#[test]
+fn tcp_connection_half_open_reset_test() {
+    let mut tracker = TcpConnectionTracker::new();
+    send(&mut tracker, client(), server(), SYN, &[]);
+    send(&mut tracker, server(), client(), RST, &[]);
+
+    let connection = connection(&tracker);
+    assert_eq!(connection.state, TcpState::Reset);
+    assert_eq!(connection.reset_by, Some(server()));
+    assert!(!connection.handshake_completed);
+
+    // port reuse: a new SYN starts a new connection
+    send(&mut tracker, client(), server(), SYN, &[]);
+    assert_eq!(self::connection(&tracker).state, TcpState::SynSent);
+    assert_eq!(self::connection(&tracker).reset_by, None);
+}
+
+#[test]
+fn tcp_connection_mid_stream_test() {
+    let mut tracker = TcpConnectionTracker::new();
+    // pure ACKs of unknown connections are ignored
+    send(&mut tracker, client(), server(), ACK, &[]);
+    assert!(tracker.is_empty());
+
+    send(&mut tracker, server(), client(), PSH_ACK, b"data");
+    let connection = connection(&tracker);
+    assert_eq!(connection.state, TcpState::Established);
+    assert_eq!(connection.initiator, None);
+    assert!(!connection.handshake_completed);
+}
+
+#[test]
+fn tcp_connection_purge_idle_test() {
+    let mut tracker = TcpConnectionTracker::new();
+    send(&mut tracker, client(), server(), SYN, &[]);
+    tracker.purge(Utc::now(), Duration::seconds(60));
+    assert_eq!(tracker.len(), 1);
+    tracker.purge(Utc::now() + Duration::seconds(120), Duration::seconds(60));
+    assert!(tracker.is_empty());
+}
@@ -0,0 +1,74 @@
+use crate::network_components::layer_4::tcp_options::{MptcpSubtype, TcpFlag, TcpFlags, TcpOption};
+use crate::network_components::layer_4::tcp_packet::TcpPacket;
+use crate::network_components::tests::TCP_DATA_IN_U8;
+
+const SYN_OPTIONS_IN_U8: [u8; 20] = [2, 4, 5, 180, 4, 2, 8, 10, 0, 0, 0, 1, 0, 0, 0, 0, 1, 3, 3, 7];
+
+#[test]
+fn tcp_flags_decoding() {
+    let flags = TcpFlags::new(0x50, 0x12);
+    assert!(flags.contains(TcpFlag::SYN));
+    assert!(flags.contains(TcpFlag::ACK));
+    assert!(!flags.contains(TcpFlag::FIN));
+    assert!(!flags.contains(TcpFlag::NS));
+    assert_eq!(flags.names(), vec!["ACK".to_string(), "SYN".to_string()]);
+    assert_eq!(flags.to_string(), "ACK SYN");
+
+    let flags = TcpFlags::new(0x51, 0xC1);
+    assert_eq!(flags.names(), vec!["NS".to_string(), "CWR".to_string(), "ECE".to_string(), "FIN".to_string()]);
+}
+
+#[test]
+fn tcp_flags_from_packet() {
+    let tcp_packet = TcpPacket::new(&TCP_DATA_IN_U8[..]);
+    assert_eq!(tcp_packet.tcp_flags().names(), vec!["URG".to_string(), "ACK".to_string(), "PSH".to_string(), "FIN".to_string()]);
+}
+
+#[test]
+fn tcp_flag_from_name() {
+    assert_eq!(TcpFlag::from_name("SYN"), Some(TcpFlag::SYN));
+    assert_eq!(TcpFlag::from_name("NS"), Some(TcpFlag::NS));
+    assert_eq!(TcpFlag::from_name("syn"), None);
+}
+
+#[test]
+fn tcp_options_syn() {
+    let options = TcpOption::parse(&SYN_OPTIONS_IN_U8);
+    assert_eq!(options, vec![
+        TcpOption::MaximumSegmentSize(1460),
+        TcpOption::SackPermitted,
+        TcpOption::Timestamps { value: 1, echo_reply: 0 },
+        TcpOption::NoOperation,
+        TcpOption::WindowScale(7),
+    ]);
+}
+
+#[test]
+fn tcp_options_sack_tfo_mptcp() {
+    let options_in_u8 = [1, 1, 5, 10, 0, 0, 0, 10, 0, 0, 0, 20, 34, 2, 30, 4, 0x20, 0x01, 254, 6, 0xF9, 0x89, 0xAB, 0xCD, 0];
+    let options = TcpOption::parse(&options_in_u8);
+    assert_eq!(options, vec![
+        TcpOption::NoOperation,
+        TcpOption::NoOperation,
+        TcpOption::Sack(vec![(10, 20)]),
+        TcpOption::FastOpen(vec![]),
+        TcpOption::Mptcp { subtype: MptcpSubtype::Dss, data: vec![0x20, 0x01] },
+        TcpOption::FastOpen(vec![0xAB, 0xCD]),
+        TcpOption::EndOfOptionList,
+    ]);
+}
+
+#[test]
+fn tcp_options_malformed() {
+    let options = TcpOption::parse(&[1, 2, 10, 5, 180]);
+    assert_eq!(options, vec![
+        TcpOption::NoOperation,
+        TcpOption::Unknown { kind: 2, data: vec![10, 5, 180] },
+    ]);
+}
+
+#[test]
+fn tcp_options_display() {
+    let options: Vec<String> = TcpOption::parse(&SYN_OPTIONS_IN_U8).iter().map(|option| option.to_string()).collect();
+    assert_eq!(options.join(", "), "MSS 1460, SACK-permitted, TS val 1 ecr 0, NOP, WS 7");
+}
@@ -14,7 +14,7 @@ fn check_packet(tcp_packet: TcpPacket) {
     assert_eq!(tcp_packet.urgent_pointer, u16::from_be_bytes([65, 255]));
     assert_eq!(tcp_packet.options, Vec::from(&TCP_DATA_IN_U8[20..32]));
     assert_eq!(tcp_packet.payload, Vec::from(&TCP_DATA_IN_U8[32..]));
-    assert_eq!(tcp_packet.upper_layer_service, UpperLayerService::HTTPS);
+    assert_eq!(tcp_packet.identification.upper_layer_service, UpperLayerService::HTTPS);
 }
 
 #[test]
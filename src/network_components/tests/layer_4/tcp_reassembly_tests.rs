@@ -0,0 +1,172 @@
+use std::net::{IpAddr, Ipv4Addr};
+use chrono::{Duration, Utc};
+use crate::network_components::layer_4::tcp_packet::TcpPacket;
+use crate::network_components::layer_4::tcp_reassembly::{Direction, Endpoint, StreamEvent, StreamEventKind, TcpReassembler};
+
+const SYN: u8 = 0x02;
+const ACK: u8 = 0x10;
+const PSH_ACK: u8 = 0x18;
+const SYN_ACK: u8 = 0x12;
+const FIN_ACK: u8 = 0x11;
+const RST: u8 = 0x04;
+
+const CLIENT_ISN: u32 = 1000;
+const SERVER_ISN: u32 = 5000;
+
+fn client() -> IpAddr { IpAddr::V4(Ipv4Addr::new(192, 168, 1, 90)) }
+fn server() -> IpAddr { IpAddr::V4(Ipv4Addr::new(142, 250, 184, 42)) }
+
+fn segment(src_port: u16, dst_port: u16, sequence_number: u32, flags: u8, payload: &[u8]) -> TcpPacket {
+    let mut tcp_data_in_u8 = Vec::new();
+    tcp_data_in_u8.extend_from_slice(&src_port.to_be_bytes());
+    tcp_data_in_u8.extend_from_slice(&dst_port.to_be_bytes());
+    tcp_data_in_u8.extend_from_slice(&sequence_number.to_be_bytes());
+    tcp_data_in_u8.extend_from_slice(&0u32.to_be_bytes());
+    tcp_data_in_u8.extend_from_slice(&[0x50, flags, 0xFF, 0xFF, 0, 0, 0, 0]);
+    tcp_data_in_u8.extend_from_slice(payload);
+    TcpPacket::new(&tcp_data_in_u8)
+}
+
+fn to_client(reassembler: &mut TcpReassembler, sequence_number: u32, flags: u8, payload: &[u8]) -> Vec<StreamEvent> {
+    reassembler.process(server(), client(), &segment(443, 33685, sequence_number, flags, payload), Utc::now())
+}
+
+fn to_server(reassembler: &mut TcpReassembler, sequence_number: u32, flags: u8, payload: &[u8]) -> Vec<StreamEvent> {
+    reassembler.process(client(), server(), &segment(33685, 443, sequence_number, flags, payload), Utc::now())
+}
+
+fn handshake(reassembler: &mut TcpReassembler) {
+    assert!(to_server(reassembler, CLIENT_ISN, SYN, &[]).is_empty());
+    assert!(to_client(reassembler, SERVER_ISN, SYN_ACK, &[]).is_empty());
+    assert!(to_server(reassembler, CLIENT_ISN + 1, ACK, &[]).is_empty());
+}
+
+fn data(events: &[StreamEvent]) -> Vec<u8> {
+    events.iter().filter_map(|event| match &event.kind {
+        StreamEventKind::Data(data) => Some(data.clone()),
+        _ => None,
+    }).flatten().collect()
+}
+
+#[test]
+fn tcp_reassembly_in_order_test() {
+    let mut reassembler = TcpReassembler::new();
+    handshake(&mut reassembler);
+
+    let events = to_server(&mut reassembler, CLIENT_ISN + 1, PSH_ACK, b"GET / ");
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].direction, Direction::ClientToServer);
+    assert_eq!(events[0].connection.client, Endpoint { ip_addr: client(), port: 33685 });
+    assert_eq!(events[0].connection.server, Endpoint { ip_addr: server(), port: 443 });
+    assert_eq!(data(&events), b"GET / ");
+
+    let events = to_server(&mut reassembler, CLIENT_ISN + 7, PSH_ACK, b"HTTP/1.1");
+    assert_eq!(data(&events), b"HTTP/1.1");
+
+    let events = to_client(&mut reassembler, SERVER_ISN + 1, PSH_ACK, b"HTTP/1.1 200 OK");
+    assert_eq!(events[0].direction, Direction::ServerToClient);
+    assert_eq!(data(&events), b"HTTP/1.1 200 OK");
+    assert_eq!(reassembler.len(), 1);
+}
+
+#[test]
+fn tcp_reassembly_out_of_order_test() {
+    let mut reassembler = TcpReassembler::new();
+    handshake(&mut reassembler);
+
+    assert!(to_server(&mut reassembler, CLIENT_ISN + 4, PSH_ACK, b"def").is_empty());
+    assert!(to_server(&mut reassembler, CLIENT_ISN + 7, PSH_ACK, b"ghi").is_empty());
+    let events = to_server(&mut reassembler, CLIENT_ISN + 1, PSH_ACK, b"abc");
+    assert_eq!(data(&events), b"abcdefghi");
+
+    let (client_statistics, _) = reassembler.statistics(Endpoint { ip_addr: client(), port: 33685 }, Endpoint { ip_addr: server(), port: 443 }).unwrap();
+    assert_eq!(client_statistics.delivered_bytes, 9);
+    assert_eq!(client_statistics.out_of_order_segments, 2);
+}
+
+#[test]
+fn tcp_reassembly_retransmission_test() {
+    let mut reassembler = TcpReassembler::new();
+    handshake(&mut reassembler);
+
+    assert_eq!(data(&to_server(&mut reassembler, CLIENT_ISN + 1, PSH_ACK, b"abcd")), b"abcd");
+    // exact retransmission
+    assert!(to_server(&mut reassembler, CLIENT_ISN + 1, PSH_ACK, b"abcd").is_empty());
+    // overlapping segment: only the new bytes are delivered
+    assert_eq!(data(&to_server(&mut reassembler, CLIENT_ISN + 3, PSH_ACK, b"cdef")), b"ef");
+
+    let (client_statistics, _) = reassembler.statistics(Endpoint { ip_addr: server(), port: 443 }, Endpoint { ip_addr: client(), port: 33685 }).unwrap();
+    assert_eq!(client_statistics.delivered_bytes, 6);
+    assert_eq!(client_statistics.retransmitted_bytes, 6);
+}
+
+#[test]
+fn tcp_reassembly_gap_on_close_test() {
+    let mut reassembler = TcpReassembler::new();
+    handshake(&mut reassembler);
+
+    assert_eq!(data(&to_server(&mut reassembler, CLIENT_ISN + 1, PSH_ACK, b"abc")), b"abc");
+    assert!(to_server(&mut reassembler, CLIENT_ISN + 9, PSH_ACK, b"xyz").is_empty());
+
+    let events = to_client(&mut reassembler, SERVER_ISN + 1, RST, &[]);
+    assert_eq!(events.iter().map(|event| event.kind.clone()).collect::<Vec<StreamEventKind>>(), vec![
+        StreamEventKind::Gap(5),
+        StreamEventKind::Data(b"xyz".to_vec()),
+        StreamEventKind::Close,
+    ]);
+    assert!(reassembler.is_empty());
+}
+
+#[test]
+fn tcp_reassembly_fin_close_test() {
+    let mut reassembler = TcpReassembler::new();
+    handshake(&mut reassembler);
+
+    assert_eq!(data(&to_server(&mut reassembler, CLIENT_ISN + 1, PSH_ACK, b"bye")), b"bye");
+    assert!(to_server(&mut reassembler, CLIENT_ISN + 4, FIN_ACK, &[]).is_empty());
+    let events = to_client(&mut reassembler, SERVER_ISN + 1, FIN_ACK, &[]);
+    assert_eq!(events.last().unwrap().kind, StreamEventKind::Close);
+    assert!(reassembler.is_empty());
+
+    // the last ACK doesn't open a new connection
+    assert!(to_server(&mut reassembler, CLIENT_ISN + 5, ACK, &[]).is_empty());
+    assert!(reassembler.is_empty());
+}
+
+#[test]
+fn tcp_reassembly_sequence_wraparound_test() {
+    let mut reassembler = TcpReassembler::new();
+    let isn = u32::MAX - 2;
+    assert!(reassembler.process(client(), server(), &segment(33685, 443, isn, SYN, &[]), Utc::now()).is_empty());
+
+    let events = reassembler.process(client(), server(), &segment(33685, 443, isn.wrapping_add(1), PSH_ACK, b"abcd"), Utc::now());
+    assert_eq!(data(&events), b"abcd");
+    let events = reassembler.process(client(), server(), &segment(33685, 443, isn.wrapping_add(5), PSH_ACK, b"efgh"), Utc::now());
+    assert_eq!(data(&events), b"efgh");
+}
+
+#[test]
+fn tcp_reassembly_client_detection_test() {
+    let mut reassembler = TcpReassembler::new();
+    // SYN not captured: the SYN-ACK sender is the server
+    assert!(to_client(&mut reassembler, SERVER_ISN, SYN_ACK, &[]).is_empty());
+    let events = to_server(&mut reassembler, CLIENT_ISN + 1, PSH_ACK, b"hello");
+    assert_eq!(events[0].direction, Direction::ClientToServer);
+    assert_eq!(events[0].connection.server.port, 443);
+}
+
+#[test]
+fn tcp_reassembly_expire_test() {
+    let mut reassembler = TcpReassembler::new();
+    handshake(&mut reassembler);
+    assert!(to_server(&mut reassembler, CLIENT_ISN + 4, PSH_ACK, b"late").is_empty());
+
+    assert!(reassembler.expire(Utc::now(), Duration::seconds(60)).is_empty());
+    let events = reassembler.expire(Utc::now() + Duration::seconds(120), Duration::seconds(60));
+    assert_eq!(events.iter().map(|event| event.kind.clone()).collect::<Vec<StreamEventKind>>(), vec![
+        StreamEventKind::Gap(3),
+        StreamEventKind::Data(b"late".to_vec()),
+        StreamEventKind::Close,
+    ]);
+    assert!(reassembler.is_empty());
+}
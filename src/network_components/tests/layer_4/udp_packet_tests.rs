@@ -7,7 +7,7 @@ fn check_packet(udp_packet: UdpPacket) {
     assert_eq!(udp_packet.dst_port, u16::from_be_bytes([1, 187]));
     assert_eq!(udp_packet.length, u16::from_be_bytes([0, 41]));
     assert_eq!(udp_packet.checksum, u16::from_be_bytes([100, 18]));
-    assert_eq!(udp_packet.upper_layer_service, UpperLayerService::HTTPS);
+    assert_eq!(udp_packet.identification.upper_layer_service, UpperLayerService::HTTPS);
 }
 
 #[test]
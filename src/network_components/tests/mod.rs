@@ -26,3 +26,6 @@ pub mod layer_4;
 
 #[cfg(test)]
 pub mod services_upper_layers;
+
+#[cfg(test)]
+pub mod checksum_tests;
@@ -0,0 +1,178 @@
+use std::net::{Ipv4Addr, Ipv6Addr};
+use chrono::Utc;
+use crate::network_components::layer_4::upd_packet::UdpPacket;
+use crate::network_components::services_upper_layers::dhcp::{DhcpLeases, DhcpMessage, DhcpMessageType, Dhcpv6Lease, Dhcpv6Message};
+
+const CLIENT_MAC: [u8; 6] = [0x00, 0x0B, 0x82, 0x01, 0xFC, 0x42];
+
+/// DHCPv4 message from the client (op 1) or from the server (op 2) with the options given
+fn dhcp(op: u8, your_ip: [u8; 4], options: &[&[u8]]) -> Vec<u8> {
+    let mut message = vec![op, 1, 6, 0, 0x39, 0x03, 0xF3, 0x26, 0, 0, 0x80, 0];
+    message.extend_from_slice(&[0; 4]);
+    message.extend_from_slice(&your_ip);
+    message.extend_from_slice(&[0; 8]);
+    message.extend_from_slice(&CLIENT_MAC);
+    message.extend_from_slice(&[0; 10 + 64 + 128]);
+    message.extend_from_slice(&[99, 130, 83, 99]);
+    for option in options {
+        message.extend_from_slice(option);
+    }
+    message.extend_from_slice(&[255, 0, 0, 0]);
+    message
+}
+
+fn discover() -> Vec<u8> {
+    dhcp(1, [0; 4], &[&[53, 1, 1], &[50, 4, 192, 168, 0, 10], &[12, 6, b'l', b'a', b'p', b't', b'o', b'p'], &[60, 8, b'M', b'S', b'F', b'T', b' ', b'5', b'.', b'0'], &[0, 0]])
+}
+
+fn ack() -> Vec<u8> {
+    dhcp(2, [192, 168, 0, 10], &[&[53, 1, 5], &[54, 4, 192, 168, 0, 1], &[51, 4, 0, 0, 0x0E, 0x10], &[1, 4, 255, 255, 255, 0], &[3, 4, 192, 168, 0, 1], &[6, 8, 8, 8, 8, 8, 8, 8, 4, 4]])
+}
+
+fn dhcpv6_option(code: u16, data: &[u8]) -> Vec<u8> {
+    let mut option = code.to_be_bytes().to_vec();
+    option.extend_from_slice(&(data.len() as u16).to_be_bytes());
+    option.extend_from_slice(data);
+    option
+}
+
+/// DHCPv6 REPLY with an address and a delegated prefix
+fn dhcpv6_reply() -> Vec<u8> {
+    let mut ia_address = "2001:db8::10".parse::<Ipv6Addr>().unwrap().octets().to_vec();
+    ia_address.extend_from_slice(&3600u32.to_be_bytes());
+    ia_address.extend_from_slice(&7200u32.to_be_bytes());
+    let mut ia_na = vec![0, 0, 0, 1, 0, 0, 0x07, 0x08, 0, 0, 0x0B, 0x40];
+    ia_na.extend(dhcpv6_option(5, &ia_address));
+
+    let mut ia_prefix = 1800u32.to_be_bytes().to_vec();
+    ia_prefix.extend_from_slice(&3600u32.to_be_bytes());
+    ia_prefix.push(56);
+    ia_prefix.extend_from_slice(&"2001:db8:100::".parse::<Ipv6Addr>().unwrap().octets());
+    let mut ia_pd = vec![0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0];
+    ia_pd.extend(dhcpv6_option(26, &ia_prefix));
+
+    let mut message = vec![7, 0x12, 0x34, 0x56];
+    message.extend(dhcpv6_option(1, &[0, 3, 0, 1, 0x00, 0x0B, 0x82, 0x01, 0xFC, 0x42]));
+    message.extend(dhcpv6_option(2, &[0, 1, 0, 1, 0xAA, 0xBB]));
+    message.extend(dhcpv6_option(3, &ia_na));
+    message.extend(dhcpv6_option(25, &ia_pd));
+    message.extend(dhcpv6_option(23, &"2001:db8::53".parse::<Ipv6Addr>().unwrap().octets()));
+    message.extend(dhcpv6_option(39, &[0, 6, b'l', b'a', b'p', b't', b'o', b'p', 3, b'l', b'a', b'n', 0]));
+    message
+}
+
+#[test]
+fn dhcp_discover_test() {
+    let message = DhcpMessage::parse(&discover()).unwrap();
+    assert_eq!(message.op, 1);
+    assert_eq!(message.transaction_id, 0x3903F326);
+    assert_eq!(message.message_type, Some(DhcpMessageType::Discover));
+    assert_eq!(message.client_hardware_address, "00:0b:82:01:fc:42");
+    assert_eq!(message.requested_ip, Some(Ipv4Addr::new(192, 168, 0, 10)));
+    assert_eq!(message.hostname.as_deref(), Some("laptop"));
+    assert_eq!(message.vendor_class.as_deref(), Some("MSFT 5.0"));
+    assert_eq!(message.address(), Some(Ipv4Addr::new(192, 168, 0, 10)));
+    assert_eq!(message.options.len(), 4);
+}
+
+#[test]
+fn dhcp_ack_test() {
+    let message = DhcpMessage::parse(&ack()).unwrap();
+    assert_eq!(message.name(), "ACK");
+    assert_eq!(message.your_ip, Ipv4Addr::new(192, 168, 0, 10));
+    assert_eq!(message.lease_time, Some(3600));
+    assert_eq!(message.subnet_mask, Some(Ipv4Addr::new(255, 255, 255, 0)));
+    assert_eq!(message.routers, vec![Ipv4Addr::new(192, 168, 0, 1)]);
+    assert_eq!(message.dns_servers, vec![Ipv4Addr::new(8, 8, 8, 8), Ipv4Addr::new(8, 8, 4, 4)]);
+    assert_eq!(message.server_id, Some(Ipv4Addr::new(192, 168, 0, 1)));
+}
+
+#[test]
+fn dhcp_malformed_test() {
+    // BOOTP message without options
+    let mut bootp = dhcp(2, [10, 0, 0, 5], &[]);
+    assert_eq!(DhcpMessage::parse(&bootp).unwrap().name(), "BOOTREPLY");
+    bootp[236] = 0;
+    assert_eq!(DhcpMessage::parse(&bootp), None);
+    assert_eq!(DhcpMessage::parse(&discover()[..200]), None);
+
+    // option truncated
+    let mut truncated = dhcp(1, [0; 4], &[&[53, 1, 3]]);
+    truncated.truncate(truncated.len() - 4);
+    truncated.extend_from_slice(&[12, 10, b'a']);
+    assert_eq!(DhcpMessage::parse(&truncated), None);
+}
+
+#[test]
+fn dhcp_verbose_test() {
+    let discover = discover();
+    let mut udp_data_in_u8 = vec![0, 68, 0, 67];
+    udp_data_in_u8.extend_from_slice(&(8 + discover.len() as u16).to_be_bytes());
+    udp_data_in_u8.extend_from_slice(&[0, 0]);
+    udp_data_in_u8.extend(discover);
+
+    let verbose = UdpPacket::new(&udp_data_in_u8).to_string();
+    assert!(verbose.contains("DHCP    : DISCOVER 0x3903f326 - [client: 00:0b:82:01:fc:42, client-ip: 0.0.0.0, your-ip: 0.0.0.0, server-ip: 0.0.0.0, relay-ip: 0.0.0.0]"));
+    assert!(verbose.contains(" > requested  : 192.168.0.10"));
+    assert!(verbose.contains(" > hostname   : laptop"));
+    assert!(!verbose.contains("Protocol details unknown"));
+
+    let ack = DhcpMessage::parse(&ack()).unwrap().to_string();
+    assert!(ack.contains(" > lease time : 3600s\n > mask       : 255.255.255.0\n > router     : 192.168.0.1\n > dns        : 8.8.8.8, 8.8.4.4\n > server id  : 192.168.0.1"));
+}
+
+#[test]
+fn dhcpv6_reply_test() {
+    let message = Dhcpv6Message::parse(&dhcpv6_reply()).unwrap();
+    assert_eq!(message.name(), "REPLY");
+    assert_eq!(message.transaction_id, 0x123456);
+    assert_eq!(message.client_id.as_deref(), Some("00030001000b8201fc42"));
+    assert_eq!(message.server_id.as_deref(), Some("00010001aabb"));
+    assert_eq!(message.leases, vec![
+        Dhcpv6Lease { address: "2001:db8::10".parse().unwrap(), prefix_length: None, preferred_lifetime: 3600, valid_lifetime: 7200 },
+        Dhcpv6Lease { address: "2001:db8:100::".parse().unwrap(), prefix_length: Some(56), preferred_lifetime: 1800, valid_lifetime: 3600 },
+    ]);
+    assert_eq!(message.dns_servers, vec!["2001:db8::53".parse::<Ipv6Addr>().unwrap()]);
+    assert_eq!(message.hostname.as_deref(), Some("laptop.lan"));
+    assert!(message.to_string().contains(" > lease      : 2001:db8:100::/56 (preferred 1800s, valid 3600s)"));
+}
+
+#[test]
+fn dhcpv6_relay_test() {
+    let mut relay = vec![12, 0];
+    relay.extend_from_slice(&"2001:db8:1::1".parse::<Ipv6Addr>().unwrap().octets());
+    relay.extend_from_slice(&"fe80::1".parse::<Ipv6Addr>().unwrap().octets());
+    relay.extend(dhcpv6_option(9, &dhcpv6_reply()));
+
+    let message = Dhcpv6Message::parse(&relay).unwrap();
+    assert_eq!(message.name(), "REPLY");
+    assert_eq!(message.relays, vec!["2001:db8:1::1".parse::<Ipv6Addr>().unwrap()]);
+    assert_eq!(message.leases.len(), 2);
+
+    assert_eq!(Dhcpv6Message::parse(&[0, 1, 2, 3]), None);
+    assert_eq!(Dhcpv6Message::parse(&dhcpv6_reply()[..20]), None);
+}
+
+#[test]
+fn dhcp_leases_test() {
+    let mut dhcp_leases = DhcpLeases::new();
+    dhcp_leases.push(&DhcpMessage::parse(&discover()).unwrap(), Utc::now());
+    dhcp_leases.push(&DhcpMessage::parse(&ack()).unwrap(), Utc::now());
+    dhcp_leases.push_v6(&Dhcpv6Message::parse(&dhcpv6_reply()).unwrap(), Utc::now());
+
+    let activity = dhcp_leases.activity();
+    assert_eq!(activity.len(), 3);
+    // hostname sent in the DISCOVER
+    assert_eq!(activity[1].message, "ACK");
+    assert_eq!(activity[1].hostname.as_deref(), Some("laptop"));
+    assert_eq!(activity[1].addresses, vec!["192.168.0.10".to_string()]);
+    assert_eq!(activity[1].lease_time, Some(3600));
+    assert_eq!(activity[2].addresses, vec!["2001:db8::10".to_string(), "2001:db8:100::/56".to_string()]);
+    assert_eq!(activity[2].lease_time, Some(7200));
+
+    let section = dhcp_leases.report_section().unwrap();
+    assert!(section.starts_with("DHCP lease activity: 3 messages\nTimestamp"));
+    assert!(section.contains("| DHCPv4  | ACK                 | 0x3903f326  | 00:0b:82:01:fc:42                        | laptop                    | 192.168.0.10                            | 3600       | 192.168.0.1"));
+    assert_eq!(dhcp_leases.report_section(), None);
+    assert_eq!(dhcp_leases.hostname("00:0b:82:01:fc:42"), Some("laptop"));
+}
@@ -0,0 +1,235 @@
+use std::net::{Ipv4Addr, Ipv6Addr};
+use chrono::Utc;
+use crate::network_components::layer_4::tcp_reassembly::{ConnectionKey, Direction, Endpoint, StreamDissector};
+use crate::network_components::layer_4::upd_packet::UdpPacket;
+use crate::network_components::services_upper_layers::dns::{DnsMessage, DnsRecordData, DnsRecordType, DnsSummary, SvcParam};
+
+fn name(name: &str) -> Vec<u8> {
+    let mut name_in_u8 = Vec::new();
+    for label in name.split('.').filter(|label| !label.is_empty()) {
+        name_in_u8.push(label.len() as u8);
+        name_in_u8.extend_from_slice(label.as_bytes());
+    }
+    name_in_u8.push(0);
+    name_in_u8
+}
+
+fn record(name_in_u8: &[u8], record_type: u16, class: u16, ttl: u32, data: &[u8]) -> Vec<u8> {
+    let mut record_in_u8 = Vec::from(name_in_u8);
+    record_in_u8.extend_from_slice(&record_type.to_be_bytes());
+    record_in_u8.extend_from_slice(&class.to_be_bytes());
+    record_in_u8.extend_from_slice(&ttl.to_be_bytes());
+    record_in_u8.extend_from_slice(&(data.len() as u16).to_be_bytes());
+    record_in_u8.extend_from_slice(data);
+    record_in_u8
+}
+
+fn header(id: u16, flags: u16, counts: [u16; 4]) -> Vec<u8> {
+    let mut header_in_u8 = Vec::new();
+    header_in_u8.extend_from_slice(&id.to_be_bytes());
+    header_in_u8.extend_from_slice(&flags.to_be_bytes());
+    counts.iter().for_each(|count| header_in_u8.extend_from_slice(&count.to_be_bytes()));
+    header_in_u8
+}
+
+fn query(id: u16, qname: &str, qtype: u16) -> Vec<u8> {
+    let mut query_in_u8 = header(id, 0x0100, [1, 0, 0, 1]);
+    query_in_u8.extend(name(qname));
+    query_in_u8.extend_from_slice(&qtype.to_be_bytes());
+    query_in_u8.extend_from_slice(&1u16.to_be_bytes());
+    // EDNS: UDP payload size 1232, DO flag, cookie option
+    query_in_u8.extend(record(&[0], 41, 1232, 0x0000_8000, &[0, 10, 0, 8, 1, 2, 3, 4, 5, 6, 7, 8]));
+    query_in_u8
+}
+
+/// Response to "www.example.com A" using compression pointers (0xC00C is the question name, offset 12).
+fn response() -> Vec<u8> {
+    let mut response_in_u8 = header(0x1a2b, 0x8180, [1, 9, 1, 0]);
+    response_in_u8.extend(name("www.example.com"));
+    response_in_u8.extend_from_slice(&[0, 1, 0, 1]);
+
+    let mut cname = name("edge");
+    cname.truncate(cname.len() - 1);
+    cname.extend_from_slice(&[0xC0, 16]); // edge.example.com
+    response_in_u8.extend(record(&[0xC0, 12], 5, 1, 300, &cname));
+    response_in_u8.extend(record(&[0xC0, 12], 1, 1, 60, &[93, 184, 216, 34]));
+    response_in_u8.extend(record(&[0xC0, 12], 28, 1, 60, &Ipv6Addr::new(0x2606, 0x2800, 0x220, 1, 0x248, 0x1893, 0x25c8, 0x1946).octets()));
+    response_in_u8.extend(record(&[0xC0, 16], 15, 1, 3600, &[&[0, 10][..], &name("mail.example.com")].concat()));
+    response_in_u8.extend(record(&[0xC0, 16], 16, 1, 3600, b"\x0bv=spf1 -all\x03foo"));
+    response_in_u8.extend(record(&[0xC0, 16], 33, 1, 3600, &[&[0, 0, 0, 5, 0x13, 0xC4][..], &name("sip.example.com")].concat()));
+    response_in_u8.extend(record(&[0xC0, 16], 12, 1, 3600, &name("host.example.com")));
+    let https = [&[0, 1][..], &[0], &[0, 1, 0, 6, 2, b'h', b'2', 2, b'h', b'3'], &[0, 3, 0, 2, 1, 187], &[0, 4, 0, 4, 1, 2, 3, 4]].concat();
+    response_in_u8.extend(record(&[0xC0, 12], 65, 1, 300, &https));
+    response_in_u8.extend(record(&[0xC0, 16], 64, 1, 300, &[&[0, 0][..], &name("svc.example.com")].concat()));
+    let soa = [&name("ns1.example.com")[..], &name("hostmaster.example.com"), &2024010101u32.to_be_bytes(), &7200u32.to_be_bytes(),
+        &3600u32.to_be_bytes(), &1209600u32.to_be_bytes(), &300u32.to_be_bytes()].concat();
+    response_in_u8.extend(record(&[0xC0, 16], 6, 1, 3600, &soa));
+    response_in_u8
+}
+
+#[test]
+fn dns_query_test() {
+    let message = DnsMessage::parse(&query(0x1234, "example.com", 1)).unwrap();
+    assert_eq!(message.id, 0x1234);
+    assert!(!message.is_response());
+    assert_eq!(message.opcode(), 0);
+    assert_eq!(message.flag_names(), vec!["RD"]);
+    assert_eq!(message.questions.len(), 1);
+    assert_eq!(message.questions[0].name, "example.com");
+    assert_eq!(message.questions[0].record_type, DnsRecordType::A);
+    assert_eq!(message.questions[0].class, 1);
+
+    let opt = &message.additionals[0];
+    assert_eq!(opt.name, ".");
+    assert_eq!(opt.record_type, DnsRecordType::OPT);
+    assert_eq!(opt.data, DnsRecordData::OPT { udp_payload_size: 1232, extended_rcode: 0, version: 0, dnssec_ok: true, options: vec![(10, vec![1, 2, 3, 4, 5, 6, 7, 8])] });
+}
+
+#[test]
+fn dns_response_records_test() {
+    let message = DnsMessage::parse(&response()).unwrap();
+    assert!(message.is_response());
+    assert_eq!(message.rcode(), 0);
+    assert_eq!(message.flag_names(), vec!["RD", "RA"]);
+    assert_eq!(message.questions[0].name, "www.example.com");
+
+    let data: Vec<DnsRecordData> = message.answers.iter().map(|record| record.data.clone()).collect();
+    assert_eq!(data, vec![
+        DnsRecordData::Name("edge.example.com".to_string()),
+        DnsRecordData::A(Ipv4Addr::new(93, 184, 216, 34)),
+        DnsRecordData::AAAA(Ipv6Addr::new(0x2606, 0x2800, 0x220, 1, 0x248, 0x1893, 0x25c8, 0x1946)),
+        DnsRecordData::MX { preference: 10, exchange: "mail.example.com".to_string() },
+        DnsRecordData::TXT(vec!["v=spf1 -all".to_string(), "foo".to_string()]),
+        DnsRecordData::SRV { priority: 0, weight: 5, port: 5060, target: "sip.example.com".to_string() },
+        DnsRecordData::Name("host.example.com".to_string()),
+        DnsRecordData::SVCB { priority: 1, target: ".".to_string(), params: vec![
+            SvcParam { key: 1, value: vec![2, b'h', b'2', 2, b'h', b'3'] },
+            SvcParam { key: 3, value: vec![1, 187] },
+            SvcParam { key: 4, value: vec![1, 2, 3, 4] },
+        ] },
+        DnsRecordData::SVCB { priority: 0, target: "svc.example.com".to_string(), params: vec![] },
+    ]);
+    assert_eq!(message.answers[0].name, "www.example.com");
+    assert_eq!(message.answers[3].name, "example.com");
+    assert_eq!(message.answers[7].record_type, DnsRecordType::HTTPS);
+    assert_eq!(message.answers[7].to_string(), "www.example.com IN HTTPS 300 1 . alpn=h2,h3 port=443 ipv4hint=1.2.3.4");
+
+    assert_eq!(message.authorities[0].data, DnsRecordData::SOA {
+        mname: "ns1.example.com".to_string(), rname: "hostmaster.example.com".to_string(),
+        serial: 2024010101, refresh: 7200, retry: 3600, expire: 1209600, minimum: 300 });
+}
+
+#[test]
+fn dns_display_test() {
+    let message = DnsMessage::parse(&response()).unwrap().to_string();
+    let lines: Vec<&str> = message.lines().collect();
+    assert_eq!(lines[0], "DNS     : response 0x1a2b NOERROR - [opcode: QUERY, flags: RD RA, questions: 1, answers: 9, authorities: 1, additionals: 0]");
+    assert_eq!(lines[1], " > question   : www.example.com IN A");
+    assert_eq!(lines[2], " > answer     : www.example.com IN CNAME 300 edge.example.com");
+    assert_eq!(lines[6], " > answer     : example.com IN TXT 3600 \"v=spf1 -all\" \"foo\"");
+    assert!(lines[11].starts_with(" > authority  : example.com IN SOA 3600 ns1.example.com hostmaster.example.com 2024010101"));
+}
+
+#[test]
+fn dns_extended_rcode_test() {
+    let mut response_in_u8 = header(1, 0x8180, [0, 0, 0, 1]);
+    response_in_u8.extend(record(&[0], 41, 1232, 0x0100_0000, &[]));
+    let message = DnsMessage::parse(&response_in_u8).unwrap();
+    assert_eq!(message.rcode(), 16); // BADVERS
+    assert!(message.to_string().starts_with("DNS     : response 0x0001 BADVERS"));
+}
+
+#[test]
+fn dns_malformed_test() {
+    // truncated header
+    assert!(DnsMessage::parse(&[0, 1, 0, 0, 0, 1]).is_none());
+    // truncated question
+    let query_in_u8 = query(1, "example.com", 1);
+    assert!(DnsMessage::parse(&query_in_u8[..20]).is_none());
+    // compression loop: the name points to itself
+    let mut looping = header(1, 0x0100, [1, 0, 0, 0]);
+    looping.extend_from_slice(&[0xC0, 12, 0, 1, 0, 1]);
+    assert!(DnsMessage::parse(&looping).is_none());
+    // unknown record type: raw data kept
+    let mut unknown = header(1, 0x8180, [0, 1, 0, 0]);
+    unknown.extend(record(&[0], 99, 1, 10, &[1, 2, 3]));
+    assert_eq!(DnsMessage::parse(&unknown).unwrap().answers[0].data, DnsRecordData::Unknown(vec![1, 2, 3]));
+}
+
+#[test]
+fn dns_over_tcp_test() {
+    let mut payload = Vec::new();
+    for message_in_u8 in [query(1, "a.example", 1), query(2, "b.example", 28)] {
+        payload.extend_from_slice(&(message_in_u8.len() as u16).to_be_bytes());
+        payload.extend(message_in_u8);
+    }
+    let messages = DnsMessage::parse_tcp(&payload);
+    assert_eq!(messages.len(), 2);
+    assert_eq!(messages[1].questions[0].record_type, DnsRecordType::AAAA);
+
+    // incomplete second message
+    assert_eq!(DnsMessage::parse_tcp(&payload[..payload.len() - 1]).len(), 1);
+}
+
+#[test]
+fn dns_verbose_udp_test() {
+    let query_in_u8 = query(0x1234, "example.com", 1);
+    let mut udp_data_in_u8 = vec![0xC3, 0x50, 0, 53];
+    udp_data_in_u8.extend_from_slice(&(8 + query_in_u8.len() as u16).to_be_bytes());
+    udp_data_in_u8.extend_from_slice(&[0, 0]);
+    udp_data_in_u8.extend(query_in_u8);
+
+    let verbose = UdpPacket::new(&udp_data_in_u8).to_string();
+    assert!(verbose.contains("DNS     : query 0x1234 - [opcode: QUERY, flags: RD"));
+    assert!(verbose.contains(" > question   : example.com IN A"));
+    assert!(!verbose.contains("Protocol details unknown"));
+}
+
+#[test]
+fn dns_summary_test() {
+    let mut dns_summary = DnsSummary::new();
+    for (id, qname) in [(1, "b.example"), (2, "A.example"), (3, "a.example"), (4, "c.example"), (5, "a.example")] {
+        dns_summary.push(&DnsMessage::parse(&query(id, qname, 1)).unwrap());
+    }
+    dns_summary.push(&DnsMessage::parse(&response()).unwrap());
+    dns_summary.push(&DnsMessage::parse(&header(7, 0x8183, [0, 0, 0, 0])).unwrap());
+    dns_summary.push(&DnsMessage::parse(&header(8, 0x8183, [0, 0, 0, 0])).unwrap());
+
+    assert_eq!(dns_summary.len(), 8);
+    assert_eq!(dns_summary.top_queried_names(2), vec![("a.example".to_string(), 3), ("b.example".to_string(), 1)]);
+    assert_eq!(dns_summary.rcode_counts(), vec![("NXDOMAIN".to_string(), 2), ("NOERROR".to_string(), 1)]);
+
+    let section = dns_summary.report_section().unwrap();
+    assert!(section.starts_with("DNS summary: 8 messages\nTop queried names:\n  3         a.example\n"));
+    assert!(section.contains("Response codes:\n  2         NXDOMAIN\n  1         NOERROR\n"));
+    assert!(dns_summary.is_empty()); // reset every report interval
+    assert_eq!(dns_summary.report_section(), None);
+}
+
+#[test]
+fn dns_summary_tcp_stream_test() {
+    let connection = ConnectionKey {
+        client: Endpoint { ip_addr: "192.168.1.90".parse().unwrap(), port: 40000 },
+        server: Endpoint { ip_addr: "192.168.1.1".parse().unwrap(), port: 53 },
+    };
+    let message_in_u8 = query(1, "example.com", 1);
+    let mut stream = (message_in_u8.len() as u16).to_be_bytes().to_vec();
+    stream.extend(message_in_u8);
+
+    let mut dns_summary = DnsSummary::new();
+    dns_summary.on_data(&connection, Direction::ClientToServer, &stream[..5], Utc::now());
+    assert!(dns_summary.is_empty());
+    dns_summary.on_data(&connection, Direction::ClientToServer, &stream[5..], Utc::now());
+    assert_eq!(dns_summary.len(), 1);
+
+    // after a gap the framing is lost
+    dns_summary.on_gap(&connection, Direction::ClientToServer, 10);
+    dns_summary.on_data(&connection, Direction::ClientToServer, &stream, Utc::now());
+    assert_eq!(dns_summary.len(), 1);
+    dns_summary.on_close(&connection);
+
+    // other services are ignored
+    let other = ConnectionKey { client: connection.client, server: Endpoint { ip_addr: connection.server.ip_addr, port: 853 } };
+    dns_summary.on_data(&other, Direction::ClientToServer, &stream, Utc::now());
+    assert_eq!(dns_summary.len(), 1);
+}
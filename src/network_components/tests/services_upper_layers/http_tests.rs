@@ -0,0 +1,148 @@
+use chrono::{Duration, Utc};
+use crate::network_components::layer_4::tcp_packet::TcpPacket;
+use crate::network_components::layer_4::tcp_reassembly::{ConnectionKey, Direction, Endpoint, StreamDissector};
+use crate::network_components::services_upper_layers::http::{HttpMessage, HttpStartLine, HttpTransactions};
+
+const REQUEST: &[u8] = b"GET /index.html?lang=en HTTP/1.1\r\nHost: www.example.com\r\nUser-Agent: curl/8.0\r\nAccept: */*\r\n\r\n";
+const RESPONSE: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=UTF-8\r\nContent-Length: 5\r\n\r\nhello";
+
+fn connection(server_port: u16) -> ConnectionKey {
+    ConnectionKey {
+        client: Endpoint { ip_addr: "192.168.1.90".parse().unwrap(), port: 33685 },
+        server: Endpoint { ip_addr: "93.184.216.34".parse().unwrap(), port: server_port },
+    }
+}
+
+fn segment(src_port: u16, dst_port: u16, payload: &[u8]) -> TcpPacket {
+    let mut tcp_data_in_u8 = Vec::new();
+    tcp_data_in_u8.extend_from_slice(&src_port.to_be_bytes());
+    tcp_data_in_u8.extend_from_slice(&dst_port.to_be_bytes());
+    tcp_data_in_u8.extend_from_slice(&[0, 0, 0, 1, 0, 0, 0, 1, 0x50, 0x18, 0xFF, 0xFF, 0, 0, 0, 0]);
+    tcp_data_in_u8.extend_from_slice(payload);
+    TcpPacket::new(&tcp_data_in_u8)
+}
+
+#[test]
+fn http_request_test() {
+    let (request, length) = HttpMessage::parse_head(REQUEST).unwrap();
+    assert_eq!(length, REQUEST.len());
+    assert_eq!(request.start_line, HttpStartLine::Request {
+        method: "GET".to_string(),
+        target: "/index.html?lang=en".to_string(),
+        version: "HTTP/1.1".to_string(),
+    });
+    assert_eq!(request.method(), Some("GET"));
+    assert_eq!(request.path(), Some("/index.html?lang=en"));
+    assert_eq!(request.host(), Some("www.example.com"));
+    assert_eq!(request.header("user-agent"), Some("curl/8.0"));
+    assert_eq!(request.content_length(), None);
+    assert_eq!(request.status_code(), None);
+}
+
+#[test]
+fn http_response_test() {
+    let (response, length) = HttpMessage::parse_head(RESPONSE).unwrap();
+    assert_eq!(length, RESPONSE.len() - 5);
+    assert_eq!(response.status_code(), Some(200));
+    assert_eq!(response.content_type(), Some("text/html; charset=UTF-8"));
+    assert_eq!(response.content_length(), Some(5));
+    assert!(!response.is_chunked());
+
+    // head split across segments
+    assert_eq!(HttpMessage::parse_head(&RESPONSE[..30]), None);
+    let partial = HttpMessage::parse(&RESPONSE[..30]).unwrap();
+    assert_eq!(partial.status_code(), Some(200));
+    assert_eq!(partial.content_type(), None);
+}
+
+#[test]
+fn http_heuristics_test() {
+    assert!(HttpMessage::looks_like_http(REQUEST));
+    assert!(HttpMessage::looks_like_http(RESPONSE));
+    assert!(HttpMessage::looks_like_http(b"POST /api HTTP/1.0\r\n"));
+    assert!(!HttpMessage::looks_like_http(b"GET /"));
+    assert!(!HttpMessage::looks_like_http(b"FETCH / HTTP/1.1\r\n"));
+    assert!(!HttpMessage::looks_like_http(b"GET / HTTP/2\r\n"));
+    assert!(!HttpMessage::looks_like_http(b"HTTP/1.1 2000 OK\r\n"));
+    assert!(!HttpMessage::looks_like_http(&[0x16, 0x03, 0x01, 0x00, 0xF1, 0x01]));
+}
+
+#[test]
+fn http_verbose_test() {
+    let verbose = segment(33685, 8080, REQUEST).to_string();
+    assert!(verbose.contains("HTTP    : request - [method: GET, host: www.example.com, path: /index.html?lang=en, version: HTTP/1.1, content-type: -, content-length: -]"));
+    assert!(verbose.contains(" > User-Agent: curl/8.0"));
+
+    let verbose = segment(80, 33685, RESPONSE).to_string();
+    assert!(verbose.contains("HTTP    : response - [status: 200 OK, version: HTTP/1.1, content-type: text/html; charset=UTF-8, content-length: 5]"));
+
+    // body segment on port 80
+    let verbose = segment(80, 33685, b"<html></html>").to_string();
+    assert!(verbose.contains("HTTP    : Protocol details unknown"));
+}
+
+#[test]
+fn http_transactions_test() {
+    let connection = connection(80);
+    let request_time = Utc::now();
+    let mut http_transactions = HttpTransactions::new();
+
+    // two pipelined requests, the second one with a body split across segments
+    let mut requests = Vec::from(REQUEST);
+    requests.extend_from_slice(b"POST /form HTTP/1.1\r\nHost: www.example.com\r\nContent-Length: 7\r\n\r\na=1");
+    http_transactions.on_data(&connection, Direction::ClientToServer, &requests, request_time);
+    http_transactions.on_data(&connection, Direction::ClientToServer, b"&b=2", request_time);
+
+    let mut responses = Vec::from(RESPONSE);
+    responses.extend_from_slice(b"HTTP/1.1 100 Continue\r\n\r\n");
+    responses.extend_from_slice(b"HTTP/1.1 302 Found\r\nTransfer-Encoding: chunked\r\nContent-Type: text/plain\r\n\r\n4\r\nmo");
+    http_transactions.on_data(&connection, Direction::ServerToClient, &responses, request_time + Duration::milliseconds(20));
+    assert_eq!(http_transactions.completed().len(), 2);
+    http_transactions.on_data(&connection, Direction::ServerToClient, b"ve\r\n0\r\n\r\n", request_time + Duration::milliseconds(25));
+
+    let completed = http_transactions.completed();
+    assert_eq!(completed[0].method, "GET");
+    assert_eq!(completed[0].path, "/index.html?lang=en");
+    assert_eq!(completed[0].status_code, Some(200));
+    assert_eq!(completed[0].content_length, Some(5));
+    assert_eq!(completed[0].response_time, Some(request_time + Duration::milliseconds(20)));
+    assert_eq!(completed[1].method, "POST");
+    assert_eq!(completed[1].status_code, Some(302));
+    assert_eq!(completed[1].content_type.as_deref(), Some("text/plain"));
+
+    // request never answered
+    http_transactions.on_data(&connection, Direction::ClientToServer, REQUEST, request_time);
+    http_transactions.on_close(&connection);
+    assert_eq!(http_transactions.completed().len(), 3);
+    assert_eq!(http_transactions.completed()[2].status_code, None);
+
+    let section = http_transactions.report_section().unwrap();
+    assert!(section.starts_with("HTTP transactions: 3\nClient"));
+    assert!(section.contains("| GET     | www.example.com                | /index.html?lang=en                      | 200    | text/html; charset=UTF-8       | 5              | 20.000"));
+    assert_eq!(http_transactions.report_section(), None);
+}
+
+#[test]
+fn http_transactions_heuristics_test() {
+    let mut http_transactions = HttpTransactions::new();
+
+    // HTTP on a non standard port is recognized from the request line
+    http_transactions.on_data(&connection(8080), Direction::ClientToServer, REQUEST, Utc::now());
+    http_transactions.on_data(&connection(8080), Direction::ServerToClient, RESPONSE, Utc::now());
+    assert_eq!(http_transactions.completed().len(), 1);
+
+    // other protocols are ignored
+    http_transactions.on_data(&connection(443), Direction::ClientToServer, &[0x16, 0x03, 0x01, 0x00, 0xF1], Utc::now());
+    http_transactions.on_data(&connection(443), Direction::ClientToServer, REQUEST, Utc::now());
+    http_transactions.on_data(&connection(443), Direction::ServerToClient, RESPONSE, Utc::now());
+    assert_eq!(http_transactions.completed().len(), 1);
+
+    // after a gap the parser resynchronizes on the next message
+    let connection = connection(80);
+    http_transactions.on_data(&connection, Direction::ClientToServer, REQUEST, Utc::now());
+    http_transactions.on_gap(&connection, Direction::ServerToClient, 100);
+    http_transactions.on_data(&connection, Direction::ServerToClient, b"lo world", Utc::now());
+    assert_eq!(http_transactions.completed().len(), 1);
+    http_transactions.on_data(&connection, Direction::ServerToClient, RESPONSE, Utc::now());
+    assert_eq!(http_transactions.completed().len(), 2);
+}
@@ -1,2 +1,20 @@
 #[cfg(test)]
 pub mod upper_layer_services_tests;
+
+#[cfg(test)]
+pub mod dns_tests;
+
+#[cfg(test)]
+pub mod http_tests;
+
+#[cfg(test)]
+pub mod tls_tests;
+
+#[cfg(test)]
+pub mod quic_tests;
+
+#[cfg(test)]
+pub mod dhcp_tests;
+
+#[cfg(test)]
+pub mod payload_inspection_tests;
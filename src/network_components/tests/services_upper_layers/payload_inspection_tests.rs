@@ -0,0 +1,74 @@
+use crate::network_components::layer_4::tcp_packet::TcpPacket;
+use crate::network_components::services_upper_layers::payload_inspection::inspect_payload;
+use crate::network_components::services_upper_layers::upper_layer_services::{identify_service, Transport, UpperLayerService, PORT_CONFIDENCE};
+
+/// TLS record with a truncated handshake message (not a ClientHello)
+const TLS_APPLICATION_DATA: [u8; 8] = [23, 3, 3, 0, 100, 1, 2, 3];
+
+fn rtp_packet() -> Vec<u8> {
+    let mut packet = vec![0x80, 0x60, 0x12, 0x34, 0, 0, 0x03, 0xE8, 0xDE, 0xAD, 0xBE, 0xEF];
+    packet.extend_from_slice(&[0x55; 160]);
+    packet
+}
+
+#[test]
+fn inspect_payload_test() {
+    let name = |transport, payload: &[u8]| inspect_payload(transport, payload).map(|payload_match| (payload_match.name, payload_match.confidence));
+    assert_eq!(name(Transport::TCP, b"SSH-2.0-OpenSSH_9.6\r\n"), Some(("SSH", 95)));
+    assert_eq!(name(Transport::TCP, b"GET /index.html HTTP/1.1\r\nHost: example.com\r\n\r\n"), Some(("HTTP", 90)));
+    assert_eq!(name(Transport::TCP, &TLS_APPLICATION_DATA), Some(("TLS", 60)));
+    assert_eq!(name(Transport::TCP, b"220 mail.example.com ESMTP Postfix\r\n"), Some(("SMTP", 85)));
+    assert_eq!(name(Transport::TCP, b"220 (vsFTPd 3.0.5)\r\n"), Some(("FTP", 80)));
+    assert_eq!(name(Transport::TCP, b"* OK [CAPABILITY IMAP4rev1] ready\r\n"), Some(("IMAP", 70)));
+    assert_eq!(name(Transport::TCP, b"\x13BitTorrent protocol\0\0\0\0\0\0\0\0"), Some(("BITTORRENT", 95)));
+    assert_eq!(name(Transport::UDP, b"INVITE sip:bob@example.com SIP/2.0\r\nVia: SIP/2.0/UDP 10.0.0.1\r\n\r\n"), Some(("SIP", 90)));
+    assert_eq!(name(Transport::UDP, &rtp_packet()), Some(("RTP", 40)));
+    assert_eq!(name(Transport::UDP, &[0x80, 200, 0, 1, 0, 0, 0, 1]), Some(("RTCP", 50)));
+
+    // query for example.com, A record
+    let dns_query = [0x12, 0x34, 1, 0, 0, 1, 0, 0, 0, 0, 0, 0, 7, b'e', b'x', b'a', b'm', b'p', b'l', b'e', 3, b'c', b'o', b'm', 0, 0, 1, 0, 1];
+    assert_eq!(name(Transport::UDP, &dns_query), Some(("DNS", 50)));
+    let mut dns_tcp = vec![0, dns_query.len() as u8];
+    dns_tcp.extend_from_slice(&dns_query);
+    assert_eq!(name(Transport::TCP, &dns_tcp), Some(("DNS", 50)));
+
+    assert_eq!(name(Transport::TCP, b""), None);
+    assert_eq!(name(Transport::TCP, b"\x00\x01binary data without structure"), None);
+    assert_eq!(name(Transport::UDP, b"SSH-2.0-OpenSSH_9.6\r\n"), None);
+}
+
+#[test]
+fn identify_service_test() {
+    // payload identification overriding or supplementing the port
+    let http_alt = identify_service(Transport::TCP, 51000, 8080, b"GET / HTTP/1.1\r\n\r\n");
+    assert_eq!(http_alt.name.as_deref(), Some("HTTP"));
+    assert_eq!(http_alt.upper_layer_service, UpperLayerService::HTTP);
+    assert!(http_alt.from_payload);
+    let ssh = identify_service(Transport::TCP, 2222, 51000, b"SSH-2.0-OpenSSH_9.6\r\n");
+    assert_eq!((ssh.name.as_deref(), ssh.confidence), (Some("SSH"), 95));
+    let rtp = identify_service(Transport::UDP, 40000, 40002, &rtp_packet());
+    assert_eq!((rtp.name.as_deref(), rtp.upper_layer_service), (Some("RTP"), UpperLayerService::UNKNOWN));
+
+    // payload identification confirming the port
+    let http = identify_service(Transport::TCP, 51000, 80, b"GET / HTTP/1.1\r\n\r\n");
+    assert_eq!((http.name.as_deref(), http.confidence, http.from_payload), (Some("HTTP"), 90, false));
+
+    // the port identification wins on weak guesses and on what TLS secures
+    let mdns = identify_service(Transport::UDP, 5353, 5353, &[0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 1, b'a', 0, 0, 1, 0, 1]);
+    assert_eq!((mdns.name.as_deref(), mdns.confidence), (Some("MDNS"), PORT_CONFIDENCE));
+    let imaps = identify_service(Transport::TCP, 993, 51000, &TLS_APPLICATION_DATA);
+    assert_eq!(imaps.name.as_deref(), Some("IMAPS"));
+    let tls = identify_service(Transport::TCP, 51000, 40000, &TLS_APPLICATION_DATA);
+    assert_eq!((tls.name.as_deref(), tls.upper_layer_service), (Some("TLS"), UpperLayerService::HTTPS));
+
+    let unknown = identify_service(Transport::TCP, 51000, 40000, b"");
+    assert_eq!((unknown.name, unknown.confidence, unknown.upper_layer_service), (None, 0, UpperLayerService::UNKNOWN));
+}
+
+#[test]
+fn payload_identification_verbose_test() {
+    let mut tcp_data_in_u8 = vec![0x08, 0xAE, 0xC7, 0x38, 0, 0, 0, 1, 0, 0, 0, 1, 0x50, 0x18, 0xFF, 0xFF, 0, 0, 0, 0];
+    tcp_data_in_u8.extend_from_slice(b"SSH-2.0-OpenSSH_9.6\r\n");
+    let verbose = TcpPacket::new(&tcp_data_in_u8).to_string();
+    assert!(verbose.contains("SSH     : Protocol details unknown - [identified from the payload, confidence: 95%]"));
+}
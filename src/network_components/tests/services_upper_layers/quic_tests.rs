@@ -0,0 +1,194 @@
+use std::net::IpAddr;
+use aes_gcm::{Aes128Gcm, KeyInit, Nonce};
+use aes_gcm::aead::{Aead, Payload};
+use aes_gcm::aes::cipher::generic_array::GenericArray;
+use chrono::{Duration, Utc};
+use crate::network_components::layer_4::tcp_reassembly::Endpoint;
+use crate::network_components::layer_4::upd_packet::UdpPacket;
+use crate::network_components::services_upper_layers::quic::{InitialKeys, QuicPacket, QuicPacketType, QuicSessions, QUIC_DRAFT_29, QUIC_VERSION_1, QUIC_VERSION_2};
+
+const DCID: [u8; 8] = [0x83, 0x94, 0xC8, 0xF0, 0x3E, 0x51, 0x57, 0x08];
+
+fn from_hex(hex: &str) -> Vec<u8> {
+    (0..hex.len()).step_by(2).map(|index| u8::from_str_radix(&hex[index..index + 2], 16).unwrap()).collect()
+}
+
+/// ClientHello handshake message with the server_name and ALPN extensions
+fn client_hello(server_name: &str) -> Vec<u8> {
+    let mut extensions = Vec::new();
+    extensions.extend_from_slice(&[0, 0]);
+    extensions.extend_from_slice(&(server_name.len() as u16 + 5).to_be_bytes());
+    extensions.extend_from_slice(&(server_name.len() as u16 + 3).to_be_bytes());
+    extensions.push(0);
+    extensions.extend_from_slice(&(server_name.len() as u16).to_be_bytes());
+    extensions.extend_from_slice(server_name.as_bytes());
+    extensions.extend_from_slice(&[0, 16, 0, 5, 0, 3, 2, b'h', b'3']);
+    extensions.extend_from_slice(&[0, 43, 0, 3, 2, 3, 4]);
+
+    let mut body = vec![3, 3];
+    body.extend_from_slice(&[0x5A; 32]);
+    body.extend_from_slice(&[0, 0, 6, 0x13, 0x01, 0x13, 0x02, 0x13, 0x03, 1, 0]);
+    body.extend_from_slice(&(extensions.len() as u16).to_be_bytes());
+    body.extend(extensions);
+
+    let mut handshake = vec![1, 0];
+    handshake.extend_from_slice(&(body.len() as u16).to_be_bytes());
+    handshake.extend(body);
+    handshake
+}
+
+/// CRYPTO frame (offset and length on 2 bytes)
+fn crypto_frame(offset: u16, data: &[u8]) -> Vec<u8> {
+    let mut frame = vec![0x06];
+    frame.extend_from_slice(&(0x4000 | offset).to_be_bytes());
+    frame.extend_from_slice(&(0x4000 | data.len() as u16).to_be_bytes());
+    frame.extend_from_slice(data);
+    frame
+}
+
+/// Client Initial packet protected with the initial keys, padded to 1200 bytes
+fn client_initial(version: u32, packet_number: u16, frames: &[u8]) -> Vec<u8> {
+    let keys = InitialKeys::client(version, &DCID).unwrap();
+    let long_packet_type = if version == QUIC_VERSION_2 { 0x10 } else { 0x00 };
+    let mut header = vec![0xC1 | long_packet_type];
+    header.extend_from_slice(&version.to_be_bytes());
+    header.push(DCID.len() as u8);
+    header.extend_from_slice(&DCID);
+    header.extend_from_slice(&[0, 0]); // empty SCID and token
+    let payload_length = 1200 - header.len() - 2;
+    header.extend_from_slice(&(0x4000 | payload_length as u16).to_be_bytes());
+    let packet_number_offset = header.len();
+    header.extend_from_slice(&packet_number.to_be_bytes());
+
+    let mut plaintext = Vec::from(frames);
+    plaintext.resize(payload_length - 2 - 16, 0);
+    let cipher = Aes128Gcm::new(GenericArray::from_slice(&keys.key));
+    let ciphertext = cipher.encrypt(Nonce::from_slice(&keys.nonce(packet_number as u64)), Payload { msg: &plaintext, aad: &header }).unwrap();
+
+    let mut packet = header;
+    packet.extend(ciphertext);
+    let mask = keys.header_protection_mask(&packet[packet_number_offset + 4..packet_number_offset + 20]);
+    packet[0] ^= mask[0] & 0x0F;
+    packet[packet_number_offset] ^= mask[1];
+    packet[packet_number_offset + 1] ^= mask[2];
+    packet
+}
+
+fn udp_packet(src_port: u16, dst_port: u16, payload: &[u8]) -> UdpPacket {
+    let mut udp_data_in_u8 = Vec::new();
+    udp_data_in_u8.extend_from_slice(&src_port.to_be_bytes());
+    udp_data_in_u8.extend_from_slice(&dst_port.to_be_bytes());
+    udp_data_in_u8.extend_from_slice(&(8 + payload.len() as u16).to_be_bytes());
+    udp_data_in_u8.extend_from_slice(&[0, 0]);
+    udp_data_in_u8.extend_from_slice(payload);
+    UdpPacket::new(&udp_data_in_u8)
+}
+
+#[test]
+fn quic_initial_keys_test() {
+    // RFC 9001, Appendix A.1
+    let keys = InitialKeys::client(QUIC_VERSION_1, &DCID).unwrap();
+    assert_eq!(Vec::from(keys.key), from_hex("1f369613dd76d5467730efcbe3b1a22d"));
+    assert_eq!(Vec::from(keys.iv), from_hex("fa044b2f42a3fd3b46fb255c"));
+    assert_eq!(Vec::from(keys.hp), from_hex("9f50449e04a0e810283a1e9933adedd2"));
+
+    // RFC 9001, Appendix A.2
+    assert_eq!(Vec::from(keys.header_protection_mask(&from_hex("d1b1c98dd7689fb8ec11d242b123dc9b"))), from_hex("437b9aec36"));
+
+    // RFC 9369, Appendix A.1
+    let keys = InitialKeys::client(QUIC_VERSION_2, &DCID).unwrap();
+    assert_eq!(Vec::from(keys.key), from_hex("8b1a0bc121284290a29e0971b5cd045d"));
+    assert_eq!(Vec::from(keys.iv), from_hex("91f73e2351d8fa91660e909f"));
+    assert_eq!(Vec::from(keys.hp), from_hex("45b95e15235d6f45a6b19cbcb0294ba9"));
+
+    assert_eq!(InitialKeys::client(0x0A0A0A0A, &DCID), None);
+}
+
+#[test]
+fn quic_client_initial_test() {
+    for version in [QUIC_VERSION_1, QUIC_VERSION_2, QUIC_DRAFT_29] {
+        let packet = client_initial(version, 2, &crypto_frame(0, &client_hello("www.example.org")));
+        assert!(QuicPacket::looks_like_quic(&packet));
+        let packets = QuicPacket::parse_datagram(&packet);
+        assert_eq!(packets.len(), 1);
+        assert_eq!(packets[0].packet_type, QuicPacketType::Initial);
+        assert_eq!(packets[0].version, Some(version));
+        assert_eq!(packets[0].dcid, DCID);
+        assert_eq!(packets[0].length, 1200);
+        assert_eq!(packets[0].packet_number, Some(2));
+
+        let client_hello = packets[0].client_hello().unwrap();
+        assert_eq!(client_hello.server_name.as_deref(), Some("www.example.org"));
+        assert_eq!(client_hello.alpn, vec!["h3".to_string()]);
+    }
+}
+
+#[test]
+fn quic_not_decrypted_test() {
+    // payload corrupted: the authentication fails
+    let mut packet = client_initial(QUIC_VERSION_1, 0, &crypto_frame(0, &client_hello("www.example.org")));
+    packet[100] ^= 0xFF;
+    let packets = QuicPacket::parse_datagram(&packet);
+    assert_eq!(packets[0].packet_type, QuicPacketType::Initial);
+    assert_eq!(packets[0].packet_number, None);
+    assert!(packets[0].crypto.is_empty());
+    assert!(packets[0].to_string().contains(" > payload not decrypted (server Initial)"));
+}
+
+#[test]
+fn quic_headers_test() {
+    // coalesced Handshake packet and short header packet
+    let mut datagram = vec![0xE0, 0, 0, 0, 1, 4, 1, 2, 3, 4, 2, 5, 6, 0x0A];
+    datagram.extend_from_slice(&[0; 10]);
+    datagram.extend_from_slice(&[0x40, 1, 2, 3, 4, 5]);
+    let packets = QuicPacket::parse_datagram(&datagram);
+    assert_eq!(packets.len(), 2);
+    assert_eq!(packets[0].packet_type, QuicPacketType::Handshake);
+    assert_eq!(packets[0].dcid, vec![1, 2, 3, 4]);
+    assert_eq!(packets[0].scid, vec![5, 6]);
+    assert_eq!(packets[0].length, 24);
+    assert_eq!(packets[0].to_string(), "QUIC    : Handshake - [version: 1, dcid: 01020304, scid: 0506, length: 24B]");
+    assert_eq!(packets[1].to_string(), "QUIC    : 1-RTT (short header) - [length: 6B]");
+
+    // version negotiation
+    let datagram = [0x80, 0, 0, 0, 0, 1, 0xAA, 1, 0xBB, 0, 0, 0, 1, 0x6B, 0x33, 0x43, 0xCF];
+    let packets = QuicPacket::parse_datagram(&datagram);
+    assert_eq!(packets[0].packet_type, QuicPacketType::VersionNegotiation);
+    assert_eq!(packets[0].to_string(), "QUIC    : Version Negotiation - [dcid: aa, scid: bb, versions: 1, 2]");
+
+    // unknown version, length beyond the datagram
+    assert!(!QuicPacket::looks_like_quic(&[0xC0, 0, 0, 0, 7, 0, 0]));
+    assert!(QuicPacket::parse_datagram(&[0xE0, 0, 0, 0, 1, 0, 0, 0x10, 1]).is_empty());
+}
+
+#[test]
+fn quic_verbose_test() {
+    let packet = client_initial(QUIC_VERSION_1, 0, &crypto_frame(0, &client_hello("www.example.org")));
+    let verbose = udp_packet(50000, 443, &packet).to_string();
+    assert!(verbose.contains("QUIC    : Initial - [version: 1, dcid: 8394c8f03e515708, scid: -, length: 1200B]"));
+    assert!(verbose.contains(" > packet number: 0\nTLS     : ClientHello - [server name: www.example.org, alpn: h3, versions: TLS 1.3]"));
+    assert!(!verbose.contains("Protocol details unknown"));
+}
+
+#[test]
+fn quic_sessions_test() {
+    let client: IpAddr = "192.168.1.90".parse().unwrap();
+    let server: IpAddr = "142.250.184.46".parse().unwrap();
+    let client_endpoint = Endpoint { ip_addr: client, port: 50000 };
+    let server_endpoint = Endpoint { ip_addr: server, port: 443 };
+
+    // ClientHello split in two Initial packets, the second CRYPTO frame first
+    let client_hello = client_hello("www.example.org");
+    let first = client_initial(QUIC_VERSION_1, 0, &crypto_frame(0, &client_hello[..50]));
+    let second = client_initial(QUIC_VERSION_1, 1, &crypto_frame(50, &client_hello[50..]));
+
+    let mut quic_sessions = QuicSessions::new();
+    quic_sessions.push(client, server, &udp_packet(50000, 443, &second), Utc::now());
+    assert_eq!(quic_sessions.server_name(client_endpoint, server_endpoint), None);
+    quic_sessions.push(client, server, &udp_packet(50000, 443, &first), Utc::now());
+    assert_eq!(quic_sessions.server_name(server_endpoint, client_endpoint), Some("www.example.org"));
+    assert_eq!(quic_sessions.len(), 1);
+
+    quic_sessions.purge(Utc::now() + Duration::seconds(60), Duration::seconds(30));
+    assert!(quic_sessions.is_empty());
+}
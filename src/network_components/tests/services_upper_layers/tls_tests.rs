@@ -0,0 +1,165 @@
+use chrono::Utc;
+use crate::network_components::layer_4::tcp_packet::TcpPacket;
+use crate::network_components::layer_4::tcp_reassembly::{ConnectionKey, Direction, Endpoint, StreamDissector};
+use crate::network_components::services_upper_layers::tls::{cipher_suite_name, version_name, ClientHello, ServerHello, TlsHandshake, TlsRecord, TlsSessions};
+
+fn vector_u16(data: &[u8]) -> Vec<u8> {
+    let mut vector = (data.len() as u16).to_be_bytes().to_vec();
+    vector.extend_from_slice(data);
+    vector
+}
+
+fn extension(extension_type: u16, data: &[u8]) -> Vec<u8> {
+    let mut extension = extension_type.to_be_bytes().to_vec();
+    extension.extend(vector_u16(data));
+    extension
+}
+
+fn alpn(protocols: &[&str]) -> Vec<u8> {
+    let mut list = Vec::new();
+    for protocol in protocols {
+        list.push(protocol.len() as u8);
+        list.extend_from_slice(protocol.as_bytes());
+    }
+    extension(16, &vector_u16(&list))
+}
+
+/// Handshake message wrapped in a single record
+fn record(handshake_type: u8, body: &[u8]) -> Vec<u8> {
+    let mut handshake = vec![handshake_type, 0];
+    handshake.extend_from_slice(&(body.len() as u16).to_be_bytes());
+    handshake.extend_from_slice(body);
+    let mut record = vec![22, 3, 1];
+    record.extend(vector_u16(&handshake));
+    record
+}
+
+/// ClientHello for `server_name`, with GREASE values and `padding` bytes of padding extension
+fn client_hello(server_name: &str, padding: usize) -> Vec<u8> {
+    let mut body = vec![3, 3];
+    body.extend_from_slice(&[0x11; 32]);
+    body.push(32);
+    body.extend_from_slice(&[0x22; 32]);
+    body.extend(vector_u16(&[0x3A, 0x3A, 0x13, 0x01, 0x13, 0x02, 0xC0, 0x2F]));
+    body.extend_from_slice(&[1, 0]);
+
+    let mut extensions = Vec::new();
+    extensions.extend(extension(0xDADA, &[]));
+    let mut server_name_list = vec![0];
+    server_name_list.extend(vector_u16(server_name.as_bytes()));
+    extensions.extend(extension(0, &vector_u16(&server_name_list)));
+    extensions.extend(alpn(&["h2", "http/1.1"]));
+    extensions.extend(extension(43, &[6, 0x7A, 0x7A, 3, 4, 3, 3]));
+    extensions.extend(extension(21, &vec![0; padding]));
+    body.extend(vector_u16(&extensions));
+    record(1, &body)
+}
+
+fn server_hello() -> Vec<u8> {
+    let mut body = vec![3, 3];
+    body.extend_from_slice(&[0x33; 32]);
+    body.push(0);
+    body.extend_from_slice(&[0x13, 0x01, 0]);
+    let mut extensions = extension(43, &[3, 4]);
+    extensions.extend(alpn(&["h2"]));
+    body.extend(vector_u16(&extensions));
+    record(2, &body)
+}
+
+fn connection() -> ConnectionKey {
+    ConnectionKey {
+        client: Endpoint { ip_addr: "192.168.1.90".parse().unwrap(), port: 33685 },
+        server: Endpoint { ip_addr: "140.82.121.3".parse().unwrap(), port: 443 },
+    }
+}
+
+#[test]
+fn tls_client_hello_test() {
+    let records = TlsRecord::parse_records(&client_hello("github.com", 0));
+    assert_eq!(records.len(), 1);
+    let client_hello = match TlsHandshake::parse(&records[0].fragment) {
+        Some((TlsHandshake::ClientHello(client_hello), _)) => client_hello,
+        _ => panic!("ClientHello expected"),
+    };
+    assert_eq!(client_hello, ClientHello {
+        legacy_version: 0x0303,
+        server_name: Some("github.com".to_string()),
+        alpn: vec!["h2".to_string(), "http/1.1".to_string()],
+        supported_versions: vec![0x0304, 0x0303],
+        cipher_suites: vec![0x1301, 0x1302, 0xC02F],
+    });
+}
+
+#[test]
+fn tls_server_hello_test() {
+    let records = TlsRecord::parse_records(&server_hello());
+    let server_hello = match TlsHandshake::parse(&records[0].fragment) {
+        Some((TlsHandshake::ServerHello(server_hello), _)) => server_hello,
+        _ => panic!("ServerHello expected"),
+    };
+    assert_eq!(server_hello, ServerHello { legacy_version: 0x0303, version: 0x0304, cipher_suite: 0x1301, alpn: Some("h2".to_string()) });
+}
+
+#[test]
+fn tls_names_test() {
+    assert_eq!(version_name(0x0304), "TLS 1.3");
+    assert_eq!(version_name(0x7F1C), "TLS 1.3 draft 28");
+    assert_eq!(version_name(0x1234), "0x1234");
+    assert_eq!(cipher_suite_name(0xCCA8), "TLS_ECDHE_RSA_WITH_CHACHA20_POLY1305_SHA256");
+    assert_eq!(cipher_suite_name(0xFFFE), "0xfffe");
+}
+
+#[test]
+fn tls_malformed_test() {
+    let mut truncated = client_hello("github.com", 0);
+    truncated.truncate(60);
+    let records = TlsRecord::parse_records(&truncated);
+    assert_eq!(records.len(), 1);
+    assert_eq!(TlsHandshake::parse(&records[0].fragment), None);
+
+    assert!(!TlsRecord::looks_like_tls(b"GET / HTTP/1.1\r\n"));
+    assert!(!TlsRecord::looks_like_tls(&[22, 3, 1]));
+    assert!(!TlsRecord::looks_like_tls(&[22, 3, 9, 0, 10]));
+    assert!(TlsRecord::looks_like_tls(&[23, 3, 3, 0, 10]));
+}
+
+#[test]
+fn tls_verbose_test() {
+    let mut tcp_data_in_u8 = vec![0x83, 0x95, 0x01, 0xBB, 0, 0, 0, 1, 0, 0, 0, 1, 0x50, 0x18, 0xFF, 0xFF, 0, 0, 0, 0];
+    tcp_data_in_u8.extend(client_hello("github.com", 0));
+    let verbose = TcpPacket::new(&tcp_data_in_u8).to_string();
+    assert!(verbose.contains("TLS     : ClientHello - [server name: github.com, alpn: h2, http/1.1, versions: TLS 1.3, TLS 1.2]"));
+    assert!(verbose.contains(" > cipher suites: TLS_AES_128_GCM_SHA256, TLS_AES_256_GCM_SHA384, TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256"));
+    assert!(!verbose.contains("Protocol details unknown"));
+
+    // application data on a non standard port
+    let mut tcp_data_in_u8 = vec![0x01, 0xBB, 0x20, 0xFB, 0, 0, 0, 1, 0, 0, 0, 1, 0x50, 0x18, 0xFF, 0xFF, 0, 0, 0, 0];
+    tcp_data_in_u8.extend_from_slice(&[23, 3, 3, 0, 100, 1, 2, 3]);
+    let verbose = TcpPacket::new(&tcp_data_in_u8).to_string();
+    assert!(verbose.contains("TLS     : application_data record - [version: TLS 1.2, length: 100]"));
+}
+
+#[test]
+fn tls_sessions_test() {
+    let connection = connection();
+    let mut tls_sessions = TlsSessions::new();
+
+    // ClientHello larger than a segment
+    let client_hello = client_hello("github.com", 1500);
+    tls_sessions.on_data(&connection, Direction::ClientToServer, &client_hello[..1400], Utc::now());
+    assert_eq!(tls_sessions.get(connection.client, connection.server), None);
+    tls_sessions.on_data(&connection, Direction::ClientToServer, &client_hello[1400..], Utc::now());
+    tls_sessions.on_data(&connection, Direction::ServerToClient, &server_hello(), Utc::now());
+
+    let session = tls_sessions.get(connection.server, connection.client).unwrap();
+    assert_eq!(session.server_name(), Some("github.com"));
+    assert_eq!(session.server_hello.as_ref().unwrap().cipher_suite, 0x1301);
+
+    tls_sessions.on_close(&connection);
+    assert_eq!(tls_sessions.get(connection.client, connection.server), None);
+
+    // not TLS
+    tls_sessions.on_data(&connection, Direction::ClientToServer, b"GET / HTTP/1.1\r\n\r\n", Utc::now());
+    tls_sessions.on_data(&connection, Direction::ClientToServer, &client_hello, Utc::now());
+    assert_eq!(tls_sessions.get(connection.client, connection.server), None);
+}
@@ -1,45 +1,112 @@
-use crate::network_components::services_upper_layers::upper_layer_services::{known_port, UpperLayerService};
+use crate::network_components::layer_4::tcp_packet::TcpPacket;
+use crate::network_components::services_upper_layers::service_registry::ServiceRegistry;
+use crate::network_components::services_upper_layers::upper_layer_services::{known_port, Transport, UpperLayerService};
 
 #[test]
 pub fn from_known_port_to_service_test() {
-    assert_eq!(UpperLayerService::from(known_port(20, 4567)), UpperLayerService::FTP);
-    assert_eq!(UpperLayerService::from(known_port(4567, 20)), UpperLayerService::FTP);
+    assert_eq!(UpperLayerService::from(known_port(Transport::TCP, 20, 4567)), UpperLayerService::FTPDATA);
+    assert_eq!(UpperLayerService::from(known_port(Transport::TCP, 4567, 20)), UpperLayerService::FTPDATA);
 
-    assert_eq!(UpperLayerService::from(known_port(22, 4567)), UpperLayerService::SSH);
-    assert_eq!(UpperLayerService::from(known_port(4567, 22)), UpperLayerService::SSH);
+    assert_eq!(UpperLayerService::from(known_port(Transport::TCP, 21, 4567)), UpperLayerService::FTP);
+    assert_eq!(UpperLayerService::from(known_port(Transport::TCP, 4567, 21)), UpperLayerService::FTP);
 
-    assert_eq!(UpperLayerService::from(known_port(23, 4567)), UpperLayerService::SMTP);
-    assert_eq!(UpperLayerService::from(known_port(4567, 23)), UpperLayerService::SMTP);
+    assert_eq!(UpperLayerService::from(known_port(Transport::TCP, 22, 4567)), UpperLayerService::SSH);
+    assert_eq!(UpperLayerService::from(known_port(Transport::TCP, 4567, 22)), UpperLayerService::SSH);
 
-    assert_eq!(UpperLayerService::from(known_port(53, 4567)), UpperLayerService::DNS);
-    assert_eq!(UpperLayerService::from(known_port(4567, 53)), UpperLayerService::DNS);
+    assert_eq!(UpperLayerService::from(known_port(Transport::TCP, 23, 4567)), UpperLayerService::TELNET);
+    assert_eq!(UpperLayerService::from(known_port(Transport::TCP, 4567, 23)), UpperLayerService::TELNET);
 
-    assert_eq!(UpperLayerService::from(known_port(80, 4567)), UpperLayerService::HTTP);
-    assert_eq!(UpperLayerService::from(known_port(4567, 80)), UpperLayerService::HTTP);
+    assert_eq!(UpperLayerService::from(known_port(Transport::TCP, 25, 4567)), UpperLayerService::SMTP);
+    assert_eq!(UpperLayerService::from(known_port(Transport::TCP, 4567, 25)), UpperLayerService::SMTP);
 
-    assert_eq!(UpperLayerService::from(known_port(110, 4567)), UpperLayerService::POP3);
-    assert_eq!(UpperLayerService::from(known_port(4567, 110)), UpperLayerService::POP3);
+    assert_eq!(UpperLayerService::from(known_port(Transport::UDP, 53, 4567)), UpperLayerService::DNS);
+    assert_eq!(UpperLayerService::from(known_port(Transport::TCP, 4567, 53)), UpperLayerService::DNS);
 
-    assert_eq!(UpperLayerService::from(known_port(115, 4567)), UpperLayerService::SFTP);
-    assert_eq!(UpperLayerService::from(known_port(4567, 115)), UpperLayerService::SFTP);
+    assert_eq!(UpperLayerService::from(known_port(Transport::TCP, 80, 4567)), UpperLayerService::HTTP);
+    assert_eq!(UpperLayerService::from(known_port(Transport::TCP, 4567, 80)), UpperLayerService::HTTP);
 
-    assert_eq!(UpperLayerService::from(known_port(161, 4567)), UpperLayerService::SNMP);
-    assert_eq!(UpperLayerService::from(known_port(4567, 161)), UpperLayerService::SNMP);
+    assert_eq!(UpperLayerService::from(known_port(Transport::TCP, 110, 4567)), UpperLayerService::POP3);
+    assert_eq!(UpperLayerService::from(known_port(Transport::TCP, 4567, 110)), UpperLayerService::POP3);
 
-    assert_eq!(UpperLayerService::from(known_port(179, 4567)), UpperLayerService::BGP);
-    assert_eq!(UpperLayerService::from(known_port(4567, 179)), UpperLayerService::BGP);
+    assert_eq!(UpperLayerService::from(known_port(Transport::TCP, 115, 4567)), UpperLayerService::SFTP);
+    assert_eq!(UpperLayerService::from(known_port(Transport::TCP, 4567, 115)), UpperLayerService::SFTP);
 
-    assert_eq!(UpperLayerService::from(known_port(443, 4567)), UpperLayerService::HTTPS);
-    assert_eq!(UpperLayerService::from(known_port(4567, 443)), UpperLayerService::HTTPS);
+    assert_eq!(UpperLayerService::from(known_port(Transport::UDP, 161, 4567)), UpperLayerService::SNMP);
+    assert_eq!(UpperLayerService::from(known_port(Transport::UDP, 4567, 161)), UpperLayerService::SNMP);
+
+    assert_eq!(UpperLayerService::from(known_port(Transport::TCP, 179, 4567)), UpperLayerService::BGP);
+    assert_eq!(UpperLayerService::from(known_port(Transport::TCP, 4567, 179)), UpperLayerService::BGP);
+
+    assert_eq!(UpperLayerService::from(known_port(Transport::TCP, 443, 4567)), UpperLayerService::HTTPS);
+    assert_eq!(UpperLayerService::from(known_port(Transport::UDP, 4567, 443)), UpperLayerService::HTTPS);
+
+    assert_eq!(UpperLayerService::from(known_port(Transport::UDP, 68, 67)), UpperLayerService::DHCP);
+    assert_eq!(UpperLayerService::from(known_port(Transport::UDP, 67, 68)), UpperLayerService::DHCP);
+
+    assert_eq!(UpperLayerService::from(known_port(Transport::UDP, 546, 547)), UpperLayerService::DHCPv6);
+    assert_eq!(UpperLayerService::from(known_port(Transport::UDP, 547, 546)), UpperLayerService::DHCPv6);
 }
 
 #[test]
 pub fn from_unknown_port_to_service_test() {
-    assert_eq!(UpperLayerService::from(known_port(2000, 2000)), UpperLayerService::UNKNOWN);
+    assert_eq!(UpperLayerService::from(known_port(Transport::TCP, 2000, 2000)), UpperLayerService::UNKNOWN);
+    // assigned port of a service without a dedicated decoder
+    assert_eq!(known_port(Transport::TCP, 51000, 3306), 3306);
+    assert_eq!(UpperLayerService::from(known_port(Transport::TCP, 51000, 3306)), UpperLayerService::UNKNOWN);
 }
 
 #[test]
 pub fn from_unknown_protocol_to_service_test() {
-    assert_eq!(UpperLayerService::from(known_port(1023, 2000)), UpperLayerService::UNKNOWN);
-    assert_eq!(UpperLayerService::from(known_port(2000, 1023)), UpperLayerService::UNKNOWN);
-}
\ No newline at end of file
+    assert_eq!(UpperLayerService::from(known_port(Transport::TCP, 1023, 2000)), UpperLayerService::UNKNOWN);
+    assert_eq!(UpperLayerService::from(known_port(Transport::UDP, 2000, 1023)), UpperLayerService::UNKNOWN);
+    // DHCP is assigned over UDP only, SSH over TCP only
+    assert_eq!(UpperLayerService::from(known_port(Transport::TCP, 68, 67)), UpperLayerService::UNKNOWN);
+    assert_eq!(UpperLayerService::from(known_port(Transport::UDP, 22, 4567)), UpperLayerService::UNKNOWN);
+}
+
+#[test]
+pub fn service_registry_test() {
+    let registry = ServiceRegistry::new();
+    assert_eq!(registry.name(Transport::TCP, 514), Some("SHELL"));
+    assert_eq!(registry.name(Transport::UDP, 514), Some("SYSLOG"));
+    assert_eq!(registry.name(Transport::UDP, 5353), Some("MDNS"));
+    assert_eq!(registry.name(Transport::TCP, 5353), None);
+
+    // both ports assigned: the lowest one is the service
+    assert_eq!(registry.service_name(Transport::TCP, 8080, 5432), Some("POSTGRESQL"));
+    assert_eq!(registry.service_name(Transport::UDP, 53, 5353), Some("DNS"));
+    assert_eq!(registry.service_name(Transport::TCP, 40000, 50000), None);
+}
+
+#[test]
+pub fn service_registry_services_file_test() {
+    let mut registry = ServiceRegistry::new();
+    let services = "# Network services, Internet style\n\
+                    \n\
+                    domain          53/tcp                          # Domain Name Server\n\
+                    custom-app      40000/tcp       capp            # in-house service\n\
+                    custom-app      40000/udp\n\
+                    sctp-only       40001/sctp\n";
+    assert_eq!(registry.extend_from_services(services), Ok(3));
+    assert_eq!(registry.name(Transport::TCP, 53), Some("DOMAIN"));
+    assert_eq!(registry.name(Transport::UDP, 53), Some("DNS"));
+    assert_eq!(registry.service_name(Transport::UDP, 40000, 50000), Some("CUSTOM-APP"));
+    assert_eq!(registry.name(Transport::TCP, 40001), None);
+
+    // a malformed line leaves the registry untouched
+    assert_eq!(registry.extend_from_services("other 40002/tcp\nbroken\n"), Err("line 2: expected port/protocol after the service name".to_string()));
+    assert_eq!(registry.extend_from_services("other 70000/tcp\n"), Err("line 1: invalid port number 70000".to_string()));
+    assert_eq!(registry.name(Transport::TCP, 40002), None);
+}
+
+#[test]
+pub fn service_name_verbose_test() {
+    // MySQL server to client, no decoder for the payload
+    let tcp_data_in_u8 = [0x0C, 0xEA, 0xC7, 0x38, 0, 0, 0, 1, 0, 0, 0, 1, 0x50, 0x18, 0xFF, 0xFF, 0, 0, 0, 0, 0x0A, 0x35, 0x2E];
+    let verbose = TcpPacket::new(&tcp_data_in_u8).to_string();
+    assert!(verbose.contains("MYSQL   : Protocol details unknown"));
+
+    let tcp_data_in_u8 = [0x9C, 0x40, 0xC7, 0x38, 0, 0, 0, 1, 0, 0, 0, 1, 0x50, 0x18, 0xFF, 0xFF, 0, 0, 0, 0, 0x0A, 0x35, 0x2E];
+    let verbose = TcpPacket::new(&tcp_data_in_u8).to_string();
+    assert!(verbose.contains("Other Protocol incapsulated in TCP/UDP segment (Unknown Protocol): Protocol details unknown"));
+}
@@ -0,0 +1,26 @@
+//! # CSV Format
+//!
+//! Comma-separated values as specified by RFC 4180: the records end with CRLF, and the fields containing a comma, a double
+//! quote or a line break are enclosed in double quotes, the double quotes inside them being doubled.
+//!
+//! ```
+//! IP src,IP dst,Port src,Port dst,L4 Protocol,Upper Service,...,Server Name
+//! 2001:db8::10,2001:db8::53,53000,53,UDP,DNS,...,
+//! 192.168.1.119,140.82.121.3,39322,443,TCP,HTTPS,...,"shop.example.com,www.example.com"
+//! ```
+
+/// Field of a record, quoted when needed
+pub fn field(value: &str) -> String {
+    if value.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Record of the given fields, ending with CRLF
+pub fn record<T: AsRef<str>>(fields: &[T]) -> String {
+    let mut record = fields.iter().map(|value| field(value.as_ref())).collect::<Vec<String>>().join(",");
+    record.push_str("\r\n");
+    record
+}
@@ -0,0 +1,411 @@
+//! # Flow Collector
+//!
+//! Decoding of the flow records exported by routers and switches: NetFlow v5, NetFlow v9 (RFC 3954), IPFIX (RFC 7011)
+//! and sFlow v5 datagrams, captured on their ports (2055, 4739 and 6343) or received on the local collector socket.
+//!
+//! The version is told by the header of the datagram, whatever the port. Templates of NetFlow v9 and IPFIX are kept for
+//! each exporter and observation domain: data records are decoded once their template is received. Records are read with
+//! the delta counters of packets and bytes (Information Elements 2 and 1), and the times of the first and last packet.
+//!
+//! sFlow flow samples carry the header of a sampled packet, decoded as a captured one, or its IP addresses and ports:
+//! each sample counts as many packets as the sampling rate (estimated counters). Counter samples aren't decoded.
+
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use crate::EthernetPacket;
+use crate::network_components::layer_2::ethernet_packet::NO_SERVICE;
+use crate::network_components::layer_3::ip_protocol_name;
+use crate::network_components::services_upper_layers::upper_layer_services::{identify_service, Transport, UpperLayerService};
+use crate::report_generator::flow_export::{DESTINATION_IPV4_ADDRESS, DESTINATION_IPV6_ADDRESS, DESTINATION_TRANSPORT_PORT, FLOW_END_MILLISECONDS,
+    FLOW_END_SYS_UP_TIME, FLOW_START_MILLISECONDS, FLOW_START_SYS_UP_TIME, IPFIX_HEADER_SIZE, IPFIX_TEMPLATE_SET, NETFLOW_V5_HEADER_SIZE,
+    NETFLOW_V5_RECORD_SIZE, NETFLOW_V9_HEADER_SIZE, NETFLOW_V9_TEMPLATE_SET, OCTET_DELTA_COUNT, PACKET_DELTA_COUNT, PROTOCOL_IDENTIFIER,
+    SOURCE_IPV4_ADDRESS, SOURCE_IPV6_ADDRESS, SOURCE_TRANSPORT_PORT};
+use crate::report_generator::ReportDataInfo;
+
+/// Lowest set ID of the data sets, the lower ones are templates
+const MIN_DATA_SET_ID: u16 = 256;
+/// Length of the variable-length fields in the IPFIX templates
+const VARIABLE_LENGTH: u16 = 65535;
+/// Maximum number of templates kept, of all the exporters
+const MAX_TEMPLATES: usize = 4096;
+
+/// Information Elements of the flow start and end in seconds
+const FLOW_START_SECONDS: u16 = 150;
+const FLOW_END_SECONDS: u16 = 151;
+
+/// sFlow formats (standard enterprise): flow samples and their records
+const SFLOW_VERSION: u32 = 5;
+const SFLOW_FLOW_SAMPLE: u32 = 1;
+const SFLOW_EXPANDED_FLOW_SAMPLE: u32 = 3;
+const SFLOW_RAW_PACKET_HEADER: u32 = 1;
+const SFLOW_IPV4_DATA: u32 = 3;
+const SFLOW_IPV6_DATA: u32 = 4;
+/// Protocol of the raw packet headers decoded: Ethernet
+const SFLOW_HEADER_ETHERNET: u32 = 1;
+
+#[derive(Debug, Clone)]
+/// `Collected Flow`: flow record of an exporter, merged into the flow table as the packets it counts
+pub struct CollectedFlow {
+    /// Report data of the flow: addresses, ports, protocol, service, bytes and time of the last packet
+    pub report_data: ReportDataInfo,
+    /// Number of packets of the flow
+    pub num_packets: usize,
+    /// Time of the first packet of the flow
+    pub timestamp_init: DateTime<Utc>,
+}
+
+/// `Template Field` of a NetFlow v9/IPFIX template (Information Element None if enterprise-specific)
+#[derive(Debug, Clone, PartialEq)]
+struct TemplateField {
+    element: Option<u16>,
+    length: u16,
+}
+
+/// Template key: exporter, version, source ID (NetFlow v9) or observation domain (IPFIX), template ID
+type TemplateKey = (IpAddr, u16, u32, u16);
+
+/// `Flow Collector` decoding the datagrams of the exporters
+pub struct FlowCollector {
+    templates: HashMap<TemplateKey, Vec<TemplateField>>,
+}
+
+/// Header of a NetFlow v5/v9 or IPFIX message: version, source ID or observation domain, export time and uptime of the exporter
+struct MessageHeader {
+    version: u16,
+    domain: u32,
+    export_time: DateTime<Utc>,
+    sys_uptime: Option<u32>,
+}
+
+impl MessageHeader {
+    /// Time of a NetFlow uptime (milliseconds since the start of the exporter)
+    fn uptime_time(&self, uptime: u32) -> DateTime<Utc> {
+        let sys_uptime = self.sys_uptime.unwrap_or(uptime);
+        self.export_time - Duration::milliseconds(sys_uptime.wrapping_sub(uptime) as i32 as i64)
+    }
+}
+
+impl FlowCollector {
+    pub fn new() -> Self {
+        FlowCollector { templates: HashMap::new() }
+    }
+
+    /// `Decode` a datagram of an exporter received at a given time, returning its flow records (the ones decoded up to the
+    /// first malformed part). Datagrams of other protocols have no records.
+    pub fn decode(&mut self, exporter: IpAddr, datagram: &[u8], received: DateTime<Utc>) -> Vec<CollectedFlow> {
+        let mut flows = Vec::new();
+        let mut reader = Reader::new(datagram);
+        let _ = match datagram {
+            [0, 5, ..] => self.netflow_v5(&mut reader, received, &mut flows),
+            [0, 9, ..] | [0, 10, ..] => self.netflow_v9_ipfix(exporter, &mut reader, received, &mut flows),
+            [0, 0, 0, 5, ..] => self.sflow(&mut reader, received, &mut flows),
+            _ => None,
+        };
+        flows
+    }
+
+    fn netflow_v5(&self, reader: &mut Reader, received: DateTime<Utc>, flows: &mut Vec<CollectedFlow>) -> Option<()> {
+        let mut header = Reader::new(reader.bytes(NETFLOW_V5_HEADER_SIZE)?);
+        let (_, count, sys_uptime) = (header.u16()?, header.u16()?, header.u32()?);
+        let export_time = unix_time(header.u32()? as i64, header.u32()?, received);
+        header.bytes(6)?;
+        // sampling mode in the 2 highest bits, sampling interval in the others
+        let sampling_interval = (header.u16()? & 0x3FFF).max(1) as usize;
+        let header = MessageHeader { version: 5, domain: 0, export_time, sys_uptime: Some(sys_uptime) };
+
+        for _ in 0..count {
+            let mut record = Reader::new(reader.bytes(NETFLOW_V5_RECORD_SIZE)?);
+            let ip_src = IpAddr::V4(Ipv4Addr::from(record.u32()?));
+            let ip_dst = IpAddr::V4(Ipv4Addr::from(record.u32()?));
+            record.bytes(8)?;
+            let (packets, bytes) = (record.u32()? as usize, record.u32()? as usize);
+            let (first, last) = (header.uptime_time(record.u32()?), header.uptime_time(record.u32()?));
+            let (port_src, port_dst) = (record.u16()?, record.u16()?);
+            record.bytes(2)?;
+            let protocol = record.u8()?;
+            let record = FlowFields {
+                ip_src: Some(ip_src), ip_dst: Some(ip_dst), port_src, port_dst, protocol,
+                packets: packets * sampling_interval, bytes: bytes * sampling_interval, first: Some(first), last: Some(last),
+            };
+            flows.extend(record.collected_flow(export_time));
+        }
+        Some(())
+    }
+
+    fn netflow_v9_ipfix(&mut self, exporter: IpAddr, reader: &mut Reader, received: DateTime<Utc>, flows: &mut Vec<CollectedFlow>) -> Option<()> {
+        let version = reader.u16()?;
+        let header = match version {
+            9 => {
+                let mut header = Reader::new(reader.bytes(NETFLOW_V9_HEADER_SIZE - 2)?);
+                let (_, sys_uptime, export_time, _, source_id) = (header.u16()?, header.u32()?, header.u32()?, header.u32()?, header.u32()?);
+                MessageHeader { version, domain: source_id, export_time: unix_time(export_time as i64, 0, received), sys_uptime: Some(sys_uptime) }
+            },
+            _ => {
+                let mut header = Reader::new(reader.bytes(IPFIX_HEADER_SIZE - 2)?);
+                let (length, export_time, _, domain) = (header.u16()? as usize, header.u32()?, header.u32()?, header.u32()?);
+                // the message length tells where the sets end
+                *reader = Reader::new(reader.data.get(..length)?);
+                reader.offset = IPFIX_HEADER_SIZE;
+                MessageHeader { version, domain, export_time: unix_time(export_time as i64, 0, received), sys_uptime: None }
+            },
+        };
+
+        while !reader.is_empty() {
+            let set_id = reader.u16()?;
+            let length = (reader.u16()? as usize).checked_sub(4)?;
+            let mut set = Reader::new(reader.bytes(length)?);
+            match set_id {
+                NETFLOW_V9_TEMPLATE_SET | IPFIX_TEMPLATE_SET => self.templates_set(exporter, &header, &mut set)?,
+                set_id if set_id >= MIN_DATA_SET_ID => {
+                    if let Some(template) = self.templates.get(&(exporter, header.version, header.domain, set_id)) {
+                        data_set(&header, template, &mut set, flows)?;
+                    }
+                },
+                // options templates and their data sets aren't decoded
+                _ => {},
+            }
+        }
+        Some(())
+    }
+
+    /// Templates of a template set, replacing the ones with the same ID. An IPFIX template without fields is withdrawn.
+    fn templates_set(&mut self, exporter: IpAddr, header: &MessageHeader, set: &mut Reader) -> Option<()> {
+        // a set is padded with less bytes than a template header
+        while set.remaining() >= 4 {
+            let (template_id, field_count) = (set.u16()?, set.u16()?);
+            let mut fields = Vec::with_capacity(field_count as usize);
+            for _ in 0..field_count {
+                let (element, length) = (set.u16()?, set.u16()?);
+                // enterprise-specific Information Element of IPFIX, followed by the enterprise number
+                if header.version == 10 && element & 0x8000 != 0 {
+                    set.u32()?;
+                    fields.push(TemplateField { element: None, length });
+                } else {
+                    fields.push(TemplateField { element: Some(element), length });
+                }
+            }
+            let key = (exporter, header.version, header.domain, template_id);
+            if fields.is_empty() {
+                self.templates.remove(&key);
+            } else if self.templates.len() < MAX_TEMPLATES || self.templates.contains_key(&key) {
+                self.templates.insert(key, fields);
+            }
+        }
+        Some(())
+    }
+
+    fn sflow(&self, reader: &mut Reader, received: DateTime<Utc>, flows: &mut Vec<CollectedFlow>) -> Option<()> {
+        if reader.u32()? != SFLOW_VERSION {
+            return None;
+        }
+        match reader.u32()? {
+            1 => reader.bytes(4)?,
+            2 => reader.bytes(16)?,
+            _ => return None,
+        };
+        // sub-agent ID, sequence number and uptime
+        reader.bytes(12)?;
+        for _ in 0..reader.u32()? {
+            let (format, length) = (reader.u32()?, reader.u32()? as usize);
+            let mut sample = Reader::new(reader.bytes(length)?);
+            let sampling_rate = match format {
+                SFLOW_FLOW_SAMPLE => { sample.bytes(4 * 2)?; let sampling_rate = sample.u32()?; sample.bytes(4 * 4)?; sampling_rate },
+                SFLOW_EXPANDED_FLOW_SAMPLE => { sample.bytes(4 * 3)?; let sampling_rate = sample.u32()?; sample.bytes(4 * 6)?; sampling_rate },
+                // counter samples and the formats of other enterprises
+                _ => continue,
+            };
+            if let Some(flow) = sflow_flow_sample(&mut sample, (sampling_rate as usize).max(1), received) {
+                flows.push(flow);
+            }
+        }
+        Some(())
+    }
+}
+
+impl Default for FlowCollector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Records of a data set decoded with their template, up to the padding
+fn data_set(header: &MessageHeader, template: &[TemplateField], set: &mut Reader, flows: &mut Vec<CollectedFlow>) -> Option<()> {
+    let min_length: usize = template.iter().map(|field| if field.length == VARIABLE_LENGTH { 1 } else { field.length as usize }).sum();
+    while min_length > 0 && set.remaining() >= min_length {
+        let mut record = FlowFields::default();
+        for field in template {
+            let length = match field.length {
+                VARIABLE_LENGTH => match set.u8()? {
+                    255 => set.u16()? as usize,
+                    length => length as usize,
+                },
+                length => length as usize,
+            };
+            let value = set.bytes(length)?;
+            if let Some(element) = field.element {
+                record.set(header, element, value);
+            }
+        }
+        flows.extend(record.collected_flow(header.export_time));
+    }
+    Some(())
+}
+
+#[derive(Default)]
+/// `Flow Fields` read from a NetFlow v9/IPFIX data record
+struct FlowFields {
+    ip_src: Option<IpAddr>,
+    ip_dst: Option<IpAddr>,
+    port_src: u16,
+    port_dst: u16,
+    protocol: u8,
+    packets: usize,
+    bytes: usize,
+    first: Option<DateTime<Utc>>,
+    last: Option<DateTime<Utc>>,
+}
+
+impl FlowFields {
+    fn set(&mut self, header: &MessageHeader, element: u16, value: &[u8]) {
+        let number = unsigned(value);
+        match (element, value.len()) {
+            (SOURCE_IPV4_ADDRESS, 4) => self.ip_src = Some(IpAddr::V4(Ipv4Addr::from(number as u32))),
+            (DESTINATION_IPV4_ADDRESS, 4) => self.ip_dst = Some(IpAddr::V4(Ipv4Addr::from(number as u32))),
+            (SOURCE_IPV6_ADDRESS, 16) => self.ip_src = <[u8; 16]>::try_from(value).ok().map(|address| IpAddr::V6(Ipv6Addr::from(address))),
+            (DESTINATION_IPV6_ADDRESS, 16) => self.ip_dst = <[u8; 16]>::try_from(value).ok().map(|address| IpAddr::V6(Ipv6Addr::from(address))),
+            (SOURCE_TRANSPORT_PORT, _) => self.port_src = number as u16,
+            (DESTINATION_TRANSPORT_PORT, _) => self.port_dst = number as u16,
+            (PROTOCOL_IDENTIFIER, _) => self.protocol = number as u8,
+            (PACKET_DELTA_COUNT, _) => self.packets = number as usize,
+            (OCTET_DELTA_COUNT, _) => self.bytes = number as usize,
+            (FLOW_START_SYS_UP_TIME, _) if header.sys_uptime.is_some() => self.first = Some(header.uptime_time(number as u32)),
+            (FLOW_END_SYS_UP_TIME, _) if header.sys_uptime.is_some() => self.last = Some(header.uptime_time(number as u32)),
+            (FLOW_START_SECONDS, _) => self.first = Utc.timestamp_opt(number as i64, 0).single(),
+            (FLOW_END_SECONDS, _) => self.last = Utc.timestamp_opt(number as i64, 0).single(),
+            (FLOW_START_MILLISECONDS, _) => self.first = Utc.timestamp_millis_opt(number as i64).single(),
+            (FLOW_END_MILLISECONDS, _) => self.last = Utc.timestamp_millis_opt(number as i64).single(),
+            _ => {},
+        }
+    }
+
+    /// Flow of the record, named after the service of its ports like the captured packets. None without IP addresses or
+    /// packets (e.g. records of other templates). The times missing are the export time.
+    fn collected_flow(&self, export_time: DateTime<Utc>) -> Option<CollectedFlow> {
+        if self.packets == 0 {
+            return None;
+        }
+        let transport = match self.protocol {
+            6 => Some(Transport::TCP),
+            17 => Some(Transport::UDP),
+            _ => None,
+        };
+        let identification = transport.map(|transport| identify_service(transport, self.port_src, self.port_dst, &[]));
+        let last = self.last.unwrap_or(export_time);
+        let report_data = ReportDataInfo {
+            ip_src: self.ip_src?.to_string(),
+            ip_dst: self.ip_dst?.to_string(),
+            port_src: if transport.is_some() { self.port_src } else { 0 },
+            port_dst: if transport.is_some() { self.port_dst } else { 0 },
+            l4_protocol: ip_protocol_name(self.protocol),
+            upper_service: identification.as_ref().map_or(String::from(NO_SERVICE), |identification| {
+                identification.name.clone().unwrap_or(format!("{:?}", UpperLayerService::UNKNOWN))
+            }),
+            service_confidence: identification.map_or(0, |identification| identification.confidence),
+            num_bytes: self.bytes,
+            timestamp_recv: last,
+            tcp_flags: Vec::new(),
+            bad_checksum: false,
+        };
+        Some(CollectedFlow { report_data, num_packets: self.packets, timestamp_init: self.first.unwrap_or(last).min(last) })
+    }
+}
+
+/// Flow of an sFlow flow sample: the raw header of the sampled packet when its record is there, otherwise its IP data
+fn sflow_flow_sample(sample: &mut Reader, sampling_rate: usize, received: DateTime<Utc>) -> Option<CollectedFlow> {
+    let (mut raw_header, mut ip_data) = (None, None);
+    for _ in 0..sample.u32()? {
+        let (format, length) = (sample.u32()?, sample.u32()? as usize);
+        let mut record = Reader::new(sample.bytes(length)?);
+        match format {
+            SFLOW_RAW_PACKET_HEADER => {
+                let (header_protocol, frame_length) = (record.u32()?, record.u32()? as usize);
+                record.bytes(4)?;
+                let header_length = record.u32()? as usize;
+                let header = record.bytes(header_length)?;
+                // the Ethernet header is needed to decode the frame
+                if header_protocol == SFLOW_HEADER_ETHERNET && header.len() >= 14 {
+                    let mut report_data = EthernetPacket::new(header).decoded_report_data();
+                    // the sampled header is truncated, its checksums can't be verified
+                    report_data.bad_checksum = false;
+                    report_data.num_bytes = frame_length * sampling_rate;
+                    report_data.timestamp_recv = received;
+                    raw_header = Some(CollectedFlow { report_data, num_packets: sampling_rate, timestamp_init: received });
+                }
+            },
+            SFLOW_IPV4_DATA | SFLOW_IPV6_DATA => {
+                let (length, protocol) = (record.u32()? as usize, record.u32()? as u8);
+                let (ip_src, ip_dst) = match format {
+                    SFLOW_IPV4_DATA => (IpAddr::V4(Ipv4Addr::from(record.u32()?)), IpAddr::V4(Ipv4Addr::from(record.u32()?))),
+                    _ => (IpAddr::V6(Ipv6Addr::from(<[u8; 16]>::try_from(record.bytes(16)?).ok()?)), IpAddr::V6(Ipv6Addr::from(<[u8; 16]>::try_from(record.bytes(16)?).ok()?))),
+                };
+                let (port_src, port_dst) = (record.u32()? as u16, record.u32()? as u16);
+                let record = FlowFields {
+                    ip_src: Some(ip_src), ip_dst: Some(ip_dst), port_src, port_dst, protocol,
+                    packets: sampling_rate, bytes: length * sampling_rate, first: None, last: None,
+                };
+                ip_data = record.collected_flow(received);
+            },
+            _ => {},
+        }
+    }
+    raw_header.or(ip_data)
+}
+
+/// Time from the UNIX time in a header, the time received if invalid
+fn unix_time(seconds: i64, nanoseconds: u32, received: DateTime<Utc>) -> DateTime<Utc> {
+    Utc.timestamp_opt(seconds, nanoseconds).single().unwrap_or(received)
+}
+
+/// Unsigned number of a field (reduced-size encoding of the counters: 1 to 8 bytes)
+fn unsigned(value: &[u8]) -> u64 {
+    value.iter().take(8).fold(0, |number, byte| number << 8 | *byte as u64)
+}
+
+/// Bounds-checked reader of big-endian fields
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Reader<'a> {
+        Reader { data, offset: 0 }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.offset >= self.data.len()
+    }
+
+    fn remaining(&self) -> usize {
+        self.data.len().saturating_sub(self.offset)
+    }
+
+    fn bytes(&mut self, length: usize) -> Option<&'a [u8]> {
+        let bytes = self.data.get(self.offset..self.offset.checked_add(length)?)?;
+        self.offset += length;
+        Some(bytes)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.bytes(1).map(|bytes| bytes[0])
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        self.bytes(2).map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.bytes(4).map(|bytes| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+}
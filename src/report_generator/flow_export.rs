@@ -0,0 +1,333 @@
+//! # Flow Export
+//!
+//! Software flow probe: at the end of each report interval the flows of the flow table are sent over UDP to a collector,
+//! as NetFlow v5, NetFlow v9 (RFC 3954) or IPFIX (RFC 7011) records.
+//!
+//! Each flow with packets in the interval is a record with the counters of the interval (delta counters), so that the
+//! collector sums the records of a flow lasting several intervals. Only the IPv4 flows can be exported with NetFlow v5.
+//! NetFlow v9 and IPFIX messages start with the templates of the IPv4 (256) and IPv6 (257) records, sent in every message
+//! since UDP doesn't guarantee their delivery:
+//!
+//! | Field (IANA IPFIX Information Element) | NetFlow v9          | IPFIX                     |
+//! |----------------------------------------|---------------------|---------------------------|
+//! | source address                         | 8 / 27              | 8 / 27                    |
+//! | destination address                    | 12 / 28             | 12 / 28                   |
+//! | source and destination ports           | 7, 11               | 7, 11                     |
+//! | protocol                               | 4                   | 4                         |
+//! | packets, bytes (8 bytes)               | 2, 1                | 2, 1                      |
+//! | first and last packet                  | 22, 21 (uptime, ms) | 152, 153 (UNIX time, ms)  |
+//!
+//! With bidirectional flow aggregation a record counts both directions of the conversation, from the client to the server.
+
+use std::net::{IpAddr, SocketAddr, UdpSocket};
+use chrono::{DateTime, Utc};
+use crate::network_components::layer_3::ip_protocol_number;
+use crate::report_generator::{ReportEntry, Result};
+
+/// Maximum size of a datagram sent to the collector, below the usual MTU
+const MAX_DATAGRAM_SIZE: usize = 1400;
+/// Maximum number of records of a NetFlow v5 datagram
+const NETFLOW_V5_MAX_RECORDS: usize = 30;
+pub const NETFLOW_V5_HEADER_SIZE: usize = 24;
+pub const NETFLOW_V5_RECORD_SIZE: usize = 48;
+pub const NETFLOW_V9_HEADER_SIZE: usize = 20;
+pub const IPFIX_HEADER_SIZE: usize = 16;
+/// Set IDs of the templates: NetFlow v9 template FlowSet and IPFIX template set
+pub const NETFLOW_V9_TEMPLATE_SET: u16 = 0;
+pub const IPFIX_TEMPLATE_SET: u16 = 2;
+/// Template IDs of the IPv4 and IPv6 records
+const IPV4_TEMPLATE_ID: u16 = 256;
+const IPV6_TEMPLATE_ID: u16 = 257;
+
+/// Information Elements of the records (NetFlow v9 field types)
+pub const OCTET_DELTA_COUNT: u16 = 1;
+pub const PACKET_DELTA_COUNT: u16 = 2;
+pub const PROTOCOL_IDENTIFIER: u16 = 4;
+pub const SOURCE_TRANSPORT_PORT: u16 = 7;
+pub const SOURCE_IPV4_ADDRESS: u16 = 8;
+pub const DESTINATION_TRANSPORT_PORT: u16 = 11;
+pub const DESTINATION_IPV4_ADDRESS: u16 = 12;
+pub const FLOW_END_SYS_UP_TIME: u16 = 21;
+pub const FLOW_START_SYS_UP_TIME: u16 = 22;
+pub const SOURCE_IPV6_ADDRESS: u16 = 27;
+pub const DESTINATION_IPV6_ADDRESS: u16 = 28;
+pub const FLOW_START_MILLISECONDS: u16 = 152;
+pub const FLOW_END_MILLISECONDS: u16 = 153;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+/// `Flow Export Version`: protocol of the records sent to the collector
+pub enum FlowExportVersion {
+    NetflowV5,
+    NetflowV9,
+    Ipfix,
+}
+
+impl FlowExportVersion {
+    /// Version from its name: `v5`, `v9` or `ipfix`
+    pub fn parse(version: &str) -> Option<FlowExportVersion> {
+        match version.to_ascii_lowercase().as_str() {
+            "v5" | "netflow-v5" => Some(FlowExportVersion::NetflowV5),
+            "v9" | "netflow-v9" => Some(FlowExportVersion::NetflowV9),
+            "ipfix" => Some(FlowExportVersion::Ipfix),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+/// `Flow Export` configuration: collector receiving the records, and their protocol
+pub struct FlowExport {
+    pub collector: SocketAddr,
+    pub version: FlowExportVersion,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// `Flow Record` exported for a flow of the report interval
+pub struct FlowRecord {
+    pub ip_src: IpAddr,
+    pub ip_dst: IpAddr,
+    pub port_src: u16,
+    pub port_dst: u16,
+    pub protocol: u8,
+    pub packets: u64,
+    pub bytes: u64,
+    pub first: DateTime<Utc>,
+    pub last: DateTime<Utc>,
+}
+
+impl FlowRecord {
+    /// Record of the packets of the report interval of an IP flow (None without packets in the interval or without IP)
+    pub fn new(entry: &ReportEntry) -> Option<FlowRecord> {
+        if entry.interval_packets == 0 {
+            return None;
+        }
+        Some(FlowRecord {
+            ip_src: entry.ip_src.parse().ok()?,
+            ip_dst: entry.ip_dst.parse().ok()?,
+            port_src: entry.port_src,
+            port_dst: entry.port_dst,
+            protocol: ip_protocol_number(&entry.l4_protocol)?,
+            packets: entry.interval_packets as u64,
+            bytes: entry.interval_bytes as u64,
+            first: entry.interval_timestamp_init,
+            last: entry.interval_timestamp_final,
+        })
+    }
+}
+
+/// `Flow Exporter` sending the flow records to the collector
+pub struct FlowExporter {
+    socket: UdpSocket,
+    flow_export: FlowExport,
+    /// Start of the exporter, origin of the NetFlow uptime
+    start_time: DateTime<Utc>,
+    /// NetFlow v5 flow sequence and IPFIX sequence number: records sent so far. NetFlow v9 sequence: datagrams sent so far
+    sequence: u32,
+}
+
+impl FlowExporter {
+    pub fn new(flow_export: FlowExport) -> Result<Self> {
+        let local_address = match flow_export.collector {
+            SocketAddr::V4(_) => "0.0.0.0:0",
+            SocketAddr::V6(_) => "[::]:0",
+        };
+        Ok(FlowExporter { socket: UdpSocket::bind(local_address)?, flow_export, start_time: Utc::now(), sequence: 0 })
+    }
+
+    /// `Local Address` of the socket the datagrams are sent from
+    pub fn local_address(&self) -> Result<SocketAddr> {
+        Ok(self.socket.local_addr()?)
+    }
+
+    /// `Sent` by this exporter: a datagram from its socket or to its collector, e.g. captured on the way out
+    pub fn sent(&self, ip_addr_src: IpAddr, port_src: u16, ip_addr_dst: IpAddr, port_dst: u16) -> bool {
+        let from_socket = self.local_address().is_ok_and(|local_address| {
+            local_address.port() == port_src && (local_address.ip().is_unspecified() || local_address.ip() == ip_addr_src)
+        });
+        from_socket || SocketAddr::new(ip_addr_dst, port_dst) == self.flow_export.collector
+    }
+
+    /// `Export` the flows of the report interval, returning the number of records sent
+    pub fn export<'a>(&mut self, entries: impl Iterator<Item = &'a ReportEntry>, now: DateTime<Utc>) -> Result<usize> {
+        let records: Vec<FlowRecord> = entries.filter_map(FlowRecord::new).collect();
+        let (datagrams, count) = self.datagrams(&records, now);
+        for datagram in datagrams {
+            self.socket.send_to(&datagram, self.flow_export.collector)?;
+        }
+        Ok(count)
+    }
+
+    /// Datagrams carrying the records, and the number of records they carry
+    pub fn datagrams(&mut self, records: &[FlowRecord], now: DateTime<Utc>) -> (Vec<Vec<u8>>, usize) {
+        match self.flow_export.version {
+            FlowExportVersion::NetflowV5 => self.netflow_v5(records, now),
+            FlowExportVersion::NetflowV9 | FlowExportVersion::Ipfix => self.netflow_v9_ipfix(records, now),
+        }
+    }
+
+    fn netflow_v5(&mut self, records: &[FlowRecord], now: DateTime<Utc>) -> (Vec<Vec<u8>>, usize) {
+        let records: Vec<&FlowRecord> = records.iter().filter(|record| record.ip_src.is_ipv4() && record.ip_dst.is_ipv4()).collect();
+        let mut datagrams = Vec::new();
+        for chunk in records.chunks(NETFLOW_V5_MAX_RECORDS) {
+            let mut datagram = Vec::with_capacity(NETFLOW_V5_HEADER_SIZE + chunk.len() * NETFLOW_V5_RECORD_SIZE);
+            datagram.extend_from_slice(&5u16.to_be_bytes());
+            datagram.extend_from_slice(&(chunk.len() as u16).to_be_bytes());
+            datagram.extend_from_slice(&self.uptime(now).to_be_bytes());
+            datagram.extend_from_slice(&(now.timestamp() as u32).to_be_bytes());
+            datagram.extend_from_slice(&now.timestamp_subsec_nanos().to_be_bytes());
+            datagram.extend_from_slice(&self.sequence.to_be_bytes());
+            // engine type and id, sampling interval
+            datagram.extend_from_slice(&[0, 0, 0, 0]);
+            for record in chunk {
+                if let (IpAddr::V4(ip_src), IpAddr::V4(ip_dst)) = (record.ip_src, record.ip_dst) {
+                    datagram.extend_from_slice(&ip_src.octets());
+                    datagram.extend_from_slice(&ip_dst.octets());
+                }
+                // next hop, input and output interfaces
+                datagram.extend_from_slice(&[0; 8]);
+                datagram.extend_from_slice(&(record.packets.min(u32::MAX as u64) as u32).to_be_bytes());
+                datagram.extend_from_slice(&(record.bytes.min(u32::MAX as u64) as u32).to_be_bytes());
+                datagram.extend_from_slice(&self.uptime(record.first).to_be_bytes());
+                datagram.extend_from_slice(&self.uptime(record.last).to_be_bytes());
+                datagram.extend_from_slice(&record.port_src.to_be_bytes());
+                datagram.extend_from_slice(&record.port_dst.to_be_bytes());
+                // pad, TCP flags, protocol, ToS, AS numbers, masks, pad
+                datagram.extend_from_slice(&[0, 0, record.protocol, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+            }
+            self.sequence = self.sequence.wrapping_add(chunk.len() as u32);
+            datagrams.push(datagram);
+        }
+        (datagrams, records.len())
+    }
+
+    fn netflow_v9_ipfix(&mut self, records: &[FlowRecord], now: DateTime<Utc>) -> (Vec<Vec<u8>>, usize) {
+        let ipfix = self.flow_export.version == FlowExportVersion::Ipfix;
+        let header_size = if ipfix { IPFIX_HEADER_SIZE } else { NETFLOW_V9_HEADER_SIZE };
+        let templates = self.template_set();
+        let record_size = |ipv6: bool| self.template_fields(ipv6).iter().map(|(_, length)| *length as usize).sum::<usize>();
+        let set_size = |count: usize, ipv6: bool| if count == 0 { 0 } else { padded(4 + count * record_size(ipv6)) };
+        let datagram_size = |ipv4_count: usize, ipv6_count: usize| header_size + templates.len() + set_size(ipv4_count, false) + set_size(ipv6_count, true);
+
+        // records of each datagram, IPv4 and IPv6 ones in separate sets
+        let mut chunks: Vec<(Vec<&FlowRecord>, Vec<&FlowRecord>)> = vec![(Vec::new(), Vec::new())];
+        for record in records {
+            let ipv6 = record.ip_src.is_ipv6();
+            let (ipv4_records, ipv6_records) = chunks.last().unwrap();
+            let (ipv4_count, ipv6_count) = (ipv4_records.len(), ipv6_records.len());
+            let size = if ipv6 { datagram_size(ipv4_count, ipv6_count + 1) } else { datagram_size(ipv4_count + 1, ipv6_count) };
+            if size > MAX_DATAGRAM_SIZE && ipv4_count + ipv6_count > 0 {
+                chunks.push((Vec::new(), Vec::new()));
+            }
+            let (ipv4_records, ipv6_records) = chunks.last_mut().unwrap();
+            if ipv6 { ipv6_records.push(record) } else { ipv4_records.push(record) }
+        }
+
+        let mut datagrams = Vec::new();
+        for (ipv4_records, ipv6_records) in chunks {
+            let mut sets = templates.clone();
+            for (template_id, records, ipv6) in [(IPV4_TEMPLATE_ID, &ipv4_records, false), (IPV6_TEMPLATE_ID, &ipv6_records, true)] {
+                if records.is_empty() {
+                    continue;
+                }
+                let mut set = Vec::new();
+                records.iter().for_each(|record| set.extend(self.data_record(record, ipv6)));
+                sets.extend_from_slice(&template_id.to_be_bytes());
+                sets.extend_from_slice(&(padded(4 + set.len()) as u16).to_be_bytes());
+                sets.extend(set);
+                sets.resize(padded(sets.len()), 0);
+            }
+
+            let count = ipv4_records.len() + ipv6_records.len();
+            let mut datagram = Vec::with_capacity(header_size + sets.len());
+            if ipfix {
+                datagram.extend_from_slice(&10u16.to_be_bytes());
+                datagram.extend_from_slice(&((header_size + sets.len()) as u16).to_be_bytes());
+                datagram.extend_from_slice(&(now.timestamp() as u32).to_be_bytes());
+                datagram.extend_from_slice(&self.sequence.to_be_bytes());
+                // observation domain
+                datagram.extend_from_slice(&0u32.to_be_bytes());
+                self.sequence = self.sequence.wrapping_add(count as u32);
+            } else {
+                datagram.extend_from_slice(&9u16.to_be_bytes());
+                // template and data records
+                datagram.extend_from_slice(&((2 + count) as u16).to_be_bytes());
+                datagram.extend_from_slice(&self.uptime(now).to_be_bytes());
+                datagram.extend_from_slice(&(now.timestamp() as u32).to_be_bytes());
+                datagram.extend_from_slice(&self.sequence.to_be_bytes());
+                // source id
+                datagram.extend_from_slice(&0u32.to_be_bytes());
+                self.sequence = self.sequence.wrapping_add(1);
+            }
+            datagram.extend(sets);
+            datagrams.push(datagram);
+        }
+        (datagrams, records.len())
+    }
+
+    /// Template set of the IPv4 and IPv6 records
+    fn template_set(&self) -> Vec<u8> {
+        let mut templates = Vec::new();
+        for (template_id, ipv6) in [(IPV4_TEMPLATE_ID, false), (IPV6_TEMPLATE_ID, true)] {
+            let fields = self.template_fields(ipv6);
+            templates.extend_from_slice(&template_id.to_be_bytes());
+            templates.extend_from_slice(&(fields.len() as u16).to_be_bytes());
+            fields.iter().for_each(|(field, length)| {
+                templates.extend_from_slice(&field.to_be_bytes());
+                templates.extend_from_slice(&length.to_be_bytes());
+            });
+        }
+        let set_id = if self.flow_export.version == FlowExportVersion::Ipfix { IPFIX_TEMPLATE_SET } else { NETFLOW_V9_TEMPLATE_SET };
+        let mut set = Vec::from(set_id.to_be_bytes());
+        set.extend_from_slice(&((4 + templates.len()) as u16).to_be_bytes());
+        set.extend(templates);
+        set
+    }
+
+    /// Fields (Information Element and length) of the IPv4 or IPv6 records
+    fn template_fields(&self, ipv6: bool) -> Vec<(u16, u16)> {
+        let mut fields = match ipv6 {
+            false => vec![(SOURCE_IPV4_ADDRESS, 4), (DESTINATION_IPV4_ADDRESS, 4)],
+            true => vec![(SOURCE_IPV6_ADDRESS, 16), (DESTINATION_IPV6_ADDRESS, 16)],
+        };
+        fields.extend([(SOURCE_TRANSPORT_PORT, 2), (DESTINATION_TRANSPORT_PORT, 2), (PROTOCOL_IDENTIFIER, 1), (PACKET_DELTA_COUNT, 8), (OCTET_DELTA_COUNT, 8)]);
+        match self.flow_export.version {
+            FlowExportVersion::Ipfix => fields.extend([(FLOW_START_MILLISECONDS, 8), (FLOW_END_MILLISECONDS, 8)]),
+            _ => fields.extend([(FLOW_START_SYS_UP_TIME, 4), (FLOW_END_SYS_UP_TIME, 4)]),
+        }
+        fields
+    }
+
+    /// Data record with the fields of the template
+    fn data_record(&self, record: &FlowRecord, ipv6: bool) -> Vec<u8> {
+        let mut data = Vec::new();
+        for (field, _) in self.template_fields(ipv6) {
+            match (field, record.ip_src, record.ip_dst) {
+                (SOURCE_IPV4_ADDRESS, IpAddr::V4(ip_src), _) => data.extend_from_slice(&ip_src.octets()),
+                (DESTINATION_IPV4_ADDRESS, _, IpAddr::V4(ip_dst)) => data.extend_from_slice(&ip_dst.octets()),
+                (SOURCE_IPV6_ADDRESS, IpAddr::V6(ip_src), _) => data.extend_from_slice(&ip_src.octets()),
+                (DESTINATION_IPV6_ADDRESS, _, IpAddr::V6(ip_dst)) => data.extend_from_slice(&ip_dst.octets()),
+                (SOURCE_TRANSPORT_PORT, ..) => data.extend_from_slice(&record.port_src.to_be_bytes()),
+                (DESTINATION_TRANSPORT_PORT, ..) => data.extend_from_slice(&record.port_dst.to_be_bytes()),
+                (PROTOCOL_IDENTIFIER, ..) => data.push(record.protocol),
+                (PACKET_DELTA_COUNT, ..) => data.extend_from_slice(&record.packets.to_be_bytes()),
+                (OCTET_DELTA_COUNT, ..) => data.extend_from_slice(&record.bytes.to_be_bytes()),
+                (FLOW_START_MILLISECONDS, ..) => data.extend_from_slice(&(record.first.timestamp_millis() as u64).to_be_bytes()),
+                (FLOW_END_MILLISECONDS, ..) => data.extend_from_slice(&(record.last.timestamp_millis() as u64).to_be_bytes()),
+                (FLOW_START_SYS_UP_TIME, ..) => data.extend_from_slice(&self.uptime(record.first).to_be_bytes()),
+                (FLOW_END_SYS_UP_TIME, ..) => data.extend_from_slice(&self.uptime(record.last).to_be_bytes()),
+                _ => {}
+            }
+        }
+        data
+    }
+
+    /// Milliseconds since the start of the exporter, wrapping around as the NetFlow uptime
+    fn uptime(&self, time: DateTime<Utc>) -> u32 {
+        (time - self.start_time).num_milliseconds().max(0) as u32
+    }
+}
+
+/// Size padded to a multiple of 4 bytes
+fn padded(size: usize) -> usize {
+    size.div_ceil(4) * 4
+}
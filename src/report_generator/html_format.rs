@@ -0,0 +1,217 @@
+//! # HTML Format
+//!
+//! Report page in a single static file, without external assets: summary totals, breakdowns of the traffic by protocol
+//! and by service, the top hosts and conversations, the protocol hierarchy, the bandwidth over time drawn as an inline SVG
+//! chart, and the table of the flows of the "report" format, sorted by clicking on the column names (a few lines of inline
+//! script).
+//!
+//! Totals, breakdowns, rankings and chart count the traffic of the report interval, the flow table has the totals since
+//! the start of each flow too.
+
+use std::collections::{BTreeMap, HashMap};
+use chrono::{DateTime, SecondsFormat, TimeZone, Utc};
+use crate::report_generator::{FlowStatus, ReportEntry};
+use crate::report_generator::traffic_summary::{ProtocolNode, TOP_N, TrafficSummary};
+
+/// Maximum number of points of the bandwidth chart: the seconds are grouped when the interval is longer
+const CHART_POINTS: i64 = 120;
+/// Size of the bandwidth chart, and of its margins for the labels
+const CHART_WIDTH: f64 = 800.0;
+const CHART_HEIGHT: f64 = 240.0;
+const CHART_MARGIN: f64 = 40.0;
+
+const STYLE: &str = r#"<style>
+body { font-family: -apple-system, "Segoe UI", Helvetica, Arial, sans-serif; margin: 2em; color: #222; }
+h1 { font-size: 1.6em; } h2 { font-size: 1.2em; margin-top: 2em; }
+table { border-collapse: collapse; font-size: 0.85em; }
+th, td { border: 1px solid #ddd; padding: 4px 8px; text-align: left; white-space: nowrap; }
+th { background: #f3f4f6; }
+table.sortable th { cursor: pointer; }
+th[data-order="asc"]::after { content: " \25B2"; } th[data-order="desc"]::after { content: " \25BC"; }
+.totals td:first-child { font-weight: bold; }
+.bar { background: #4f81bd; height: 0.8em; }
+.breakdowns { display: flex; flex-wrap: wrap; gap: 3em; }
+.flows { overflow-x: auto; }
+svg text { font-size: 11px; fill: #555; }
+</style>
+"#;
+
+const SCRIPT: &str = r#"<script>
+document.querySelectorAll("table.sortable th").forEach(function (th) {
+  th.addEventListener("click", function () {
+    var table = th.closest("table"), column = th.cellIndex, ascending = th.dataset.order !== "asc";
+    table.querySelectorAll("th").forEach(function (other) { delete other.dataset.order; });
+    th.dataset.order = ascending ? "asc" : "desc";
+    var number = /^-?\d+(\.\d+)?%?$/;
+    var rows = Array.prototype.slice.call(table.tBodies[0].rows);
+    rows.sort(function (a, b) {
+      var x = a.cells[column].textContent, y = b.cells[column].textContent;
+      var order = number.test(x) && number.test(y) ? parseFloat(x) - parseFloat(y) : x.localeCompare(y);
+      return ascending ? order : -order;
+    });
+    rows.forEach(function (row) { table.tBodies[0].appendChild(row); });
+  });
+});
+</script>
+"#;
+
+/// Page of the report: `columns` are the names of the `fields` of each flow
+pub fn render(columns: &[String], flows: &[(&ReportEntry, Vec<String>)], bandwidth: &BTreeMap<i64, usize>, bad_checksums: usize, generated_at: DateTime<Utc>) -> String {
+    let mut page = String::from("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>Packet Snooper report</title>\n");
+    page.push_str(STYLE);
+    page.push_str("</head>\n<body>\n");
+    page.push_str(format!("<h1>Packet Snooper report</h1>\n<p>Generated at {}</p>\n", generated_at.to_rfc3339_opts(SecondsFormat::Secs, true)).as_str());
+
+    let entries: Vec<&ReportEntry> = flows.iter().map(|(entry, _)| *entry).collect();
+    page.push_str(totals(&entries, bad_checksums).as_str());
+    page.push_str("<h2>Bandwidth</h2>\n");
+    page.push_str(bandwidth_chart(bandwidth).as_str());
+    page.push_str("<div class=\"breakdowns\">\n");
+    page.push_str(breakdown("Protocols", "Protocol", &entries, |entry| entry.l4_protocol.as_str()).as_str());
+    page.push_str(breakdown("Services", "Service", &entries, |entry| entry.upper_service.as_str()).as_str());
+    page.push_str("</div>\n");
+    let traffic_summary = TrafficSummary::new(entries.iter().copied(), TOP_N);
+    page.push_str("<div class=\"breakdowns\">\n");
+    page.push_str(top_hosts(&traffic_summary).as_str());
+    page.push_str(top_conversations(&traffic_summary).as_str());
+    page.push_str("</div>\n");
+    page.push_str(protocol_hierarchy(&traffic_summary.protocol_hierarchy).as_str());
+
+    page.push_str("<h2>Flows</h2>\n<div class=\"flows\">\n<table class=\"sortable\">\n<thead><tr>");
+    columns.iter().for_each(|column| page.push_str(format!("<th>{}</th>", escape(column)).as_str()));
+    page.push_str("</tr></thead>\n<tbody>\n");
+    for (_, fields) in flows {
+        page.push_str("<tr>");
+        fields.iter().for_each(|field| page.push_str(format!("<td>{}</td>", escape(field)).as_str()));
+        page.push_str("</tr>\n");
+    }
+    page.push_str("</tbody>\n</table>\n</div>\n");
+
+    page.push_str(SCRIPT);
+    page.push_str("</body>\n</html>\n");
+    page
+}
+
+/// Summary totals of the report interval
+fn totals(entries: &[&ReportEntry], bad_checksums: usize) -> String {
+    let status_count = |status: FlowStatus| entries.iter().filter(|entry| entry.flow_status == status).count();
+    let rows = [
+        ("Flows", entries.len().to_string()),
+        ("New flows", status_count(FlowStatus::New).to_string()),
+        ("Ended flows", status_count(FlowStatus::Ended).to_string()),
+        ("Packets", entries.iter().map(|entry| entry.interval_packets).sum::<usize>().to_string()),
+        ("Bytes", format_bytes(entries.iter().map(|entry| entry.interval_bytes).sum::<usize>() as f64)),
+        ("Packets with bad checksum", bad_checksums.to_string()),
+    ];
+    let mut totals = String::from("<h2>Totals</h2>\n<table class=\"totals\">\n");
+    rows.iter().for_each(|(name, value)| totals.push_str(format!("<tr><td>{}</td><td>{}</td></tr>\n", name, value).as_str()));
+    totals.push_str("</table>\n");
+    totals
+}
+
+/// Breakdown of the flows, packets and bytes of the report interval by the value of `key`, with the share of the bytes
+fn breakdown(title: &str, name: &str, entries: &[&ReportEntry], key: impl Fn(&ReportEntry) -> &str) -> String {
+    let mut groups: HashMap<&str, (usize, usize, usize)> = HashMap::new();
+    for entry in entries {
+        let group = groups.entry(key(entry)).or_default();
+        group.0 += 1;
+        group.1 += entry.interval_packets;
+        group.2 += entry.interval_bytes;
+    }
+    let total_bytes: usize = groups.values().map(|(_, _, bytes)| bytes).sum();
+    let mut groups: Vec<(&str, (usize, usize, usize))> = groups.into_iter().collect();
+    groups.sort_by(|(name, (_, _, bytes)), (other_name, (_, _, other_bytes))| other_bytes.cmp(bytes).then(name.cmp(other_name)));
+
+    let mut breakdown = format!("<div>\n<h2>{}</h2>\n<table class=\"sortable\">\n<thead><tr><th>{}</th><th>Flows</th><th>Packets</th><th>Bytes</th><th>Share</th><th></th></tr></thead>\n<tbody>\n", title, name);
+    for (name, (flows, packets, bytes)) in groups {
+        let share = if total_bytes == 0 { 0.0 } else { bytes as f64 * 100.0 / total_bytes as f64 };
+        breakdown.push_str(format!("<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{:.1}%</td><td style=\"width: 120px\"><div class=\"bar\" style=\"width: {:.1}%\"></div></td></tr>\n",
+            escape(name), flows, packets, bytes, share, share).as_str());
+    }
+    breakdown.push_str("</tbody>\n</table>\n</div>\n");
+    breakdown
+}
+
+/// Top hosts by bytes sent and received in the report interval
+fn top_hosts(traffic_summary: &TrafficSummary) -> String {
+    let mut top_hosts = String::from("<div>\n<h2>Top hosts</h2>\n<table class=\"sortable\">\n<thead><tr><th>Host</th><th>Bytes sent</th><th>Bytes received</th><th>Packets sent</th><th>Packets received</th></tr></thead>\n<tbody>\n");
+    for host in traffic_summary.hosts.iter() {
+        top_hosts.push_str(format!("<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            host.address, host.sent.bytes, host.received.bytes, host.sent.packets, host.received.packets).as_str());
+    }
+    top_hosts.push_str("</tbody>\n</table>\n</div>\n");
+    top_hosts
+}
+
+/// Top conversations between two hosts by bytes in both directions in the report interval
+fn top_conversations(traffic_summary: &TrafficSummary) -> String {
+    let mut top_conversations = String::from("<div>\n<h2>Top conversations</h2>\n<table class=\"sortable\">\n<thead><tr><th>Host A</th><th>Host B</th><th>Flows</th><th>Bytes A&rarr;B</th><th>Bytes B&rarr;A</th><th>Packets A&rarr;B</th><th>Packets B&rarr;A</th></tr></thead>\n<tbody>\n");
+    for conversation in traffic_summary.conversations.iter() {
+        top_conversations.push_str(format!("<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            conversation.address_a, conversation.address_b, conversation.flows, conversation.a_to_b.bytes, conversation.b_to_a.bytes,
+            conversation.a_to_b.packets, conversation.b_to_a.packets).as_str());
+    }
+    top_conversations.push_str("</tbody>\n</table>\n</div>\n");
+    top_conversations
+}
+
+/// Protocol hierarchy of the report interval, each protocol indented under the one carrying it
+fn protocol_hierarchy(root: &ProtocolNode) -> String {
+    let mut hierarchy = String::from("<h2>Protocol hierarchy</h2>\n<table>\n<thead><tr><th>Protocol</th><th>Packets</th><th>% Packets</th><th>Bytes</th><th>% Bytes</th><th></th></tr></thead>\n<tbody>\n");
+    for (depth, protocol) in root.rows() {
+        let (packets_share, bytes_share) = protocol.traffic.share(&root.traffic);
+        hierarchy.push_str(format!("<tr><td style=\"padding-left: {}em\">{}</td><td>{}</td><td>{:.1}%</td><td>{}</td><td>{:.1}%</td><td style=\"width: 120px\"><div class=\"bar\" style=\"width: {:.1}%\"></div></td></tr>\n",
+            0.5 + 1.5 * depth as f64, escape(&protocol.name), protocol.traffic.packets, packets_share, protocol.traffic.bytes, bytes_share, bytes_share).as_str());
+    }
+    hierarchy.push_str("</tbody>\n</table>\n");
+    hierarchy
+}
+
+/// Chart of the bytes per second received in the report interval, `bandwidth` having the bytes of each second
+fn bandwidth_chart(bandwidth: &BTreeMap<i64, usize>) -> String {
+    let (first, last) = match (bandwidth.keys().next(), bandwidth.keys().next_back()) {
+        (Some(first), Some(last)) => (*first, *last),
+        _ => return String::from("<p>No packets in the report interval.</p>\n"),
+    };
+    let seconds_per_point = ((last - first + 1) + CHART_POINTS - 1) / CHART_POINTS;
+    let points = (last - first) / seconds_per_point + 1;
+    let mut rates = vec![0.0; points as usize];
+    for (second, bytes) in bandwidth {
+        rates[((second - first) / seconds_per_point) as usize] += *bytes as f64 / seconds_per_point as f64;
+    }
+    let max_rate = rates.iter().cloned().fold(0.0, f64::max).max(1.0);
+
+    let (left, top) = (CHART_MARGIN * 2.0, CHART_MARGIN / 2.0);
+    let (width, height) = (CHART_WIDTH - left - CHART_MARGIN / 2.0, CHART_HEIGHT - top - CHART_MARGIN);
+    let x = |point: usize| left + if points > 1 { point as f64 * width / (points - 1) as f64 } else { width / 2.0 };
+    let y = |rate: f64| top + height - rate * height / max_rate;
+    let line: Vec<String> = rates.iter().enumerate().map(|(point, rate)| format!("{:.1},{:.1}", x(point), y(*rate))).collect();
+    let time = |second: i64| Utc.timestamp_opt(second, 0).single().map_or(String::new(), |time| time.format("%H:%M:%S").to_string());
+
+    let mut chart = format!("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\" role=\"img\" aria-label=\"Bandwidth over time\">\n", CHART_WIDTH, CHART_HEIGHT);
+    chart.push_str(format!("<line x1=\"{0}\" y1=\"{1}\" x2=\"{0}\" y2=\"{2}\" stroke=\"#999\"/>\n", left, top, top + height).as_str());
+    chart.push_str(format!("<line x1=\"{0}\" y1=\"{2}\" x2=\"{1}\" y2=\"{2}\" stroke=\"#999\"/>\n", left, left + width, top + height).as_str());
+    chart.push_str(format!("<polygon points=\"{:.1},{:.1} {} {:.1},{:.1}\" fill=\"#4f81bd\" fill-opacity=\"0.2\"/>\n", x(0), top + height, line.join(" "), x(rates.len() - 1), top + height).as_str());
+    chart.push_str(format!("<polyline points=\"{}\" fill=\"none\" stroke=\"#4f81bd\" stroke-width=\"2\"/>\n", line.join(" ")).as_str());
+    chart.push_str(format!("<text x=\"{}\" y=\"{}\" text-anchor=\"end\">{}/s</text>\n", left - 6.0, top + 4.0, format_bytes(max_rate)).as_str());
+    chart.push_str(format!("<text x=\"{}\" y=\"{}\" text-anchor=\"end\">0</text>\n", left - 6.0, top + height).as_str());
+    chart.push_str(format!("<text x=\"{}\" y=\"{}\">{}</text>\n", left, top + height + 16.0, time(first)).as_str());
+    chart.push_str(format!("<text x=\"{}\" y=\"{}\" text-anchor=\"end\">{}</text>\n", left + width, top + height + 16.0, time(last)).as_str());
+    chart.push_str("</svg>\n");
+    chart
+}
+
+/// Bytes with a binary unit prefix
+fn format_bytes(bytes: f64) -> String {
+    let units = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let exponent = (0..units.len()).rev().find(|exponent| bytes >= 1024f64.powi(*exponent as i32)).unwrap_or(0);
+    match exponent {
+        0 => format!("{:.0} B", bytes),
+        _ => format!("{:.1} {}", bytes / 1024f64.powi(exponent as i32), units[exponent]),
+    }
+}
+
+/// Text escaped for the content of an element
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
@@ -0,0 +1,308 @@
+//! # JSON Format
+//!
+//! Versioned schema of the machine-readable formats, independent of the internal representation of the packets.
+//! Timestamps are RFC 3339 strings in UTC, ports, sizes and counters are numbers, and the values not available are `null`.
+//! Fields are only added within a schema version: a change of type or meaning increases `schema_version`.
+//!
+//! The "json" format writes a document for each report, with the flows of the interval (the summaries of the "report"
+//! format are not included):
+//! ```
+//! {
+//!   "schema_version": 1,
+//!   "generated_at": "2022-08-11T21:33:50.125081Z",
+//!   "flow_aggregation": "unidirectional",            // or "bidirectional": `src` is the client, `dst` the server
+//!   "bad_checksums": 0,                              // packets with a bad checksum in the interval
+//!   "flows": [{
+//!     "src": { "address": "192.168.1.119", "port": 39322 },  // address: IP, MAC without IP, null for the OTHER bucket
+//!     "dst": { "address": "140.82.121.3", "port": 443 },     // port: 0 without TCP/UDP
+//!     "protocol": "TCP",
+//!     "service": "HTTPS",                            // null without TCP/UDP
+//!     "bytes": 1849, "packets": 11,                  // since the start of the flow
+//!     "interval_bytes": 1849, "interval_packets": 11,
+//!     "first_seen": "2022-08-11T21:33:35.232940Z", "last_seen": "2022-08-11T21:33:36.096701Z",
+//!     "status": "ended",                             // "new", "continuing" or "ended"
+//!     "size": { "min": 66, "max": 583, "mean": 168.09 },
+//!     "inter_arrival_ms": { "min": 0.027, "max": 412.905, "mean": 86.376 },  // null with a single packet
+//!     "tcp": { "state": "CLOSED", "initiator": "src", "handshake_completed": true, "reset_by": null },  // null without TCP
+//!     "alpn": "h2",                                  // TLS: selected by the server, QUIC: offered by the client
+//!     "server_name": "github.com",                   // TLS/QUIC server name indication
+//!     "directions": null                             // bidirectional: { "client_to_server": { "bytes", "packets" }, "server_to_client" }
+//!   }]
+//! }
+//! ```
+//!
+//! The "ndjson" format writes a line for each packet, with the layers decoded (the layers missing are `null`):
+//! ```
+//! {"schema_version":1,"timestamp":"2022-08-13T09:01:24.713816Z","size":66,"bad_checksum":false,
+//!  "ethernet":{"src":"e0:b9:e5:30:ef:98","dst":"74:e5:f9:16:ee:9b","ether_type":"IPv4"},
+//!  "ip":{"version":4,"src":"3.93.161.174","dst":"192.168.1.119","ttl":233,"protocol":6,"protocol_name":"TCP"},
+//!  "tcp":{"src_port":443,"dst_port":55070,"sequence_number":2579486785,"ack_number":518000164,"flags":["ACK"],"window_size":114,"payload_length":0},
+//!  "udp":null,"icmp":null,"service":{"name":"HTTPS","confidence":50}}
+//! ```
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::Serialize;
+use crate::EthernetPacket;
+use crate::network_components::layer_2::ethernet_packet::{EtherType, NO_SERVICE};
+use crate::network_components::layer_4::icmp_packet::{IcmpPacket, IcmpVersion};
+use crate::network_components::layer_3::ip_protocol_name;
+use crate::report_generator::{FlowAggregation, ReportEntry};
+
+/// Version of the schema, increased with any change of type or meaning of a field
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize)]
+/// `JSON Report` with the flows of a report interval
+pub struct JsonReport {
+    pub schema_version: u32,
+    pub generated_at: String,
+    pub flow_aggregation: &'static str,
+    pub bad_checksums: usize,
+    pub flows: Vec<JsonFlow>,
+}
+
+impl JsonReport {
+    pub fn new<'a>(entries: impl Iterator<Item = &'a ReportEntry>, flow_aggregation: FlowAggregation, bad_checksums: usize, generated_at: DateTime<Utc>) -> Self {
+        JsonReport {
+            schema_version: SCHEMA_VERSION,
+            generated_at: timestamp(generated_at),
+            flow_aggregation: match flow_aggregation {
+                FlowAggregation::Unidirectional => "unidirectional",
+                FlowAggregation::Bidirectional => "bidirectional",
+            },
+            bad_checksums,
+            flows: entries.map(JsonFlow::new).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+/// Endpoint of a flow
+pub struct JsonEndpoint {
+    pub address: Option<String>,
+    pub port: u16,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonStatistics {
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+}
+
+#[derive(Debug, Serialize)]
+/// State of the TCP connection of a flow, the endpoints being "src" or "dst"
+pub struct JsonTcpConnection {
+    pub state: String,
+    pub initiator: Option<&'static str>,
+    pub handshake_completed: bool,
+    pub reset_by: Option<&'static str>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonCounters {
+    pub bytes: usize,
+    pub packets: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonDirections {
+    pub client_to_server: JsonCounters,
+    pub server_to_client: JsonCounters,
+}
+
+#[derive(Debug, Serialize)]
+/// `JSON Flow`: a report entry
+pub struct JsonFlow {
+    pub src: JsonEndpoint,
+    pub dst: JsonEndpoint,
+    pub protocol: String,
+    pub service: Option<String>,
+    pub bytes: usize,
+    pub packets: usize,
+    pub interval_bytes: usize,
+    pub interval_packets: usize,
+    pub first_seen: String,
+    pub last_seen: String,
+    pub status: String,
+    pub size: JsonStatistics,
+    pub inter_arrival_ms: Option<JsonStatistics>,
+    pub tcp: Option<JsonTcpConnection>,
+    pub alpn: Option<String>,
+    pub server_name: Option<String>,
+    pub directions: Option<JsonDirections>,
+}
+
+impl JsonFlow {
+    fn new(entry: &ReportEntry) -> Self {
+        let milliseconds = |duration: Option<chrono::Duration>| duration.map(|duration| duration.num_microseconds().unwrap_or(i64::MAX) as f64 / 1000.0);
+        let side = |endpoint| Some(entry.side(endpoint)).filter(|side| *side != NO_SERVICE);
+        JsonFlow {
+            src: JsonEndpoint { address: Some(entry.ip_src.clone()).filter(|address| address != NO_SERVICE), port: entry.port_src },
+            dst: JsonEndpoint { address: Some(entry.ip_dst.clone()).filter(|address| address != NO_SERVICE), port: entry.port_dst },
+            protocol: entry.l4_protocol.clone(),
+            service: Some(entry.upper_service.clone()).filter(|service| service != NO_SERVICE),
+            bytes: entry.num_bytes,
+            packets: entry.num_packets,
+            interval_bytes: entry.interval_bytes,
+            interval_packets: entry.interval_packets,
+            first_seen: timestamp(entry.timestamp_init),
+            last_seen: timestamp(entry.timestamp_final),
+            status: entry.flow_status.to_string(),
+            size: JsonStatistics { min: entry.min_size as f64, max: entry.max_size as f64, mean: entry.mean_size() },
+            inter_arrival_ms: match (milliseconds(entry.min_inter_arrival), milliseconds(entry.max_inter_arrival), milliseconds(entry.mean_inter_arrival())) {
+                (Some(min), Some(max), Some(mean)) => Some(JsonStatistics { min, max, mean }),
+                _ => None,
+            },
+            tcp: entry.tcp_connection.as_ref().map(|connection| JsonTcpConnection {
+                state: connection.state.to_string(),
+                initiator: side(connection.initiator),
+                handshake_completed: connection.handshake_completed,
+                reset_by: side(connection.reset_by),
+            }),
+            alpn: entry.alpn.clone(),
+            server_name: entry.server_name.clone(),
+            directions: entry.directions.as_ref().map(|(client_to_server, server_to_client)| JsonDirections {
+                client_to_server: JsonCounters { bytes: client_to_server.num_bytes, packets: client_to_server.num_packets },
+                server_to_client: JsonCounters { bytes: server_to_client.num_bytes, packets: server_to_client.num_packets },
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonEthernet {
+    pub src: String,
+    pub dst: String,
+    /// "IPv4", "IPv6", "ARP", "802.3", null if unknown
+    pub ether_type: Option<&'static str>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonIp {
+    pub version: u8,
+    pub src: String,
+    pub dst: String,
+    /// Time to live (IPv4) or hop limit (IPv6)
+    pub ttl: u8,
+    /// Protocol number of the payload, after the IPv6 Hop by Hop extension header
+    pub protocol: u8,
+    pub protocol_name: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonTcp {
+    pub src_port: u16,
+    pub dst_port: u16,
+    pub sequence_number: u32,
+    pub ack_number: u32,
+    pub flags: Vec<String>,
+    pub window_size: u16,
+    pub payload_length: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonUdp {
+    pub src_port: u16,
+    pub dst_port: u16,
+    pub length: u16,
+    pub payload_length: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonIcmp {
+    #[serde(rename = "type")]
+    pub icmp_type: u8,
+    pub code: u8,
+    pub type_name: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+/// Upper layer service of a TCP/UDP packet, with the confidence of the identification from 0 to 100
+pub struct JsonService {
+    pub name: String,
+    pub confidence: u8,
+}
+
+#[derive(Debug, Serialize)]
+/// `JSON Packet` with the layers decoded
+pub struct JsonPacket {
+    pub schema_version: u32,
+    pub timestamp: String,
+    pub size: usize,
+    pub bad_checksum: bool,
+    pub ethernet: JsonEthernet,
+    pub ip: Option<JsonIp>,
+    pub tcp: Option<JsonTcp>,
+    pub udp: Option<JsonUdp>,
+    pub icmp: Option<JsonIcmp>,
+    pub service: Option<JsonService>,
+}
+
+impl JsonPacket {
+    pub fn new(ether_packet: &EthernetPacket) -> Self {
+        let report_data = ether_packet.decoded_report_data();
+        let ip_protocol = ether_packet.ip_protocol();
+        let tcp = ether_packet.tcp_packet().map(|(_, _, tcp_packet)| JsonTcp {
+            src_port: tcp_packet.src_port,
+            dst_port: tcp_packet.dst_port,
+            sequence_number: tcp_packet.sequence_number,
+            ack_number: tcp_packet.ack_number,
+            flags: tcp_packet.tcp_flags().names(),
+            window_size: tcp_packet.window_size,
+            payload_length: tcp_packet.payload.len(),
+        });
+        let udp = ether_packet.udp_packet().map(|(_, _, udp_packet)| JsonUdp {
+            src_port: udp_packet.src_port,
+            dst_port: udp_packet.dst_port,
+            length: udp_packet.length,
+            payload_length: udp_packet.payload.len(),
+        });
+        let icmp = ip_protocol.as_ref().and_then(|(_, _, protocol, segment)| {
+            let icmp_version = match protocol {
+                1 => IcmpVersion::ICMPv4,
+                58 => IcmpVersion::ICMPv6,
+                _ => return None,
+            };
+            let icmp_packet = IcmpPacket::new(segment, icmp_version)?;
+            Some(JsonIcmp { icmp_type: icmp_packet.icmp_type, code: icmp_packet.code, type_name: icmp_packet.type_name() })
+        });
+        let service = match tcp.is_some() || udp.is_some() {
+            true => Some(JsonService { name: report_data.upper_service.clone(), confidence: report_data.service_confidence }),
+            false => None,
+        };
+        JsonPacket {
+            schema_version: SCHEMA_VERSION,
+            timestamp: timestamp(ether_packet.timestamp_recv),
+            size: ether_packet.size,
+            bad_checksum: report_data.bad_checksum,
+            ethernet: JsonEthernet {
+                src: ether_packet.mac_addr_src.to_string(),
+                dst: ether_packet.mac_addr_dst.to_string(),
+                ether_type: ether_packet.ether_type.map(|ether_type| match ether_type {
+                    EtherType::IPV4 => "IPv4",
+                    EtherType::IPV6 => "IPv6",
+                    EtherType::ARP => "ARP",
+                    EtherType::Ethernet802_3 => "802.3",
+                }),
+            },
+            ip: ip_protocol.map(|(ip_addr_src, ip_addr_dst, protocol, _)| JsonIp {
+                version: if ip_addr_src.is_ipv4() { 4 } else { 6 },
+                src: ip_addr_src.to_string(),
+                dst: ip_addr_dst.to_string(),
+                ttl: if ip_addr_src.is_ipv4() { ether_packet.payload[8] } else { ether_packet.payload[7] },
+                protocol,
+                protocol_name: ip_protocol_name(protocol),
+            }),
+            tcp,
+            udp,
+            icmp,
+            service,
+        }
+    }
+}
+
+/// Timestamp in the RFC 3339 format, in UTC with microseconds
+fn timestamp(timestamp: DateTime<Utc>) -> String {
+    timestamp.to_rfc3339_opts(SecondsFormat::Micros, true)
+}
@@ -3,23 +3,58 @@
 //! Module to handle `periodic report generation` about the traffic analyzed.
 //!
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, hash_map};
 use std::error::Error;
 use std::fmt::{Display, Formatter};
 use std::fs::OpenOptions;
 use std::io::{Write};
+use std::net::{IpAddr, SocketAddr, UdpSocket};
 use std::path::{PathBuf};
 use std::sync::{Arc, Condvar, Mutex};
 use crate::{ConfigOptions, EthernetPacket};
+use crate::network_components::checksum::ChecksumStatus;
+use crate::network_components::layer_4::tcp_connection::{TcpConnection, TcpConnectionTracker};
+use crate::network_components::layer_4::tcp_reassembly::{Endpoint, StreamDissector, StreamEvent, TcpReassembler};
+use crate::network_components::services_upper_layers::dhcp::{DhcpLeases, DhcpMessage, Dhcpv6Message};
+use crate::network_components::services_upper_layers::dns::{DnsMessage, DnsSummary};
+use crate::network_components::services_upper_layers::http::HttpTransactions;
+use crate::network_components::services_upper_layers::quic::QuicSessions;
+use crate::network_components::services_upper_layers::tls::TlsSessions;
+use crate::network_components::services_upper_layers::upper_layer_services::UpperLayerService;
+use crate::report_generator::flow_collector::{CollectedFlow, FlowCollector};
+use crate::report_generator::flow_export::FlowExporter;
+use crate::report_generator::json_format::JsonReport;
+use crate::report_generator::packet_filter::PacketFilter;
+use crate::report_generator::traffic_summary::{TOP_N, TrafficSummary};
 use std::time::{Duration};
 use std::thread;
 use std::thread::JoinHandle;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, SecondsFormat, Utc};
 use serde::{Serialize, Deserialize};
 
+pub mod csv_format;
+pub mod flow_collector;
+pub mod flow_export;
+pub mod html_format;
+pub mod json_format;
+pub mod packet_filter;
+pub mod traffic_summary;
+
 #[cfg(test)]
 mod tests;
 
+/// Ports below this value are well-known service ports, used to tell the server apart when the client is unknown
+const WELL_KNOWN_PORTS: u16 = 1024;
+
+/// Seconds of inactivity after which a TCP connection is no longer tracked nor reassembled
+const TCP_IDLE_TIMEOUT_S: i64 = 300;
+
+/// Time after which the collector thread stops waiting for a datagram, to tell if it has to end
+const COLLECTOR_READ_TIMEOUT_MS: u64 = 250;
+
+/// Widths of the columns of the "report" format
+const REPORT_COLUMN_WIDTHS: [usize; 29] = [25, 25, 9, 9, 15, 15, 15, 15, 35, 35, 10, 15, 15, 9, 9, 9, 12, 12, 12, 12, 9, 9, 9, 15, 30, 15, 15, 15, 15];
+
 #[derive(Debug, PartialEq)]
 /// Report Generator custom Error type `RGError`.
 pub struct RGError {
@@ -68,21 +103,103 @@ impl From<std::io::Error> for RGError {
 /// HTTPS   : Protocol details unknown
 ///  > [5316a3ef6a27bfbabfc244c649d4ccace446e75d84ccd4375195135b63bb3341d7393688672704bce19900ad6a3364b163b535a7a7c2d65d03d7f3a43ebdc6d107c92ba82c638eab45f8e9...]
 /// ```
-/// - Report
+/// - Report: one row for each flow, kept across report intervals until it ends (idle or active timeout, TCP FIN/RST). The rows
+///   are sorted by the bytes of the report interval, and followed by the top hosts, conversations and services and by the
+///   protocol hierarchy (see [`traffic_summary`])
+/// ```
+/// IP src          | IP dst          | Port src  | Port dst  | L4 Protocol     | Upper Service   | Num. Bytes      | Num. Packets    | Initial Timestamp                 | Final Timestamp                   | Flow       | Interval Bytes  | Interval Pkts   | Min Size  | Max Size  | Mean Size | Min IAT ms   | Max IAT ms   | Mean IAT ms  | TCP State    | Initiator | Handshake | Reset by  | ALPN            | Server Name
+/// 192.168.1.119   | 142.250.184.46  | 46374     | 443       | UDP             | HTTPS           | 5906            | 9               | 2022-08-11 21:33:46.756617241 UTC | 2022-08-11 21:33:49.164702665 UTC | new        | 5906            | 9               | 74        | 1292      | 656.2     | 0.052        | 1204.311     | 301.011      | -            | -         | -         | -         | -               | -
+/// 192.168.1.119   | 142.250.184.46  | 40589     | 443       | UDP             | HTTPS           | 73653           | 102             | 2022-08-11 21:32:49.964760509 UTC | 2022-08-11 21:33:50.125081873 UTC | continuing | 3653            | 6               | 66        | 1292      | 722.1     | 0.031        | 5012.874     | 595.645      | -            | -         | -         | -         | -               | -
+/// 192.168.1.119   | 140.82.121.3    | 39322     | 443       | TCP             | HTTPS           | 1849            | 11              | 2022-08-11 21:33:35.232940691 UTC | 2022-08-11 21:33:36.096701586 UTC | ended      | 1849            | 11              | 66        | 583       | 168.1     | 0.027        | 412.905      | 86.376       | CLOSED       | src       | yes       | -         | h2              | github.com
+/// ```
+/// Packets other than TCP/UDP are in the report too: other IP protocols are keyed by IP addresses and protocol (ports 0),
+/// frames without IP by MAC addresses and EtherType, and the packets that can't be decoded share an `OTHER` row
 /// ```
-/// IP src          | IP dst          | Port src  | Port dst  | L4 Protocol     | Upper Service   | Num. Bytes      | Initial Timestamp                 | Final Timestamp
-/// 192.168.1.119   | 142.250.184.46  | 46374     | 443       | UDP             | HTTPS           | 5906            | 2022-08-11 21:33:46.756617241 UTC | 2022-08-11 21:33:49.164702665 UTC
-/// 192.168.1.119   | 142.250.184.46  | 40589     | 443       | UDP             | HTTPS           | 3653            | 2022-08-11 21:33:49.964760509 UTC | 2022-08-11 21:33:50.125081873 UTC
-/// 192.168.1.119   | 140.82.121.3    | 39322     | 443       | TCP             | HTTPS           | 1849            | 2022-08-11 21:33:35.232940691 UTC | 2022-08-11 21:33:36.096701586 UTC
+/// 192.168.1.119   | 192.168.1.1     | 0         | 0         | ICMP            | -               | 196             | 2               | ...
+/// e0:b9:e5:30:ef:98 | ff:ff:ff:ff:ff:ff | 0       | 0         | ARP             | -               | 42              | 1               | ...
+/// -               | -               | 0         | 0         | OTHER           | -               | 16              | 1               | ...
 /// ```
+/// - Report with bidirectional flow aggregation: both directions in the same row, from the client to the server
+/// ```
+/// IP client       | IP server       | Port client | Port server | ... | ALPN | Server Name | Bytes c->s      | Bytes s->c      | Packets c->s    | Packets s->c
+/// 192.168.1.119   | 140.82.121.3    | 39322       | 443         | ... | h2   | github.com  | 617             | 1232            | 9               | 7
+/// ```
+/// - Csv: the flows of the "report" format as comma-separated values (RFC 4180), with a header line and without the summaries
+/// ```
+/// IP src,IP dst,Port src,Port dst,L4 Protocol,Upper Service,Num. Bytes,Num. Packets,Initial Timestamp,Final Timestamp,Flow,...
+/// 192.168.1.119,140.82.121.3,39322,443,TCP,HTTPS,1849,11,2022-08-11T21:33:35.232940Z,2022-08-11T21:33:36.096701Z,ended,...
+/// ```
+/// - Json: the flows of the "report" format as a JSON document, and Ndjson: a JSON line for each packet, with the layers
+///   decoded. Both follow the versioned schema of [`json_format`]
+/// ```
+/// {"schema_version":1,"generated_at":"2022-08-11T21:33:50.125081Z","flow_aggregation":"unidirectional","bad_checksums":0,"flows":[...]}
+/// {"schema_version":1,"timestamp":"2022-08-13T09:01:24.713816Z","size":66,"bad_checksum":false,"ethernet":{...},"ip":{...},"tcp":{...},...}
+/// ```
+/// - Html: a page with the totals, the breakdowns by protocol and by service, the top hosts and conversations, the protocol
+///   hierarchy, the bandwidth chart and the sortable table of the flows, in a single file (see [`html_format`])
 ///
 pub enum ReportFormat {
     /// Simple analysis of each packet captured.
     Raw,
     /// In depth analysis of each packet captured.
     Verbose,
-    /// Brief summary collapsed for IPs, ports, L4 protocol. Initial and final timestamps of packets belonging to the corresponding class are available.
+    /// Brief summary collapsed for IPs, ports, L4 protocol. Initial and final timestamps of packets belonging to the corresponding class are available,
+    /// together with the state of TCP connections at the end of the interval. Flows are kept across report intervals until they end.
     Report,
+    /// Flows of the "report" format, as comma-separated values to be loaded into a spreadsheet.
+    Csv,
+    /// Flows of the "report" format, as a JSON document.
+    Json,
+    /// Each packet captured, as a line of JSON.
+    Ndjson,
+    /// Flows of the "report" format, with totals, breakdowns and a bandwidth chart, as a static HTML page.
+    Html,
+}
+
+impl ReportFormat {
+    /// Whether the packets are aggregated into flows ("report", "csv", "json" and "html" formats) instead of being dumped one by one
+    pub fn aggregates_flows(&self) -> bool {
+        matches!(self, ReportFormat::Report | ReportFormat::Csv | ReportFormat::Json | ReportFormat::Html)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+/// `Flow Aggregation` of the packets into the rows of the "report" format
+pub enum FlowAggregation {
+    /// One row for each direction (IP src, IP dst, port src, port dst, L4 protocol).
+    Unidirectional,
+    /// One row for each conversation: both directions are collapsed in the same row, oriented from the client to the server.
+    Bidirectional,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+/// `Flow Status` of a report entry at the end of a report interval
+pub enum FlowStatus {
+    /// Flow started in the report interval.
+    New,
+    /// Flow started in a previous report interval and still active.
+    Continuing,
+    /// Flow ended in the report interval (idle or active timeout, TCP FIN/RST).
+    Ended,
+}
+
+impl Display for FlowStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FlowStatus::New => write!(f, "new"),
+            FlowStatus::Continuing => write!(f, "continuing"),
+            FlowStatus::Ended => write!(f, "ended"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+/// `Direction Counters` of a bidirectional flow
+pub struct DirectionCounters {
+    /// Bytes sent
+    pub num_bytes: usize,
+    /// Packets sent
+    pub num_packets: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -100,10 +217,19 @@ pub struct ReportDataInfo {
     pub l4_protocol: String,
     /// Upper layer service (HTTP/...)
     pub upper_service: String,
+    /// Confidence of the identification of the upper layer service, from 0 (unknown) to 100 (certain)
+    #[serde(default)]
+    pub service_confidence: u8,
     /// Size in bytes
     pub num_bytes: usize,
     /// Timestamp of received packet
     pub timestamp_recv: DateTime<Utc>,
+    /// Names of the TCP flags set (SYN/ACK/...). Empty for other protocols
+    #[serde(default)]
+    pub tcp_flags: Vec<String>,
+    /// Packet with a wrong IPv4 header or TCP/UDP/ICMP checksum
+    #[serde(default)]
+    pub bad_checksum: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -121,17 +247,188 @@ pub struct ReportEntry {
     pub l4_protocol: String,
     /// Upper layer service (HTTP/...)
     pub upper_service: String,
-    /// Number of bytes received
+    /// Number of bytes received since the start of the flow
     pub num_bytes: usize,
+    /// Number of packets received since the start of the flow
+    pub num_packets: usize,
+    /// Number of bytes received in the current report interval
+    pub interval_bytes: usize,
+    /// Number of packets received in the current report interval
+    pub interval_packets: usize,
+    /// Size in bytes of the smallest packet
+    pub min_size: usize,
+    /// Size in bytes of the largest packet
+    pub max_size: usize,
+    /// Shortest time between two consecutive packets (None with a single packet)
+    pub min_inter_arrival: Option<chrono::Duration>,
+    /// Longest time between two consecutive packets (None with a single packet)
+    pub max_inter_arrival: Option<chrono::Duration>,
     /// Timestamp of the first packet received belonging in this class
     pub timestamp_init: DateTime<Utc>,
     /// Timestamp of the last packet received belonging in this class
     pub timestamp_final: DateTime<Utc>,
+    /// Timestamp of the first packet received in the current report interval
+    pub interval_timestamp_init: DateTime<Utc>,
+    /// Timestamp of the last packet received in the current report interval
+    pub interval_timestamp_final: DateTime<Utc>,
+    /// Flow started in the current report interval, started before or ended
+    pub flow_status: FlowStatus,
+    /// State of the TCP connection at the end of the report interval (None for other protocols)
+    pub tcp_connection: Option<TcpConnection>,
+    /// Client to server and server to client counters (None with unidirectional flow aggregation)
+    pub directions: Option<(DirectionCounters, DirectionCounters)>,
+    /// Client to server and server to client counters of the current report interval (None with unidirectional flow aggregation)
+    pub interval_directions: Option<(DirectionCounters, DirectionCounters)>,
+    /// Application protocol of the connection (ALPN): selected by the server over TCP, offered by the client over QUIC
+    pub alpn: Option<String>,
+    /// Server name requested in the TLS ClientHello (SNI), over TCP or QUIC
+    pub server_name: Option<String>,
+}
+
+impl ReportEntry {
+    /// Source and destination endpoints of the entry (None if the IP addresses can't be parsed)
+    pub fn endpoints(&self) -> Option<(Endpoint, Endpoint)> {
+        let ip_addr_src = self.ip_src.parse::<IpAddr>().ok()?;
+        let ip_addr_dst = self.ip_dst.parse::<IpAddr>().ok()?;
+        Some((Endpoint { ip_addr: ip_addr_src, port: self.port_src }, Endpoint { ip_addr: ip_addr_dst, port: self.port_dst }))
+    }
+
+    /// Mean size in bytes of the packets
+    pub fn mean_size(&self) -> f64 {
+        if self.num_packets == 0 { 0.0 } else { self.num_bytes as f64 / self.num_packets as f64 }
+    }
+
+    /// Mean time between two consecutive packets (None with a single packet)
+    pub fn mean_inter_arrival(&self) -> Option<chrono::Duration> {
+        if self.num_packets < 2 { return None; }
+        Some((self.timestamp_final - self.timestamp_init) / (self.num_packets as i32 - 1))
+    }
+
+    /// `Update` the counters and the statistics with a new packet
+    fn update(&mut self, num_bytes: usize, timestamp_recv: DateTime<Utc>) {
+        if self.num_packets == 0 {
+            self.min_size = num_bytes;
+            self.max_size = num_bytes;
+        } else {
+            self.min_size = self.min_size.min(num_bytes);
+            self.max_size = self.max_size.max(num_bytes);
+            let inter_arrival = (timestamp_recv - self.timestamp_final).max(chrono::Duration::zero());
+            self.min_inter_arrival = Some(self.min_inter_arrival.map_or(inter_arrival, |min| min.min(inter_arrival)));
+            self.max_inter_arrival = Some(self.max_inter_arrival.map_or(inter_arrival, |max| max.max(inter_arrival)));
+        }
+        if self.interval_packets == 0 {
+            self.interval_timestamp_init = timestamp_recv;
+        }
+        self.num_bytes += num_bytes;
+        self.num_packets += 1;
+        self.interval_bytes += num_bytes;
+        self.interval_packets += 1;
+        self.timestamp_final = timestamp_recv;
+        self.interval_timestamp_final = timestamp_recv;
+    }
+
+    /// `Merge` the counters of a flow record collected from an exporter: its packets have the mean size, their times
+    /// between the first and the last one are unknown
+    fn merge(&mut self, num_bytes: usize, num_packets: usize, timestamp_init: DateTime<Utc>, timestamp_final: DateTime<Utc>) {
+        let mean_size = num_bytes / num_packets.max(1);
+        if self.num_packets == 0 {
+            self.min_size = mean_size;
+            self.max_size = mean_size;
+        } else {
+            self.min_size = self.min_size.min(mean_size);
+            self.max_size = self.max_size.max(mean_size);
+        }
+        if self.interval_packets == 0 {
+            (self.interval_timestamp_init, self.interval_timestamp_final) = (timestamp_init, timestamp_final);
+        } else {
+            self.interval_timestamp_init = self.interval_timestamp_init.min(timestamp_init);
+            self.interval_timestamp_final = self.interval_timestamp_final.max(timestamp_final);
+        }
+        self.num_bytes += num_bytes;
+        self.num_packets += num_packets;
+        self.interval_bytes += num_bytes;
+        self.interval_packets += num_packets;
+        self.timestamp_init = self.timestamp_init.min(timestamp_init);
+        self.timestamp_final = self.timestamp_final.max(timestamp_final);
+    }
+
+    /// `Absorb` the counters of another entry of the same flow, left under a different key (e.g. before its service was
+    /// identified)
+    fn absorb(&mut self, other: ReportEntry) {
+        if self.num_packets == 0 {
+            self.min_size = other.min_size;
+            self.max_size = other.max_size;
+        } else if other.num_packets > 0 {
+            self.min_size = self.min_size.min(other.min_size);
+            self.max_size = self.max_size.max(other.max_size);
+        }
+        self.min_inter_arrival = match (self.min_inter_arrival, other.min_inter_arrival) {
+            (Some(min), Some(other_min)) => Some(min.min(other_min)),
+            (min, other_min) => min.or(other_min),
+        };
+        self.max_inter_arrival = self.max_inter_arrival.max(other.max_inter_arrival);
+        if self.interval_packets == 0 {
+            (self.interval_timestamp_init, self.interval_timestamp_final) = (other.interval_timestamp_init, other.interval_timestamp_final);
+        } else if other.interval_packets > 0 {
+            self.interval_timestamp_init = self.interval_timestamp_init.min(other.interval_timestamp_init);
+            self.interval_timestamp_final = self.interval_timestamp_final.max(other.interval_timestamp_final);
+        }
+        self.num_bytes += other.num_bytes;
+        self.num_packets += other.num_packets;
+        self.interval_bytes += other.interval_bytes;
+        self.interval_packets += other.interval_packets;
+        if other.timestamp_init < self.timestamp_init {
+            self.timestamp_init = other.timestamp_init;
+            self.flow_status = other.flow_status;
+        }
+        self.timestamp_final = self.timestamp_final.max(other.timestamp_final);
+        self.tcp_connection = self.tcp_connection.take().or(other.tcp_connection);
+        self.alpn = self.alpn.take().or(other.alpn);
+        self.server_name = self.server_name.take().or(other.server_name);
+
+        let same_client = self.ip_src == other.ip_src && self.port_src == other.port_src;
+        for (directions, other_directions) in [(&mut self.directions, other.directions), (&mut self.interval_directions, other.interval_directions)] {
+            if let (Some((client_to_server, server_to_client)), Some((other_1, other_2))) = (directions.as_mut(), other_directions) {
+                let (other_client_to_server, other_server_to_client) = if same_client { (other_1, other_2) } else { (other_2, other_1) };
+                client_to_server.num_bytes += other_client_to_server.num_bytes;
+                client_to_server.num_packets += other_client_to_server.num_packets;
+                server_to_client.num_bytes += other_server_to_client.num_bytes;
+                server_to_client.num_packets += other_server_to_client.num_packets;
+            }
+        }
+    }
+
+    /// `Count Direction` of the packets of a report data, with bidirectional flow aggregation
+    fn count_direction(&mut self, rg_info: &ReportDataInfo, num_packets: usize) {
+        let from_client = self.ip_src == rg_info.ip_src && self.port_src == rg_info.port_src;
+        for (client_to_server, server_to_client) in [self.directions.as_mut(), self.interval_directions.as_mut()].into_iter().flatten() {
+            let counters = if from_client { client_to_server } else { server_to_client };
+            counters.num_bytes += rg_info.num_bytes;
+            counters.num_packets += num_packets;
+        }
+    }
+
+    /// Endpoint of the entry ("src"/"dst") matching the one given
+    fn side(&self, endpoint: Option<Endpoint>) -> &'static str {
+        match (self.endpoints(), endpoint) {
+            (Some((src, _)), Some(endpoint)) if src == endpoint => "src",
+            (Some((_, dst)), Some(endpoint)) if dst == endpoint => "dst",
+            _ => "-",
+        }
+    }
+}
+
+/// Inter-arrival time in milliseconds ("-" when not available)
+fn format_inter_arrival(inter_arrival: Option<chrono::Duration>) -> String {
+    match inter_arrival {
+        Some(inter_arrival) => format!("{:.3}", inter_arrival.num_microseconds().unwrap_or(i64::MAX) as f64 / 1000.0),
+        None => "-".to_string(),
+    }
 }
 
 impl Display for ReportEntry {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{0: <25} | {1: <25} | {2: <9} | {3: <9} | {4: <15} | {5: <15} | {6: <15} | {7: <35} | {8: <35}",
+        write!(f, "{0: <25} | {1: <25} | {2: <9} | {3: <9} | {4: <15} | {5: <15} | {6: <15} | {7: <15} | {8: <35} | {9: <35} | {10: <10} | {11: <15} | {12: <15}",
             self.ip_src,
             self.ip_dst,
             self.port_src,
@@ -139,8 +436,87 @@ impl Display for ReportEntry {
             self.l4_protocol,
             self.upper_service,
             self.num_bytes,
+            self.num_packets,
             self.timestamp_init,
-            self.timestamp_final)
+            self.timestamp_final,
+            self.flow_status,
+            self.interval_bytes,
+            self.interval_packets).unwrap();
+
+        write!(f, " | {0: <9} | {1: <9} | {2: <9} | {3: <12} | {4: <12} | {5: <12}",
+            self.min_size,
+            self.max_size,
+            format!("{:.1}", self.mean_size()),
+            format_inter_arrival(self.min_inter_arrival),
+            format_inter_arrival(self.max_inter_arrival),
+            format_inter_arrival(self.mean_inter_arrival())).unwrap();
+
+        match &self.tcp_connection {
+            Some(connection) => {
+                write!(f, " | {0: <12} | {1: <9} | {2: <9} | {3: <9}",
+                    connection.state,
+                    self.side(connection.initiator),
+                    if connection.handshake_completed { "yes" } else { "no" },
+                    self.side(connection.reset_by))
+            },
+            None => write!(f, " | {0: <12} | {1: <9} | {2: <9} | {3: <9}", "-", "-", "-", "-"),
+        }.unwrap();
+
+        write!(f, " | {0: <15} | {1: <30}", self.alpn.as_deref().unwrap_or("-"), self.server_name.as_deref().unwrap_or("-")).unwrap();
+
+        match &self.directions {
+            Some((client_to_server, server_to_client)) => {
+                write!(f, " | {0: <15} | {1: <15} | {2: <15} | {3: <15}",
+                    client_to_server.num_bytes,
+                    server_to_client.num_bytes,
+                    client_to_server.num_packets,
+                    server_to_client.num_packets)
+            },
+            None => Ok(()),
+        }
+    }
+}
+
+impl ReportEntry {
+    /// `CSV Fields` of the entry, in the order of the columns of the "report" format. The values not available are empty,
+    /// and the timestamps are in the RFC 3339 format.
+    pub fn csv_fields(&self) -> Vec<String> {
+        let optional = |value: Option<String>| value.unwrap_or_default();
+        let inter_arrival = |inter_arrival: Option<chrono::Duration>| if inter_arrival.is_some() { format_inter_arrival(inter_arrival) } else { String::new() };
+        let mut fields = vec![
+            self.ip_src.clone(),
+            self.ip_dst.clone(),
+            self.port_src.to_string(),
+            self.port_dst.to_string(),
+            self.l4_protocol.clone(),
+            self.upper_service.clone(),
+            self.num_bytes.to_string(),
+            self.num_packets.to_string(),
+            self.timestamp_init.to_rfc3339_opts(SecondsFormat::Micros, true),
+            self.timestamp_final.to_rfc3339_opts(SecondsFormat::Micros, true),
+            self.flow_status.to_string(),
+            self.interval_bytes.to_string(),
+            self.interval_packets.to_string(),
+            self.min_size.to_string(),
+            self.max_size.to_string(),
+            format!("{:.1}", self.mean_size()),
+            inter_arrival(self.min_inter_arrival),
+            inter_arrival(self.max_inter_arrival),
+            inter_arrival(self.mean_inter_arrival()),
+        ];
+        let connection = self.tcp_connection.as_ref();
+        fields.extend([
+            optional(connection.map(|connection| connection.state.to_string())),
+            optional(connection.map(|connection| self.side(connection.initiator).to_string())),
+            optional(connection.map(|connection| if connection.handshake_completed { "yes" } else { "no" }.to_string())),
+            optional(connection.map(|connection| self.side(connection.reset_by).to_string())),
+            optional(self.alpn.clone()),
+            optional(self.server_name.clone()),
+        ]);
+        if let Some((client_to_server, server_to_client)) = &self.directions {
+            fields.extend([client_to_server.num_bytes, server_to_client.num_bytes, client_to_server.num_packets, server_to_client.num_packets].map(|counter| counter.to_string()));
+        }
+        fields
     }
 }
 
@@ -158,13 +534,64 @@ pub struct InnerReportGenerator {
     time_interval: Duration,
     /// Type of report
     report_format: ReportFormat,
-    /// Filters applied to incoming packets
-    packet_filter: String,
+    /// Filter expression selecting the incoming packets
+    packet_filter: PacketFilter,
+    /// Aggregation of the packets into the rows of the "report" format
+    flow_aggregation: FlowAggregation,
 
     /// Raw data used for Verbose report generation
     data: Vec<u8>,
-    /// Formatted data collapsed for a series of key elements (IPs, Ports, L4 protocol)
+    /// Flow table: formatted data collapsed for a series of key elements (IPs, Ports, L4 protocol), kept across report intervals
     data_format: HashMap<String, ReportEntry>,
+    /// Flows ended in the current report interval
+    ended_flows: Vec<ReportEntry>,
+    /// Time after the last packet of a flow after which the flow is ended
+    flow_idle_timeout: Duration,
+    /// Time after the first packet of a flow after which the flow is ended (and a new flow started)
+    flow_active_timeout: Duration,
+    /// Number of packets with a bad checksum in the current report interval
+    bad_checksums: usize,
+    /// TCP connections state
+    tcp_connections: TcpConnectionTracker,
+    /// TCP connections reassembly
+    tcp_reassembler: TcpReassembler,
+    /// DNS summary (top queried names, response codes) of the "report" format
+    dns_summary: DnsSummary,
+    /// DHCP lease activity of the "report" format
+    dhcp_leases: DhcpLeases,
+    /// HTTP transactions (requests paired with responses) of the "report" format
+    http_transactions: HttpTransactions,
+    /// TLS handshake metadata (server name) of the open connections
+    tls_sessions: TlsSessions,
+    /// QUIC handshake metadata (server name) of the recent connections
+    quic_sessions: QuicSessions,
+    /// Application-layer dissectors receiving the reassembled TCP byte streams
+    stream_dissectors: Vec<Box<dyn StreamDissector>>,
+    /// Most confident service identification of the recent flows, shared by both directions
+    flow_services: HashMap<String, FlowService>,
+    /// Bytes received in each second (UNIX time) of the report interval, charted by the "html" format. Counted in every
+    /// aggregated format, the flows continuing when switching to the "html" one
+    bandwidth: BTreeMap<i64, usize>,
+    /// Exporter of the flows to a NetFlow/IPFIX collector
+    flow_exporter: Option<FlowExporter>,
+    /// Exports to the flow collector failed so far, closing the reports in the "report" format
+    flow_export_failures: usize,
+    /// Decoder of the NetFlow/IPFIX/sFlow datagrams captured or received on the collector socket
+    flow_collector: FlowCollector,
+    /// Address of the collector socket: the datagrams captured on their way to it are received there too
+    flow_collector_address: Option<SocketAddr>,
+    /// Timestamp of the last packet seen: the clock expiring the flows and the TCP streams, along with the current time
+    /// unless the capture is replayed from a file
+    last_seen: Option<DateTime<Utc>>,
+    /// Whether the packets are replayed from a capture file rather than captured live
+    offline_capture: bool,
+}
+
+/// `Flow Service` identified for a flow, kept until the flow is inactive for a while
+struct FlowService {
+    name: String,
+    confidence: u8,
+    last_seen: DateTime<Utc>,
 }
 
 impl InnerReportGenerator {
@@ -174,53 +601,327 @@ impl InnerReportGenerator {
             file_path: config_options.file_path,
             time_interval: config_options.time_interval,
             report_format: config_options.report_format,
-            packet_filter: config_options.packet_filter,
+            packet_filter: PacketFilter::parse(&config_options.packet_filter)?,
+            flow_aggregation: config_options.flow_aggregation,
             data: Vec::new(),
             data_format: HashMap::new(),
+            ended_flows: Vec::new(),
+            flow_idle_timeout: config_options.flow_idle_timeout,
+            flow_active_timeout: config_options.flow_active_timeout,
+            bad_checksums: 0,
+            tcp_connections: TcpConnectionTracker::new(),
+            tcp_reassembler: TcpReassembler::new(),
+            dns_summary: DnsSummary::new(),
+            dhcp_leases: DhcpLeases::new(),
+            http_transactions: HttpTransactions::new(),
+            tls_sessions: TlsSessions::new(),
+            quic_sessions: QuicSessions::new(),
+            stream_dissectors: Vec::new(),
+            flow_services: HashMap::new(),
+            bandwidth: BTreeMap::new(),
+            flow_exporter: config_options.flow_export.map(FlowExporter::new).transpose()?,
+            flow_export_failures: 0,
+            flow_collector: FlowCollector::new(),
+            flow_collector_address: config_options.flow_collector,
+            last_seen: None,
+            offline_capture: false,
         })
     }
 
     /// `push` into raw data or data_format data. Used later for report generation.
     pub fn push(&mut self, packet: &str) {
+        let ether_packet = EthernetPacket::from_json(packet).unwrap();
+        self.advance_clock(ether_packet.timestamp_recv);
+        let mut rg_info = ether_packet.decoded_report_data();
+        if self.report_format.aggregates_flows() {
+            // the flow records are selected by the filter one by one, whatever the datagram carrying them
+            self.collect_captured_flows(&ether_packet);
+            self.identify_flow_service(&mut rg_info);
+        }
+        // same filter for every format, so that a dump can be narrowed to a conversation too: the TCP tracking and the
+        // summaries see the selected packets only
+        let payload = if self.packet_filter.inspects_payload() { ether_packet.l4_payload() } else { Vec::new() };
+        if !self.apply_filter(&rg_info, &payload) {
+            return;
+        }
+        self.track_tcp(&ether_packet);
+        if self.report_format.aggregates_flows() {
+            self.inspect_udp(&ether_packet);
+        }
+
         match self.report_format {
-            ReportFormat::Report => {
-                match EthernetPacket::from_json(&packet).unwrap().report_data {
-                    Some(rg_info) => {
-                        let key = self.key_gen(rg_info.clone());
-
-                        // add to hash map
-                        let value = ReportEntry {
-                            ip_src: rg_info.ip_src,
-                            ip_dst: rg_info.ip_dst,
-                            port_src: rg_info.port_src,
-                            port_dst: rg_info.port_dst,
-                            l4_protocol: rg_info.l4_protocol,
-                            upper_service: rg_info.upper_service,
-                            num_bytes: 0,
-                            timestamp_init: rg_info.timestamp_recv,
-                            timestamp_final: rg_info.timestamp_recv };
-
-                        if self.apply_filter(&key) {
-                            let entry = self.data_format.entry(key).or_insert(value);
-                            entry.num_bytes += rg_info.num_bytes;
-                            entry.timestamp_final = rg_info.timestamp_recv;
-                        }
-                    },
-                    None => ()
+            ReportFormat::Report | ReportFormat::Csv | ReportFormat::Json | ReportFormat::Html => {
+                if rg_info.bad_checksum {
+                    self.bad_checksums += 1;
                 }
+                let entry = self.flow_entry(&rg_info);
+                entry.update(rg_info.num_bytes, rg_info.timestamp_recv);
+                entry.count_direction(&rg_info, 1);
             },
-            _ => {  // ReportFormat::Raw && ReportFormat::Verbose
-                let mut dump_packet = self.format_packet(packet);
+            _ => {  // ReportFormat::Raw && ReportFormat::Verbose && ReportFormat::Ndjson
+                if ether_packet.checksum_status() == ChecksumStatus::Invalid {
+                    self.bad_checksums += 1;
+                }
+                let mut dump_packet = self.format_packet(&ether_packet);
 
-                self.data.append(&mut Vec::from("----------------\n"));
+                if self.report_format != ReportFormat::Ndjson {
+                    self.data.append(&mut Vec::from("----------------\n"));
+                }
                 self.data.append(&mut dump_packet);
             }
         }
     }
 
+    /// `Identify Flow Service`: a flow is named after the most confident identification among its packets, so that the packets
+    /// identified only from the ports (e.g. the TCP handshake) and the ones identified from the payload share the same row.
+    /// The rows of the flow named after a less confident identification are renamed.
+    fn identify_flow_service(&mut self, rg_info: &mut ReportDataInfo) {
+        let (mut endpoint_1, mut endpoint_2) = ((&rg_info.ip_src, rg_info.port_src), (&rg_info.ip_dst, rg_info.port_dst));
+        if endpoint_2 < endpoint_1 {
+            std::mem::swap(&mut endpoint_1, &mut endpoint_2);
+        }
+        let flow = format!("{} {} {} {} {}", endpoint_1.0, endpoint_1.1, endpoint_2.0, endpoint_2.1, rg_info.l4_protocol);
+
+        let previous_name = match self.flow_services.get_mut(&flow) {
+            Some(flow_service) => {
+                flow_service.last_seen = rg_info.timestamp_recv;
+                if flow_service.confidence >= rg_info.service_confidence {
+                    rg_info.upper_service = flow_service.name.clone();
+                    rg_info.service_confidence = flow_service.confidence;
+                    return;
+                }
+                flow_service.confidence = rg_info.service_confidence;
+                Some(std::mem::replace(&mut flow_service.name, rg_info.upper_service.clone()))
+            },
+            None => {
+                self.flow_services.insert(flow, FlowService { name: rg_info.upper_service.clone(), confidence: rg_info.service_confidence, last_seen: rg_info.timestamp_recv });
+                None
+            }
+        };
+
+        if let Some(previous_name) = previous_name.filter(|name| *name != rg_info.upper_service) {
+            for (ip_src, ip_dst, port_src, port_dst) in [(&rg_info.ip_src, &rg_info.ip_dst, rg_info.port_src, rg_info.port_dst), (&rg_info.ip_dst, &rg_info.ip_src, rg_info.port_dst, rg_info.port_src)] {
+                let mut previous = rg_info.clone();
+                (previous.ip_src, previous.ip_dst, previous.port_src, previous.port_dst) = (ip_src.clone(), ip_dst.clone(), port_src, port_dst);
+                previous.upper_service = previous_name.clone();
+                let previous_key = self.key_gen(previous.clone());
+                if let Some(mut entry) = self.data_format.remove(&previous_key) {
+                    entry.upper_service = rg_info.upper_service.clone();
+                    previous.upper_service = rg_info.upper_service.clone();
+                    match self.data_format.entry(self.key_gen(previous)) {
+                        hash_map::Entry::Occupied(mut existing) => existing.get_mut().absorb(entry),
+                        hash_map::Entry::Vacant(vacant) => { vacant.insert(entry); },
+                    }
+                }
+            }
+        }
+    }
+
+    /// `Flow Entry` of the packets of a report data in the flow table, started when there's none or when the previous one is
+    /// expired
+    fn flow_entry(&mut self, rg_info: &ReportDataInfo) -> &mut ReportEntry {
+        *self.bandwidth.entry(rg_info.timestamp_recv.timestamp()).or_default() += rg_info.num_bytes;
+        let key = self.key_gen(rg_info.clone());
+        if self.data_format.get(&key).is_some_and(|entry| self.is_expired(entry, rg_info.timestamp_recv)) {
+            self.end_flow(&key);
+        }
+        let value = self.new_entry(rg_info);
+        let entry = self.data_format.entry(key).or_insert(value);
+        if entry.server_name.is_none() {
+            entry.server_name = entry.endpoints().and_then(|(src, dst)| match entry.l4_protocol.as_str() {
+                "TCP" => self.tls_sessions.get(src, dst).and_then(|session| session.server_name()),
+                "UDP" => self.quic_sessions.server_name(src, dst),
+                _ => None,
+            }).map(String::from);
+        }
+        if entry.alpn.is_none() {
+            entry.alpn = entry.endpoints().and_then(|(src, dst)| match entry.l4_protocol.as_str() {
+                "TCP" => self.tls_sessions.get(src, dst).and_then(|session| session.alpn()).map(String::from),
+                "UDP" => self.quic_sessions.alpn(src, dst).map(|alpn| alpn.join(",")),
+                _ => None,
+            });
+        }
+        entry
+    }
+
+    /// `Collect Flows` of a NetFlow/IPFIX/sFlow datagram received from an exporter on the collector socket. The flows are
+    /// merged into the flow table of the aggregated formats only.
+    pub fn collect_flows(&mut self, exporter: IpAddr, datagram: &[u8], received: DateTime<Utc>) {
+        self.advance_clock(received);
+        if self.report_format.aggregates_flows() {
+            let flows = self.flow_collector.decode(exporter, datagram, received);
+            flows.into_iter().for_each(|flow| self.aggregate_flow(flow));
+        }
+    }
+
+    /// `Aggregate Flow` collected from an exporter into the flow table, selected by the same filter as the packets
+    fn aggregate_flow(&mut self, flow: CollectedFlow) {
+        if !self.apply_filter(&flow.report_data, &[]) {
+            return;
+        }
+        let entry = self.flow_entry(&flow.report_data);
+        entry.merge(flow.report_data.num_bytes, flow.num_packets, flow.timestamp_init, flow.report_data.timestamp_recv);
+        entry.count_direction(&flow.report_data, flow.num_packets);
+    }
+
+    /// `New Entry` of the "report" format, oriented from the client to the server with bidirectional flow aggregation
+    fn new_entry(&self, rg_info: &ReportDataInfo) -> ReportEntry {
+        let mut entry = ReportEntry {
+            ip_src: rg_info.ip_src.clone(),
+            ip_dst: rg_info.ip_dst.clone(),
+            port_src: rg_info.port_src,
+            port_dst: rg_info.port_dst,
+            l4_protocol: rg_info.l4_protocol.clone(),
+            upper_service: rg_info.upper_service.clone(),
+            num_bytes: 0,
+            num_packets: 0,
+            interval_bytes: 0,
+            interval_packets: 0,
+            min_size: 0,
+            max_size: 0,
+            min_inter_arrival: None,
+            max_inter_arrival: None,
+            timestamp_init: rg_info.timestamp_recv,
+            timestamp_final: rg_info.timestamp_recv,
+            interval_timestamp_init: rg_info.timestamp_recv,
+            interval_timestamp_final: rg_info.timestamp_recv,
+            flow_status: FlowStatus::New,
+            tcp_connection: None,
+            directions: None,
+            interval_directions: None,
+            alpn: None,
+            server_name: None };
+
+        if self.flow_aggregation == FlowAggregation::Bidirectional {
+            if !self.is_client(&entry) {
+                std::mem::swap(&mut entry.ip_src, &mut entry.ip_dst);
+                std::mem::swap(&mut entry.port_src, &mut entry.port_dst);
+            }
+            entry.directions = Some((DirectionCounters::default(), DirectionCounters::default()));
+            entry.interval_directions = Some((DirectionCounters::default(), DirectionCounters::default()));
+        }
+        entry
+    }
+
+    /// `Is Expired` telling if a flow is over at a given time: idle for more than the idle timeout,
+    /// or active for more than the active timeout (long-lived flows are split in several flows).
+    fn is_expired(&self, entry: &ReportEntry, now: DateTime<Utc>) -> bool {
+        let idle_timeout = chrono::Duration::from_std(self.flow_idle_timeout).unwrap();
+        let active_timeout = chrono::Duration::from_std(self.flow_active_timeout).unwrap();
+        now - entry.timestamp_final > idle_timeout || now - entry.timestamp_init > active_timeout
+    }
+
+    /// `End Flow` moving an entry out of the flow table, to be reported as ended at the end of the interval
+    fn end_flow(&mut self, key: &str) {
+        if let Some(mut entry) = self.data_format.remove(key) {
+            entry.tcp_connection = self.tcp_connection(&entry);
+            entry.flow_status = FlowStatus::Ended;
+            self.ended_flows.push(entry);
+        }
+    }
+
+    /// `TCP Connection` state of an entry (None for other protocols)
+    fn tcp_connection(&self, entry: &ReportEntry) -> Option<TcpConnection> {
+        if entry.l4_protocol != "TCP" {
+            return None;
+        }
+        entry.endpoints().and_then(|(src, dst)| self.tcp_connections.get(src, dst).cloned())
+    }
+
+    /// `Is Client` telling if the source of the entry is the client of the conversation: the initiator of the TCP connection,
+    /// otherwise the sender of the first packet unless it uses a well-known port and the receiver doesn't.
+    fn is_client(&self, entry: &ReportEntry) -> bool {
+        if let Some((src, dst)) = entry.endpoints() {
+            if let Some(initiator) = self.tcp_connections.get(src, dst).and_then(|connection| connection.initiator) {
+                return initiator == src;
+            }
+        }
+        !(entry.port_src < WELL_KNOWN_PORTS && entry.port_dst >= WELL_KNOWN_PORTS)
+    }
+
+    /// `Track TCP` connections state and reassemble TCP segments, delivering the in-order byte streams to the application-layer dissectors
+    fn track_tcp(&mut self, ether_packet: &EthernetPacket) {
+        if let Some((ip_addr_src, ip_addr_dst, tcp_packet)) = ether_packet.tcp_packet() {
+            self.tcp_connections.process(ip_addr_src, ip_addr_dst, &tcp_packet, ether_packet.timestamp_recv);
+            let events = self.tcp_reassembler.process(ip_addr_src, ip_addr_dst, &tcp_packet, ether_packet.timestamp_recv);
+            self.dispatch_stream_events(events);
+        }
+    }
+
+    fn dispatch_stream_events(&mut self, events: Vec<StreamEvent>) {
+        for event in events.iter() {
+            if self.report_format.aggregates_flows() {
+                event.dispatch(&mut self.dns_summary);
+                event.dispatch(&mut self.http_transactions);
+                event.dispatch(&mut self.tls_sessions);
+            }
+            for dissector in self.stream_dissectors.iter_mut() {
+                event.dispatch(dissector.as_mut());
+            }
+        }
+    }
+
+    /// `Collect Captured Flows`: the flow records of the NetFlow/IPFIX/sFlow datagrams captured are merged into the flow
+    /// table, unless they're received on the collector socket too or they're the ones exported, counted already.
+    fn collect_captured_flows(&mut self, ether_packet: &EthernetPacket) {
+        if let Some((ip_addr_src, ip_addr_dst, udp_packet)) = ether_packet.udp_packet() {
+            if matches!(udp_packet.identification.upper_layer_service, UpperLayerService::NETFLOW | UpperLayerService::IPFIX | UpperLayerService::SFLOW)
+                && self.flow_collector_address.map(|address| address.port()) != Some(udp_packet.dst_port)
+                && !self.flow_exporter.as_ref().is_some_and(|flow_exporter| flow_exporter.sent(ip_addr_src, udp_packet.src_port, ip_addr_dst, udp_packet.dst_port)) {
+                let flows = self.flow_collector.decode(ip_addr_src, &udp_packet.payload, ether_packet.timestamp_recv);
+                flows.into_iter().for_each(|flow| self.aggregate_flow(flow));
+            }
+        }
+    }
+
+    /// `Inspect UDP` datagrams feeding the summaries of the "report" format: DNS messages (DNS over TCP is received
+    /// through the TCP reassembly), DHCP lease activity and QUIC handshakes (server name of the connections).
+    fn inspect_udp(&mut self, ether_packet: &EthernetPacket) {
+        if let Some((ip_addr_src, ip_addr_dst, udp_packet)) = ether_packet.udp_packet() {
+            match udp_packet.identification.upper_layer_service {
+                UpperLayerService::DNS => {
+                    if let Some(message) = DnsMessage::parse(&udp_packet.payload) {
+                        self.dns_summary.push(&message);
+                    }
+                },
+                UpperLayerService::DHCP => {
+                    if let Some(message) = DhcpMessage::parse(&udp_packet.payload) {
+                        self.dhcp_leases.push(&message, ether_packet.timestamp_recv);
+                    }
+                },
+                UpperLayerService::DHCPv6 => {
+                    if let Some(message) = Dhcpv6Message::parse(&udp_packet.payload) {
+                        self.dhcp_leases.push_v6(&message, ether_packet.timestamp_recv);
+                    }
+                },
+                _ => {}
+            }
+            self.quic_sessions.push(ip_addr_src, ip_addr_dst, &udp_packet, ether_packet.timestamp_recv);
+        }
+    }
+
+    /// `Add Stream Dissector` receiving the reassembled TCP byte streams
+    pub fn add_stream_dissector(&mut self, dissector: Box<dyn StreamDissector>) {
+        self.stream_dissectors.push(dissector);
+    }
+
+    /// `Stream Sections` appended to the report by the application-layer dissectors
+    fn stream_sections(&mut self) -> String {
+        let events = self.tcp_reassembler.expire(self.clock(), chrono::Duration::seconds(TCP_IDLE_TIMEOUT_S));
+        self.dispatch_stream_events(events);
+
+        let mut sections = String::new();
+        for dissector in self.stream_dissectors.iter_mut() {
+            if let Some(section) = dissector.report_section() {
+                sections.push('\n');
+                sections.push_str(section.as_str());
+            }
+        }
+        sections
+    }
+
     /// `Format Packet` depending on the report format specifier
-    fn format_packet(&self, packet: &str) -> Vec<u8> {
-        let ether_packet = EthernetPacket::from_json(&packet).unwrap();
+    fn format_packet(&self, ether_packet: &EthernetPacket) -> Vec<u8> {
         Vec::from(format!("{}", ether_packet.display_as(self.report_format.clone())))
     }
 
@@ -232,57 +933,232 @@ impl InnerReportGenerator {
                 .truncate(true)
                 .open(self.file_path.as_path())?;
 
+        let report = self.render_report();
+        let char_num = file.write(report.as_slice())?;
         match self.report_format {
-            ReportFormat::Report => {
-                let mut report = String::from(format!("{0: <25} | {1: <25} | {2: <9} | {3: <9} | {4: <15} | {5: <15} | {6: <15} | {7: <35} | {8: <35}\n",
-                "IP src", "IP dst", "Port src", "Port dst", "L4 Protocol", "Upper Service", "Num. Bytes", "Initial Timestamp", "Final Timestamp").as_str());
-
-                self.data_format.iter_mut().for_each(|(_, value)| { report.push_str(format!("{}\n", value).as_str())});
-
-                let char_num = file.write(report.as_ref())?;
+            ReportFormat::Raw | ReportFormat::Verbose | ReportFormat::Ndjson => println!("Printing data into file"),
+            _ => println!("Printing data for report"),
+        }
+        Ok(char_num)
+    }
 
-                self.data_format.clear();
+    /// `Render Report` of the data accumulated in the current report interval, in the current format, starting a new interval.
+    fn render_report(&mut self) -> Vec<u8> {
+        let mut report = std::mem::take(&mut self.data);
+        if !self.report_format.aggregates_flows() {
+            let sections = self.stream_sections();
+            // a line for each packet only in the "ndjson" format: the summaries don't fit in the stream
+            if self.report_format != ReportFormat::Ndjson {
+                report.extend_from_slice(sections.as_bytes());
+                report.extend_from_slice(self.bad_checksums_summary().as_bytes());
+            }
+            self.bad_checksums = 0;
+            return report;
+        }
 
-                println!("Printing data for report");
-                Ok(char_num)
+        self.end_interval_flows();
+        // the summaries of the application-layer dissectors are in the "report" format only, their streams are expired anyway
+        let stream_sections = self.stream_sections();
+        match self.report_format {
+            ReportFormat::Csv => {
+                report.extend_from_slice(csv_format::record(&self.report_columns()).as_bytes());
+                self.interval_flows().for_each(|value| report.extend_from_slice(csv_format::record(&value.csv_fields()).as_bytes()));
+            },
+            ReportFormat::Json => {
+                let json_report = JsonReport::new(self.interval_flows(), self.flow_aggregation, self.bad_checksums, Utc::now());
+                report.append(&mut serde_json::to_vec_pretty(&json_report).unwrap());
+                report.push(b'\n');
+            },
+            ReportFormat::Html => {
+                let flows: Vec<(&ReportEntry, Vec<String>)> = self.interval_flows().map(|entry| (entry, entry.csv_fields())).collect();
+                let page = html_format::render(&self.report_columns(), &flows, &self.bandwidth, self.bad_checksums, Utc::now());
+                report.extend_from_slice(page.as_bytes());
             },
             _ => {
-                let char_num = file.write(self.data.as_slice())?;
+                report.extend_from_slice(self.report_header().as_bytes());
+                self.interval_flows().for_each(|value| report.extend_from_slice(format!("{}\n", value).as_bytes()));
+                if let Some(traffic_summary) = TrafficSummary::new(self.interval_flows(), TOP_N).report_section() {
+                    report.push(b'\n');
+                    report.extend_from_slice(traffic_summary.as_bytes());
+                }
+                report.extend_from_slice(stream_sections.as_bytes());
+                if let Some(dns_summary) = self.dns_summary.report_section() {
+                    report.push(b'\n');
+                    report.extend_from_slice(dns_summary.as_bytes());
+                }
+                if let Some(dhcp_leases) = self.dhcp_leases.report_section() {
+                    report.push(b'\n');
+                    report.extend_from_slice(dhcp_leases.as_bytes());
+                }
+                if let Some(http_transactions) = self.http_transactions.report_section() {
+                    report.push(b'\n');
+                    report.extend_from_slice(http_transactions.as_bytes());
+                }
+                report.extend_from_slice(self.bad_checksums_summary().as_bytes());
+                if self.flow_exporter.is_some() {
+                    report.extend_from_slice(self.flow_export_summary().as_bytes());
+                }
+            },
+        }
+        self.export_flows();
 
-                self.data.clear();
+        self.start_interval_flows();
+        report
+    }
 
-                println!("Printing data into file");
-                Ok(char_num)
+    /// `Interval Flows`: the flows of the report interval sorted by the bytes of the interval (largest first); on ties, the
+    /// flows ended in the interval are listed before the active ones
+    fn interval_flows(&self) -> impl Iterator<Item = &ReportEntry> {
+        let mut flows: Vec<&ReportEntry> = self.ended_flows.iter().chain(self.data_format.values()).collect();
+        flows.sort_by_key(|flow| std::cmp::Reverse(flow.interval_bytes));
+        flows.into_iter()
+    }
+
+    /// `Export Flows` of the report interval to the flow collector, when configured. A failed export is counted, the flows
+    /// of the interval being lost for the collector
+    fn export_flows(&mut self) {
+        if let Some(flow_exporter) = self.flow_exporter.as_mut() {
+            let flows = self.ended_flows.iter().chain(self.data_format.values());
+            if flow_exporter.export(flows, Utc::now()).is_err() {
+                self.flow_export_failures += 1;
+            }
+        }
+    }
+
+    /// `Set Packet Filter` selecting the packets pushed from now on
+    pub fn set_packet_filter(&mut self, packet_filter: PacketFilter) {
+        self.packet_filter = packet_filter;
+    }
+
+    /// `Set Report Format` of the packets pushed from now on. The data accumulated in the current interval is not lost: when
+    /// switching from or to a packet dump (raw/verbose/ndjson), it's written right away as a report in the previous format, so
+    /// that every report is in a single format. The flows continue between the aggregated formats.
+    pub fn set_report_format(&mut self, report_format: ReportFormat) -> Result<()> {
+        if self.report_format != report_format && !(self.report_format.aggregates_flows() && report_format.aggregates_flows()) {
+            self.generate_report()?;
+        }
+        self.report_format = report_format;
+        Ok(())
+    }
+
+    /// `Set Time Interval` of the periodic report generation, counted from the last report generated
+    pub fn set_time_interval(&mut self, time_interval: Duration) {
+        self.time_interval = time_interval;
+    }
+
+    /// `Set Offline Capture` when the packets are replayed from a capture file: the flows and the TCP streams are expired
+    /// against the timestamps of the packets instead of the current time
+    pub fn set_offline_capture(&mut self, offline_capture: bool) {
+        self.offline_capture = offline_capture;
+    }
+
+    /// `End Interval Flows` at the end of a report interval: the flows ended by a timeout or by the TCP connection closure are
+    /// moved out of the flow table, the others get the current state of their TCP connection
+    fn end_interval_flows(&mut self) {
+        let now = self.clock();
+        let keys: Vec<String> = self.data_format.keys().cloned().collect();
+        for key in keys {
+            let entry = self.data_format.get(&key).unwrap();
+            let tcp_connection = self.tcp_connection(entry);
+            let terminated = tcp_connection.as_ref().is_some_and(|connection| connection.is_terminated());
+            if terminated || self.is_expired(entry, now) {
+                self.end_flow(&key);
+            } else {
+                self.data_format.get_mut(&key).unwrap().tcp_connection = tcp_connection;
             }
         }
+        self.tcp_connections.purge(now, chrono::Duration::seconds(TCP_IDLE_TIMEOUT_S));
+        self.quic_sessions.purge(now, chrono::Duration::from_std(self.flow_idle_timeout).unwrap());
+        self.flow_services.retain(|_, flow_service| now - flow_service.last_seen <= chrono::Duration::from_std(self.flow_idle_timeout).unwrap());
     }
 
-    /// `Key Generation` based on a set of packet characteristics (IPs, Ports, L4 protocol)
+    /// `Advance Clock` to the timestamp of a packet, the packets being received in order apart from a little jitter
+    fn advance_clock(&mut self, timestamp: DateTime<Utc>) {
+        self.last_seen = Some(self.last_seen.map_or(timestamp, |last_seen| last_seen.max(timestamp)));
+    }
+
+    /// `Clock` of the flow and stream expiry: the current time for a live capture, so that the idle flows time out when the
+    /// capture goes quiet; the timestamp of the last packet seen for a capture file (the current time before any packet), so
+    /// that it's expired the same way whenever it's analyzed
+    fn clock(&self) -> DateTime<Utc> {
+        match self.offline_capture {
+            true => self.last_seen.unwrap_or_else(Utc::now),
+            false => self.last_seen.map_or_else(Utc::now, |last_seen| last_seen.max(Utc::now())),
+        }
+    }
+
+    /// `Start Interval Flows` once reported: the ended flows are dropped, the active ones continue in the next report interval
+    fn start_interval_flows(&mut self) {
+        self.ended_flows.clear();
+        self.data_format.values_mut().for_each(|value| {
+            value.flow_status = FlowStatus::Continuing;
+            value.interval_bytes = 0;
+            value.interval_packets = 0;
+            if let Some(interval_directions) = value.interval_directions.as_mut() {
+                *interval_directions = (DirectionCounters::default(), DirectionCounters::default());
+            }
+        });
+        self.bad_checksums = 0;
+        self.bandwidth.clear();
+    }
+
+    /// `Report Columns` names of the "report" and "csv" formats
+    fn report_columns(&self) -> Vec<String> {
+        let (src, dst) = match self.flow_aggregation {
+            FlowAggregation::Unidirectional => ("src", "dst"),
+            FlowAggregation::Bidirectional => ("client", "server"),
+        };
+        let mut columns = vec![format!("IP {}", src), format!("IP {}", dst), format!("Port {}", src), format!("Port {}", dst)];
+        columns.extend(["L4 Protocol", "Upper Service", "Num. Bytes", "Num. Packets", "Initial Timestamp", "Final Timestamp", "Flow", "Interval Bytes",
+            "Interval Pkts", "Min Size", "Max Size", "Mean Size", "Min IAT ms", "Max IAT ms", "Mean IAT ms", "TCP State", "Initiator", "Handshake",
+            "Reset by", "ALPN", "Server Name"].map(String::from));
+        if self.flow_aggregation == FlowAggregation::Bidirectional {
+            columns.extend(["Bytes c->s", "Bytes s->c", "Packets c->s", "Packets s->c"].map(String::from));
+        }
+        columns
+    }
+
+    /// `Report Header` with the column names of the "report" format
+    fn report_header(&self) -> String {
+        let mut header = self.report_columns().iter().zip(REPORT_COLUMN_WIDTHS)
+            .map(|(column, width)| format!("{0: <1$}", column, width))
+            .collect::<Vec<String>>()
+            .join(" | ");
+        header.push('\n');
+        header
+    }
+
+    /// `Bad Checksums Summary` closing each report with the number of packets with a bad checksum in the interval
+    fn bad_checksums_summary(&self) -> String {
+        format!("\nPackets with bad checksum: {}\n", self.bad_checksums)
+    }
+
+    /// `Flow Export Summary` closing each report with the number of exports to the flow collector failed so far
+    fn flow_export_summary(&self) -> String {
+        format!("Failed flow exports: {}\n", self.flow_export_failures)
+    }
+
+    /// `Key Generation` based on a set of packet characteristics (IPs, Ports, L4 protocol).
+    /// With bidirectional flow aggregation the endpoints are sorted, so that both directions share the same key.
     pub fn key_gen(&self, re_info: ReportDataInfo) -> String {
+        let (mut ip_src, mut ip_dst, mut port_src, mut port_dst) = (re_info.ip_src, re_info.ip_dst, re_info.port_src, re_info.port_dst);
+        if self.flow_aggregation == FlowAggregation::Bidirectional && (&ip_dst, port_dst) < (&ip_src, port_src) {
+            std::mem::swap(&mut ip_src, &mut ip_dst);
+            std::mem::swap(&mut port_src, &mut port_dst);
+        }
         String::from(format!("{} {} {} {} {} {}",
-            re_info.ip_src,
-            re_info.ip_dst,
-            re_info.port_src,
-            re_info.port_dst,
+            ip_src,
+            ip_dst,
+            port_src,
+            port_dst,
             re_info.l4_protocol,
             re_info.upper_service,
         ))
     }
 
-    /// `Apply Filter` searching in the key for keywords inside the packet_filter specified in configuration phase
-    fn apply_filter(&self, key: &str) -> bool {
-        for filter in self.packet_filter.split_whitespace() {
-            let mut found = false;
-            for elem in key.split_whitespace() {
-                if filter == elem {
-                    found = true;
-                }
-            }
-            if !found {
-                return false;
-            }
-        }
-        true
+    /// `Apply Filter`: whether the packet is selected by the packet_filter specified in configuration phase
+    fn apply_filter(&self, re_info: &ReportDataInfo, payload: &[u8]) -> bool {
+        self.packet_filter.matches(re_info, payload)
     }
 }
 
@@ -304,7 +1180,11 @@ pub struct ReportGenerator {
     inner_struct: Arc<Mutex<InnerReportGenerator>>,
     /// Timer thread. Periodically calls for a report generation
     timer_thread: Option<JoinHandle<()>>,
+    /// Collector thread. Receives the datagrams of the flow exporters on the collector socket, when configured
+    collector_thread: Option<JoinHandle<()>>,
     end_thread: Arc<Mutex<bool>>,
+    /// Wakes the timer thread up when it has to end
+    end_thread_cv: Arc<Condvar>,
 }
 
 impl ReportGenerator {
@@ -312,24 +1192,31 @@ impl ReportGenerator {
     pub fn new(config_options: ConfigOptions, stop_thread: Arc<Mutex<bool>>, stop_thread_cv: Arc<Condvar>) -> Result<ReportGenerator> {
         let end_thread = Arc::new(Mutex::new(false));
         let end_thread2 = end_thread.clone();
+        let end_thread_cv = Arc::new(Condvar::new());
+        let end_thread_cv2 = end_thread_cv.clone();
 
-        let inner_struct = Arc::new(Mutex::new(InnerReportGenerator::new(config_options).unwrap()));
+        let collector_socket = config_options.flow_collector.map(UdpSocket::bind).transpose()?;
+        let inner_struct = Arc::new(Mutex::new(InnerReportGenerator::new(config_options)?));
 
         let mut report_generator = Self {
             inner_struct,
             timer_thread: None,
-            end_thread
+            collector_thread: None,
+            end_thread,
+            end_thread_cv,
         };
 
-        report_generator.activate(stop_thread, stop_thread_cv, end_thread2);
+        if let Some(collector_socket) = collector_socket {
+            report_generator.activate_collector(collector_socket, stop_thread.clone(), end_thread2.clone())?;
+        }
+        report_generator.activate(stop_thread, stop_thread_cv, end_thread2, end_thread_cv2);
 
         Ok(report_generator)
     }
 
     /// `activate` thread for periodic report generation (timer)
-    fn activate(&mut self, stop_thread: Arc<Mutex<bool>>, stop_thread_cv: Arc<Condvar>, end_thread: Arc<Mutex<bool>>) {
+    fn activate(&mut self, stop_thread: Arc<Mutex<bool>>, stop_thread_cv: Arc<Condvar>, end_thread: Arc<Mutex<bool>>, end_thread_cv: Arc<Condvar>) {
         let clone_inner_report_generator = self.inner_struct.clone();
-        let time_interval = clone_inner_report_generator.lock().unwrap().time_interval;
 
         self.timer_thread = Option::from(thread::spawn(move || {
             let mut count = 0;
@@ -340,26 +1227,72 @@ impl ReportGenerator {
                     stop_flag = *stop_thread_cv.wait(stop_thread.lock().unwrap()).unwrap();
                 }
 
-                thread::sleep(Duration::from_secs(1));
+                // one second, unless the timer is ended meanwhile
+                let (ended, _) = end_thread_cv.wait_timeout_while(end_thread.lock().unwrap(), Duration::from_secs(1), |ended| !*ended).unwrap();
+                if *ended { return; }
+                drop(ended);
                 count += 1;
-                if count == time_interval.as_secs() && *end_thread.lock().unwrap() != true && *stop_thread.lock().unwrap() != true {
-                    clone_inner_report_generator.lock().unwrap().generate_report().unwrap();
-                    count = 0;
+                if *end_thread.lock().unwrap() != true && *stop_thread.lock().unwrap() != true {
+                    // the time interval can be changed while the timer is running
+                    let mut inner_report_generator = clone_inner_report_generator.lock().unwrap();
+                    if count >= inner_report_generator.time_interval.as_secs() {
+                        inner_report_generator.generate_report().unwrap();
+                        count = 0;
+                    }
                 }
             }
         }))
     }
 
+    /// `activate_collector` thread merging the flow records of the datagrams received on the collector socket, unless stopped
+    fn activate_collector(&mut self, socket: UdpSocket, stop_thread: Arc<Mutex<bool>>, end_thread: Arc<Mutex<bool>>) -> Result<()> {
+        let clone_inner_report_generator = self.inner_struct.clone();
+        socket.set_read_timeout(Some(Duration::from_millis(COLLECTOR_READ_TIMEOUT_MS)))?;
+
+        self.collector_thread = Option::from(thread::spawn(move || {
+            let mut datagram = [0; 65535];
+            while !*end_thread.lock().unwrap() {
+                if let Ok((size, exporter)) = socket.recv_from(&mut datagram) {
+                    if !*stop_thread.lock().unwrap() {
+                        clone_inner_report_generator.lock().unwrap().collect_flows(exporter.ip(), &datagram[..size], Utc::now());
+                    }
+                }
+            }
+        }));
+        Ok(())
+    }
+
     /// `push` inside struct data. Used in the report when the timer fires.
     pub fn push(&mut self, packet: &str) {
         self.inner_struct.lock().unwrap().push(packet);
     }
 
+    /// `add_stream_dissector` receiving the reassembled TCP byte streams of the packets pushed.
+    pub fn add_stream_dissector(&mut self, dissector: Box<dyn StreamDissector>) {
+        self.inner_struct.lock().unwrap().add_stream_dissector(dissector);
+    }
+
+    /// `set_packet_filter` of the packets pushed from now on, while the timer is running.
+    pub fn set_packet_filter(&mut self, packet_filter: PacketFilter) {
+        self.inner_struct.lock().unwrap().set_packet_filter(packet_filter);
+    }
+
+    /// `set_report_format` of the packets pushed from now on, while the timer is running. No data is lost.
+    pub fn set_report_format(&mut self, report_format: ReportFormat) -> Result<()> {
+        self.inner_struct.lock().unwrap().set_report_format(report_format)
+    }
+
+    /// `set_time_interval` of the report generation, while the timer is running.
+    pub fn set_time_interval(&mut self, time_interval: Duration) {
+        self.inner_struct.lock().unwrap().set_time_interval(time_interval);
+    }
+
 }
 
 impl Drop for ReportGenerator {
     fn drop(&mut self) {
         *self.end_thread.lock().unwrap() = true;
+        self.end_thread_cv.notify_all();
         match self.timer_thread.take() {
             Some(res) => {
                 match res.join() {
@@ -369,5 +1302,8 @@ impl Drop for ReportGenerator {
             },
             None => (),
         };
+        if let Some(collector_thread) = self.collector_thread.take() {
+            let _ = collector_thread.join();
+        }
     }
 }
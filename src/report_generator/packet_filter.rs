@@ -0,0 +1,673 @@
+//! # Packet Filter
+//!
+//! Boolean expression language selecting the packets analyzed by the report generator.
+//!
+//! An expression combines terms with `and` (`&&`), `or` (`||`), `not` (`!`) and parentheses. Terms written one after the
+//! other without an operator are joined with `and`, so a list of keywords (`TCP 443`) keeps selecting the packets matching
+//! all of them.
+//!
+//! A term is either a keyword or a field comparison:
+//! - keywords: a protocol, service or TCP flag name (`tcp`, `https`, `syn`), a port number (`443`), an IP address or a CIDR
+//!   block (`10.0.0.1`, `10.0.0.0/8`) or a MAC address (`e0:b9:e5:30:ef:98`), matching either endpoint
+//! - comparisons: `field == value`, `!=`, `<`, `<=`, `>`, `>=`, and `field in value` where the value is a range (`80..90`,
+//!   bounds included), a CIDR block or a set (`{80, 443, 8000..8080}`)
+//! - payload matching: `payload contains "text"` or hex bytes (`payload contains 47:45:54`, `payload contains 0x474554`), and
+//!   `payload matches "regex"`, over the payload of the TCP segments and UDP datagrams
+//!
+//! | Field                                   | Values                                                  |
+//! |-----------------------------------------|---------------------------------------------------------|
+//! | `ip.src`, `ip.dst`, `ip.addr`           | IP address or CIDR block (`ip.dst == 10.0.0.0/8`)      |
+//! |                                         | MAC address, for ARP and the other non IP packets       |
+//! | `port`, `srcport`, `dstport`            | TCP or UDP port                                         |
+//! | `tcp.port`, `tcp.srcport`, `tcp.dstport`| TCP port                                                |
+//! | `udp.port`, `udp.srcport`, `udp.dstport`| UDP port                                                |
+//! | `proto`                                 | Layer 4 protocol (`TCP`, `UDP`, `ICMP`, `ARP`, ...)      |
+//! | `service`                               | Upper layer service (`HTTPS`, `DNS`, `UNKNOWN`, ...)    |
+//! | `tcp.flags`                             | TCP flag set (`tcp.flags == SYN`)                       |
+//! | `len`                                   | Size of the packet in bytes                             |
+//! | `payload`                               | TCP/UDP payload, with `contains` and `matches` only     |
+//!
+//! Names are compared ignoring the case. The fields matching both endpoints (`ip.addr`, `port`, ...) and `tcp.flags` are
+//! true when any of their values matches, while `!=` is true when none does: `ip.addr != 10.0.0.1` excludes the packets
+//! from and to that address.
+//!
+//! Quoted text may contain `\"`, and for `contains` the escapes `\\`, `\r`, `\n`, `\t` and `\xNN`. Regular expressions
+//! (Rust `regex` syntax) are matched against the raw bytes of the payload: `(?i)` makes them case insensitive.
+//!
+//! ```
+//! service == https and not ip.dst in 203.0.113.0/24
+//! (tcp.port in 80..90 or udp.port == 53) && !ip.src == 192.168.1.1
+//! tcp.dstport == 8080 and payload matches "order_id=ORD-0*4711\b"
+//! ```
+
+use std::fmt::{Display, Formatter};
+use std::net::IpAddr;
+use regex::bytes::Regex;
+use crate::report_generator::{RGError, ReportDataInfo, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Endpoint of the packet a field refers to
+enum Side {
+    Src,
+    Dst,
+    Any,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Field of the packet that can be compared
+enum Field {
+    Ip(Side),
+    /// Port of the given layer 4 protocol (TCP or UDP when not given)
+    Port(Option<&'static str>, Side),
+    Proto,
+    Service,
+    TcpFlags,
+    Length,
+    /// Payload of the TCP segment or UDP datagram
+    Payload,
+}
+
+const FIELDS: [(&str, Field); 17] = [
+    ("ip.src", Field::Ip(Side::Src)),
+    ("ip.dst", Field::Ip(Side::Dst)),
+    ("ip.addr", Field::Ip(Side::Any)),
+    ("port", Field::Port(None, Side::Any)),
+    ("srcport", Field::Port(None, Side::Src)),
+    ("dstport", Field::Port(None, Side::Dst)),
+    ("tcp.port", Field::Port(Some("TCP"), Side::Any)),
+    ("tcp.srcport", Field::Port(Some("TCP"), Side::Src)),
+    ("tcp.dstport", Field::Port(Some("TCP"), Side::Dst)),
+    ("udp.port", Field::Port(Some("UDP"), Side::Any)),
+    ("udp.srcport", Field::Port(Some("UDP"), Side::Src)),
+    ("udp.dstport", Field::Port(Some("UDP"), Side::Dst)),
+    ("proto", Field::Proto),
+    ("service", Field::Service),
+    ("tcp.flags", Field::TcpFlags),
+    ("len", Field::Length),
+    ("payload", Field::Payload),
+];
+
+impl Field {
+    fn parse(name: &str) -> Option<Field> {
+        FIELDS.iter().find(|(field_name, _)| field_name.eq_ignore_ascii_case(name)).map(|(_, field)| *field)
+    }
+
+    fn is_numeric(&self) -> bool {
+        matches!(self, Field::Port(..) | Field::Length)
+    }
+
+    /// Values of the field in the packet: none when the packet doesn't have it (e.g. the ports of an ICMP packet)
+    fn values<'a>(&self, report_data: &'a ReportDataInfo) -> Vec<FieldValue<'a>> {
+        match self {
+            Field::Ip(side) => {
+                let addresses = [(Side::Src, &report_data.ip_src), (Side::Dst, &report_data.ip_dst)];
+                addresses.into_iter()
+                    .filter(|(address_side, _)| *side == Side::Any || side == address_side)
+                    .filter_map(|(_, address)| match address.parse::<IpAddr>() {
+                        Ok(address) => Some(FieldValue::Address(address)),
+                        // the packets without IP header carry the MAC addresses
+                        Err(_) => mac_address(address).map(FieldValue::Mac),
+                    })
+                    .collect()
+            },
+            Field::Port(l4_protocol, side) => {
+                let has_ports = match l4_protocol {
+                    Some(l4_protocol) => report_data.l4_protocol == *l4_protocol,
+                    None => report_data.l4_protocol == "TCP" || report_data.l4_protocol == "UDP",
+                };
+                if !has_ports {
+                    return Vec::new();
+                }
+                [(Side::Src, report_data.port_src), (Side::Dst, report_data.port_dst)].into_iter()
+                    .filter(|(port_side, _)| *side == Side::Any || side == port_side)
+                    .map(|(_, port)| FieldValue::Number(port as u64))
+                    .collect()
+            },
+            Field::Proto => vec![FieldValue::Name(&report_data.l4_protocol)],
+            Field::Service => vec![FieldValue::Name(&report_data.upper_service)],
+            Field::TcpFlags => report_data.tcp_flags.iter().map(|flag| FieldValue::Name(flag)).collect(),
+            Field::Length => vec![FieldValue::Number(report_data.num_bytes as u64)],
+            // compared with `contains` and `matches` only
+            Field::Payload => Vec::new(),
+        }
+    }
+}
+
+/// Value of a field in a packet
+enum FieldValue<'a> {
+    Address(IpAddr),
+    Mac([u8; 6]),
+    Number(u64),
+    Name(&'a str),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Comparison operator
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    In,
+    /// Byte pattern in the payload
+    Contains,
+    /// Regular expression matching the payload
+    Matches,
+}
+
+impl Display for Op {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let op = match self {
+            Op::Eq => "==",
+            Op::Ne => "!=",
+            Op::Lt => "<",
+            Op::Le => "<=",
+            Op::Gt => ">",
+            Op::Ge => ">=",
+            Op::In => "in",
+            Op::Contains => "contains",
+            Op::Matches => "matches",
+        };
+        write!(f, "{}", op)
+    }
+}
+
+/// MAC address written as six hex bytes separated by colons or dashes (`e0:b9:e5:30:ef:98`)
+fn mac_address(text: &str) -> Option<[u8; 6]> {
+    let bytes: Vec<&str> = text.split([':', '-']).collect();
+    if bytes.len() != 6 || bytes.iter().any(|byte| byte.len() != 2) {
+        return None;
+    }
+    let mut address = [0; 6];
+    for (i, byte) in bytes.iter().enumerate() {
+        address[i] = u8::from_str_radix(byte, 16).ok()?;
+    }
+    Some(address)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// `CIDR` block, a single address being a block with the full prefix
+struct Cidr {
+    address: IpAddr,
+    prefix: u8,
+}
+
+impl Cidr {
+    fn parse(cidr: &str) -> Option<Cidr> {
+        let (address, prefix) = match cidr.split_once('/') {
+            Some((address, prefix)) => (address.parse::<IpAddr>().ok()?, Some(prefix.parse::<u8>().ok()?)),
+            None => (cidr.parse::<IpAddr>().ok()?, None),
+        };
+        let max_prefix = if address.is_ipv4() { 32 } else { 128 };
+        match prefix {
+            Some(prefix) if prefix > max_prefix => None,
+            prefix => Some(Cidr { address, prefix: prefix.unwrap_or(max_prefix) }),
+        }
+    }
+
+    fn contains(&self, address: &IpAddr) -> bool {
+        match (self.address, address) {
+            (IpAddr::V4(network), IpAddr::V4(address)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix as u32).unwrap_or(0);
+                u32::from(network) & mask == u32::from(*address) & mask
+            },
+            (IpAddr::V6(network), IpAddr::V6(address)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix as u32).unwrap_or(0);
+                u128::from(network) & mask == u128::from(*address) & mask
+            },
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Value a field is compared to
+enum Value {
+    Address(Cidr),
+    Mac([u8; 6]),
+    Number(u64),
+    /// Inclusive range of numbers
+    Range(u64, u64),
+    Name(String),
+    Bytes(Vec<u8>),
+    Regex(PayloadRegex),
+}
+
+#[derive(Debug, Clone)]
+/// Regular expression over the payload, compared by its source
+struct PayloadRegex(Regex);
+
+impl PartialEq for PayloadRegex {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.as_str() == other.0.as_str()
+    }
+}
+
+impl Value {
+    fn matches(&self, value: &FieldValue) -> bool {
+        match (self, value) {
+            (Value::Address(cidr), FieldValue::Address(address)) => cidr.contains(address),
+            (Value::Mac(mac), FieldValue::Mac(address)) => mac == address,
+            (Value::Number(number), FieldValue::Number(value)) => number == value,
+            (Value::Range(first, last), FieldValue::Number(value)) => first <= value && value <= last,
+            (Value::Name(name), FieldValue::Name(value)) => name.eq_ignore_ascii_case(value),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Parsed filter expression
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    /// Keyword matched against the fields it can be the value of
+    Keyword(Value),
+    Compare(Field, Op, Vec<Value>),
+}
+
+impl Expr {
+    fn matches(&self, report_data: &ReportDataInfo, payload: &[u8]) -> bool {
+        match self {
+            Expr::And(left, right) => left.matches(report_data, payload) && right.matches(report_data, payload),
+            Expr::Or(left, right) => left.matches(report_data, payload) || right.matches(report_data, payload),
+            Expr::Not(expr) => !expr.matches(report_data, payload),
+            Expr::Keyword(value) => {
+                let fields: &[Field] = match value {
+                    Value::Address(_) | Value::Mac(_) => &[Field::Ip(Side::Any)],
+                    Value::Number(_) | Value::Range(..) => &[Field::Port(None, Side::Any)],
+                    Value::Name(_) => &[Field::Proto, Field::Service, Field::TcpFlags],
+                    Value::Bytes(_) | Value::Regex(_) => &[],
+                };
+                fields.iter().any(|field| field.values(report_data).iter().any(|field_value| value.matches(field_value)))
+            },
+            Expr::Compare(Field::Payload, _, values) => values.iter().any(|value| match value {
+                Value::Bytes(pattern) => pattern.is_empty() || payload.windows(pattern.len()).any(|window| window == pattern.as_slice()),
+                Value::Regex(regex) => regex.0.is_match(payload),
+                _ => false,
+            }),
+            Expr::Compare(field, op, values) => {
+                let field_values = field.values(report_data);
+                let any_equal = || field_values.iter().any(|field_value| values.iter().any(|value| value.matches(field_value)));
+                let bound = match (op, values.first()) {
+                    (Op::Eq | Op::In, _) => return any_equal(),
+                    (Op::Ne, _) => return !any_equal(),
+                    (_, Some(Value::Number(bound))) => *bound,
+                    _ => return false,
+                };
+                field_values.iter().any(|field_value| match (field_value, op) {
+                    (FieldValue::Number(number), Op::Lt) => *number < bound,
+                    (FieldValue::Number(number), Op::Le) => *number <= bound,
+                    (FieldValue::Number(number), Op::Gt) => *number > bound,
+                    (FieldValue::Number(number), Op::Ge) => *number >= bound,
+                    _ => false,
+                })
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Quoted(String),
+    Op(Op),
+    Not,
+    And,
+    Or,
+    LParen,
+    RParen,
+    LBrace,
+    RBrace,
+    Comma,
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Word(word) => write!(f, "`{}`", word),
+            Token::Quoted(text) => write!(f, "\"{}\"", text),
+            Token::Op(op) => write!(f, "`{}`", op),
+            Token::Not => write!(f, "`not`"),
+            Token::And => write!(f, "`and`"),
+            Token::Or => write!(f, "`or`"),
+            Token::LParen => write!(f, "`(`"),
+            Token::RParen => write!(f, "`)`"),
+            Token::LBrace => write!(f, "`{{`"),
+            Token::RBrace => write!(f, "`}}`"),
+            Token::Comma => write!(f, "`,`"),
+        }
+    }
+}
+
+fn filter_error(message: String) -> RGError {
+    RGError::new(message.as_str())
+}
+
+/// Splits the filter into tokens, each with its position (starting from 1)
+fn tokenize(filter: &str) -> Result<Vec<(Token, usize)>> {
+    let chars: Vec<char> = filter.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let position = i + 1;
+        let next = chars.get(i + 1).copied();
+        let (token, length) = match chars[i] {
+            c if c.is_whitespace() => { i += 1; continue; },
+            '(' => (Token::LParen, 1),
+            ')' => (Token::RParen, 1),
+            '{' => (Token::LBrace, 1),
+            '}' => (Token::RBrace, 1),
+            ',' => (Token::Comma, 1),
+            '=' if next == Some('=') => (Token::Op(Op::Eq), 2),
+            '!' if next == Some('=') => (Token::Op(Op::Ne), 2),
+            '!' => (Token::Not, 1),
+            '<' if next == Some('=') => (Token::Op(Op::Le), 2),
+            '<' => (Token::Op(Op::Lt), 1),
+            '>' if next == Some('=') => (Token::Op(Op::Ge), 2),
+            '>' => (Token::Op(Op::Gt), 1),
+            '&' if next == Some('&') => (Token::And, 2),
+            '|' if next == Some('|') => (Token::Or, 2),
+            '"' => {
+                // the escapes other than `\"` are kept, to be interpreted by `contains` or by the regular expression
+                let mut text = String::new();
+                let mut end = i + 1;
+                loop {
+                    match (chars.get(end), chars.get(end + 1)) {
+                        (Some('"'), _) => break,
+                        (Some('\\'), Some('"')) => { text.push('"'); end += 2; },
+                        (Some('\\'), Some(escaped)) => { text.push('\\'); text.push(*escaped); end += 2; },
+                        (Some(c), _) => { text.push(*c); end += 1; },
+                        (None, _) => return Err(filter_error(format!("unterminated string at position {}", position))),
+                    }
+                }
+                (Token::Quoted(text), end + 1 - i)
+            },
+            c if is_word_char(c) => {
+                let length = chars[i..].iter().take_while(|c| is_word_char(**c)).count();
+                let word: String = chars[i..i + length].iter().collect();
+                let token = match word.to_ascii_lowercase().as_str() {
+                    "and" => Token::And,
+                    "or" => Token::Or,
+                    "not" => Token::Not,
+                    "in" => Token::Op(Op::In),
+                    "contains" => Token::Op(Op::Contains),
+                    "matches" => Token::Op(Op::Matches),
+                    _ => Token::Word(word),
+                };
+                (token, length)
+            },
+            c => return Err(filter_error(format!("unexpected character `{}` at position {}", c, position))),
+        };
+        tokens.push((token, position));
+        i += length;
+    }
+    Ok(tokens)
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '.' | ':' | '/' | '-' | '_')
+}
+
+/// Recursive descent parser, from the lowest precedence (`or`) to the highest (`not` and the terms)
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    index: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.index).map(|(token, _)| token)
+    }
+
+    fn next(&mut self) -> Option<(Token, usize)> {
+        let token = self.tokens.get(self.index).cloned();
+        self.index += 1;
+        token
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.index += 1;
+            return true;
+        }
+        false
+    }
+
+    fn unexpected(token: Option<(Token, usize)>) -> RGError {
+        match token {
+            Some((token, position)) => filter_error(format!("unexpected {} at position {}", token, position)),
+            None => filter_error(String::from("unexpected end of the filter")),
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr> {
+        let mut expr = self.and()?;
+        while self.eat(&Token::Or) {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    /// Terms one after the other are joined with `and`
+    fn and(&mut self) -> Result<Expr> {
+        let mut expr = self.not()?;
+        loop {
+            let implicit = matches!(self.peek(), Some(Token::Word(_) | Token::Quoted(_) | Token::Not | Token::LParen));
+            if !self.eat(&Token::And) && !implicit {
+                return Ok(expr);
+            }
+            expr = Expr::And(Box::new(expr), Box::new(self.not()?));
+        }
+    }
+
+    fn not(&mut self) -> Result<Expr> {
+        if self.eat(&Token::Not) {
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+        self.term()
+    }
+
+    fn term(&mut self) -> Result<Expr> {
+        match self.next() {
+            Some((Token::LParen, position)) => {
+                let expr = self.or()?;
+                if !self.eat(&Token::RParen) {
+                    return match self.tokens.get(self.index) {
+                        Some(_) => Err(Parser::unexpected(self.next())),
+                        None => Err(filter_error(format!("missing `)` for the `(` at position {}", position))),
+                    };
+                }
+                Ok(expr)
+            },
+            Some((Token::Word(word), position)) => {
+                if let Some(Token::Op(op)) = self.peek().cloned() {
+                    let field = Field::parse(&word).ok_or_else(|| filter_error(format!("unknown field `{}` at position {}", word, position)))?;
+                    self.index += 1;
+                    return self.comparison(field, &word, op);
+                }
+                if Field::parse(&word).is_some() {
+                    return Err(filter_error(format!("expected a comparison after the field `{}` at position {}", word, position)));
+                }
+                Ok(Expr::Keyword(keyword(&word, position)?))
+            },
+            Some((Token::Quoted(text), _)) => Ok(Expr::Keyword(Value::Name(text))),
+            token => Err(Parser::unexpected(token)),
+        }
+    }
+
+    fn comparison(&mut self, field: Field, name: &str, op: Op) -> Result<Expr> {
+        if matches!(op, Op::Lt | Op::Le | Op::Gt | Op::Ge) && !field.is_numeric() {
+            return Err(filter_error(format!("`{}` can't be compared with `{}`, it's not a number", name, op)));
+        }
+        if field == Field::Payload && !matches!(op, Op::Contains | Op::Matches) {
+            return Err(filter_error(format!("`{}` can only be compared with `contains` or `matches`", name)));
+        }
+        if field != Field::Payload && matches!(op, Op::Contains | Op::Matches) {
+            return Err(filter_error(format!("`{}` can't be compared with `{}`, it's not the payload", name, op)));
+        }
+        let mut values = Vec::new();
+        if op == Op::In && self.eat(&Token::LBrace) {
+            while !self.eat(&Token::RBrace) {
+                values.push(self.value(field, op)?);
+                self.eat(&Token::Comma);
+            }
+            if values.is_empty() {
+                return Err(filter_error(format!("empty set of values for `{}`", name)));
+            }
+        } else {
+            values.push(self.value(field, op)?);
+        }
+        Ok(Expr::Compare(field, op, values))
+    }
+
+    fn value(&mut self, field: Field, op: Op) -> Result<Value> {
+        let (text, position, quoted) = match self.next() {
+            Some((Token::Word(word), position)) => (word, position, false),
+            Some((Token::Quoted(text), position)) if !field.is_numeric() => (text, position, true),
+            Some((token, position)) => return Err(filter_error(format!("expected a value after `{}`, found {} at position {}", op, token, position))),
+            None => return Err(filter_error(format!("expected a value after `{}`", op))),
+        };
+        match field {
+            Field::Ip(_) => Cidr::parse(&text).map(Value::Address).or_else(|| mac_address(&text).map(Value::Mac))
+                .ok_or_else(|| filter_error(format!("`{}` at position {} is not an IP address, a CIDR block or a MAC address", text, position))),
+            Field::Port(..) | Field::Length => {
+                let number = |text: &str| text.parse::<u64>().map_err(|_| filter_error(format!("`{}` at position {} is not a number", text, position)));
+                match text.split_once("..") {
+                    Some(_) if op != Op::In => Err(filter_error(format!("the range `{}` at position {} needs the `in` operator", text, position))),
+                    Some((first, last)) => {
+                        let (first, last) = (number(first)?, number(last)?);
+                        if first > last {
+                            return Err(filter_error(format!("the range `{}` at position {} is empty", text, position)));
+                        }
+                        Ok(Value::Range(first, last))
+                    },
+                    None => Ok(Value::Number(number(&text)?)),
+                }
+            },
+            Field::Proto | Field::Service | Field::TcpFlags => Ok(Value::Name(text)),
+            Field::Payload => {
+                match (op, quoted) {
+                    (Op::Matches, true) => Regex::new(&text).map(|regex| Value::Regex(PayloadRegex(regex)))
+                        .map_err(|_| filter_error(format!("invalid regular expression \"{}\" at position {}", text, position))),
+                    (Op::Matches, false) => Err(filter_error(format!("expected a quoted regular expression at position {}", position))),
+                    (_, true) => unescape(&text).map(Value::Bytes)
+                        .ok_or_else(|| filter_error(format!("invalid escape in \"{}\" at position {}", text, position))),
+                    (_, false) => hex_bytes(&text).map(Value::Bytes)
+                        .ok_or_else(|| filter_error(format!("`{}` at position {} is neither quoted text nor hex bytes (47:45:54 or 0x474554)", text, position))),
+                }
+            },
+        }
+    }
+}
+
+/// Bytes of quoted text, with the `\\`, `\r`, `\n`, `\t` and `\xNN` escapes
+fn unescape(text: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buffer = [0; 4];
+            bytes.extend_from_slice(c.encode_utf8(&mut buffer).as_bytes());
+            continue;
+        }
+        match chars.next()? {
+            '\\' => bytes.push(b'\\'),
+            '"' => bytes.push(b'"'),
+            'r' => bytes.push(b'\r'),
+            'n' => bytes.push(b'\n'),
+            't' => bytes.push(b'\t'),
+            'x' => {
+                let hex: String = chars.by_ref().take(2).collect();
+                bytes.push(u8::from_str_radix(&hex, 16).ok().filter(|_| hex.len() == 2)?);
+            },
+            _ => return None,
+        }
+    }
+    Some(bytes)
+}
+
+/// Bytes written in hex, separated by colons (`47:45:54`) or after `0x` (`0x474554`)
+fn hex_bytes(text: &str) -> Option<Vec<u8>> {
+    let bytes: Vec<&str> = match text.strip_prefix("0x") {
+        Some(hex) if hex.len() % 2 == 0 => (0..hex.len()).step_by(2).map(|i| hex.get(i..i + 2)).collect::<Option<_>>()?,
+        Some(_) => return None,
+        None => text.split(':').collect(),
+    };
+    if bytes.is_empty() || bytes.iter().any(|byte| byte.len() != 2) {
+        return None;
+    }
+    bytes.iter().map(|byte| u8::from_str_radix(byte, 16).ok()).collect()
+}
+
+/// Value of a keyword: an IP address or CIDR block, a MAC address, a port, or a protocol, service or TCP flag name
+fn keyword(word: &str, position: usize) -> Result<Value> {
+    if let Some(cidr) = Cidr::parse(word) {
+        return Ok(Value::Address(cidr));
+    }
+    if let Some(mac) = mac_address(word) {
+        return Ok(Value::Mac(mac));
+    }
+    if word.chars().all(|c| c.is_ascii_digit()) {
+        return word.parse::<u16>().map(|port| Value::Number(port as u64))
+            .map_err(|_| filter_error(format!("`{}` at position {} is not a port number", word, position)));
+    }
+    if word.contains('.') || word.contains(':') || word.contains('/') {
+        return Err(filter_error(format!("`{}` at position {} is neither an IP or MAC address nor a known field", word, position)));
+    }
+    Ok(Value::Name(word.to_string()))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// `Packet Filter` parsed from its expression, selecting the packets analyzed by the report generator
+pub struct PacketFilter {
+    /// Expression as given
+    source: String,
+    /// Parsed expression, none when the filter is empty (every packet is selected)
+    expression: Option<Expr>,
+}
+
+impl PacketFilter {
+    /// Parses the filter expression, returning an error describing the first problem (with its position) if it's invalid
+    pub fn parse(filter: &str) -> Result<PacketFilter> {
+        let mut parser = Parser { tokens: tokenize(filter)?, index: 0 };
+        let expression = match parser.peek() {
+            Some(_) => Some(parser.or()?),
+            None => None,
+        };
+        if parser.index < parser.tokens.len() {
+            return Err(Parser::unexpected(parser.next()));
+        }
+        Ok(PacketFilter { source: filter.trim().to_string(), expression })
+    }
+
+    /// Whether the packet described by `report_data`, with the TCP/UDP `payload` given, is selected by the filter
+    pub fn matches(&self, report_data: &ReportDataInfo, payload: &[u8]) -> bool {
+        match &self.expression {
+            Some(expression) => expression.matches(report_data, payload),
+            None => true,
+        }
+    }
+
+    /// Whether the filter looks into the payload, that is needed only then
+    pub fn inspects_payload(&self) -> bool {
+        fn inspects_payload(expression: &Expr) -> bool {
+            match expression {
+                Expr::And(left, right) | Expr::Or(left, right) => inspects_payload(left) || inspects_payload(right),
+                Expr::Not(expression) => inspects_payload(expression),
+                Expr::Compare(field, ..) => *field == Field::Payload,
+                Expr::Keyword(_) => false,
+            }
+        }
+        self.expression.as_ref().is_some_and(inspects_payload)
+    }
+}
+
+impl Display for PacketFilter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
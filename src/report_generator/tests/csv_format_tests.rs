@@ -0,0 +1,16 @@
+use crate::report_generator::csv_format;
+
+#[test]
+pub fn csv_field_test() {
+    assert_eq!(csv_format::field("2001:db8::10"), "2001:db8::10");
+    assert_eq!(csv_format::field(""), "");
+    assert_eq!(csv_format::field("shop.example.com,www.example.com"), "\"shop.example.com,www.example.com\"");
+    assert_eq!(csv_format::field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    assert_eq!(csv_format::field("two\r\nlines"), "\"two\r\nlines\"");
+}
+
+#[test]
+pub fn csv_record_test() {
+    assert_eq!(csv_format::record(&["IP src", "Port src", "Server Name"]), "IP src,Port src,Server Name\r\n");
+    assert_eq!(csv_format::record(&["a,b".to_string(), String::new(), "c".to_string()]), "\"a,b\",,c\r\n");
+}
@@ -0,0 +1,198 @@
+use std::net::IpAddr;
+use chrono::{Duration, TimeZone, Utc};
+use crate::report_generator::flow_collector::{CollectedFlow, FlowCollector};
+use crate::report_generator::flow_export::{FlowExport, FlowExporter, FlowExportVersion, FlowRecord};
+
+fn exporter_address() -> IpAddr {
+    "10.0.0.1".parse().unwrap()
+}
+
+fn flow_records() -> Vec<FlowRecord> {
+    // NetFlow times are the uptime of the exporter, started before the flows
+    let first = Utc::now() + Duration::milliseconds(10);
+    let record = |ip_src: &str, ip_dst: &str, port_src, port_dst, protocol, packets, bytes| FlowRecord {
+        ip_src: ip_src.parse().unwrap(),
+        ip_dst: ip_dst.parse().unwrap(),
+        port_src,
+        port_dst,
+        protocol,
+        packets,
+        bytes,
+        first,
+        last: first + Duration::milliseconds(1500),
+    };
+    vec![record("192.168.1.90", "142.250.184.42", 33685, 443, 6, 3, 180), record("2001:db8::1", "2001:db8::35", 53000, 53, 17, 1, 86)]
+}
+
+fn exported_flows(version: FlowExportVersion) -> (Vec<FlowRecord>, Vec<CollectedFlow>) {
+    let mut exporter = FlowExporter::new(FlowExport { collector: "127.0.0.1:2055".parse().unwrap(), version }).unwrap();
+    let records = flow_records();
+    let (datagrams, _) = exporter.datagrams(&records, records[0].last + Duration::milliseconds(100));
+    let mut collector = FlowCollector::new();
+    let flows = datagrams.iter().flat_map(|datagram| collector.decode(exporter_address(), datagram, Utc::now())).collect();
+    (records, flows)
+}
+
+fn assert_flow(flow: &CollectedFlow, record: &FlowRecord, upper_service: &str, resolution_ms: i64) {
+    let report_data = &flow.report_data;
+    assert_eq!(report_data.ip_src, record.ip_src.to_string());
+    assert_eq!(report_data.ip_dst, record.ip_dst.to_string());
+    assert_eq!((report_data.port_src, report_data.port_dst), (record.port_src, record.port_dst));
+    assert_eq!(report_data.upper_service, upper_service);
+    assert_eq!((flow.num_packets, report_data.num_bytes), (record.packets as usize, record.bytes as usize));
+    assert!((flow.timestamp_init - record.first).num_milliseconds().abs() <= resolution_ms);
+    assert!((report_data.timestamp_recv - record.last).num_milliseconds().abs() <= resolution_ms);
+}
+
+#[test]
+pub fn netflow_v5_decode_test() {
+    let (records, flows) = exported_flows(FlowExportVersion::NetflowV5);
+    assert_eq!(flows.len(), 1);
+    assert_eq!(flows[0].report_data.l4_protocol, "TCP");
+    // uptimes of a millisecond, export time of a nanosecond
+    assert_flow(&flows[0], &records[0], "HTTPS", 1);
+}
+
+#[test]
+pub fn netflow_v9_decode_test() {
+    let (records, flows) = exported_flows(FlowExportVersion::NetflowV9);
+    assert_eq!(flows.len(), 2);
+    // uptimes of a millisecond, export time of a second
+    assert_flow(&flows[0], &records[0], "HTTPS", 1000);
+    assert_eq!(flows[1].report_data.l4_protocol, "UDP");
+    assert_flow(&flows[1], &records[1], "DNS", 1000);
+}
+
+#[test]
+pub fn ipfix_decode_test() {
+    let (records, flows) = exported_flows(FlowExportVersion::Ipfix);
+    assert_eq!(flows.len(), 2);
+    assert_flow(&flows[0], &records[0], "HTTPS", 1);
+    assert_flow(&flows[1], &records[1], "DNS", 1);
+}
+
+#[test]
+pub fn netflow_v9_templates_test() {
+    let mut exporter = FlowExporter::new(FlowExport { collector: "127.0.0.1:2055".parse().unwrap(), version: FlowExportVersion::NetflowV9 }).unwrap();
+    let records = flow_records();
+    let (datagrams, _) = exporter.datagrams(&records, Utc::now());
+    let datagram = &datagrams[0];
+    let templates_length = u16::from_be_bytes([datagram[22], datagram[23]]) as usize;
+    // the same message without its template FlowSet
+    let mut without_templates = datagram[..20].to_vec();
+    without_templates.extend_from_slice(&datagram[20 + templates_length..]);
+
+    let mut collector = FlowCollector::new();
+    assert!(collector.decode(exporter_address(), &without_templates, Utc::now()).is_empty());
+    assert_eq!(collector.decode(exporter_address(), datagram, Utc::now()).len(), 2);
+    // templates are kept for each exporter
+    assert_eq!(collector.decode(exporter_address(), &without_templates, Utc::now()).len(), 2);
+    assert!(collector.decode("10.0.0.2".parse().unwrap(), &without_templates, Utc::now()).is_empty());
+    // truncated messages are decoded up to the end
+    assert!(collector.decode(exporter_address(), &without_templates[..30], Utc::now()).is_empty());
+}
+
+#[test]
+pub fn ipfix_variable_length_fields_test() {
+    let export_time = Utc.timestamp_opt(1660381284, 0).unwrap();
+    let mut template = Vec::new();
+    // template 300: IPv4 addresses, protocol, packets and bytes in 4 bytes, an enterprise-specific field, the interface name
+    template.extend_from_slice(&[0, 2, 0, 40, 1, 44, 0, 7]);
+    template.extend_from_slice(&[0, 8, 0, 4, 0, 12, 0, 4, 0, 4, 0, 1, 0, 2, 0, 4, 0, 1, 0, 4]);
+    template.extend_from_slice(&[0x80, 100, 0, 2, 0, 0, 0, 9, 0, 82, 255, 255]);
+    let mut data = vec![1, 44, 0, 32];
+    data.extend_from_slice(&[192, 168, 1, 90, 8, 8, 8, 8, 1, 0, 0, 0, 3, 0, 0, 0, 252, 0, 0, 3, b'e', b't', b'h']);
+    // padding
+    data.extend_from_slice(&[0, 0, 0, 0, 0]);
+    let mut datagram = vec![0, 10];
+    datagram.extend_from_slice(&((16 + template.len() + data.len()) as u16).to_be_bytes());
+    datagram.extend_from_slice(&(export_time.timestamp() as u32).to_be_bytes());
+    datagram.extend_from_slice(&[0, 0, 0, 1, 0, 0, 0, 0]);
+    datagram.extend(template);
+    datagram.extend(data);
+
+    let mut collector = FlowCollector::new();
+    let flows = collector.decode(exporter_address(), &datagram, Utc::now());
+    assert_eq!(flows.len(), 1);
+    let report_data = &flows[0].report_data;
+    assert_eq!((report_data.ip_src.as_str(), report_data.ip_dst.as_str()), ("192.168.1.90", "8.8.8.8"));
+    assert_eq!((report_data.port_src, report_data.port_dst), (0, 0));
+    assert_eq!((report_data.l4_protocol.as_str(), report_data.upper_service.as_str()), ("ICMP", "-"));
+    assert_eq!((flows[0].num_packets, report_data.num_bytes), (3, 252));
+    // without times, the flow is at the export time
+    assert_eq!((flows[0].timestamp_init, report_data.timestamp_recv), (export_time, export_time));
+
+    // template withdrawal
+    let mut withdrawal = datagram[..16].to_vec();
+    withdrawal.extend_from_slice(&[0, 2, 0, 8, 1, 44, 0, 0]);
+    withdrawal[2..4].copy_from_slice(&24u16.to_be_bytes());
+    assert!(collector.decode(exporter_address(), &withdrawal, Utc::now()).is_empty());
+    let mut data_only = datagram[..16].to_vec();
+    data_only.extend_from_slice(&datagram[56..]);
+    let length = data_only.len() as u16;
+    data_only[2..4].copy_from_slice(&length.to_be_bytes());
+    assert!(collector.decode(exporter_address(), &data_only, Utc::now()).is_empty());
+}
+
+fn sflow_datagram(samples: &[(u32, Vec<u8>)]) -> Vec<u8> {
+    let mut datagram = vec![0, 0, 0, 5, 0, 0, 0, 1, 10, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 3, 232];
+    datagram.extend_from_slice(&(samples.len() as u32).to_be_bytes());
+    for (format, sample) in samples {
+        datagram.extend_from_slice(&format.to_be_bytes());
+        datagram.extend_from_slice(&(sample.len() as u32).to_be_bytes());
+        datagram.extend_from_slice(sample);
+    }
+    datagram
+}
+
+fn sflow_record(format: u32, data: &[u8]) -> Vec<u8> {
+    let mut record = format.to_be_bytes().to_vec();
+    record.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    record.extend_from_slice(data);
+    record
+}
+
+#[test]
+pub fn sflow_decode_test() {
+    let mut frame = vec![116, 229, 249, 22, 238, 155, 224, 185, 229, 48, 239, 152, 8, 0, 69, 0, 5, 220, 0, 0, 64, 0, 64, 6, 0, 0];
+    frame.extend_from_slice(&[192, 168, 1, 90, 142, 250, 184, 42, 131, 149, 1, 187, 0, 0, 0, 1, 0, 0, 0, 0, 0x50, 0x10, 255, 255, 0, 0, 0, 0]);
+    frame.extend_from_slice(&[0; 2]);
+    let mut raw_header = vec![0, 0, 0, 1, 0, 0, 5, 234, 0, 0, 0, 4];
+    raw_header.extend_from_slice(&(frame.len() as u32).to_be_bytes());
+    raw_header.extend_from_slice(&frame);
+
+    // flow sample: sequence number, source ID, sampling rate 100, sample pool, drops, interfaces, records
+    let mut flow_sample = vec![0, 0, 0, 1, 0, 0, 0, 3, 0, 0, 0, 100, 0, 0, 39, 16, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 4, 0, 0, 0, 1];
+    flow_sample.extend(sflow_record(1, &raw_header));
+
+    // expanded flow sample with the IPv6 data of the sampled packet only
+    let mut ipv6_data = vec![0, 0, 5, 0, 0, 0, 0, 17];
+    ipv6_data.extend_from_slice(&"2001:db8::1".parse::<std::net::Ipv6Addr>().unwrap().octets());
+    ipv6_data.extend_from_slice(&"2001:db8::35".parse::<std::net::Ipv6Addr>().unwrap().octets());
+    ipv6_data.extend_from_slice(&[0, 0, 207, 8, 0, 0, 0, 53, 0, 0, 0, 0, 0, 0, 0, 0]);
+    let mut expanded_flow_sample = vec![0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 10, 0, 0, 0, 100, 0, 0, 0, 0];
+    expanded_flow_sample.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 4, 0, 0, 0, 1]);
+    expanded_flow_sample.extend(sflow_record(4, &ipv6_data));
+
+    let datagram = sflow_datagram(&[(1, flow_sample), (2, vec![0; 12]), (3, expanded_flow_sample)]);
+    let received = Utc::now();
+    let flows = FlowCollector::new().decode(exporter_address(), &datagram, received);
+    assert_eq!(flows.len(), 2);
+
+    let report_data = &flows[0].report_data;
+    assert_eq!((report_data.ip_src.as_str(), report_data.ip_dst.as_str()), ("192.168.1.90", "142.250.184.42"));
+    assert_eq!((report_data.port_src, report_data.port_dst), (33685, 443));
+    assert_eq!((report_data.l4_protocol.as_str(), report_data.upper_service.as_str()), ("TCP", "HTTPS"));
+    // scaled by the sampling rate
+    assert_eq!((flows[0].num_packets, report_data.num_bytes), (100, 1514 * 100));
+    assert!(!report_data.bad_checksum);
+    assert_eq!((flows[0].timestamp_init, report_data.timestamp_recv), (received, received));
+
+    let report_data = &flows[1].report_data;
+    assert_eq!((report_data.ip_src.as_str(), report_data.port_dst, report_data.upper_service.as_str()), ("2001:db8::1", 53, "DNS"));
+    assert_eq!((flows[1].num_packets, report_data.num_bytes), (10, 1280 * 10));
+
+    // other protocols, truncated datagrams
+    assert!(FlowCollector::new().decode(exporter_address(), b"\x01\x02\x03", received).is_empty());
+    assert!(FlowCollector::new().decode(exporter_address(), &datagram[..60], received).is_empty());
+}
@@ -0,0 +1,151 @@
+use chrono::{TimeZone, Utc};
+use crate::report_generator::flow_export::{FlowExport, FlowExporter, FlowExportVersion, FlowRecord};
+
+fn flow_record(ip_src: &str, ip_dst: &str, packets: u64, bytes: u64) -> FlowRecord {
+    FlowRecord {
+        ip_src: ip_src.parse().unwrap(),
+        ip_dst: ip_dst.parse().unwrap(),
+        port_src: 33685,
+        port_dst: 443,
+        protocol: 6,
+        packets,
+        bytes,
+        first: Utc.timestamp_millis_opt(1660381284713).unwrap(),
+        last: Utc.timestamp_millis_opt(1660381285713).unwrap(),
+    }
+}
+
+fn flow_exporter(version: FlowExportVersion) -> FlowExporter {
+    FlowExporter::new(FlowExport { collector: "127.0.0.1:2055".parse().unwrap(), version }).unwrap()
+}
+
+fn u16_at(datagram: &[u8], offset: usize) -> u16 {
+    u16::from_be_bytes([datagram[offset], datagram[offset + 1]])
+}
+
+fn u32_at(datagram: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes(datagram[offset..offset + 4].try_into().unwrap())
+}
+
+fn u64_at(datagram: &[u8], offset: usize) -> u64 {
+    u64::from_be_bytes(datagram[offset..offset + 8].try_into().unwrap())
+}
+
+#[test]
+pub fn flow_export_version_parse_test() {
+    assert_eq!(FlowExportVersion::parse("v5"), Some(FlowExportVersion::NetflowV5));
+    assert_eq!(FlowExportVersion::parse("NetFlow-v9"), Some(FlowExportVersion::NetflowV9));
+    assert_eq!(FlowExportVersion::parse("IPFIX"), Some(FlowExportVersion::Ipfix));
+    assert_eq!(FlowExportVersion::parse("v7"), None);
+}
+
+#[test]
+pub fn netflow_v5_datagram_test() {
+    let mut exporter = flow_exporter(FlowExportVersion::NetflowV5);
+    let records = [flow_record("192.168.1.90", "142.250.184.42", 3, 180), flow_record("fe80::1", "fe80::2", 1, 86)];
+    let now = Utc::now();
+    let (datagrams, count) = exporter.datagrams(&records, now);
+
+    // IPv6 flows can't be exported with NetFlow v5
+    assert_eq!(count, 1);
+    assert_eq!(datagrams.len(), 1);
+    let datagram = &datagrams[0];
+    assert_eq!(datagram.len(), 24 + 48);
+    assert_eq!(u16_at(datagram, 0), 5);
+    assert_eq!(u16_at(datagram, 2), 1);
+    assert_eq!(u32_at(datagram, 8), now.timestamp() as u32);
+    assert_eq!(u32_at(datagram, 16), 0);
+    assert_eq!(datagram[24..28], [192, 168, 1, 90]);
+    assert_eq!(datagram[28..32], [142, 250, 184, 42]);
+    assert_eq!(u32_at(datagram, 40), 3);
+    assert_eq!(u32_at(datagram, 44), 180);
+    assert_eq!(u16_at(datagram, 56), 33685);
+    assert_eq!(u16_at(datagram, 58), 443);
+    assert_eq!(datagram[62], 6);
+
+    // the flow sequence counts the records sent so far, 30 records at most in a datagram
+    let records = vec![flow_record("192.168.1.90", "142.250.184.42", 1, 60); 31];
+    let (datagrams, count) = exporter.datagrams(&records, now);
+    assert_eq!(count, 31);
+    assert_eq!(datagrams.iter().map(|datagram| u16_at(datagram, 2)).collect::<Vec<u16>>(), vec![30, 1]);
+    assert_eq!(datagrams.iter().map(|datagram| u32_at(datagram, 16)).collect::<Vec<u32>>(), vec![1, 31]);
+}
+
+#[test]
+pub fn netflow_v9_datagram_test() {
+    let mut exporter = flow_exporter(FlowExportVersion::NetflowV9);
+    let mut records = [flow_record("192.168.1.90", "142.250.184.42", 3, 180), flow_record("fe80::1", "fe80::2", 1, 86)];
+    // NetFlow v9 times are the uptime of the exporter
+    let now = Utc::now();
+    records[0].first = now;
+    records[0].last = now + chrono::Duration::milliseconds(1000);
+    let (datagrams, count) = exporter.datagrams(&records, now);
+
+    assert_eq!(count, 2);
+    assert_eq!(datagrams.len(), 1);
+    let datagram = &datagrams[0];
+    assert_eq!(u16_at(datagram, 0), 9);
+    // two templates and two data records
+    assert_eq!(u16_at(datagram, 2), 4);
+    assert_eq!(u32_at(datagram, 12), 0);
+
+    // template FlowSet with the IPv4 and IPv6 templates, 9 fields each
+    assert_eq!(u16_at(datagram, 20), 0);
+    let templates_length = u16_at(datagram, 22) as usize;
+    assert_eq!(templates_length, 4 + 2 * (4 + 9 * 4));
+    assert_eq!((u16_at(datagram, 24), u16_at(datagram, 26)), (256, 9));
+    assert_eq!((u16_at(datagram, 28), u16_at(datagram, 30)), (8, 4));
+    assert_eq!((u16_at(datagram, 56), u16_at(datagram, 58)), (22, 4));
+    assert_eq!((u16_at(datagram, 64), u16_at(datagram, 66)), (257, 9));
+    assert_eq!((u16_at(datagram, 68), u16_at(datagram, 70)), (27, 16));
+
+    // data FlowSets, padded to 4 bytes
+    let ipv4_set = 20 + templates_length;
+    assert_eq!(u16_at(datagram, ipv4_set), 256);
+    assert_eq!(u16_at(datagram, ipv4_set + 2), 4 + 40);
+    assert_eq!(datagram[ipv4_set + 4..ipv4_set + 8], [192, 168, 1, 90]);
+    assert_eq!(u16_at(datagram, ipv4_set + 12), 33685);
+    assert_eq!(datagram[ipv4_set + 16], 6);
+    assert_eq!(u64_at(datagram, ipv4_set + 17), 3);
+    assert_eq!(u64_at(datagram, ipv4_set + 25), 180);
+    assert_eq!(u32_at(datagram, ipv4_set + 37) - u32_at(datagram, ipv4_set + 33), 1000);
+    let ipv6_set = ipv4_set + 44;
+    assert_eq!(u16_at(datagram, ipv6_set), 257);
+    assert_eq!(u16_at(datagram, ipv6_set + 2), 68);
+    assert_eq!(datagram.len(), ipv6_set + 68);
+
+    // the sequence counts the datagrams sent so far
+    let (datagrams, _) = exporter.datagrams(&records, Utc::now());
+    assert_eq!(u32_at(&datagrams[0], 12), 1);
+}
+
+#[test]
+pub fn ipfix_datagram_test() {
+    let mut exporter = flow_exporter(FlowExportVersion::Ipfix);
+    let records = vec![flow_record("192.168.1.90", "142.250.184.42", 3, 180); 40];
+    let now = Utc::now();
+    let (datagrams, count) = exporter.datagrams(&records, now);
+
+    assert_eq!(count, 40);
+    assert!(datagrams.len() > 1);
+    let mut sequence = 0;
+    for datagram in &datagrams {
+        assert!(datagram.len() <= 1400);
+        assert_eq!(u16_at(datagram, 0), 10);
+        assert_eq!(u16_at(datagram, 2) as usize, datagram.len());
+        assert_eq!(u32_at(datagram, 4), now.timestamp() as u32);
+        // the sequence number counts the data records sent so far
+        assert_eq!(u32_at(datagram, 8), sequence);
+        // template set with the IPFIX set ID, flow start and end in milliseconds
+        assert_eq!(u16_at(datagram, 16), 2);
+        let templates_length = u16_at(datagram, 18) as usize;
+        assert_eq!((u16_at(datagram, 52), u16_at(datagram, 54)), (152, 8));
+        let data_set = 16 + templates_length;
+        assert_eq!(u16_at(datagram, data_set), 256);
+        let records = (u16_at(datagram, data_set + 2) as usize - 4) / 45;
+        assert_eq!(u64_at(datagram, data_set + 4 + 29), 1660381284713);
+        assert_eq!(u64_at(datagram, data_set + 4 + 37), 1660381285713);
+        sequence += records as u32;
+    }
+    assert_eq!(sequence, 40);
+}
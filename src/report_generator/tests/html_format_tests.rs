@@ -0,0 +1,47 @@
+use std::collections::BTreeMap;
+use chrono::{TimeZone, Utc};
+use crate::report_generator::{FlowStatus, ReportEntry};
+use crate::report_generator::html_format;
+use crate::report_generator::tests::report_entry;
+
+#[test]
+pub fn html_render_test() {
+    let https = report_entry("192.168.1.119", "142.250.184.46", "UDP", "HTTPS", 2, 3000);
+    let dns = ReportEntry { flow_status: FlowStatus::Ended, ..report_entry("192.168.1.119", "192.168.1.1", "UDP", "DNS", 2, 1000) };
+    let script = ReportEntry { flow_status: FlowStatus::Continuing, ..report_entry("192.168.1.119", "10.0.0.1", "TCP", "<script>", 2, 0) };
+    let columns = vec!["IP src".to_string(), "IP dst".to_string(), "Upper Service".to_string()];
+    let flows: Vec<(&ReportEntry, Vec<String>)> = [&https, &dns, &script].into_iter()
+        .map(|entry| (entry, vec![entry.ip_src.clone(), entry.ip_dst.clone(), entry.upper_service.clone()]))
+        .collect();
+    let bandwidth = BTreeMap::from([(1660253626, 3000), (1660253628, 1000)]);
+
+    let page = html_format::render(&columns, &flows, &bandwidth, 1, Utc.with_ymd_and_hms(2022, 8, 11, 21, 34, 0).unwrap());
+    assert!(page.starts_with("<!DOCTYPE html>"));
+    assert!(page.ends_with("</html>\n"));
+    // self-contained
+    assert!(!page.contains("src=\"http") && !page.contains("href=\"http"));
+    assert!(page.contains("<tr><td>Flows</td><td>3</td></tr>"));
+    assert!(page.contains("<tr><td>Bytes</td><td>3.9 KiB</td></tr>"));
+    assert!(page.contains("<tr><td>Packets with bad checksum</td><td>1</td></tr>"));
+    assert!(page.contains("<tr><td>UDP</td><td>2</td><td>4</td><td>4000</td><td>100.0%</td>"));
+    assert!(page.contains("<tr><td>HTTPS</td><td>1</td><td>2</td><td>3000</td><td>75.0%</td>"));
+    assert!(page.contains("<th>IP src</th><th>IP dst</th><th>Upper Service</th>"));
+    assert!(page.contains("<td>&lt;script&gt;</td>"));
+    assert!(!page.contains("<td><script>"));
+    assert!(page.contains("<tr><td>192.168.1.119</td><td>4000</td><td>0</td><td>6</td><td>0</td></tr>"));
+    assert!(page.contains("<tr><td>142.250.184.46</td><td>192.168.1.119</td><td>1</td><td>0</td><td>3000</td><td>0</td><td>2</td></tr>"));
+    assert!(page.contains("<td style=\"padding-left: 3.5em\">UDP</td><td>4</td><td>66.7%</td><td>4000</td><td>100.0%</td>"));
+    assert!(page.contains("<td style=\"padding-left: 5em\">&lt;script&gt;</td>"));
+    // a point for each second, the highest rate on top
+    assert!(page.contains("<polyline points=\"80.0,20.0 430.0,200.0 780.0,140.0\""));
+    assert!(page.contains(">2.9 KiB/s</text>"));
+    assert!(page.contains(">21:33:46</text>") && page.contains(">21:33:48</text>"));
+}
+
+#[test]
+pub fn html_render_empty_test() {
+    let page = html_format::render(&["IP src".to_string()], &[], &BTreeMap::new(), 0, Utc::now());
+    assert!(page.contains("<p>No packets in the report interval.</p>"));
+    assert!(page.contains("<tr><td>Flows</td><td>0</td></tr>"));
+    assert!(!page.contains("<svg"));
+}
@@ -0,0 +1,45 @@
+use serde_json::{json, Value};
+use crate::EthernetPacket;
+use crate::report_generator::json_format::{JsonPacket, SCHEMA_VERSION};
+use crate::report_generator::tests::PACKET;
+
+fn json_packet(ether_packet: &EthernetPacket) -> Value {
+    serde_json::to_value(JsonPacket::new(ether_packet)).unwrap()
+}
+
+#[test]
+pub fn json_packet_tcp_test() {
+    let packet = json_packet(&EthernetPacket::from_json(PACKET).unwrap());
+
+    assert_eq!(packet["schema_version"], SCHEMA_VERSION);
+    assert_eq!(packet["timestamp"], "2022-08-13T09:01:24.713816Z");
+    assert_eq!(packet["size"], 66);
+    assert_eq!(packet["ethernet"], json!({ "src": "e0:b9:e5:30:ef:98", "dst": "74:e5:f9:16:ee:9b", "ether_type": "IPv4" }));
+    assert_eq!(packet["ip"], json!({ "version": 4, "src": "3.93.161.174", "dst": "192.168.1.119", "ttl": 233, "protocol": 6, "protocol_name": "TCP" }));
+    assert_eq!(packet["tcp"], json!({
+        "src_port": 443, "dst_port": 55070, "sequence_number": 2579486785u32, "ack_number": 518000164,
+        "flags": ["ACK"], "window_size": 114, "payload_length": 0
+    }));
+    assert_eq!(packet["udp"], Value::Null);
+    assert_eq!(packet["icmp"], Value::Null);
+    assert_eq!(packet["service"]["name"], "HTTPS");
+}
+
+#[test]
+pub fn json_packet_other_layers_test() {
+    let mut icmp_frame = vec![116, 229, 249, 22, 238, 155, 224, 185, 229, 48, 239, 152, 8, 0, 69, 0, 0, 28, 0, 1, 0, 0, 64, 1, 0, 0, 192, 168, 1, 90, 8, 8, 8, 8];
+    icmp_frame.extend_from_slice(&[8, 0, 0, 0, 0, 1, 0, 1]);
+    let packet = json_packet(&EthernetPacket::new(&icmp_frame));
+    assert_eq!(packet["ip"]["protocol_name"], "ICMP");
+    assert_eq!(packet["ip"]["ttl"], 64);
+    assert_eq!(packet["icmp"], json!({ "type": 8, "code": 0, "type_name": "Echo Request" }));
+    assert_eq!(packet["tcp"], Value::Null);
+    assert_eq!(packet["service"], Value::Null);
+    assert_eq!(packet["bad_checksum"], true);
+
+    let arp_request = EthernetPacket::new(&[255, 255, 255, 255, 255, 255, 224, 185, 229, 48, 239, 152, 8, 6, 0, 1, 8, 0, 6, 4, 0, 1]);
+    let packet = json_packet(&arp_request);
+    assert_eq!(packet["ethernet"]["ether_type"], "ARP");
+    assert_eq!(packet["ethernet"]["dst"], "ff:ff:ff:ff:ff:ff");
+    assert_eq!(packet["ip"], Value::Null);
+}
@@ -1,10 +1,65 @@
+use chrono::{TimeZone, Utc};
+use crate::report_generator::{FlowStatus, ReportEntry};
+
 const PACKET: &'static str = "{\"mac_addr_dst\":{\"mac_raw\":[116,229,249,22,238,155]},\"mac_addr_src\":{\"mac_raw\":[224,185,229,48,239,152]},\"ether_type\":\"IPV4\",\"payload\":[69,0,0,52,61,246,64,0,233,6,236,162,3,93,161,174,192,168,1,119,1,187,215,30,153,191,216,65,30,224,14,36,128,16,0,114,12,83,0,0,1,1,8,10,93,226,9,254,1,227,33,43],\"size\":66,\"timestamp_recv\":\"2022-08-13T09:01:24.713816911Z\",\"report_data\":{\"ip_src\":\"3.93.161.174\",\"ip_dst\":\"192.168.1.119\",\"port_src\":443,\"port_dst\":55070,\"l4_protocol\":\"TCP\",\"upper_service\":\"HTTPS\",\"num_bytes\":66,\"timestamp_recv\":\"2022-08-13T09:01:24.713816911Z\"}}";
 const PACKET2: &'static str = "{\"mac_addr_dst\":{\"mac_raw\":[116,229,249,22,238,155]},\"mac_addr_src\":{\"mac_raw\":[224,185,229,48,239,152]},\"ether_type\":\"IPV4\",\"payload\":[69,0,0,52,61,246,64,0,233,6,236,162,3,93,161,174,192,168,1,120,1,187,215,30,153,191,216,65,30,224,14,36,128,16,0,114,12,83,0,0,1,1,8,10,93,226,9,254,1,227,33,43],\"size\":66,\"timestamp_recv\":\"2022-08-13T09:01:24.713816911Z\",\"report_data\":{\"ip_src\":\"3.93.161.174\",\"ip_dst\":\"192.168.1.120\",\"port_src\":443,\"port_dst\":55070,\"l4_protocol\":\"TCP\",\"upper_service\":\"HTTPS\",\"num_bytes\":66,\"timestamp_recv\":\"2022-08-13T09:01:24.713816911Z\"}}";
 const PACKET3: &'static str = "{\"mac_addr_dst\":{\"mac_raw\":[116,229,249,22,238,155]},\"mac_addr_src\":{\"mac_raw\":[224,185,229,48,239,152]},\"ether_type\":\"IPV4\",\"payload\":[69,0,0,52,61,246,64,0,233,6,236,162,3,93,161,174,192,168,1,121,1,187,215,30,153,191,216,65,30,224,14,36,128,16,0,114,12,83,0,0,1,1,8,10,93,226,9,254,1,227,33,43],\"size\":66,\"timestamp_recv\":\"2022-08-13T09:01:24.713816911Z\",\"report_data\":{\"ip_src\":\"3.93.161.174\",\"ip_dst\":\"192.168.1.121\",\"port_src\":443,\"port_dst\":55070,\"l4_protocol\":\"TCP\",\"upper_service\":\"HTTPS\",\"num_bytes\":66,\"timestamp_recv\":\"2022-08-13T09:01:24.713816911Z\"}}";
 const PACKET_SIZE: usize = 66;
 
+/// `Report Entry` of the report interval between the two addresses, the other fields being zero or empty
+fn report_entry(ip_src: &str, ip_dst: &str, l4_protocol: &str, upper_service: &str, interval_packets: usize, interval_bytes: usize) -> ReportEntry {
+    let timestamp = Utc.with_ymd_and_hms(2022, 8, 11, 21, 33, 46).unwrap();
+    ReportEntry {
+        ip_src: ip_src.to_string(),
+        ip_dst: ip_dst.to_string(),
+        port_src: 0,
+        port_dst: 0,
+        l4_protocol: l4_protocol.to_string(),
+        upper_service: upper_service.to_string(),
+        num_bytes: interval_bytes,
+        num_packets: interval_packets,
+        interval_bytes,
+        interval_packets,
+        min_size: 0,
+        max_size: 0,
+        min_inter_arrival: None,
+        max_inter_arrival: None,
+        timestamp_init: timestamp,
+        timestamp_final: timestamp,
+        interval_timestamp_init: timestamp,
+        interval_timestamp_final: timestamp,
+        flow_status: FlowStatus::New,
+        tcp_connection: None,
+        directions: None,
+        interval_directions: None,
+        alpn: None,
+        server_name: None,
+    }
+}
+
 #[cfg(test)]
 pub mod report_generator_main_tests;
 
 #[cfg(test)]
 pub mod report_generator_inner_tests;
+
+#[cfg(test)]
+pub mod packet_filter_tests;
+
+#[cfg(test)]
+pub mod csv_format_tests;
+
+#[cfg(test)]
+pub mod json_format_tests;
+
+#[cfg(test)]
+pub mod html_format_tests;
+
+#[cfg(test)]
+pub mod flow_export_tests;
+
+#[cfg(test)]
+pub mod flow_collector_tests;
+
+#[cfg(test)]
+pub mod traffic_summary_tests;
@@ -0,0 +1,143 @@
+use chrono::Utc;
+use crate::report_generator::ReportDataInfo;
+use crate::report_generator::packet_filter::PacketFilter;
+
+fn report_data(ip_src: &str, ip_dst: &str, port_src: u16, port_dst: u16, l4_protocol: &str, upper_service: &str, tcp_flags: &[&str]) -> ReportDataInfo {
+    ReportDataInfo {
+        ip_src: ip_src.to_string(),
+        ip_dst: ip_dst.to_string(),
+        port_src,
+        port_dst,
+        l4_protocol: l4_protocol.to_string(),
+        upper_service: upper_service.to_string(),
+        service_confidence: 50,
+        num_bytes: 120,
+        timestamp_recv: Utc::now(),
+        tcp_flags: tcp_flags.iter().map(|flag| flag.to_string()).collect(),
+        bad_checksum: false,
+    }
+}
+
+fn matches(filter: &str, report_data: &ReportDataInfo) -> bool {
+    PacketFilter::parse(filter).unwrap().matches(report_data, &[])
+}
+
+#[test]
+pub fn packet_filter_keywords_test() {
+    let https = report_data("192.168.1.90", "151.101.1.69", 51000, 443, "TCP", "HTTPS", &["ACK", "PSH"]);
+
+    assert!(matches("", &https));
+    assert!(matches("TCP 443", &https));
+    assert!(matches("tcp https psh", &https));
+    assert!(matches("192.168.1.90 151.101.0.0/16", &https));
+    assert!(matches("\"https\"", &https));
+    assert!(!matches("UDP", &https));
+    assert!(!matches("TCP SYN", &https));
+    assert!(!matches("TCP 80", &https));
+    assert!(!matches("10.0.0.0/8", &https));
+}
+
+#[test]
+pub fn packet_filter_expressions_test() {
+    let https = report_data("192.168.1.90", "151.101.1.69", 51000, 443, "TCP", "HTTPS", &["ACK"]);
+    let cdn = report_data("192.168.1.90", "203.0.113.7", 51001, 443, "TCP", "HTTPS", &["ACK"]);
+    let dns = report_data("2001:db8::10", "2001:db8::53", 53000, 53, "UDP", "DNS", &[]);
+    let icmp = report_data("192.168.1.90", "8.8.8.8", 0, 0, "ICMP", "-", &[]);
+
+    let filter = PacketFilter::parse("service == https and not ip.dst in 203.0.113.0/24").unwrap();
+    assert!(filter.matches(&https, &[]));
+    assert!(!filter.matches(&cdn, &[]));
+    assert!(!filter.matches(&dns, &[]));
+
+    let filter = PacketFilter::parse("(tcp.port in 440..450 || udp.dstport == 53) && !ip.dst == 151.101.1.69").unwrap();
+    assert!(!filter.matches(&https, &[]));
+    assert!(filter.matches(&cdn, &[]));
+    assert!(filter.matches(&dns, &[]));
+    assert!(!filter.matches(&icmp, &[]));
+
+    assert!(matches("ip.addr == 2001:db8::/32 and proto == udp", &dns));
+    assert!(matches("port in {22, 53, 8000..8080}", &dns));
+    assert!(matches("srcport > 50000 and len <= 120 and len >= 100", &https));
+    assert!(!matches("tcp.srcport < 1024", &https));
+    assert!(matches("tcp.flags == ack and tcp.flags != syn", &https));
+    // none of the values is equal
+    assert!(!matches("ip.addr != 192.168.1.90", &https));
+    assert!(matches("ip.addr != 10.0.0.1", &https));
+    // fields missing in the packet
+    assert!(!matches("port == 0", &icmp));
+    assert!(matches("proto == icmp or port == 0", &icmp));
+    assert!(!matches("ip.src == 10.0.0.1", &report_data("e0:b9:e5:30:ef:98", "ff:ff:ff:ff:ff:ff", 0, 0, "ARP", "-", &[])));
+    // `not` binds tighter than `and`, `and` tighter than `or`
+    assert!(matches("not udp and tcp or dns", &https));
+    assert!(!matches("not (udp or tcp)", &https));
+    assert!(matches("udp and dns or https", &https));
+}
+
+#[test]
+pub fn packet_filter_mac_address_test() {
+    let arp = report_data("e0:b9:e5:30:ef:98", "ff:ff:ff:ff:ff:ff", 0, 0, "ARP", "-", &[]);
+    let https = report_data("192.168.1.90", "151.101.1.69", 51000, 443, "TCP", "HTTPS", &["ACK"]);
+
+    assert!(matches("ip.src == e0:b9:e5:30:ef:98", &arp));
+    assert!(matches("ip.src == E0-B9-E5-30-EF-98", &arp));
+    assert!(matches("ip.dst == ff:ff:ff:ff:ff:ff and proto == arp", &arp));
+    assert!(matches("ip.addr in {10.0.0.1, ff:ff:ff:ff:ff:ff}", &arp));
+    assert!(matches("arp and e0:b9:e5:30:ef:98", &arp));
+    assert!(!matches("ip.dst == e0:b9:e5:30:ef:98", &arp));
+    assert!(matches("not ff:ff:ff:ff:ff:ff", &https));
+    assert!(!matches("ip.src == e0:b9:e5:30:ef:98", &https));
+}
+
+#[test]
+pub fn packet_filter_payload_test() {
+    let http = report_data("192.168.1.90", "10.0.0.8", 51000, 8080, "TCP", "HTTP", &["ACK", "PSH"]);
+    let payload = b"POST /orders HTTP/1.1\r\nUser-Agent: curl/8.0\r\n\r\n{\"order_id\": \"ORD-004711\"}";
+    let payload_matches = |filter: &str, payload: &[u8]| PacketFilter::parse(filter).unwrap().matches(&http, payload);
+
+    assert!(payload_matches("payload contains \"User-Agent\"", payload));
+    assert!(payload_matches("tcp.dstport == 8080 and payload contains \"\\\"ORD-004711\\\"\"", payload));
+    assert!(!payload_matches("payload contains \"user-agent\"", payload));
+    assert!(payload_matches("payload contains \"HTTP/1.1\\r\\n\"", payload));
+    assert!(payload_matches("payload contains 50:4f:53:54", payload));
+    assert!(payload_matches("payload contains 0x504F5354 and not payload contains \"\\x00\"", payload));
+    assert!(payload_matches("payload matches \"(?i)^post /orders\"", payload));
+    assert!(payload_matches("payload matches \"ORD-0*4711\\b\"", payload));
+    assert!(!payload_matches("payload matches \"ORD-0*4712\"", payload));
+    // a packet without payload
+    assert!(!payload_matches("payload contains \"GET\"", b""));
+    assert!(payload_matches("not payload matches \".\"", b""));
+
+    assert!(!PacketFilter::parse("tcp and port == 8080").unwrap().inspects_payload());
+    assert!(PacketFilter::parse("tcp and not (port == 22 or payload contains 00:00)").unwrap().inspects_payload());
+}
+
+#[test]
+pub fn packet_filter_errors_test() {
+    let error = |filter: &str| PacketFilter::parse(filter).unwrap_err().message;
+
+    assert_eq!(error("ip.sorce == 10.0.0.1"), "unknown field `ip.sorce` at position 1");
+    assert_eq!(error("tcp and (udp or"), "unexpected end of the filter");
+    assert_eq!(error("(tcp or udp"), "missing `)` for the `(` at position 1");
+    assert_eq!(error("tcp )"), "unexpected `)` at position 5");
+    assert_eq!(error("ip.dst == 10.0.0.300"), "`10.0.0.300` at position 11 is not an IP address, a CIDR block or a MAC address");
+    assert_eq!(error("ip.dst in 10.0.0.0/40"), "`10.0.0.0/40` at position 11 is not an IP address, a CIDR block or a MAC address");
+    assert_eq!(error("tcp.port == 80..90"), "the range `80..90` at position 13 needs the `in` operator");
+    assert_eq!(error("tcp.port in 90..80"), "the range `90..80` at position 13 is empty");
+    assert_eq!(error("port == http"), "`http` at position 9 is not a number");
+    assert_eq!(error("service > http"), "`service` can't be compared with `>`, it's not a number");
+    assert_eq!(error("service =="), "expected a value after `==`");
+    assert_eq!(error("tcp.port in {}"), "empty set of values for `tcp.port`");
+    assert_eq!(error("ip.src"), "expected a comparison after the field `ip.src` at position 1");
+    assert_eq!(error("TCP 70000"), "`70000` at position 5 is not a port number");
+    assert_eq!(error("ip.src == e0:b9:e5:30:ef"), "`e0:b9:e5:30:ef` at position 11 is not an IP address, a CIDR block or a MAC address");
+    assert_eq!(error("10.0.0"), "`10.0.0` at position 1 is neither an IP or MAC address nor a known field");
+    assert_eq!(error("tcp ~ udp"), "unexpected character `~` at position 5");
+    assert_eq!(error("service == \"http"), "unterminated string at position 12");
+    assert_eq!(error("port = 80"), "unexpected character `=` at position 6");
+    assert_eq!(error("payload == GET"), "`payload` can only be compared with `contains` or `matches`");
+    assert_eq!(error("service contains http"), "`service` can't be compared with `contains`, it's not the payload");
+    assert_eq!(error("payload contains 4:45"), "`4:45` at position 18 is neither quoted text nor hex bytes (47:45:54 or 0x474554)");
+    assert_eq!(error("payload contains \"\\x4\""), "invalid escape in \"\\x4\" at position 18");
+    assert_eq!(error("payload matches GET"), "expected a quoted regular expression at position 17");
+    assert_eq!(error("payload matches \"(GET\""), "invalid regular expression \"(GET\" at position 17");
+}
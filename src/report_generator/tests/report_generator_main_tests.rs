@@ -1,3 +1,4 @@
+use std::net::UdpSocket;
 use std::sync::{Arc, Condvar, Mutex};
 use std::thread::sleep;
 use std::time::Duration;
@@ -105,4 +106,32 @@ pub fn timer_thread_stopped_test() {
     assert_eq!(report_generator.inner_struct.lock().unwrap().data_format.len(), 0);
 }
 
+#[test]
+pub fn flow_collector_thread_test() {
+    let stop_thread = Arc::new(Mutex::new(false));
+    let stop_thread_cv = Arc::new(Condvar::new());
+    let mut options = ConfigOptions::new(Device::lookup().unwrap().name.as_str(), 15, "output.txt", ReportFormat::Report, "");
+    // a free port, released to the collector
+    let collector_address = UdpSocket::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+    options.flow_collector = Some(collector_address);
+    let report_generator = ReportGenerator::new(options, stop_thread.clone(), stop_thread_cv.clone()).unwrap();
+    assert!(report_generator.collector_thread.is_some());
+
+    let mut record = vec![192, 168, 1, 90, 142, 250, 184, 42, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 180];
+    record.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 0, 131, 149, 1, 187, 0, 0, 6, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+    let mut datagram = vec![0, 5, 0, 1, 0, 0, 0, 0, 98, 247, 105, 100, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    datagram.extend(record);
+    let exporter = UdpSocket::bind("127.0.0.1:0").unwrap();
+    exporter.send_to(&datagram, collector_address).unwrap();
+    sleep(Duration::from_millis(500));
+    assert_eq!(report_generator.inner_struct.lock().unwrap().data_format.values().map(|entry| entry.num_packets).sum::<usize>(), 3);
+
+    // the datagrams received while stopped are dropped
+    *stop_thread.lock().unwrap() = true;
+    exporter.send_to(&datagram, collector_address).unwrap();
+    sleep(Duration::from_millis(500));
+    assert_eq!(report_generator.inner_struct.lock().unwrap().data_format.values().map(|entry| entry.num_packets).sum::<usize>(), 3);
+    *stop_thread.lock().unwrap() = false;
+    stop_thread_cv.notify_all();
+}
 
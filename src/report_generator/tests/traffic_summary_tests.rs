@@ -0,0 +1,92 @@
+use std::net::IpAddr;
+use crate::report_generator::DirectionCounters;
+use crate::report_generator::traffic_summary::{TrafficCounters, TrafficSummary};
+use crate::report_generator::tests::report_entry;
+
+fn ip(address: &str) -> IpAddr {
+    address.parse().unwrap()
+}
+
+#[test]
+pub fn traffic_summary_test() {
+    let entries = [
+        report_entry("192.168.1.119", "142.250.184.46", "UDP", "HTTPS", 2, 3000),
+        report_entry("142.250.184.46", "192.168.1.119", "UDP", "HTTPS", 4, 5000),
+        report_entry("192.168.1.119", "192.168.1.1", "UDP", "DNS", 2, 200),
+        report_entry("192.168.1.119", "140.82.121.3", "TCP", "HTTPS", 3, 1200),
+        report_entry("fe80::1", "ff02::1", "ICMPv6", "-", 1, 86),
+        report_entry("e0:b9:e5:30:ef:98", "ff:ff:ff:ff:ff:ff", "ARP", "-", 1, 42),
+        report_entry("-", "-", "OTHER", "-", 1, 16),
+        // continuing flow without traffic in the report interval
+        report_entry("192.168.1.119", "10.0.0.1", "TCP", "SSH", 0, 0),
+    ];
+
+    let summary = TrafficSummary::new(entries.iter(), 3);
+    let hosts: Vec<(IpAddr, usize, usize)> = summary.hosts.iter().map(|host| (host.address, host.sent.bytes, host.received.bytes)).collect();
+    assert_eq!(hosts, vec![(ip("192.168.1.119"), 4400, 5000), (ip("142.250.184.46"), 5000, 3000), (ip("140.82.121.3"), 0, 1200)]);
+    assert_eq!(summary.hosts[0].sent, TrafficCounters { packets: 7, bytes: 4400 });
+
+    // both directions of the same hosts are a single conversation
+    let conversation = &summary.conversations[0];
+    assert_eq!((conversation.address_a, conversation.address_b, conversation.flows), (ip("142.250.184.46"), ip("192.168.1.119"), 2));
+    assert_eq!((conversation.a_to_b.bytes, conversation.b_to_a.bytes), (5000, 3000));
+    assert_eq!(summary.conversations.len(), 3);
+
+    let services: Vec<(&str, usize, usize)> = summary.services.iter().map(|service| (service.service.as_str(), service.flows, service.traffic.bytes)).collect();
+    assert_eq!(services, vec![("HTTPS", 3, 9200), ("DNS", 1, 200)]);
+
+    let ethernet = &summary.protocol_hierarchy;
+    assert_eq!((ethernet.name.as_str(), ethernet.traffic), ("Ethernet", TrafficCounters { packets: 14, bytes: 9544 }));
+    let names: Vec<&str> = ethernet.children.iter().map(|child| child.name.as_str()).collect();
+    assert_eq!(names, vec!["IPv4", "IPv6", "ARP", "OTHER"]);
+    let ipv4 = ethernet.child("IPv4").unwrap();
+    assert_eq!(ipv4.traffic, TrafficCounters { packets: 11, bytes: 9400 });
+    let udp = ipv4.child("UDP").unwrap();
+    assert_eq!(udp.child("HTTPS").unwrap().traffic, TrafficCounters { packets: 6, bytes: 8000 });
+    assert_eq!(udp.child("DNS").unwrap().traffic, TrafficCounters { packets: 2, bytes: 200 });
+    assert_eq!(ipv4.child("TCP").unwrap().child("HTTPS").unwrap().traffic.bytes, 1200);
+    assert!(ethernet.child("IPv6").unwrap().child("ICMPv6").unwrap().children.is_empty());
+    let (packets_share, bytes_share) = udp.traffic.share(&ethernet.traffic);
+    assert!((packets_share - 8.0 * 100.0 / 14.0).abs() < 1e-9);
+    assert!((bytes_share - 8200.0 * 100.0 / 9544.0).abs() < 1e-9);
+}
+
+#[test]
+pub fn traffic_summary_bidirectional_test() {
+    // with bidirectional flow aggregation the entry is oriented from the client to the server
+    let mut entry = report_entry("192.168.1.119", "140.82.121.3", "TCP", "HTTPS", 16, 1849);
+    entry.interval_directions = Some((DirectionCounters { num_bytes: 617, num_packets: 9 }, DirectionCounters { num_bytes: 1232, num_packets: 7 }));
+
+    let summary = TrafficSummary::new([entry].iter(), 10);
+    let client = summary.hosts.iter().find(|host| host.address == ip("192.168.1.119")).unwrap();
+    assert_eq!((client.sent, client.received), (TrafficCounters { packets: 9, bytes: 617 }, TrafficCounters { packets: 7, bytes: 1232 }));
+    let server = summary.hosts.iter().find(|host| host.address == ip("140.82.121.3")).unwrap();
+    assert_eq!((server.sent, server.received), (client.received, client.sent));
+
+    // the lower address is "A"
+    let conversation = &summary.conversations[0];
+    assert_eq!((conversation.address_a, conversation.a_to_b.bytes, conversation.b_to_a.bytes), (ip("140.82.121.3"), 1232, 617));
+    assert_eq!(summary.protocol_hierarchy.traffic, TrafficCounters { packets: 16, bytes: 1849 });
+}
+
+#[test]
+pub fn traffic_summary_report_section_test() {
+    let idle = report_entry("192.168.1.119", "10.0.0.1", "TCP", "SSH", 0, 0);
+    assert!(TrafficSummary::new([idle].iter(), 10).report_section().is_none());
+
+    let entries = [
+        report_entry("192.168.1.119", "192.168.1.1", "UDP", "DNS", 1, 100),
+        report_entry("e0:b9:e5:30:ef:98", "ff:ff:ff:ff:ff:ff", "ARP", "-", 1, 100),
+    ];
+    let section = TrafficSummary::new(entries.iter(), 10).report_section().unwrap();
+    for title in ["Top hosts:", "Top conversations:", "Top services:", "Protocol hierarchy:"] {
+        assert!(section.lines().any(|line| line == title), "{}", title);
+    }
+    let ethernet = section.lines().find(|line| line.starts_with("  Ethernet ")).unwrap();
+    assert!(ethernet.contains("| 100.0%"));
+    let dns = section.lines().find(|line| line.starts_with("        DNS ")).unwrap();
+    assert_eq!(dns.split('|').map(str::trim).collect::<Vec<&str>>(), vec!["DNS", "1", "50.0%", "100", "50.0%"]);
+    assert!(section.lines().any(|line| line.starts_with("    ARP ")));
+    // the MAC addresses aren't hosts
+    assert!(!section.contains("e0:b9:e5:30:ef:98"));
+}
@@ -0,0 +1,266 @@
+//! # Traffic Summary
+//!
+//! Summary sections of the "report" and "html" formats, ranking the traffic of the report interval: the top hosts by bytes
+//! sent and received, the top conversations between two hosts, the top services, and the protocol hierarchy
+//! (Ethernet → IPv4/IPv6/ARP → TCP/UDP/ICMP → services) with the share of the packets and of the bytes.
+//! ```
+//! Top hosts:
+//!   Host                                    | Bytes sent      | Bytes received  | Packets sent    | Packets received
+//!   192.168.1.119                           | 2466            | 78459           | 20              | 108
+//! ...
+//! Protocol hierarchy:
+//!   Protocol                  | Packets         | % Packets | Bytes           | % Bytes
+//!   Ethernet                  | 131             | 100.0%    | 81015           | 100.0%
+//!     IPv4                    | 130             | 99.2%     | 80973           | 99.9%
+//!       UDP                   | 119             | 90.8%     | 79124           | 97.7%
+//!         HTTPS               | 117             | 89.3%     | 78954           | 97.5%
+//! ```
+//!
+//! Hosts and conversations are IP addresses: the frames without IP and the `OTHER` bucket are counted in the protocol
+//! hierarchy only.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use crate::network_components::layer_2::ethernet_packet::NO_SERVICE;
+use crate::report_generator::ReportEntry;
+
+/// Number of hosts, conversations and services ranked in the summary sections
+pub const TOP_N: usize = 10;
+
+/// Root of the protocol hierarchy: every packet captured is an Ethernet frame
+const ROOT_PROTOCOL: &str = "Ethernet";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+/// `Traffic Counters` of the report interval
+pub struct TrafficCounters {
+    pub packets: usize,
+    pub bytes: usize,
+}
+
+impl TrafficCounters {
+    fn add(&mut self, other: TrafficCounters) {
+        self.packets += other.packets;
+        self.bytes += other.bytes;
+    }
+
+    /// Share in percent of the packets and of the bytes of `total`
+    pub fn share(&self, total: &TrafficCounters) -> (f64, f64) {
+        let percent = |value: usize, total: usize| if total == 0 { 0.0 } else { value as f64 * 100.0 / total as f64 };
+        (percent(self.packets, total.packets), percent(self.bytes, total.bytes))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// `Host Traffic` sent and received by an IP address
+pub struct HostTraffic {
+    pub address: IpAddr,
+    pub sent: TrafficCounters,
+    pub received: TrafficCounters,
+}
+
+impl HostTraffic {
+    /// Bytes sent and received, ranking the hosts
+    pub fn bytes(&self) -> usize {
+        self.sent.bytes + self.received.bytes
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// `Conversation Traffic` between two IP addresses, in both directions (`address_a` is the lower address)
+pub struct ConversationTraffic {
+    pub address_a: IpAddr,
+    pub address_b: IpAddr,
+    /// Flows of the report interval between the two addresses
+    pub flows: usize,
+    pub a_to_b: TrafficCounters,
+    pub b_to_a: TrafficCounters,
+}
+
+impl ConversationTraffic {
+    /// Bytes in both directions, ranking the conversations
+    pub fn bytes(&self) -> usize {
+        self.a_to_b.bytes + self.b_to_a.bytes
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// `Service Traffic` of the flows named after an upper layer service
+pub struct ServiceTraffic {
+    pub service: String,
+    pub flows: usize,
+    pub traffic: TrafficCounters,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// `Protocol Node` of the protocol hierarchy, with the traffic of the protocol and of the ones above it
+pub struct ProtocolNode {
+    pub name: String,
+    pub traffic: TrafficCounters,
+    /// Protocols carried, sorted by bytes (largest first)
+    pub children: Vec<ProtocolNode>,
+}
+
+impl ProtocolNode {
+    fn new(name: &str) -> Self {
+        ProtocolNode { name: name.to_string(), traffic: TrafficCounters::default(), children: Vec::new() }
+    }
+
+    /// `Child` protocol carried by this one
+    pub fn child(&self, name: &str) -> Option<&ProtocolNode> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    /// `Add` the traffic of a stack of protocols carried by this one
+    fn add(&mut self, protocols: &[&str], traffic: TrafficCounters) {
+        self.traffic.add(traffic);
+        if let Some((protocol, protocols)) = protocols.split_first() {
+            let index = match self.children.iter().position(|child| child.name == *protocol) {
+                Some(index) => index,
+                None => {
+                    self.children.push(ProtocolNode::new(protocol));
+                    self.children.len() - 1
+                }
+            };
+            self.children[index].add(protocols, traffic);
+        }
+    }
+
+    fn sort(&mut self) {
+        self.children.sort_by(|child_1, child_2| child_2.traffic.bytes.cmp(&child_1.traffic.bytes).then(child_1.name.cmp(&child_2.name)));
+        self.children.iter_mut().for_each(ProtocolNode::sort);
+    }
+
+    /// `Rows` of the hierarchy, depth first, with the depth of each protocol
+    pub fn rows(&self) -> Vec<(usize, &ProtocolNode)> {
+        let mut rows = vec![(0, self)];
+        for child in self.children.iter() {
+            rows.extend(child.rows().into_iter().map(|(depth, node)| (depth + 1, node)));
+        }
+        rows
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// `Traffic Summary` of the flows of a report interval
+pub struct TrafficSummary {
+    /// Top hosts by bytes sent and received
+    pub hosts: Vec<HostTraffic>,
+    /// Top conversations by bytes in both directions
+    pub conversations: Vec<ConversationTraffic>,
+    /// Top services by bytes
+    pub services: Vec<ServiceTraffic>,
+    pub protocol_hierarchy: ProtocolNode,
+}
+
+impl TrafficSummary {
+    /// `new` summary of the traffic of the report interval of `entries`, ranking the `top_n` hosts, conversations and services
+    pub fn new<'a>(entries: impl Iterator<Item = &'a ReportEntry>, top_n: usize) -> Self {
+        let mut hosts: HashMap<IpAddr, HostTraffic> = HashMap::new();
+        let mut conversations: HashMap<(IpAddr, IpAddr), ConversationTraffic> = HashMap::new();
+        let mut services: HashMap<&str, ServiceTraffic> = HashMap::new();
+        let mut protocol_hierarchy = ProtocolNode::new(ROOT_PROTOCOL);
+
+        for entry in entries.filter(|entry| entry.interval_packets > 0) {
+            let traffic = TrafficCounters { packets: entry.interval_packets, bytes: entry.interval_bytes };
+            let ip_addrs = entry.ip_src.parse::<IpAddr>().ok().zip(entry.ip_dst.parse::<IpAddr>().ok());
+
+            let mut protocols = Vec::new();
+            match ip_addrs {
+                Some((IpAddr::V4(_), _)) => protocols.push("IPv4"),
+                Some((IpAddr::V6(_), _)) => protocols.push("IPv6"),
+                None => {},
+            }
+            protocols.push(entry.l4_protocol.as_str());
+            if entry.upper_service != NO_SERVICE {
+                protocols.push(entry.upper_service.as_str());
+                let service = services.entry(entry.upper_service.as_str())
+                    .or_insert_with(|| ServiceTraffic { service: entry.upper_service.clone(), flows: 0, traffic: TrafficCounters::default() });
+                service.flows += 1;
+                service.traffic.add(traffic);
+            }
+            protocol_hierarchy.add(&protocols, traffic);
+
+            let (ip_src, ip_dst) = match ip_addrs {
+                Some(ip_addrs) => ip_addrs,
+                None => continue,
+            };
+            // without the directions (unidirectional flow aggregation) the whole entry goes from the source to the destination
+            let (forward, backward) = match &entry.interval_directions {
+                Some((client_to_server, server_to_client)) => (
+                    TrafficCounters { packets: client_to_server.num_packets, bytes: client_to_server.num_bytes },
+                    TrafficCounters { packets: server_to_client.num_packets, bytes: server_to_client.num_bytes }),
+                None => (traffic, TrafficCounters::default()),
+            };
+            for (address, sent, received) in [(ip_src, forward, backward), (ip_dst, backward, forward)] {
+                let host = hosts.entry(address)
+                    .or_insert_with(|| HostTraffic { address, sent: TrafficCounters::default(), received: TrafficCounters::default() });
+                host.sent.add(sent);
+                host.received.add(received);
+            }
+            let (address_a, address_b, a_to_b, b_to_a) = if ip_src <= ip_dst { (ip_src, ip_dst, forward, backward) } else { (ip_dst, ip_src, backward, forward) };
+            let conversation = conversations.entry((address_a, address_b))
+                .or_insert_with(|| ConversationTraffic { address_a, address_b, flows: 0, a_to_b: TrafficCounters::default(), b_to_a: TrafficCounters::default() });
+            conversation.flows += 1;
+            conversation.a_to_b.add(a_to_b);
+            conversation.b_to_a.add(b_to_a);
+        }
+
+        let mut hosts: Vec<HostTraffic> = hosts.into_values().collect();
+        hosts.sort_by(|host_1, host_2| host_2.bytes().cmp(&host_1.bytes()).then(host_1.address.cmp(&host_2.address)));
+        hosts.truncate(top_n);
+        let mut conversations: Vec<ConversationTraffic> = conversations.into_values().collect();
+        conversations.sort_by(|conversation_1, conversation_2| conversation_2.bytes().cmp(&conversation_1.bytes())
+            .then((conversation_1.address_a, conversation_1.address_b).cmp(&(conversation_2.address_a, conversation_2.address_b))));
+        conversations.truncate(top_n);
+        let mut services: Vec<ServiceTraffic> = services.into_values().collect();
+        services.sort_by(|service_1, service_2| service_2.traffic.bytes.cmp(&service_1.traffic.bytes).then(service_1.service.cmp(&service_2.service)));
+        services.truncate(top_n);
+        protocol_hierarchy.sort();
+
+        TrafficSummary { hosts, conversations, services, protocol_hierarchy }
+    }
+
+    /// No traffic in the report interval
+    pub fn is_empty(&self) -> bool {
+        self.protocol_hierarchy.traffic.packets == 0
+    }
+
+    /// `Report Section` of the "report" format (None without traffic in the report interval)
+    pub fn report_section(&self) -> Option<String> {
+        if self.is_empty() {
+            return None;
+        }
+        let total = self.protocol_hierarchy.traffic;
+
+        let mut section = format!("Top hosts:\n  {0: <39} | {1: <15} | {2: <15} | {3: <15} | {4: <15}\n",
+            "Host", "Bytes sent", "Bytes received", "Packets sent", "Packets received");
+        for host in self.hosts.iter() {
+            section.push_str(format!("  {0: <39} | {1: <15} | {2: <15} | {3: <15} | {4: <15}\n",
+                host.address, host.sent.bytes, host.received.bytes, host.sent.packets, host.received.packets).as_str());
+        }
+
+        section.push_str(format!("\nTop conversations:\n  {0: <39} | {1: <39} | {2: <9} | {3: <15} | {4: <15} | {5: <15} | {6: <15}\n",
+            "Host A", "Host B", "Flows", "Bytes A->B", "Bytes B->A", "Packets A->B", "Packets B->A").as_str());
+        for conversation in self.conversations.iter() {
+            section.push_str(format!("  {0: <39} | {1: <39} | {2: <9} | {3: <15} | {4: <15} | {5: <15} | {6: <15}\n",
+                conversation.address_a, conversation.address_b, conversation.flows, conversation.a_to_b.bytes, conversation.b_to_a.bytes,
+                conversation.a_to_b.packets, conversation.b_to_a.packets).as_str());
+        }
+
+        section.push_str(format!("\nTop services:\n  {0: <15} | {1: <9} | {2: <15} | {3: <15} | {4: <9}\n", "Service", "Flows", "Packets", "Bytes", "% Bytes").as_str());
+        for service in self.services.iter() {
+            let (_, bytes_share) = service.traffic.share(&total);
+            section.push_str(format!("  {0: <15} | {1: <9} | {2: <15} | {3: <15} | {4: <9}\n",
+                service.service, service.flows, service.traffic.packets, service.traffic.bytes, format!("{:.1}%", bytes_share)).as_str());
+        }
+
+        section.push_str(format!("\nProtocol hierarchy:\n  {0: <25} | {1: <15} | {2: <9} | {3: <15} | {4: <9}\n", "Protocol", "Packets", "% Packets", "Bytes", "% Bytes").as_str());
+        for (depth, protocol) in self.protocol_hierarchy.rows() {
+            let (packets_share, bytes_share) = protocol.traffic.share(&total);
+            section.push_str(format!("  {0: <25} | {1: <15} | {2: <9} | {3: <15} | {4: <9}\n",
+                format!("{}{}", "  ".repeat(depth), protocol.name), protocol.traffic.packets, format!("{:.1}%", packets_share),
+                protocol.traffic.bytes, format!("{:.1}%", bytes_share)).as_str());
+        }
+        Some(section)
+    }
+}
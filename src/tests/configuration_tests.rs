@@ -2,6 +2,10 @@ use std::path::PathBuf;
 use std::time::Duration;
 use pcap::Device;
 use crate::{PacketSnooper, ReportFormat, State};
+use crate::report_generator::FlowAggregation;
+use crate::report_generator::flow_export::{FlowExport, FlowExportVersion};
+use crate::network_components::services_upper_layers::service_registry::service_registry;
+use crate::network_components::services_upper_layers::upper_layer_services::Transport;
 
 #[test]
 pub fn packet_snooper_set_device_normal_test() {
@@ -138,6 +142,17 @@ pub fn packet_snooper_set_report_format_normal_test() {
     assert_eq!(ps.config_options.report_format, ReportFormat::Report);
 }
 
+#[test]
+pub fn packet_snooper_set_report_format_csv_test() {
+    let mut ps = PacketSnooper::new();
+
+    ps.state = State::ReportFormat; // forcing packet_snooper into a specific state (not safe, just for testing purposes)
+    assert!(ps.set_report_format("csv").is_ok());
+
+    assert_eq!(ps.state, State::PacketFilter);
+    assert_eq!(ps.config_options.report_format, ReportFormat::Csv);
+}
+
 #[test]
 pub fn packet_snooper_set_report_format_in_invalid_state_test() {
     let format_report = "report";
@@ -186,6 +201,20 @@ pub fn packet_snooper_set_packet_filter_invalid_format_test() {
     assert_eq!(ps.state, State::PacketFilter);
 }
 
+#[test]
+pub fn packet_snooper_set_packet_filter_invalid_expression_test() {
+    let mut ps = PacketSnooper::new();
+
+    ps.state = State::PacketFilter; // forcing packet_snooper into a specific state (not safe, just for testing purposes)
+    let res = ps.set_packet_filter("service == https and not ip.dst in 203.0.113.0/33");
+    assert!(res.is_err());
+    assert_eq!(res.unwrap_err().message, "Invalid packet filter given as a parameter (`203.0.113.0/33` at position 36 is not an IP address, a CIDR block or a MAC address).");
+    assert_eq!(ps.state, State::PacketFilter);
+
+    assert!(ps.set_packet_filter("service == https and not ip.dst in 203.0.113.0/24").is_ok());
+    assert_eq!(ps.state, State::Ready);
+}
+
 #[test]
 pub fn packet_snooper_set_packet_filter_in_invalid_state_test() {
     let packet_filter = "TCP";
@@ -209,4 +238,156 @@ pub fn packet_snooper_set_packet_filter_in_invalid_state_test() {
     }
 }
 
+#[test]
+pub fn packet_snooper_set_checksum_offload_test() {
+    let error_str = "Invalid call on set_checksum_offload when in an illegal state.";
+    let mut ps = PacketSnooper::new();
+    assert!(!ps.config_options.checksum_offload);
+
+    let invalid_states = [State::Working, State::Stopped];
+    let valid_states = [State::ConfigDevice, State::ConfigTimeInterval, State::ConfigFile, State::ReportFormat, State::PacketFilter, State::Ready];
+
+    for state in invalid_states {
+        ps.state = state; // forcing packet_snooper into a specific state (not safe, just for testing purposes)
+        let res = ps.set_checksum_offload(true);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().message, error_str);
+        assert!(!ps.config_options.checksum_offload);
+    }
+
+    for state in valid_states {
+        ps.state = state; // forcing packet_snooper into a specific state (not safe, just for testing purposes)
+        let res = ps.set_checksum_offload(true);
+        assert!(res.is_ok());
+        assert!(ps.config_options.checksum_offload);
+        ps.set_checksum_offload(false).unwrap();
+    }
+}
+
+#[test]
+pub fn packet_snooper_set_flow_aggregation_test() {
+    let error_str = "Invalid call on set_flow_aggregation when in an illegal state.";
+    let mut ps = PacketSnooper::new();
+    assert_eq!(ps.config_options.flow_aggregation, FlowAggregation::Unidirectional);
+
+    let res = ps.set_flow_aggregation("sideways");
+    assert!(res.is_err());
+    assert_eq!(res.unwrap_err().message, "Invalid flow aggregation name given as a parameter.");
+
+    for state in [State::Working, State::Stopped] {
+        ps.state = state; // forcing packet_snooper into a specific state (not safe, just for testing purposes)
+        let res = ps.set_flow_aggregation("bidirectional");
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().message, error_str);
+        assert_eq!(ps.config_options.flow_aggregation, FlowAggregation::Unidirectional);
+    }
+
+    ps.state = State::Ready;
+    ps.set_flow_aggregation("bidirectional").unwrap();
+    assert_eq!(ps.config_options.flow_aggregation, FlowAggregation::Bidirectional);
+    ps.set_flow_aggregation("unidirectional").unwrap();
+    assert_eq!(ps.config_options.flow_aggregation, FlowAggregation::Unidirectional);
+}
+
+#[test]
+pub fn packet_snooper_set_flow_timeouts_test() {
+    let error_str = "Invalid call on set_flow_timeouts when in an illegal state.";
+    let mut ps = PacketSnooper::new();
+    assert_eq!(ps.config_options.flow_idle_timeout, Duration::from_secs(15));
+    assert_eq!(ps.config_options.flow_active_timeout, Duration::from_secs(1800));
+
+    for (idle_timeout, active_timeout) in [(0, 60), (60, 30)] {
+        let res = ps.set_flow_timeouts(idle_timeout, active_timeout);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().message, "Invalid flow timeouts given as a parameter.");
+    }
+
+    for state in [State::Working, State::Stopped] {
+        ps.state = state; // forcing packet_snooper into a specific state (not safe, just for testing purposes)
+        let res = ps.set_flow_timeouts(30, 600);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().message, error_str);
+    }
+
+    ps.state = State::Ready;
+    ps.set_flow_timeouts(30, 600).unwrap();
+    assert_eq!(ps.config_options.flow_idle_timeout, Duration::from_secs(30));
+    assert_eq!(ps.config_options.flow_active_timeout, Duration::from_secs(600));
+}
+
+#[test]
+pub fn packet_snooper_set_services_file_test() {
+    let error_str = "Invalid call on set_services_file when in an illegal state.";
+    let services_file = "services_configuration_test.txt";
+    std::fs::write(services_file, "# in-house services\nconfig-test-app 45678/tcp\n").unwrap();
+    let mut ps = PacketSnooper::new();
+
+    let res = ps.set_services_file("missing_services_file.txt");
+    assert!(res.is_err());
+    assert!(res.unwrap_err().message.starts_with("Invalid services file given as a parameter"));
+
+    for state in [State::Working, State::Stopped] {
+        ps.state = state; // forcing packet_snooper into a specific state (not safe, just for testing purposes)
+        let res = ps.set_services_file(services_file);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().message, error_str);
+        assert_eq!(service_registry().name(Transport::TCP, 45678), None);
+    }
+
+    ps.state = State::Ready;
+    ps.set_services_file(services_file).unwrap();
+    std::fs::remove_file(services_file).unwrap();
+    assert_eq!(service_registry().name(Transport::TCP, 45678), Some("CONFIG-TEST-APP"));
+}
+
+#[test]
+pub fn packet_snooper_set_flow_export_test() {
+    let error_str = "Invalid call on set_flow_export when in an illegal state.";
+    let mut ps = PacketSnooper::new();
+    assert_eq!(ps.config_options.flow_export, None);
+
+    let res = ps.set_flow_export("not a collector", "ipfix");
+    assert!(res.is_err());
+    assert_eq!(res.unwrap_err().message, "Invalid flow export collector given as a parameter.");
+    let res = ps.set_flow_export("127.0.0.1:2055", "v7");
+    assert!(res.is_err());
+    assert_eq!(res.unwrap_err().message, "Invalid flow export version given as a parameter.");
+
+    for state in [State::Working, State::Stopped] {
+        ps.state = state; // forcing packet_snooper into a specific state (not safe, just for testing purposes)
+        let res = ps.set_flow_export("127.0.0.1:2055", "ipfix");
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().message, error_str);
+    }
+
+    ps.state = State::Ready;
+    ps.set_flow_export("127.0.0.1:2055", "v9").unwrap();
+    assert_eq!(ps.config_options.flow_export, Some(FlowExport { collector: "127.0.0.1:2055".parse().unwrap(), version: FlowExportVersion::NetflowV9 }));
+}
+
+#[test]
+pub fn packet_snooper_set_flow_collector_test() {
+    let error_str = "Invalid call on set_flow_collector when in an illegal state.";
+    let mut ps = PacketSnooper::new();
+    assert_eq!(ps.config_options.flow_collector, None);
+
+    // not an address, or an address already in use
+    let socket = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+    for address in ["not an address".to_string(), socket.local_addr().unwrap().to_string()] {
+        let res = ps.set_flow_collector(address.as_str());
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().message, "Invalid flow collector address given as a parameter.");
+    }
+
+    for state in [State::Working, State::Stopped] {
+        ps.state = state; // forcing packet_snooper into a specific state (not safe, just for testing purposes)
+        let res = ps.set_flow_collector("127.0.0.1:0");
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().message, error_str);
+    }
+
+    ps.state = State::Ready;
+    ps.set_flow_collector("127.0.0.1:0").unwrap();
+    assert_eq!(ps.config_options.flow_collector, Some("127.0.0.1:0".parse().unwrap()));
+}
 
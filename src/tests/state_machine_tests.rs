@@ -1,6 +1,6 @@
 use std::thread::{JoinHandle, sleep};
 use std::time::Duration;
-use crate::{PacketSnooper, State};
+use crate::{PacketSnooper, ReportFormat, State};
 use crate::tests::complete_setup;
 
 #[test]
@@ -248,3 +248,33 @@ pub fn packet_snooper_rapid_abort_test() {
         i -= 1;
     }
 }
+
+#[test]
+pub fn packet_snooper_swap_settings_test() {
+    let mut ps = complete_setup();
+
+    for state in [State::ConfigDevice, State::PacketFilter, State::Ready] {
+        ps.state = state; // forcing packet_snooper into a specific state (not safe, just for testing purposes)
+        assert_eq!(ps.swap_packet_filter("udp").unwrap_err().message, "Invalid call on swap_packet_filter when in an illegal state.");
+        assert_eq!(ps.swap_report_format("raw").unwrap_err().message, "Invalid call on swap_report_format when in an illegal state.");
+        assert_eq!(ps.swap_time_interval(5).unwrap_err().message, "Invalid call on swap_time_interval when in an illegal state.");
+    }
+
+    ps.start().unwrap();
+    ps.swap_packet_filter("udp.port == 53").unwrap();
+    assert_eq!(ps.swap_packet_filter("udp.port == dns").unwrap_err().message, "Invalid packet filter given as a parameter (`dns` at position 13 is not a number).");
+    ps.swap_report_format("verbose").unwrap();
+    assert_eq!(ps.swap_report_format("xml").unwrap_err().message, "Invalid format name given as a parameter");
+    ps.stop().unwrap();
+    assert_eq!(ps.swap_time_interval(0).unwrap_err().message, "Invalid time interval given as a parameter.");
+    ps.swap_time_interval(5).unwrap();
+    assert_eq!(ps.state, State::Stopped);
+    assert!(ps.report_generator.is_some());
+
+    assert_eq!(ps.config_options.packet_filter, "udp.port == 53");
+    assert_eq!(ps.config_options.report_format, ReportFormat::Verbose);
+    assert_eq!(ps.config_options.time_interval, Duration::from_secs(5));
+
+    ps.end().unwrap();
+    assert!(ps.report_generator.is_none());
+}